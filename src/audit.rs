@@ -0,0 +1,461 @@
+//! An append-only audit trail of every change made to the `AccountsChart` and `Transaction`s of a
+//! `Ledger`.
+
+use std::convert::TryFrom;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, NaiveDateTime};
+use json::JsonValue;
+
+use error::Error;
+use history::UndoHistory;
+use journal::{check_account, hash_transaction};
+use ledger::Ledger;
+use transaction::Transaction;
+use {Account, AccountsChart};
+
+/// An enumeration of the kinds of change recorded in the audit trail.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AuditAction {
+    /// An `Account` was added to the `AccountsChart`, along with any sub-accounts it already had.
+    CreateAccount,
+
+    /// The details of an `Account` were edited. The snapshots of the `Account` do not include its
+    /// sub-accounts.
+    UpdateAccount,
+
+    /// An `Account` was removed from the `AccountsChart`, along with its sub-accounts, by undoing
+    /// its creation.
+    RemoveAccount,
+
+    /// A `Transaction` was added to the `Ledger`.
+    CreateTransaction,
+
+    /// A `Transaction` was edited, or the status of it or one of its `Split`s was changed.
+    UpdateTransaction,
+
+    /// A `Transaction` was voided.
+    VoidTransaction,
+
+    /// A `Transaction` was removed from the `Ledger`.
+    RemoveTransaction,
+}
+
+impl<'a> From<&'a str> for AuditAction {
+    fn from(value: &'a str) -> AuditAction {
+        match value.to_lowercase().as_str() {
+            "updateaccount" => AuditAction::UpdateAccount,
+            "removeaccount" => AuditAction::RemoveAccount,
+            "createtransaction" => AuditAction::CreateTransaction,
+            "updatetransaction" => AuditAction::UpdateTransaction,
+            "voidtransaction" => AuditAction::VoidTransaction,
+            "removetransaction" => AuditAction::RemoveTransaction,
+            _ => AuditAction::CreateAccount,
+        }
+    }
+}
+
+/// A snapshot of an `Account` or `Transaction` at one point in the audit trail.
+#[derive(Clone, PartialEq, Debug)]
+pub enum AuditValue {
+    /// A snapshot of an `Account`.
+    Account(Account),
+
+    /// A snapshot of a `Transaction`.
+    Transaction(Transaction),
+}
+
+impl AuditValue {
+    /// Convert this `AuditValue` to its JSON form, an object holding the JSON form of either an
+    /// `account` or a `transaction`.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        match *self {
+            AuditValue::Account(ref account) => value["account"] = account.to_json(),
+            AuditValue::Transaction(ref transaction) => {
+                value["transaction"] = transaction.to_json()
+            }
+        }
+
+        value
+    }
+}
+
+/// Create an `AuditValue` object from its JSON form, as produced by `AuditValue::to_json()`.
+///
+/// A snapshot that is not a valid `Account` or `Transaction` is rejected with
+/// `Error::InvalidJson`.
+impl<'a> TryFrom<&'a JsonValue> for AuditValue {
+    type Error = Error;
+
+    fn try_from(value: &'a JsonValue) -> Result<AuditValue, Error> {
+        if value["account"].is_null() {
+            Transaction::try_from(&value["transaction"]).map(AuditValue::Transaction)
+        } else {
+            check_account(&value["account"])?;
+            Ok(AuditValue::Account(Account::from(&value["account"])))
+        }
+    }
+}
+
+/// A single, immutable entry in the audit trail of a `Ledger`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct AuditEvent {
+    sequence: u64,
+    timestamp: NaiveDateTime,
+    actor: String,
+    action: AuditAction,
+    subject_id: String,
+    parent_id: Option<String>,
+    before: Option<AuditValue>,
+    after: Option<AuditValue>,
+}
+
+impl AuditEvent {
+    /// Retrieve the position of this `AuditEvent` in the audit trail, starting from 1.
+    pub fn get_sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Retrieve the time, in UTC, at which the change was made.
+    pub fn get_timestamp(&self) -> NaiveDateTime {
+        self.timestamp
+    }
+
+    /// Retrieve the name of the user or process that made the change, as a `String`.
+    pub fn get_actor(&self) -> String {
+        String::clone(&self.actor)
+    }
+
+    /// Retrieve the kind of change that was made.
+    pub fn get_action(&self) -> AuditAction {
+        self.action
+    }
+
+    /// Retrieve the id of the `Account` or `Transaction` that was changed, as a `String`.
+    pub fn get_subject_id(&self) -> String {
+        String::clone(&self.subject_id)
+    }
+
+    /// Retrieve the id of the parent of a newly created or removed `Account`, or `None` if it is a
+    /// top-level `Account` or the change was not to an `Account`.
+    pub fn get_parent_id(&self) -> Option<String> {
+        self.parent_id.clone()
+    }
+
+    /// Retrieve the `Account` or `Transaction` as it was before the change, if it existed.
+    pub fn get_before(&self) -> Option<AuditValue> {
+        self.before.clone()
+    }
+
+    /// Retrieve the `Account` or `Transaction` as it was after the change, if it still exists.
+    pub fn get_after(&self) -> Option<AuditValue> {
+        self.after.clone()
+    }
+
+    /// Convert this `AuditEvent` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["sequence"] = self.sequence.into();
+        value["timestamp"] = self.timestamp.format(TIMESTAMP_FORMAT).to_string().into();
+        value["actor"] = self.actor.as_str().into();
+        value["action"] = format!("{:?}", self.action).into();
+        value["subject_id"] = self.subject_id.as_str().into();
+        value["parent_id"] = self.parent_id.clone().into();
+        value["before"] = self.before.as_ref().map(AuditValue::to_json).into();
+        value["after"] = self.after.as_ref().map(AuditValue::to_json).into();
+
+        value
+    }
+}
+
+/// Create an `AuditEvent` object from its JSON form, as produced by `AuditEvent::to_json()`.
+///
+/// An event whose snapshots are not valid is rejected with `Error::InvalidJson`.
+impl<'a> TryFrom<&'a JsonValue> for AuditEvent {
+    type Error = Error;
+
+    fn try_from(value: &'a JsonValue) -> Result<AuditEvent, Error> {
+        let snapshot = |value: &JsonValue| {
+            if value.is_null() {
+                Ok(None)
+            } else {
+                AuditValue::try_from(value).map(Some)
+            }
+        };
+
+        Ok(AuditEvent {
+            sequence: value["sequence"].as_u64().unwrap_or(0),
+            timestamp: value["timestamp"]
+                .as_str()
+                .and_then(|timestamp| {
+                    NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()
+                })
+                .unwrap_or_default(),
+            actor: String::from(value["actor"].as_str().unwrap_or("")),
+            action: AuditAction::from(value["action"].as_str().unwrap_or("")),
+            subject_id: String::from(value["subject_id"].as_str().unwrap_or("")),
+            parent_id: value["parent_id"].as_str().map(String::from),
+            before: snapshot(&value["before"])?,
+            after: snapshot(&value["after"])?,
+        })
+    }
+}
+
+/// The format in which the timestamps of `AuditEvent`s are saved.
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+/// The audit trail of a `Ledger`, along with the actor and clock used to record new events, and
+/// the undo history built from those events.
+#[derive(Clone)]
+pub(crate) struct AuditTrail {
+    events: Vec<AuditEvent>,
+    actor: String,
+    clock: fn() -> NaiveDateTime,
+    pub(crate) history: UndoHistory,
+}
+
+impl AuditTrail {
+    pub(crate) fn new() -> AuditTrail {
+        AuditTrail {
+            events: vec![],
+            actor: String::from("system"),
+            clock: get_system_time,
+            history: UndoHistory::default(),
+        }
+    }
+
+    /// Replace the recorded events with ones loaded from a saved `Ledger`. Changes made before
+    /// the `Ledger` was saved cannot be undone.
+    pub(crate) fn restore(&mut self, events: Vec<AuditEvent>) {
+        self.events = events;
+        self.history.clear();
+    }
+
+    pub(crate) fn record_account(
+        &mut self,
+        action: AuditAction,
+        parent_id: Option<String>,
+        before: Option<Account>,
+        after: Option<Account>,
+    ) {
+        let subject_id = match (&before, &after) {
+            (_, &Some(ref account)) | (&Some(ref account), _) => account.get_id(),
+            _ => return,
+        };
+        self.push(
+            action,
+            subject_id,
+            parent_id,
+            before.map(AuditValue::Account),
+            after.map(AuditValue::Account),
+        );
+    }
+
+    pub(crate) fn record_transaction(
+        &mut self,
+        action: AuditAction,
+        before: Option<Transaction>,
+        after: Option<Transaction>,
+    ) {
+        let subject_id = match (&before, &after) {
+            (_, &Some(ref transaction)) | (&Some(ref transaction), _) => transaction.get_id(),
+            _ => return,
+        };
+        self.push(
+            action,
+            subject_id,
+            None,
+            before.map(AuditValue::Transaction),
+            after.map(AuditValue::Transaction),
+        );
+    }
+
+    fn push(
+        &mut self,
+        action: AuditAction,
+        subject_id: String,
+        parent_id: Option<String>,
+        before: Option<AuditValue>,
+        after: Option<AuditValue>,
+    ) {
+        let event = AuditEvent {
+            sequence: self.events.len() as u64 + 1,
+            timestamp: (self.clock)(),
+            actor: String::clone(&self.actor),
+            action,
+            subject_id,
+            parent_id,
+            before,
+            after,
+        };
+        self.history.record(&event);
+        self.events.push(event);
+    }
+}
+
+impl Ledger {
+    /// Set the name of the user or process recorded in the audit trail for subsequent changes.
+    ///
+    /// Changes are attributed to `system` until an actor is set.
+    pub fn set_actor(&mut self, actor: &str) {
+        self.audit_mut().actor = String::from(actor);
+    }
+
+    /// Set the function used to timestamp subsequent changes in the audit trail.
+    ///
+    /// By default, the system clock is used, which under WebAssembly is the clock of the
+    /// JavaScript host. A different clock is useful for testing. The creation of the `Account`s
+    /// passed to `Ledger::new()` is always timestamped with the system clock.
+    pub fn set_clock(&mut self, clock: fn() -> NaiveDateTime) {
+        self.audit_mut().clock = clock;
+    }
+
+    /// Retrieve every `AuditEvent` recorded for this `Ledger`, oldest first.
+    pub fn get_audit_log(&self) -> Vec<AuditEvent> {
+        self.audit().events.to_vec()
+    }
+
+    /// Retrieve the `AuditEvent`s recording changes to the `Account` with the given id, oldest
+    /// first.
+    pub fn get_account_history(&self, id: &str) -> Vec<AuditEvent> {
+        self.get_history(id, |value| matches!(*value, AuditValue::Account(_)))
+    }
+
+    /// Retrieve the `AuditEvent`s recording changes to the `Transaction` with the given id,
+    /// oldest first.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, AuditAction, AuditValue, Currency,
+    ///#               Ledger, Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("04", "Dues", "Dues", AccountType::Income,
+    ///#                                          Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// ledger.set_actor("treasurer");
+    ///
+    /// let mut dues = Transaction::new("0001", NaiveDate::from_ymd_opt(2018, 3, 1).unwrap(),
+    ///                                 "Membership dues");
+    /// dues.add_split(Split::new("01", 5000));
+    /// dues.add_split(Split::new("04", -5000));
+    /// ledger.add_transaction(dues).unwrap();
+    /// ledger.void_transaction("0001", NaiveDate::from_ymd_opt(2018, 3, 2).unwrap()).unwrap();
+    ///
+    /// let history = ledger.get_transaction_history("0001");
+    /// assert_eq!(history.len(), 2);
+    /// assert_eq!(history[0].get_action(), AuditAction::CreateTransaction);
+    /// assert_eq!(history[1].get_action(), AuditAction::VoidTransaction);
+    /// assert_eq!(history[1].get_actor(), "treasurer");
+    /// assert!(history[1].get_before().is_some());
+    /// ```
+    pub fn get_transaction_history(&self, id: &str) -> Vec<AuditEvent> {
+        self.get_history(id, |value| matches!(*value, AuditValue::Transaction(_)))
+    }
+
+    /// Reconstruct the `AccountsChart` and `Transaction`s of this `Ledger` as they were at the
+    /// given moment, by replaying every `AuditEvent` recorded at or before it.
+    ///
+    /// The reconstructed `Ledger` has the replayed events as its audit trail. Fiscal periods,
+    /// reconciliations and schedules are not part of the audit trail, and are not reconstructed.
+    pub fn replay(&self, as_of: NaiveDateTime) -> Ledger {
+        self.replay_events(|event| event.timestamp <= as_of)
+    }
+
+    /// Reconstruct the `AccountsChart` and `Transaction`s of this `Ledger` as they were
+    /// immediately after the `AuditEvent` with the given sequence number.
+    ///
+    /// See `replay()` for details.
+    pub fn replay_through(&self, sequence: u64) -> Ledger {
+        self.replay_events(|event| event.sequence <= sequence)
+    }
+
+    fn get_history<F>(&self, id: &str, is_subject: F) -> Vec<AuditEvent>
+    where
+        F: Fn(&AuditValue) -> bool,
+    {
+        self.audit()
+            .events
+            .iter()
+            .filter(|event| {
+                event.subject_id == id
+                    && event
+                        .after
+                        .as_ref()
+                        .or(event.before.as_ref())
+                        .is_some_and(&is_subject)
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn replay_events<F>(&self, include: F) -> Ledger
+    where
+        F: Fn(&AuditEvent) -> bool,
+    {
+        let mut ledger = Ledger::new(AccountsChart::new());
+        ledger.audit_mut().clock = self.audit().clock;
+
+        let mut chart = AccountsChart::new();
+        let mut transactions: Vec<Transaction> = vec![];
+        for event in self.audit().events.iter().filter(|event| include(event)) {
+            match event.after {
+                Some(AuditValue::Account(ref account)) => match event.action {
+                    AuditAction::CreateAccount => {
+                        chart.insert_account(event.parent_id.as_deref(), Account::clone(account));
+                    }
+                    _ => {
+                        chart.replace_account(account);
+                    }
+                },
+                Some(AuditValue::Transaction(ref transaction)) => {
+                    ledger.seal_transaction(&event.subject_id, Some(hash_transaction(transaction)));
+                    match transactions
+                        .iter_mut()
+                        .find(|existing| existing.get_id() == event.subject_id)
+                    {
+                        Some(existing) => *existing = Transaction::clone(transaction),
+                        None => transactions.push(Transaction::clone(transaction)),
+                    }
+                }
+                None => match event.before {
+                    Some(AuditValue::Account(_)) => {
+                        chart.remove_account(&event.subject_id);
+                    }
+                    _ => {
+                        ledger.seal_transaction(&event.subject_id, None);
+                        transactions.retain(|existing| existing.get_id() != event.subject_id);
+                    }
+                },
+            }
+            ledger.audit_mut().events.push(AuditEvent::clone(event));
+        }
+
+        ledger.restore(chart, transactions);
+        ledger
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn get_system_time() -> NaiveDateTime {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    DateTime::from_timestamp(elapsed.as_secs() as i64, elapsed.subsec_nanos())
+        .unwrap_or_default()
+        .naive_utc()
+}
+
+/// WebAssembly has no system clock of its own, so the time is taken from the JavaScript host.
+#[cfg(target_arch = "wasm32")]
+fn get_system_time() -> NaiveDateTime {
+    DateTime::from_timestamp_millis(js_sys::Date::now() as i64)
+        .unwrap_or_default()
+        .naive_utc()
+}
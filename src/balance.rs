@@ -0,0 +1,136 @@
+//! Options for computing the balances of `Account`s in a `Ledger`.
+
+use chrono::NaiveDate;
+
+use transaction::{DateBasis, EntrySource, Split, Transaction, TransactionStatus};
+use translation::Translation;
+
+/// An enumeration for restricting a balance to postings that have reached a given status.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum StatusFilter {
+    /// Include every posting, regardless of status. Voided postings are offset by their
+    /// reversals, so they do not affect the balance.
+    #[default]
+    All,
+
+    /// Include only postings that are `TransactionStatus::Cleared` or
+    /// `TransactionStatus::Reconciled`.
+    Cleared,
+
+    /// Include only postings that are `TransactionStatus::Reconciled`.
+    Reconciled,
+}
+
+/// Options controlling which postings are included when computing an `Account`'s balance.
+///
+/// By default, a balance includes every posting made directly to the `Account`, on any date.
+///
+/// # Examples
+/// ```
+///# extern crate chrono;
+///# extern crate cratchit;
+///# use chrono::NaiveDate;
+///# use cratchit::{BalanceOptions, DateBasis};
+/// let options = BalanceOptions::new()
+///     .as_of(NaiveDate::from_ymd_opt(2018, 12, 31).unwrap())
+///     .date_basis(DateBasis::Effective)
+///     .include_sub_accounts(true);
+/// ```
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct BalanceOptions {
+    start: Option<NaiveDate>,
+    end: Option<NaiveDate>,
+    date_basis: DateBasis,
+    status_filter: StatusFilter,
+    include_sub_accounts: bool,
+    exclude_closing_entries: bool,
+    translation: Option<Translation>,
+}
+
+impl BalanceOptions {
+    /// Create a new `BalanceOptions` object including every posting to the `Account`.
+    pub fn new() -> BalanceOptions {
+        BalanceOptions::default()
+    }
+
+    /// Exclude postings dated after `date`.
+    pub fn as_of(mut self, date: NaiveDate) -> BalanceOptions {
+        self.end = Some(date);
+        self
+    }
+
+    /// Exclude postings dated before `date`. Combined with `as_of()`, this computes the net
+    /// change of an `Account` over a period rather than its balance at a point in time.
+    pub fn since(mut self, date: NaiveDate) -> BalanceOptions {
+        self.start = Some(date);
+        self
+    }
+
+    /// Choose which of each `Transaction`'s dates is compared against the date range.
+    pub fn date_basis(mut self, basis: DateBasis) -> BalanceOptions {
+        self.date_basis = basis;
+        self
+    }
+
+    /// Include only postings whose `TransactionStatus` satisfies the given `StatusFilter`.
+    pub fn status(mut self, filter: StatusFilter) -> BalanceOptions {
+        self.status_filter = filter;
+        self
+    }
+
+    /// Whether postings to sub-accounts of the `Account` should also be included. This is
+    /// required to compute a balance for a placeholder account.
+    pub fn include_sub_accounts(mut self, include: bool) -> BalanceOptions {
+        self.include_sub_accounts = include;
+        self
+    }
+
+    /// Whether the entries posted by `Ledger::post_closing_entry()` should be excluded, so that
+    /// the balances of Income and Expense `Account`s show their activity rather than being
+    /// zeroed at the end of each fiscal year.
+    pub fn exclude_closing_entries(mut self, exclude: bool) -> BalanceOptions {
+        self.exclude_closing_entries = exclude;
+        self
+    }
+
+    /// Translate balances into a reporting currency. Each `Account`'s balance is translated
+    /// separately, so balances that include sub-accounts in other currencies can be combined.
+    pub fn translate(mut self, translation: Translation) -> BalanceOptions {
+        self.translation = Some(translation);
+        self
+    }
+
+    pub(crate) fn includes_sub_accounts(&self) -> bool {
+        self.include_sub_accounts
+    }
+
+    pub(crate) fn get_start(&self) -> Option<NaiveDate> {
+        self.start
+    }
+
+    pub(crate) fn get_end(&self) -> Option<NaiveDate> {
+        self.end
+    }
+
+    pub(crate) fn get_translation(&self) -> Option<&Translation> {
+        self.translation.as_ref()
+    }
+
+    pub(crate) fn includes_split(&self, split: &Split) -> bool {
+        matches!(
+            (self.status_filter, split.get_status()),
+            (StatusFilter::All, _)
+                | (StatusFilter::Cleared, TransactionStatus::Cleared)
+                | (_, TransactionStatus::Reconciled)
+        )
+    }
+
+    pub(crate) fn includes(&self, transaction: &Transaction) -> bool {
+        let date = transaction.get_date_for(self.date_basis);
+
+        self.start.is_none_or(|start| date >= start)
+            && self.end.is_none_or(|end| date <= end)
+            && !(self.exclude_closing_entries
+                && transaction.get_source() == EntrySource::YearEndClose)
+    }
+}
@@ -0,0 +1,732 @@
+//! Bills received from vendors, and the payments made against them.
+
+use chrono::NaiveDate;
+use json::JsonValue;
+
+use error::Error;
+use ledger::Ledger;
+use transaction::{parse_date, EntrySource, Split, Transaction, TransactionStatus};
+use AccountType;
+
+/// An enumeration of the stages a `Bill` moves through.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BillStatus {
+    /// The `Bill` has been entered, and nothing has been paid.
+    Open,
+
+    /// Part of the `Bill` has been paid, and it is not yet due.
+    PartiallyPaid,
+
+    /// The `Bill` has been paid in full, or settled with an early-payment discount.
+    Paid,
+
+    /// The `Bill` is past its due date with an amount still outstanding.
+    Overdue,
+
+    /// The `Bill` was cancelled, and its entry reversed.
+    Void,
+}
+
+/// A line of a `Bill`, allocating part of its amount to an Expense or Asset `Account`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BillLine {
+    account_id: String,
+    description: String,
+    amount: i64,
+}
+
+impl BillLine {
+    /// Create a new `BillLine`.
+    ///
+    /// # Arguments
+    /// * `account_id`: The id of the Expense or Asset `Account` the line is debited to.
+    /// * `description`: A description of what was bought.
+    /// * `amount`: The amount of the line, in the smallest unit of the currency.
+    pub fn new(account_id: &str, description: &str, amount: i64) -> BillLine {
+        BillLine {
+            account_id: String::from(account_id),
+            description: String::from(description),
+            amount,
+        }
+    }
+
+    /// Retrieve the id of the `Account` this line is debited to, as a `String`.
+    pub fn get_account_id(&self) -> String {
+        String::clone(&self.account_id)
+    }
+
+    /// Retrieve the description of this line, as a `String`.
+    pub fn get_description(&self) -> String {
+        String::clone(&self.description)
+    }
+
+    /// Retrieve the amount of this line.
+    pub fn get_amount(&self) -> i64 {
+        self.amount
+    }
+
+    /// Convert this `BillLine` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["account_id"] = self.account_id.as_str().into();
+        value["description"] = self.description.as_str().into();
+        value["amount"] = self.amount.into();
+
+        value
+    }
+}
+
+/// Create a `BillLine` object from its JSON form, as produced by `BillLine::to_json()`.
+impl<'a> From<&'a JsonValue> for BillLine {
+    fn from(value: &'a JsonValue) -> BillLine {
+        BillLine::new(
+            value["account_id"].as_str().unwrap_or(""),
+            value["description"].as_str().unwrap_or(""),
+            value["amount"].as_i64().unwrap_or(0),
+        )
+    }
+}
+
+/// A payment made against a `Bill`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BillPayment {
+    transaction_id: String,
+    date: NaiveDate,
+    amount: i64,
+    discount: i64,
+}
+
+impl BillPayment {
+    /// Retrieve the id of the `Transaction` recording the payment, as a `String`. A batch
+    /// payment records the payments of several `Bill`s in one `Transaction`.
+    pub fn get_transaction_id(&self) -> String {
+        String::clone(&self.transaction_id)
+    }
+
+    /// Retrieve the date the payment was made.
+    pub fn get_date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// Retrieve the amount paid.
+    pub fn get_amount(&self) -> i64 {
+        self.amount
+    }
+
+    /// Retrieve the early-payment discount taken with the payment.
+    pub fn get_discount(&self) -> i64 {
+        self.discount
+    }
+
+    fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["transaction_id"] = self.transaction_id.as_str().into();
+        value["date"] = self.date.to_string().into();
+        value["amount"] = self.amount.into();
+        value["discount"] = self.discount.into();
+
+        value
+    }
+}
+
+/// A bill received from a vendor `Contact`.
+///
+/// Adding a `Bill` to a `Ledger` posts it, crediting the total to the vendor's payable `Account`
+/// and debiting the `Account` of each line. It is due according to the `PaymentTerms` of the
+/// vendor unless a due date is set, and those terms also decide whether an early-payment
+/// discount can be taken.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Bill {
+    id: String,
+    contact_id: String,
+    reference: String,
+    date: NaiveDate,
+    due_date: Option<NaiveDate>,
+    discount_date: Option<NaiveDate>,
+    discount: i64,
+    lines: Vec<BillLine>,
+    posted: bool,
+    voided: bool,
+    payments: Vec<BillPayment>,
+}
+
+impl Bill {
+    /// Create a new `Bill` with no lines.
+    ///
+    /// # Arguments
+    /// * `id`: The id of the `Bill`. It is also the id of the `Transaction` posting it.
+    /// * `contact_id`: The id of the vendor `Contact` that sent the `Bill`.
+    /// * `date`: The date of the `Bill`, from which its `PaymentTerms` are counted.
+    pub fn new(id: &str, contact_id: &str, date: NaiveDate) -> Bill {
+        Bill {
+            id: String::from(id),
+            contact_id: String::from(contact_id),
+            reference: String::new(),
+            date,
+            due_date: None,
+            discount_date: None,
+            discount: 0,
+            lines: vec![],
+            posted: false,
+            voided: false,
+            payments: vec![],
+        }
+    }
+
+    /// Retrieve the id of this `Bill`, as a `String`.
+    pub fn get_id(&self) -> String {
+        String::clone(&self.id)
+    }
+
+    /// Retrieve the id of the vendor `Contact`, as a `String`.
+    pub fn get_contact_id(&self) -> String {
+        String::clone(&self.contact_id)
+    }
+
+    /// Retrieve the vendor's own number for this `Bill`, as a `String`.
+    pub fn get_reference(&self) -> String {
+        String::clone(&self.reference)
+    }
+
+    /// Set the vendor's own number for this `Bill`.
+    pub fn set_reference(&mut self, reference: &str) {
+        self.reference = String::from(reference);
+    }
+
+    /// Retrieve the date of this `Bill`.
+    pub fn get_date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// Retrieve the date payment is due. Unless set, a `Bill` is due according to the
+    /// `PaymentTerms` its `Contact` had when the `Bill` was entered.
+    pub fn get_due_date(&self) -> Option<NaiveDate> {
+        self.due_date
+    }
+
+    /// Set the date payment is due, overriding the `PaymentTerms` of the `Contact`.
+    pub fn set_due_date(&mut self, date: NaiveDate) {
+        self.due_date = Some(date);
+    }
+
+    /// Retrieve the last date on which the early-payment discount can be taken, if the
+    /// `PaymentTerms` of the `Contact` offered one when the `Bill` was entered.
+    pub fn get_discount_date(&self) -> Option<NaiveDate> {
+        self.discount_date
+    }
+
+    /// Compute the early-payment discount available if this `Bill` is paid in full on `date`.
+    /// No discount is available after a partial payment.
+    pub fn get_available_discount(&self, date: NaiveDate) -> i64 {
+        if self.payments.is_empty() && self.discount_date.is_some_and(|last| date <= last) {
+            self.discount
+        } else {
+            0
+        }
+    }
+
+    /// Add a line to this `Bill`.
+    pub fn add_line(&mut self, line: BillLine) {
+        self.lines.push(line);
+    }
+
+    /// Retrieve the lines of this `Bill`.
+    pub fn get_lines(&self) -> Vec<BillLine> {
+        Vec::clone(&self.lines)
+    }
+
+    /// Compute the total of this `Bill`.
+    pub fn get_total(&self) -> i64 {
+        self.lines.iter().map(BillLine::get_amount).sum()
+    }
+
+    /// Retrieve the payments made against this `Bill`, oldest first.
+    pub fn get_payments(&self) -> Vec<BillPayment> {
+        Vec::clone(&self.payments)
+    }
+
+    /// Compute the total paid against this `Bill`, excluding discounts.
+    pub fn get_paid(&self) -> i64 {
+        self.payments.iter().map(|payment| payment.amount).sum()
+    }
+
+    /// Compute the early-payment discounts taken on this `Bill`.
+    pub fn get_discount_taken(&self) -> i64 {
+        self.payments.iter().map(|payment| payment.discount).sum()
+    }
+
+    /// Compute the amount of this `Bill` still owed. Nothing is owed on a void `Bill`.
+    pub fn get_outstanding(&self) -> i64 {
+        if self.posted && !self.voided {
+            self.get_total() - self.get_paid() - self.get_discount_taken()
+        } else {
+            0
+        }
+    }
+
+    /// Determine the `BillStatus` of this `Bill` on the given date. A `Bill` with an amount
+    /// outstanding after its due date is `Overdue`, even if part of it was paid.
+    pub fn get_status(&self, today: NaiveDate) -> BillStatus {
+        if self.voided || !self.posted {
+            BillStatus::Void
+        } else if self.get_outstanding() <= 0 {
+            BillStatus::Paid
+        } else if self.due_date.is_some_and(|due| today > due) {
+            BillStatus::Overdue
+        } else if self.payments.is_empty() {
+            BillStatus::Open
+        } else {
+            BillStatus::PartiallyPaid
+        }
+    }
+
+    /// Convert this `Bill` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["id"] = self.id.as_str().into();
+        value["contact_id"] = self.contact_id.as_str().into();
+        value["reference"] = self.reference.as_str().into();
+        value["date"] = self.date.to_string().into();
+        value["due_date"] = self.due_date.map(|date| date.to_string()).into();
+        value["discount_date"] = self.discount_date.map(|date| date.to_string()).into();
+        value["discount"] = self.discount.into();
+        value["lines"] = JsonValue::Array(self.lines.iter().map(BillLine::to_json).collect());
+        value["posted"] = self.posted.into();
+        value["voided"] = self.voided.into();
+        value["payments"] =
+            JsonValue::Array(self.payments.iter().map(BillPayment::to_json).collect());
+
+        value
+    }
+}
+
+/// Create a `Bill` object from its JSON form, as produced by `Bill::to_json()`.
+impl<'a> From<&'a JsonValue> for Bill {
+    fn from(value: &'a JsonValue) -> Bill {
+        let mut bill = Bill::new(
+            value["id"].as_str().unwrap_or(""),
+            value["contact_id"].as_str().unwrap_or(""),
+            parse_date(&value["date"]).unwrap_or_default(),
+        );
+        bill.reference = String::from(value["reference"].as_str().unwrap_or(""));
+        bill.due_date = parse_date(&value["due_date"]);
+        bill.discount_date = parse_date(&value["discount_date"]);
+        bill.discount = value["discount"].as_i64().unwrap_or(0);
+        bill.lines = value["lines"].members().map(BillLine::from).collect();
+        bill.posted = value["posted"].as_bool().unwrap_or(false);
+        bill.voided = value["voided"].as_bool().unwrap_or(false);
+        bill.payments = value["payments"]
+            .members()
+            .filter_map(|payment| {
+                parse_date(&payment["date"]).map(|date| BillPayment {
+                    transaction_id: String::from(payment["transaction_id"].as_str().unwrap_or("")),
+                    date,
+                    amount: payment["amount"].as_i64().unwrap_or(0),
+                    discount: payment["discount"].as_i64().unwrap_or(0),
+                })
+            })
+            .collect();
+
+        bill
+    }
+}
+
+impl Ledger {
+    /// Set the `Account` to which early-payment discounts taken on `Bill`s are credited.
+    ///
+    /// Unless an `Account` is set, no discounts are taken.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Account` was set, or an `Error` if it does not exist, is a placeholder,
+    ///   or is not an Income or Expense `Account`.
+    pub fn set_purchase_discount_account(&mut self, account_id: &str) -> Result<(), Error> {
+        let account = match self.get_chart().get_account_by_id(account_id) {
+            Some(account) => account,
+            None => return Err(Error::UnknownAccount(String::from(account_id))),
+        };
+
+        if account.is_placeholder() {
+            return Err(Error::PlaceholderAccount(String::from(account_id)));
+        }
+
+        match account.get_account_type() {
+            AccountType::Income | AccountType::Expense => {
+                *self.purchase_discount_account_mut() = Some(String::from(account_id));
+                Ok(())
+            }
+            _ => Err(Error::WrongAccountType(String::from(account_id))),
+        }
+    }
+
+    /// Retrieve the id of the `Account` to which early-payment discounts are credited, if one
+    /// has been set.
+    pub fn get_purchase_discount_account(&self) -> Option<String> {
+        self.purchase_discount_account().clone()
+    }
+
+    /// Enter a `Bill`, posting its `Transaction`.
+    ///
+    /// Unless set, the due date, discount date and discount of the `Bill` are fixed from the
+    /// `PaymentTerms` of its `Contact`, so later changes to the terms do not affect it.
+    ///
+    /// The `Transaction` has the id and date of the `Bill` and is marked with
+    /// `EntrySource::Bill`. It credits the total to the control `Account` of the vendor
+    /// `Contact`, tagged with the `Contact`, and debits each line.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Bill` was entered, or an `Error` if a `Bill` with the same id already
+    ///   exists, its `Contact` does not exist or has no control `Account`, the control `Account`
+    ///   is not a Liability, a line is not debited to an Expense or Asset `Account`, or the
+    ///   `Transaction` cannot be added.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Bill, BillLine,
+    ///#               BillStatus, Contact, ContactKind, Currency, Ledger, PaymentTerms};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("02", "Payable", "Payable",
+    ///#                                          AccountType::Liability, Currency::USDollar,
+    ///#                                          false));
+    ///# chart.add_top_level_account(Account::new("05", "Rink Rental", "Rink Rental",
+    ///#                                          AccountType::Expense, Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// let mut arena = Contact::new("ARENA", "Hasse Arena", ContactKind::Vendor);
+    /// arena.set_control_account("02");
+    /// arena.set_payment_terms(PaymentTerms::net(30));
+    /// ledger.add_contact(arena).unwrap();
+    ///
+    /// let date = NaiveDate::from_ymd_opt(2019, 1, 5).unwrap();
+    /// let mut bill = Bill::new("BILL-1", "ARENA", date);
+    /// bill.add_line(BillLine::new("05", "January ice", 80000));
+    /// ledger.add_bill(bill).unwrap();
+    ///
+    /// let bill = ledger.get_bill("BILL-1").unwrap();
+    /// assert_eq!(bill.get_due_date(), NaiveDate::from_ymd_opt(2019, 2, 4));
+    /// assert_eq!(bill.get_status(date), BillStatus::Open);
+    /// assert_eq!(ledger.get_balance("02", &BalanceOptions::new()), Ok(80000));
+    /// ```
+    pub fn add_bill(&mut self, bill: Bill) -> Result<(), Error> {
+        if self.bills().iter().any(|existing| existing.id == bill.id) {
+            return Err(Error::DuplicateBill(bill.id));
+        }
+        let (payable_id, terms) = match self.get_contact(&bill.contact_id) {
+            Some(contact) => match contact.get_control_account() {
+                Some(account_id) => (account_id, contact.get_payment_terms()),
+                None => return Err(Error::NoControlAccount(bill.get_contact_id())),
+            },
+            None => return Err(Error::UnknownContact(bill.get_contact_id())),
+        };
+        match self.get_chart().get_account_by_id(&payable_id) {
+            Some(ref account) if account.get_account_type() == AccountType::Liability => {}
+            Some(_) => return Err(Error::WrongAccountType(payable_id)),
+            None => return Err(Error::UnknownAccount(payable_id)),
+        }
+        for line in &bill.lines {
+            match self.get_chart().get_account_by_id(&line.account_id) {
+                Some(ref account)
+                    if matches!(
+                        account.get_account_type(),
+                        AccountType::Expense | AccountType::Asset
+                    ) => {}
+                Some(_) => return Err(Error::WrongAccountType(line.get_account_id())),
+                None => return Err(Error::UnknownAccount(line.get_account_id())),
+            }
+        }
+
+        let mut transaction = Transaction::new(&bill.id, bill.date, &describe(&bill));
+        transaction.set_source(EntrySource::Bill);
+        let mut payable = Split::new(&payable_id, -bill.get_total());
+        payable.set_contact(&bill.contact_id);
+        transaction.add_split(payable);
+        for line in &bill.lines {
+            let mut split = Split::new(&line.account_id, line.amount);
+            split.set_memo(&line.description);
+            transaction.add_split(split);
+        }
+        self.add_transaction(transaction)?;
+
+        self.bills_mut().push(Bill {
+            due_date: bill.due_date.or(Some(terms.get_due_date(bill.date))),
+            discount_date: terms.get_discount_date(bill.date),
+            discount: terms.get_discount(bill.get_total()),
+            posted: true,
+            voided: false,
+            payments: vec![],
+            ..bill
+        });
+
+        Ok(())
+    }
+
+    /// Make a payment against a single `Bill`, posting a `Transaction` that debits the vendor's
+    /// control `Account` and credits the `Account` paid from. If the payment settles the `Bill`
+    /// in full within its discount period, the discount is taken and only the rest is paid.
+    ///
+    /// # Arguments
+    /// * `id`: The id of the `Bill` paid.
+    /// * `transaction_id`: The id of the `Transaction` to post.
+    /// * `date`: The date of the payment.
+    /// * `amount`: The amount paid, which may be less than the amount outstanding.
+    /// * `account_id`: The id of the `Account` the payment is made from.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the payment was made, `Error::InvalidBillState` if the `Bill` is void or
+    ///   the amount is not positive, `Error::Overpayment` if the amount is more than is owed, or
+    ///   an `Error` if the `Transaction` cannot be added.
+    pub fn record_bill_payment(
+        &mut self,
+        id: &str,
+        transaction_id: &str,
+        date: NaiveDate,
+        amount: i64,
+        account_id: &str,
+    ) -> Result<(), Error> {
+        let bill = self.get_bill_or_error(id)?;
+        let discount = self.get_discount_for(&bill, date);
+        if amount <= 0 || bill.get_outstanding() <= 0 {
+            return Err(Error::InvalidBillState(String::from(id)));
+        }
+        if amount > bill.get_outstanding() - discount {
+            return Err(Error::Overpayment(String::from(id)));
+        }
+
+        let discount = if amount == bill.get_outstanding() - discount {
+            discount
+        } else {
+            0
+        };
+        self.post_bill_payments(
+            transaction_id,
+            date,
+            account_id,
+            vec![(bill, amount, discount)],
+        )
+    }
+
+    /// Pay several `Bill`s in full with a single `Transaction`, such as one check run. Each
+    /// `Bill` paid within its discount period has its early-payment discount taken.
+    ///
+    /// # Returns
+    /// * The total paid from the `Account`, or an `Error` if a `Bill` does not exist, is void,
+    ///   has nothing outstanding or is listed more than once, or the `Transaction` cannot be
+    ///   added. No `Bill` is paid if any cannot be.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Bill, BillLine,
+    ///#               BillStatus, Contact, ContactKind, Currency, Ledger, PaymentTerms};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("02", "Payable", "Payable",
+    ///#                                          AccountType::Liability, Currency::USDollar,
+    ///#                                          false));
+    ///# chart.add_top_level_account(Account::new("04", "Purchase Discounts", "Purchase Discounts",
+    ///#                                          AccountType::Income, Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("05", "Supplies", "Supplies",
+    ///#                                          AccountType::Expense, Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// ledger.set_purchase_discount_account("04").unwrap();
+    /// let mut vendor = Contact::new("ACME", "Acme Supply", ContactKind::Vendor);
+    /// vendor.set_control_account("02");
+    /// vendor.set_payment_terms(PaymentTerms::net(30).discount(2.0, 10));
+    /// ledger.add_contact(vendor).unwrap();
+    ///
+    /// for (id, day) in &[("B-1", 2), ("B-2", 20)] {
+    ///     let mut bill = Bill::new(id, "ACME", NaiveDate::from_ymd_opt(2019, 1, *day).unwrap());
+    ///     bill.add_line(BillLine::new("05", "Supplies", 50000));
+    ///     ledger.add_bill(bill).unwrap();
+    /// }
+    ///
+    /// let date = NaiveDate::from_ymd_opt(2019, 1, 25).unwrap();
+    /// let paid = ledger.pay_bills(&["B-1", "B-2"], "CHK-101", date, "01").unwrap();
+    /// assert_eq!(paid, 50000 + 49000);
+    /// assert_eq!(ledger.get_balance("04", &BalanceOptions::new()), Ok(1000));
+    /// assert_eq!(ledger.get_bill("B-2").unwrap().get_status(date), BillStatus::Paid);
+    /// ```
+    pub fn pay_bills(
+        &mut self,
+        ids: &[&str],
+        transaction_id: &str,
+        date: NaiveDate,
+        account_id: &str,
+    ) -> Result<i64, Error> {
+        let mut payments = vec![];
+        for (index, &id) in ids.iter().enumerate() {
+            let bill = self.get_bill_or_error(id)?;
+            if bill.get_outstanding() <= 0 || ids[..index].contains(&id) {
+                return Err(Error::InvalidBillState(String::from(id)));
+            }
+
+            let discount = self.get_discount_for(&bill, date);
+            let amount = bill.get_outstanding() - discount;
+            payments.push((bill, amount, discount));
+        }
+
+        let total = payments.iter().map(|payment| payment.1).sum();
+        self.post_bill_payments(transaction_id, date, account_id, payments)?;
+
+        Ok(total)
+    }
+
+    /// Void a `Bill`, voiding its `Transaction` with a reversal dated `date`.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Bill` was voided, `Error::InvalidBillState` if it is already void or
+    ///   payments have been made against it, or an `Error` if its `Transaction` cannot be voided.
+    pub fn void_bill(&mut self, id: &str, date: NaiveDate) -> Result<(), Error> {
+        let bill = self.get_bill_or_error(id)?;
+        if !bill.posted || bill.voided || !bill.payments.is_empty() {
+            return Err(Error::InvalidBillState(String::from(id)));
+        }
+
+        self.void_transaction(id, date)?;
+
+        Ok(())
+    }
+
+    /// Retrieve the `Bill` with the given id, if it has been entered in this `Ledger`.
+    ///
+    /// Whether the `Bill` is void, and which payments are applied against it, follows the
+    /// `Transaction`s in the `Ledger`, so undoing or voiding one of them is reflected here.
+    pub fn get_bill(&self, id: &str) -> Option<Bill> {
+        self.bills()
+            .iter()
+            .find(|bill| bill.id == id)
+            .map(|bill| self.sync_bill(bill))
+    }
+
+    /// Retrieve every `Bill` entered in this `Ledger`, in the order they were entered.
+    pub fn get_bills(&self) -> Vec<Bill> {
+        self.bills()
+            .iter()
+            .map(|bill| self.sync_bill(bill))
+            .collect()
+    }
+
+    /// Retrieve the `Bill`s with an amount outstanding that are due on or before `date`, or
+    /// whose early-payment discount expires by then, ordered by due date.
+    pub fn get_bills_to_pay(&self, date: NaiveDate) -> Vec<Bill> {
+        let mut bills: Vec<Bill> = self
+            .get_bills()
+            .into_iter()
+            .filter(|bill| {
+                bill.get_outstanding() > 0
+                    && (bill.due_date.is_none_or(|due| due <= date)
+                        || bill.get_available_discount(date) > 0)
+            })
+            .collect();
+        bills.sort_by_key(|bill| bill.due_date);
+
+        bills
+    }
+
+    /// Post one `Transaction` paying a set of `Bill`s, and record the payment against each.
+    fn post_bill_payments(
+        &mut self,
+        transaction_id: &str,
+        date: NaiveDate,
+        account_id: &str,
+        payments: Vec<(Bill, i64, i64)>,
+    ) -> Result<(), Error> {
+        let ids: Vec<String> = payments.iter().map(|payment| payment.0.get_id()).collect();
+        let mut transaction = Transaction::new(
+            transaction_id,
+            date,
+            &format!("Payment for {}", ids.join(", ")),
+        );
+        transaction.set_source(EntrySource::Bill);
+
+        let total: i64 = payments.iter().map(|payment| payment.1).sum();
+        let discounts: i64 = payments.iter().map(|payment| payment.2).sum();
+        transaction.add_split(Split::new(account_id, -total));
+        for &(ref bill, amount, discount) in &payments {
+            let payable_id = self
+                .get_transaction_by_id(&bill.id)
+                .and_then(|posted| posted.get_splits().into_iter().next())
+                .map(|split| split.get_account_id())
+                .unwrap_or_default();
+            let mut payable = Split::new(&payable_id, amount + discount);
+            payable.set_contact(&bill.contact_id);
+            payable.set_memo(&bill.id);
+            transaction.add_split(payable);
+        }
+        if discounts != 0 {
+            if let Some(discount_id) = self.get_purchase_discount_account() {
+                let mut split = Split::new(&discount_id, -discounts);
+                split.set_memo("Early payment discount");
+                transaction.add_split(split);
+            }
+        }
+        self.add_transaction(transaction)?;
+
+        for (bill, amount, discount) in payments {
+            let index = self.get_bill_index(&bill.id)?;
+            self.bills_mut()[index].payments.push(BillPayment {
+                transaction_id: String::from(transaction_id),
+                date,
+                amount,
+                discount,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Compute the early-payment discount that can be taken on a `Bill` paid on `date`, which is
+    /// none unless a purchase discount `Account` is set.
+    fn get_discount_for(&self, bill: &Bill, date: NaiveDate) -> i64 {
+        if self.get_purchase_discount_account().is_some() {
+            bill.get_available_discount(date)
+        } else {
+            0
+        }
+    }
+
+    /// Bring the state of a stored `Bill` up to date with the `Ledger`.
+    fn sync_bill(&self, bill: &Bill) -> Bill {
+        let get_status = |id: &str| {
+            self.get_transaction_by_id(id)
+                .map(|transaction| transaction.get_status())
+        };
+        let posted =
+            |id: &str| get_status(id).is_some_and(|status| status != TransactionStatus::Voided);
+
+        let mut bill = Bill::clone(bill);
+        bill.voided = get_status(&bill.id) == Some(TransactionStatus::Voided);
+        bill.posted = posted(&bill.id);
+        bill.payments
+            .retain(|payment| posted(&payment.transaction_id));
+
+        bill
+    }
+
+    fn get_bill_or_error(&self, id: &str) -> Result<Bill, Error> {
+        self.get_bill(id)
+            .ok_or_else(|| Error::UnknownBill(String::from(id)))
+    }
+
+    fn get_bill_index(&self, id: &str) -> Result<usize, Error> {
+        self.bills()
+            .iter()
+            .position(|bill| bill.id == id)
+            .ok_or_else(|| Error::UnknownBill(String::from(id)))
+    }
+}
+
+/// Describe the `Transaction` posting a `Bill`.
+fn describe(bill: &Bill) -> String {
+    if bill.reference.is_empty() {
+        format!("Bill {}", bill.id)
+    } else {
+        format!("Bill {} ({})", bill.id, bill.reference)
+    }
+}
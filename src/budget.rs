@@ -0,0 +1,353 @@
+//! Budgets of expected amounts per `Account` and period, and comparisons with actual balances.
+
+use chrono::{Datelike, NaiveDate};
+use json::JsonValue;
+
+use balance::BalanceOptions;
+use error::Error;
+use fiscal::get_next_month_start;
+use ledger::Ledger;
+use transaction::parse_date;
+
+/// An enumeration of the lengths of the periods a `Budget` is divided into.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum BudgetPeriod {
+    /// Each period is a calendar month.
+    #[default]
+    Monthly,
+
+    /// Each period is three months long.
+    Quarterly,
+
+    /// Each period is twelve months long.
+    Yearly,
+}
+
+impl BudgetPeriod {
+    /// Retrieve the number of months in each period.
+    pub fn get_months(self) -> u32 {
+        match self {
+            BudgetPeriod::Monthly => 1,
+            BudgetPeriod::Quarterly => 3,
+            BudgetPeriod::Yearly => 12,
+        }
+    }
+}
+
+/// Convert from a string slice to a `BudgetPeriod` value, ignoring case. Unknown values are
+/// treated as `Monthly`.
+impl<'a> From<&'a str> for BudgetPeriod {
+    fn from(value: &'a str) -> BudgetPeriod {
+        match value.to_lowercase().as_str() {
+            "quarterly" => BudgetPeriod::Quarterly,
+            "yearly" => BudgetPeriod::Yearly,
+            _ => BudgetPeriod::Monthly,
+        }
+    }
+}
+
+/// A set of amounts expected to be posted to `Account`s in each period.
+///
+/// Amounts are signed in the same way as balances, so an amount budgeted for an Income or
+/// Expense `Account` is normally positive. Quarters and years begin in the month set with
+/// `fiscal_year_start()`, which is January by default.
+///
+/// # Examples
+/// ```
+///# extern crate chrono;
+///# extern crate cratchit;
+///# use chrono::NaiveDate;
+///# use cratchit::{Budget, BudgetPeriod};
+/// let mut budget = Budget::new("FY2019", "Operating budget", BudgetPeriod::Quarterly)
+///     .fiscal_year_start(7);
+/// budget.set_amount("05-01", NaiveDate::from_ymd_opt(2018, 8, 15).unwrap(), 90000);
+///
+/// assert_eq!(budget.get_amount("05-01", NaiveDate::from_ymd_opt(2018, 7, 1).unwrap()), 90000);
+/// assert_eq!(budget.get_amount("05-01", NaiveDate::from_ymd_opt(2018, 10, 1).unwrap()), 0);
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct Budget {
+    id: String,
+    name: String,
+    period: BudgetPeriod,
+    start_month: u32,
+    amounts: Vec<(String, NaiveDate, i64)>,
+}
+
+impl Budget {
+    /// Create a new `Budget` with no amounts.
+    pub fn new(id: &str, name: &str, period: BudgetPeriod) -> Budget {
+        Budget {
+            id: String::from(id),
+            name: String::from(name),
+            period,
+            start_month: 1,
+            amounts: vec![],
+        }
+    }
+
+    /// Set the month (1 to 12) in which quarters and years of this `Budget` are counted from.
+    pub fn fiscal_year_start(mut self, month: u32) -> Budget {
+        self.start_month = month.clamp(1, 12);
+        self
+    }
+
+    /// Retrieve the id of this `Budget`, as a `String`.
+    pub fn get_id(&self) -> String {
+        String::clone(&self.id)
+    }
+
+    /// Retrieve the name of this `Budget`, as a `String`.
+    pub fn get_name(&self) -> String {
+        String::clone(&self.name)
+    }
+
+    /// Retrieve the `BudgetPeriod` of this `Budget`.
+    pub fn get_period(&self) -> BudgetPeriod {
+        self.period
+    }
+
+    /// Set the amount budgeted for an `Account` in the period containing `date`, replacing any
+    /// amount already set for that period. An amount of 0 removes it.
+    pub fn set_amount(&mut self, account_id: &str, date: NaiveDate, amount: i64) {
+        let start = self.get_period_start(date);
+        self.amounts
+            .retain(|&(ref id, period, _)| id != account_id || period != start);
+        if amount != 0 {
+            self.amounts.push((String::from(account_id), start, amount));
+            self.amounts.sort_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
+        }
+    }
+
+    /// Retrieve the amount budgeted for an `Account` in the period containing `date`. Amounts
+    /// budgeted for its sub-accounts are not included.
+    pub fn get_amount(&self, account_id: &str, date: NaiveDate) -> i64 {
+        let start = self.get_period_start(date);
+        self.amounts
+            .iter()
+            .find(|&&(ref id, period, _)| id == account_id && period == start)
+            .map_or(0, |&(_, _, amount)| amount)
+    }
+
+    /// Retrieve the ids of the `Account`s with an amount budgeted in any period, in order.
+    pub fn get_account_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.amounts.iter().map(|entry| entry.0.clone()).collect();
+        ids.dedup();
+        ids
+    }
+
+    /// Compute the amount budgeted for an `Account` between two dates, inclusive. The amount of
+    /// a period only partly within the dates is pro-rated by the number of days included.
+    pub fn get_amount_between(&self, account_id: &str, start: NaiveDate, end: NaiveDate) -> i64 {
+        let mut total = 0.0;
+        for &(ref id, period_start, amount) in &self.amounts {
+            if id != account_id {
+                continue;
+            }
+
+            let period_end = self.get_period_end(period_start);
+            let from = period_start.max(start);
+            let to = period_end.min(end);
+            if from > to {
+                continue;
+            }
+
+            let days = (to - from).num_days() + 1;
+            let period_days = (period_end - period_start).num_days() + 1;
+            total += amount as f64 * days as f64 / period_days as f64;
+        }
+
+        total.round() as i64
+    }
+
+    /// Convert this `Budget` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["id"] = self.id.as_str().into();
+        value["name"] = self.name.as_str().into();
+        value["period"] = format!("{:?}", self.period).into();
+        value["start_month"] = self.start_month.into();
+        value["amounts"] = JsonValue::Array(
+            self.amounts
+                .iter()
+                .map(|&(ref account_id, start, amount)| {
+                    let mut entry = JsonValue::new_object();
+                    entry["account_id"] = account_id.as_str().into();
+                    entry["start"] = start.format("%Y-%m-%d").to_string().into();
+                    entry["amount"] = amount.into();
+                    entry
+                })
+                .collect(),
+        );
+
+        value
+    }
+
+    /// Find the first day of the period containing `date`.
+    fn get_period_start(&self, date: NaiveDate) -> NaiveDate {
+        let months = self.period.get_months();
+        let offset = (date.month0() + 12 - (self.start_month - 1)) % 12 % months;
+        let total = date.year() * 12 + date.month0() as i32 - offset as i32;
+        NaiveDate::from_ymd_opt(total.div_euclid(12), total.rem_euclid(12) as u32 + 1, 1).unwrap()
+    }
+
+    /// Find the last day of the period beginning on `start`.
+    fn get_period_end(&self, start: NaiveDate) -> NaiveDate {
+        let mut next = start;
+        for _ in 0..self.period.get_months() {
+            next = get_next_month_start(next);
+        }
+
+        next.pred_opt().unwrap()
+    }
+}
+
+/// Create a `Budget` object from its JSON form, as produced by `Budget::to_json()`.
+impl<'a> From<&'a JsonValue> for Budget {
+    fn from(value: &'a JsonValue) -> Budget {
+        let mut budget = Budget::new(
+            value["id"].as_str().unwrap_or(""),
+            value["name"].as_str().unwrap_or(""),
+            BudgetPeriod::from(value["period"].as_str().unwrap_or("")),
+        )
+        .fiscal_year_start(value["start_month"].as_u32().unwrap_or(1));
+        for entry in value["amounts"].members() {
+            if let Some(start) = parse_date(&entry["start"]) {
+                budget.set_amount(
+                    entry["account_id"].as_str().unwrap_or(""),
+                    start,
+                    entry["amount"].as_i64().unwrap_or(0),
+                );
+            }
+        }
+
+        budget
+    }
+}
+
+/// A line of a budget-versus-actual report for a single `Account`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BudgetVariance {
+    account_id: String,
+    budget: i64,
+    actual: i64,
+}
+
+impl BudgetVariance {
+    /// Retrieve the id of the `Account` this line reports on, as a `String`.
+    pub fn get_account_id(&self) -> String {
+        String::clone(&self.account_id)
+    }
+
+    /// Retrieve the amount budgeted for the `Account` and its sub-accounts.
+    pub fn get_budget(&self) -> i64 {
+        self.budget
+    }
+
+    /// Retrieve the net change in the balance of the `Account` and its sub-accounts.
+    pub fn get_actual(&self) -> i64 {
+        self.actual
+    }
+
+    /// Retrieve the difference between the actual and budgeted amounts. A positive difference
+    /// means more was posted than budgeted.
+    pub fn get_difference(&self) -> i64 {
+        self.actual - self.budget
+    }
+
+    /// Retrieve the difference as a percentage of the budgeted amount, or `None` if nothing was
+    /// budgeted.
+    pub fn get_percent(&self) -> Option<f64> {
+        if self.budget == 0 {
+            None
+        } else {
+            Some(self.get_difference() as f64 * 100.0 / self.budget as f64)
+        }
+    }
+}
+
+impl Ledger {
+    /// Compare a `Budget` with the actual change in the balances of the `Account`s it covers
+    /// between two dates, inclusive.
+    ///
+    /// There is a line for each `Account` with an amount budgeted, and for each of their
+    /// ancestors in the `AccountsChart`. The budget and actual amounts of each line include
+    /// those of its sub-accounts, so placeholder accounts report the roll-up of their children.
+    /// Year-end closing entries are not counted as activity.
+    ///
+    /// # Returns
+    /// * The lines of the report, ordered by `Account` id, or an `Error` if the `Budget` has an
+    ///   amount for an `Account` that does not exist.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, Budget, BudgetPeriod, Currency,
+    ///#               Ledger, Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("05", "Supplies", "Supplies",
+    ///#                                          AccountType::Expense, Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// let mut purchase = Transaction::new("0001", NaiveDate::from_ymd_opt(2019, 1, 10).unwrap(),
+    ///                                     "Art supplies");
+    /// purchase.add_split(Split::new("05", 60000));
+    /// purchase.add_split(Split::new("01", -60000));
+    /// ledger.add_transaction(purchase).unwrap();
+    ///
+    /// let mut budget = Budget::new("2019", "Program budget", BudgetPeriod::Monthly);
+    /// budget.set_amount("05", NaiveDate::from_ymd_opt(2019, 1, 1).unwrap(), 50000);
+    ///
+    /// let report = ledger.get_budget_variance(&budget,
+    ///                                         NaiveDate::from_ymd_opt(2019, 1, 1).unwrap(),
+    ///                                         NaiveDate::from_ymd_opt(2019, 1, 31).unwrap())
+    ///     .unwrap();
+    /// assert_eq!(report[0].get_difference(), 10000);
+    /// assert_eq!(report[0].get_percent(), Some(20.0));
+    /// ```
+    pub fn get_budget_variance(
+        &self,
+        budget: &Budget,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<BudgetVariance>, Error> {
+        let mut account_ids: Vec<String> = vec![];
+        for account_id in budget.get_account_ids() {
+            if self.get_chart().get_account_by_id(&account_id).is_none() {
+                return Err(Error::UnknownAccount(account_id));
+            }
+
+            for ancestor_id in self.get_chart().get_ancestor_ids(&account_id) {
+                if !account_ids.contains(&ancestor_id) {
+                    account_ids.push(ancestor_id);
+                }
+            }
+        }
+        account_ids.sort();
+
+        let options = BalanceOptions::new()
+            .since(start)
+            .as_of(end)
+            .include_sub_accounts(true)
+            .exclude_closing_entries(true);
+        let mut report = vec![];
+        for account_id in account_ids {
+            let budgeted = self
+                .get_chart()
+                .get_sub_tree_ids(&account_id)
+                .iter()
+                .map(|id| budget.get_amount_between(id, start, end))
+                .sum();
+            report.push(BudgetVariance {
+                actual: self.get_balance(&account_id, &options)?,
+                account_id,
+                budget: budgeted,
+            });
+        }
+
+        Ok(report)
+    }
+}
@@ -0,0 +1,203 @@
+//! Year-end closing of Income and Expense accounts into retained earnings.
+
+use balance::BalanceOptions;
+use error::Error;
+use exchange::is_foreign_currency;
+use ledger::Ledger;
+use transaction::{DateBasis, EntrySource, Split, Transaction};
+use {AccountType, Currency};
+
+impl Ledger {
+    /// Generate, without posting, the closing entry for a fiscal year.
+    ///
+    /// The closing entry zeroes the balance of every Income and Expense `Account` for the
+    /// fiscal year, and posts the net income (or loss) to a retained earnings account. Balances
+    /// are computed using the effective date of each `Transaction`, so that adjustments posted
+    /// after year end are included.
+    ///
+    /// The entry is in the `Currency` of the retained earnings account. An `Account` in another
+    /// currency is closed at its carrying value, so that the amount removed from it is the value
+    /// at which its postings were recorded.
+    ///
+    /// # Arguments
+    /// * `year`: The fiscal year to close. Its periods must have been added with
+    ///   `add_fiscal_year()`.
+    /// * `retained_earnings_id`: The id of the Equity `Account` to close into.
+    ///
+    /// # Returns
+    /// * The closing `Transaction`, dated on the last day of the fiscal year, or an `Error` if the
+    ///   fiscal year is unknown or the retained earnings account is not a non-placeholder Equity
+    ///   account.
+    pub fn preview_closing_entry(
+        &self,
+        year: i32,
+        retained_earnings_id: &str,
+    ) -> Result<Transaction, Error> {
+        let currency = match self.get_chart().get_account_by_id(retained_earnings_id) {
+            Some(ref account) if account.is_placeholder() => {
+                return Err(Error::PlaceholderAccount(String::from(
+                    retained_earnings_id,
+                )));
+            }
+            Some(ref account) if account.get_account_type() != AccountType::Equity => {
+                return Err(Error::WrongAccountType(String::from(retained_earnings_id)));
+            }
+            Some(account) => account.get_currency(),
+            None => return Err(Error::UnknownAccount(String::from(retained_earnings_id))),
+        };
+
+        let periods: Vec<_> = self
+            .get_periods()
+            .into_iter()
+            .filter(|period| period.get_year() == year)
+            .collect();
+        let (start, end) = match (periods.first(), periods.last()) {
+            (Some(first), Some(last)) => (first.get_start(), last.get_end()),
+            _ => return Err(Error::UnknownFiscalYear(year)),
+        };
+
+        let options = BalanceOptions::new()
+            .since(start)
+            .as_of(end)
+            .date_basis(DateBasis::Effective);
+
+        let mut account_ids = self.get_chart().get_account_ids();
+        account_ids.sort();
+
+        let mut closing = Transaction::new(
+            &get_closing_entry_id(year),
+            end,
+            &format!("Year-end closing entry for FY{}", year),
+        );
+        closing.set_source(EntrySource::YearEndClose);
+        if currency != Currency::Unknown {
+            closing.set_currency(currency);
+        }
+
+        let mut net_income = 0;
+        for account_id in account_ids {
+            let account = self.get_chart().get_account_by_id(&account_id).unwrap();
+            let account_type = account.get_account_type();
+            if account.is_placeholder()
+                || (account_type != AccountType::Income && account_type != AccountType::Expense)
+            {
+                continue;
+            }
+
+            let balance = self.get_balance(&account_id, &options)?;
+            let foreign = is_foreign_currency(account.get_currency(), closing.get_currency());
+            let carrying_value = if foreign {
+                self.get_carrying_value(&account_id, &options)?
+            } else {
+                balance
+            };
+            if balance == 0 && carrying_value == 0 {
+                continue;
+            }
+
+            // Income accounts have a credit balance, so closing them requires a debit, and
+            // Expense accounts the reverse.
+            let sign = match account_type {
+                AccountType::Income => 1,
+                _ => -1,
+            };
+            let (amount, value) = (sign * balance, sign * carrying_value);
+            net_income += value;
+
+            let mut split = if foreign && amount != 0 {
+                Split::new_foreign(&account_id, amount, value as f64 / amount as f64)
+            } else {
+                Split::new(&account_id, amount)
+            };
+            if foreign {
+                split.set_value(value);
+            }
+            split.set_memo(&format!("Close {}", account.get_name()));
+            closing.add_split(split);
+        }
+
+        if net_income != 0 {
+            let mut split = Split::new(retained_earnings_id, -net_income);
+            split.set_memo(&format!("Net income for FY{}", year));
+            closing.add_split(split);
+        }
+
+        Ok(closing)
+    }
+
+    /// Generate and post the closing entry for a fiscal year.
+    ///
+    /// See `preview_closing_entry()` for how the entry is computed. The entry is marked with
+    /// `EntrySource::YearEndClose`.
+    ///
+    /// # Returns
+    /// * The id of the closing `Transaction`, `Error::NothingToClose` if no Income or Expense
+    ///   `Account` has a balance for the year, or an `Error` if the entry could not be
+    ///   generated, the year has already been closed, or the last period of the year is closed.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Currency,
+    ///#               FiscalCalendar, Ledger, Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("03", "Retained Earnings", "Retained Earnings",
+    ///#                                          AccountType::Equity, Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("04", "Dues", "Dues", AccountType::Income,
+    ///#                                          Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// ledger.add_fiscal_year(&FiscalCalendar::Monthly { start_month: 1 }, 2018).unwrap();
+    ///
+    /// let mut dues = Transaction::new("0001", NaiveDate::from_ymd_opt(2018, 3, 1).unwrap(),
+    ///                                 "Membership dues");
+    /// dues.add_split(Split::new("01", 5000));
+    /// dues.add_split(Split::new("04", -5000));
+    /// ledger.add_transaction(dues).unwrap();
+    ///
+    /// ledger.post_closing_entry(2018, "03").unwrap();
+    /// assert_eq!(ledger.get_balance("04", &BalanceOptions::new()).unwrap(), 0);
+    /// assert_eq!(ledger.get_balance("03", &BalanceOptions::new()).unwrap(), 5000);
+    /// ```
+    pub fn post_closing_entry(
+        &mut self,
+        year: i32,
+        retained_earnings_id: &str,
+    ) -> Result<String, Error> {
+        let closing = self.preview_closing_entry(year, retained_earnings_id)?;
+        let id = closing.get_id();
+        if self.get_transaction_by_id(&id).is_some() {
+            return Err(Error::DuplicateTransaction(id));
+        }
+        if closing.splits().is_empty() {
+            return Err(Error::NothingToClose(year));
+        }
+
+        self.add_transaction(closing)?;
+
+        Ok(id)
+    }
+
+    /// Remove the closing entry of a fiscal year, e.g. after the year has been reopened to post
+    /// further adjustments.
+    ///
+    /// # Returns
+    /// * The removed closing `Transaction`, or an `Error` if the year has not been closed or the
+    ///   last period of the year is still closed.
+    pub fn undo_closing_entry(&mut self, year: i32) -> Result<Transaction, Error> {
+        let id = get_closing_entry_id(year);
+        match self.get_transaction_by_id(&id) {
+            Some(ref closing) if closing.get_source() == EntrySource::YearEndClose => {
+                self.remove_transaction(&id)
+            }
+            _ => Err(Error::UnknownTransaction(id)),
+        }
+    }
+}
+
+fn get_closing_entry_id(year: i32) -> String {
+    format!("CLOSE-FY{}", year)
+}
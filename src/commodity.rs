@@ -0,0 +1,162 @@
+//! Commodities other than currencies, such as securities, that `Account`s can hold in units.
+
+use json::JsonValue;
+
+use error::Error;
+use ledger::Ledger;
+
+/// An enumeration of the kinds of commodity an `Account` can hold.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum CommodityKind {
+    /// Shares of a company, identified by a ticker symbol.
+    Stock,
+
+    /// Units of a mutual fund or exchange-traded fund.
+    MutualFund,
+
+    /// A cryptocurrency, such as bitcoin.
+    Crypto,
+
+    /// Points or miles earned in a loyalty program.
+    LoyaltyPoints,
+
+    /// Any other kind of commodity.
+    #[default]
+    Other,
+}
+
+/// Convert from a string slice to a `CommodityKind` value, ignoring case. Unknown values are
+/// treated as `Other`.
+impl<'a> From<&'a str> for CommodityKind {
+    fn from(value: &'a str) -> CommodityKind {
+        match value.to_lowercase().as_str() {
+            "stock" => CommodityKind::Stock,
+            "mutualfund" => CommodityKind::MutualFund,
+            "crypto" => CommodityKind::Crypto,
+            "loyaltypoints" => CommodityKind::LoyaltyPoints,
+            _ => CommodityKind::Other,
+        }
+    }
+}
+
+/// A commodity that an `Account` can hold in units, in the same way that other `Account`s hold
+/// amounts of a `Currency`.
+///
+/// Amounts posted to an `Account` holding a commodity are numbers of units, expressed in the
+/// smallest fraction of a unit that can be held (e.g. thousandths of a share).
+#[derive(Clone, PartialEq, Debug)]
+pub struct Commodity {
+    symbol: String,
+    name: String,
+    kind: CommodityKind,
+    fraction: u32,
+}
+
+impl Commodity {
+    /// Create a new `Commodity` object.
+    ///
+    /// # Arguments
+    /// * `symbol`: The symbol identifying the commodity, e.g. a ticker such as `AAPL`. It is also
+    ///   the code used for the commodity in the `PriceDatabase`.
+    /// * `name`: The human-readable name of the commodity.
+    /// * `kind`: The `CommodityKind` of the commodity.
+    /// * `fraction`: The number of units posted for one whole unit of the commodity, e.g. 1000
+    ///   if a thousandth of a share can be held. A fraction of 0 is treated as 1.
+    pub fn new(symbol: &str, name: &str, kind: CommodityKind, fraction: u32) -> Commodity {
+        Commodity {
+            symbol: String::from(symbol),
+            name: String::from(name),
+            kind,
+            fraction: fraction.max(1),
+        }
+    }
+
+    /// Retrieve the symbol identifying this `Commodity`, as a `String`.
+    pub fn get_symbol(&self) -> String {
+        String::clone(&self.symbol)
+    }
+
+    /// Retrieve the name of this `Commodity`, as a `String`.
+    pub fn get_name(&self) -> String {
+        String::clone(&self.name)
+    }
+
+    /// Retrieve the `CommodityKind` of this `Commodity`.
+    pub fn get_kind(&self) -> CommodityKind {
+        self.kind
+    }
+
+    /// Retrieve the number of units posted for one whole unit of this `Commodity`.
+    pub fn get_fraction(&self) -> u32 {
+        self.fraction
+    }
+
+    /// Convert this `Commodity` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["symbol"] = self.symbol.as_str().into();
+        value["name"] = self.name.as_str().into();
+        value["kind"] = format!("{:?}", self.kind).into();
+        value["fraction"] = self.fraction.into();
+
+        value
+    }
+}
+
+/// Create a `Commodity` object from its JSON form, as produced by `Commodity::to_json()`.
+impl<'a> From<&'a JsonValue> for Commodity {
+    fn from(value: &'a JsonValue) -> Commodity {
+        Commodity::new(
+            value["symbol"].as_str().unwrap_or(""),
+            value["name"].as_str().unwrap_or(""),
+            CommodityKind::from(value["kind"].as_str().unwrap_or("")),
+            value["fraction"].as_u32().unwrap_or(1),
+        )
+    }
+}
+
+impl Ledger {
+    /// Add a `Commodity` to the commodities that `Account`s of this `Ledger` can hold.
+    ///
+    /// An `Account` holds a commodity once it is set with `Account::set_commodity()`, and
+    /// `Transaction`s can only post to it once the commodity has been added.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Commodity` was added, or `Error::DuplicateCommodity` if a commodity
+    ///   with the same symbol already exists.
+    ///
+    /// # Examples
+    /// ```
+    ///# use cratchit::{AccountsChart, Commodity, CommodityKind, Ledger};
+    /// let mut ledger = Ledger::new(AccountsChart::new());
+    /// let apple = Commodity::new("AAPL", "Apple Inc.", CommodityKind::Stock, 1);
+    /// ledger.add_commodity(apple).unwrap();
+    ///
+    /// assert_eq!(ledger.get_commodity("AAPL").unwrap().get_kind(), CommodityKind::Stock);
+    /// assert!(ledger
+    ///     .add_commodity(Commodity::new("AAPL", "Apple", CommodityKind::Stock, 1))
+    ///     .is_err());
+    /// ```
+    pub fn add_commodity(&mut self, commodity: Commodity) -> Result<(), Error> {
+        if self.get_commodity(&commodity.symbol).is_some() {
+            return Err(Error::DuplicateCommodity(commodity.symbol));
+        }
+
+        self.commodities_mut().push(commodity);
+
+        Ok(())
+    }
+
+    /// Retrieve the `Commodity` with the given symbol, if it has been added to this `Ledger`.
+    pub fn get_commodity(&self, symbol: &str) -> Option<Commodity> {
+        self.commodities()
+            .iter()
+            .find(|commodity| commodity.symbol == symbol)
+            .cloned()
+    }
+
+    /// Retrieve every `Commodity` added to this `Ledger`, in the order they were added.
+    pub fn get_commodities(&self) -> Vec<Commodity> {
+        self.commodities().to_vec()
+    }
+}
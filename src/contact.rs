@@ -0,0 +1,448 @@
+//! Customers, vendors and employees that `Split`s can be tagged with.
+
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
+use json::JsonValue;
+
+use balance::BalanceOptions;
+use error::Error;
+use ledger::Ledger;
+use {AccountType, NormalBalance};
+
+/// An enumeration of the kinds of party a `Contact` can be.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum ContactKind {
+    /// A party that buys from the business, and whose unpaid invoices are receivables.
+    #[default]
+    Customer,
+
+    /// A party that sells to the business, and whose unpaid bills are payables.
+    Vendor,
+
+    /// A person employed by the business.
+    Employee,
+}
+
+/// Convert from a string slice to a `ContactKind` value, ignoring case. Unknown values are
+/// treated as `Customer`.
+impl<'a> From<&'a str> for ContactKind {
+    fn from(value: &'a str) -> ContactKind {
+        match value.to_lowercase().as_str() {
+            "vendor" => ContactKind::Vendor,
+            "employee" => ContactKind::Employee,
+            _ => ContactKind::Customer,
+        }
+    }
+}
+
+/// The terms on which a `Contact` pays or is paid, such as "2/10 net 30": due in 30 days, with
+/// a 2% discount if paid within 10 days.
+///
+/// # Examples
+/// ```
+///# extern crate chrono;
+///# extern crate cratchit;
+///# use chrono::NaiveDate;
+///# use cratchit::PaymentTerms;
+/// let terms = PaymentTerms::net(30).discount(2.0, 10);
+/// let issued = NaiveDate::from_ymd_opt(2019, 3, 1).unwrap();
+///
+/// assert_eq!(terms.get_due_date(issued), NaiveDate::from_ymd_opt(2019, 3, 31).unwrap());
+/// assert_eq!(terms.get_discount_date(issued), NaiveDate::from_ymd_opt(2019, 3, 11));
+/// assert_eq!(terms.get_discount(125000), 2500);
+/// ```
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct PaymentTerms {
+    net_days: u32,
+    discount_percent: f64,
+    discount_days: u32,
+}
+
+impl PaymentTerms {
+    /// Create new `PaymentTerms` under which payment is due `days` after the document date, with
+    /// no discount for paying early. The default terms are due on receipt.
+    pub fn net(days: u32) -> PaymentTerms {
+        PaymentTerms {
+            net_days: days,
+            discount_percent: 0.0,
+            discount_days: 0,
+        }
+    }
+
+    /// Offer a discount of `percent` of the amount for paying within `days` of the document
+    /// date.
+    pub fn discount(mut self, percent: f64, days: u32) -> PaymentTerms {
+        self.discount_percent = percent;
+        self.discount_days = days;
+        self
+    }
+
+    /// Retrieve the number of days after the document date that payment is due.
+    pub fn get_net_days(&self) -> u32 {
+        self.net_days
+    }
+
+    /// Retrieve the percentage discount offered for paying early.
+    pub fn get_discount_percent(&self) -> f64 {
+        self.discount_percent
+    }
+
+    /// Retrieve the number of days after the document date within which the discount applies.
+    pub fn get_discount_days(&self) -> u32 {
+        self.discount_days
+    }
+
+    /// Compute the date payment is due for a document dated `date`.
+    pub fn get_due_date(&self, date: NaiveDate) -> NaiveDate {
+        date + Duration::days(i64::from(self.net_days))
+    }
+
+    /// Compute the last date on which the discount applies for a document dated `date`, or
+    /// `None` if no discount is offered.
+    pub fn get_discount_date(&self, date: NaiveDate) -> Option<NaiveDate> {
+        if self.discount_percent > 0.0 {
+            Some(date + Duration::days(i64::from(self.discount_days)))
+        } else {
+            None
+        }
+    }
+
+    /// Compute the discount on `amount` for paying early, rounded to the nearest unit.
+    pub fn get_discount(&self, amount: i64) -> i64 {
+        (amount as f64 * self.discount_percent / 100.0).round() as i64
+    }
+
+    /// Convert these `PaymentTerms` to their JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["net_days"] = self.net_days.into();
+        value["discount_percent"] = self.discount_percent.into();
+        value["discount_days"] = self.discount_days.into();
+
+        value
+    }
+}
+
+/// Create `PaymentTerms` from their JSON form, as produced by `PaymentTerms::to_json()`.
+impl<'a> From<&'a JsonValue> for PaymentTerms {
+    fn from(value: &'a JsonValue) -> PaymentTerms {
+        PaymentTerms::net(value["net_days"].as_u32().unwrap_or(0)).discount(
+            value["discount_percent"].as_f64().unwrap_or(0.0),
+            value["discount_days"].as_u32().unwrap_or(0),
+        )
+    }
+}
+
+/// A customer, vendor or employee that the business deals with.
+///
+/// `Split`s can be tagged with a `Contact` using `Split::set_contact()`, so that the balances
+/// of receivable and payable `Account`s can be reported per party without an `Account` for each.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Contact {
+    id: String,
+    name: String,
+    kind: ContactKind,
+    address: String,
+    tax_id: String,
+    payment_terms: PaymentTerms,
+    default_account: Option<String>,
+    control_account: Option<String>,
+}
+
+impl Contact {
+    /// Create a new `Contact` object, with no address or tax id, and payment due on receipt.
+    ///
+    /// # Arguments
+    /// * `id`: The id identifying the `Contact` in the `Ledger`.
+    /// * `name`: The name of the person or organization.
+    /// * `kind`: The `ContactKind` of the `Contact`.
+    pub fn new(id: &str, name: &str, kind: ContactKind) -> Contact {
+        Contact {
+            id: String::from(id),
+            name: String::from(name),
+            kind,
+            address: String::new(),
+            tax_id: String::new(),
+            payment_terms: PaymentTerms::default(),
+            default_account: None,
+            control_account: None,
+        }
+    }
+
+    /// Retrieve the id of this `Contact`, as a `String`.
+    pub fn get_id(&self) -> String {
+        String::clone(&self.id)
+    }
+
+    /// Retrieve the name of this `Contact`, as a `String`.
+    pub fn get_name(&self) -> String {
+        String::clone(&self.name)
+    }
+
+    /// Set the name of this `Contact`.
+    pub fn set_name(&mut self, name: &str) {
+        self.name = String::from(name);
+    }
+
+    /// Retrieve the `ContactKind` of this `Contact`.
+    pub fn get_kind(&self) -> ContactKind {
+        self.kind
+    }
+
+    /// Retrieve the postal address of this `Contact`, as a `String`.
+    pub fn get_address(&self) -> String {
+        String::clone(&self.address)
+    }
+
+    /// Set the postal address of this `Contact`. Lines may be separated with newlines.
+    pub fn set_address(&mut self, address: &str) {
+        self.address = String::from(address);
+    }
+
+    /// Retrieve the tax identification number of this `Contact`, as a `String`.
+    pub fn get_tax_id(&self) -> String {
+        String::clone(&self.tax_id)
+    }
+
+    /// Set the tax identification number of this `Contact`.
+    pub fn set_tax_id(&mut self, tax_id: &str) {
+        self.tax_id = String::from(tax_id);
+    }
+
+    /// Retrieve the `PaymentTerms` agreed with this `Contact`.
+    pub fn get_payment_terms(&self) -> PaymentTerms {
+        self.payment_terms
+    }
+
+    /// Set the `PaymentTerms` agreed with this `Contact`.
+    pub fn set_payment_terms(&mut self, terms: PaymentTerms) {
+        self.payment_terms = terms;
+    }
+
+    /// Retrieve the id of the `Account` that sales to or purchases from this `Contact` are
+    /// normally posted to, if one is set.
+    pub fn get_default_account(&self) -> Option<String> {
+        self.default_account.clone()
+    }
+
+    /// Set the id of the `Account` that sales to or purchases from this `Contact` are normally
+    /// posted to, such as an Income or Expense `Account`.
+    pub fn set_default_account(&mut self, account_id: &str) {
+        self.default_account = Some(String::from(account_id));
+    }
+
+    /// Retrieve the id of the receivable or payable `Account` that amounts owed by or to this
+    /// `Contact` are posted to, if one is set.
+    pub fn get_control_account(&self) -> Option<String> {
+        self.control_account.clone()
+    }
+
+    /// Set the id of the receivable or payable `Account` that amounts owed by or to this
+    /// `Contact` are posted to.
+    pub fn set_control_account(&mut self, account_id: &str) {
+        self.control_account = Some(String::from(account_id));
+    }
+
+    /// Convert this `Contact` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["id"] = self.id.as_str().into();
+        value["name"] = self.name.as_str().into();
+        value["kind"] = format!("{:?}", self.kind).into();
+        value["address"] = self.address.as_str().into();
+        value["tax_id"] = self.tax_id.as_str().into();
+        value["payment_terms"] = self.payment_terms.to_json();
+        value["default_account"] = self.default_account.clone().into();
+        value["control_account"] = self.control_account.clone().into();
+
+        value
+    }
+}
+
+/// Create a `Contact` object from its JSON form, as produced by `Contact::to_json()`.
+impl<'a> From<&'a JsonValue> for Contact {
+    fn from(value: &'a JsonValue) -> Contact {
+        let mut contact = Contact::new(
+            value["id"].as_str().unwrap_or(""),
+            value["name"].as_str().unwrap_or(""),
+            ContactKind::from(value["kind"].as_str().unwrap_or("")),
+        );
+        contact.address = String::from(value["address"].as_str().unwrap_or(""));
+        contact.tax_id = String::from(value["tax_id"].as_str().unwrap_or(""));
+        contact.payment_terms = PaymentTerms::from(&value["payment_terms"]);
+        contact.default_account = value["default_account"].as_str().map(String::from);
+        contact.control_account = value["control_account"].as_str().map(String::from);
+
+        contact
+    }
+}
+
+impl Ledger {
+    /// Add a `Contact` to the parties that `Split`s of this `Ledger` can be tagged with.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Contact` was added, `Error::DuplicateContact` if a `Contact` with the
+    ///   same id already exists, `Error::UnknownAccount` if its default or control `Account`
+    ///   does not exist, or `Error::WrongAccountType` if it is a vendor whose control `Account`
+    ///   is not a Liability.
+    ///
+    /// # Examples
+    /// ```
+    ///# use cratchit::{AccountsChart, Contact, ContactKind, Ledger};
+    /// let mut ledger = Ledger::new(AccountsChart::new());
+    /// let mut school = Contact::new("LNHS", "Lakeville North High School", ContactKind::Customer);
+    /// school.set_address("19600 Ipava Ave\nLakeville, MN 55044");
+    /// ledger.add_contact(school).unwrap();
+    ///
+    /// assert_eq!(ledger.get_contact("LNHS").unwrap().get_kind(), ContactKind::Customer);
+    /// assert!(ledger
+    ///     .add_contact(Contact::new("LNHS", "Lakeville North", ContactKind::Customer))
+    ///     .is_err());
+    /// ```
+    pub fn add_contact(&mut self, contact: Contact) -> Result<(), Error> {
+        if self.get_contact(&contact.id).is_some() {
+            return Err(Error::DuplicateContact(contact.id));
+        }
+        self.check_contact_accounts(&contact)?;
+
+        self.contacts_mut().push(contact);
+
+        Ok(())
+    }
+
+    /// Replace the details of the `Contact` with the same id as `contact`.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Contact` was replaced, `Error::UnknownContact` if it does not exist,
+    ///   `Error::UnknownAccount` if its default or control `Account` does not exist, or
+    ///   `Error::WrongAccountType` if it is a vendor whose control `Account` is not a Liability.
+    pub fn update_contact(&mut self, contact: Contact) -> Result<(), Error> {
+        self.check_contact_accounts(&contact)?;
+        match self
+            .contacts_mut()
+            .iter_mut()
+            .find(|existing| existing.id == contact.id)
+        {
+            Some(existing) => {
+                *existing = contact;
+                Ok(())
+            }
+            None => Err(Error::UnknownContact(contact.id)),
+        }
+    }
+
+    /// Retrieve the `Contact` with the given id, if it has been added to this `Ledger`.
+    pub fn get_contact(&self, id: &str) -> Option<Contact> {
+        self.contacts()
+            .iter()
+            .find(|contact| contact.id == id)
+            .cloned()
+    }
+
+    /// Retrieve every `Contact` added to this `Ledger`, in the order they were added.
+    pub fn get_contacts(&self) -> Vec<Contact> {
+        self.contacts().to_vec()
+    }
+
+    /// Compute the balance of an `Account` owed by or to each `Contact`, from the `Split`s tagged
+    /// with that `Contact`. `Split`s with no `Contact` are not included.
+    ///
+    /// # Returns
+    /// * A `HashMap` from the id of each `Contact` with a tagged `Split` to its balance, signed
+    ///   by the `Account`'s normal balance, or `Error::UnknownAccount` if the `Account` does not
+    ///   exist.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Contact, ContactKind,
+    ///#               Currency, Ledger, Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Receivable", "Receivable",
+    ///#                                          AccountType::Asset, Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("04", "Ice Time", "Ice Time",
+    ///#                                          AccountType::Income, Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// ledger.add_contact(Contact::new("LNHS", "Lakeville North", ContactKind::Customer)).unwrap();
+    ///
+    /// let mut receivable = Split::new("01", 25000);
+    /// receivable.set_contact("LNHS");
+    /// let mut transaction = Transaction::new("0001",
+    ///                                        NaiveDate::from_ymd_opt(2018, 11, 3).unwrap(),
+    ///                                        "Ice time");
+    /// transaction.add_split(receivable);
+    /// transaction.add_split(Split::new("04", -25000));
+    /// ledger.add_transaction(transaction).unwrap();
+    ///
+    /// let balances = ledger.get_contact_balances("01", &BalanceOptions::new()).unwrap();
+    /// assert_eq!(balances["LNHS"], 25000);
+    /// ```
+    pub fn get_contact_balances(
+        &self,
+        account_id: &str,
+        options: &BalanceOptions,
+    ) -> Result<HashMap<String, i64>, Error> {
+        let account = match self.get_chart().get_account_by_id(account_id) {
+            Some(account) => account,
+            None => return Err(Error::UnknownAccount(String::from(account_id))),
+        };
+        let account_ids = if options.includes_sub_accounts() {
+            self.get_chart().get_sub_tree_ids(account_id)
+        } else {
+            vec![account.get_id()]
+        };
+        let sign = match account.get_account_type().get_normal_balance() {
+            NormalBalance::Debit => 1,
+            NormalBalance::Credit => -1,
+        };
+
+        let mut balances = HashMap::new();
+        for transaction in self.transactions() {
+            if !options.includes(transaction) {
+                continue;
+            }
+
+            for split in transaction.splits() {
+                if let Some(contact_id) = split.get_contact() {
+                    if options.includes_split(split)
+                        && account_ids.contains(&split.get_account_id())
+                    {
+                        *balances.entry(contact_id).or_insert(0) += split.get_amount() * sign;
+                    }
+                }
+            }
+        }
+
+        Ok(balances)
+    }
+
+    /// Ensure that the default and control `Account`s of a `Contact` exist.
+    fn check_contact_accounts(&self, contact: &Contact) -> Result<(), Error> {
+        for account_id in contact
+            .default_account
+            .iter()
+            .chain(contact.control_account.iter())
+        {
+            if self.get_chart().get_account_by_id(account_id).is_none() {
+                return Err(Error::UnknownAccount(String::clone(account_id)));
+            }
+        }
+        if let (ContactKind::Vendor, Some(ref account_id)) =
+            (contact.kind, &contact.control_account)
+        {
+            if self
+                .get_chart()
+                .get_account_by_id(account_id)
+                .map(|account| account.get_account_type())
+                != Some(AccountType::Liability)
+            {
+                return Err(Error::WrongAccountType(String::clone(account_id)));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,453 @@
+//! Corporate actions that change the units or cost basis of a `Commodity` held.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use json::JsonValue;
+
+use error::Error;
+use exchange::get_share;
+use ledger::Ledger;
+use lot::Lot;
+use transaction::{parse_date, EntrySource, Split, Transaction};
+
+/// An enumeration of the kinds of `CorporateAction`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum CorporateActionKind {
+    /// A stock split, or a reverse split, in which each unit held becomes
+    /// `numerator / denominator` units; see `CorporateAction::new_split()`.
+    Split { numerator: i64, denominator: i64 },
+
+    /// A merger or ticker change, in which the units held are exchanged for units of the
+    /// `Commodity` held by another `Account`; see `CorporateAction::new_merger()`.
+    Merger {
+        account_id: String,
+        numerator: i64,
+        denominator: i64,
+    },
+
+    /// A return of capital paid into a cash `Account`, which reduces the cost basis of the units
+    /// held; see `CorporateAction::new_return_of_capital()`.
+    ReturnOfCapital { account_id: String, amount: i64 },
+}
+
+/// An event, such as a stock split, that changes the units or cost basis of every open `Lot`
+/// of a `Commodity` held by an `Account`.
+///
+/// A `CorporateAction` is posted to a `Ledger` with `Ledger::post_corporate_action()`, which
+/// records it and posts a `Transaction` with the same id adjusting the balances affected.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CorporateAction {
+    id: String,
+    date: NaiveDate,
+    account_id: String,
+    kind: CorporateActionKind,
+}
+
+impl CorporateAction {
+    /// Create a stock split, in which each unit held becomes `numerator / denominator` units.
+    ///
+    /// The number of units in each open `Lot` is scaled, rounded to the nearest unit, while its
+    /// total cost is unchanged. A 4-for-1 split has a numerator of 4 and a denominator of 1, and a
+    /// 1-for-10 reverse split a numerator of 1 and a denominator of 10.
+    pub fn new_split(
+        id: &str,
+        date: NaiveDate,
+        account_id: &str,
+        numerator: i64,
+        denominator: i64,
+    ) -> CorporateAction {
+        CorporateAction::new(
+            id,
+            date,
+            account_id,
+            CorporateActionKind::Split {
+                numerator,
+                denominator,
+            },
+        )
+    }
+
+    /// Create a merger or ticker change, in which every open `Lot` held by `account_id` moves to
+    /// `into_id`, the `Account` holding the new `Commodity`, with each unit becoming
+    /// `numerator / denominator` units of the new commodity. Each `Lot` keeps its id, date and
+    /// cost, so the holding period carries over. A ticker change has a ratio of 1 to 1.
+    pub fn new_merger(
+        id: &str,
+        date: NaiveDate,
+        account_id: &str,
+        into_id: &str,
+        numerator: i64,
+        denominator: i64,
+    ) -> CorporateAction {
+        CorporateAction::new(
+            id,
+            date,
+            account_id,
+            CorporateActionKind::Merger {
+                account_id: String::from(into_id),
+                numerator,
+                denominator,
+            },
+        )
+    }
+
+    /// Create a return of capital of `amount`, paid into the `Account` `cash_id`.
+    ///
+    /// The cost of each open `Lot` is reduced by its share of `amount`, in proportion to its
+    /// units, and no income is recorded. The share of a `Lot` cannot exceed its cost.
+    pub fn new_return_of_capital(
+        id: &str,
+        date: NaiveDate,
+        account_id: &str,
+        cash_id: &str,
+        amount: i64,
+    ) -> CorporateAction {
+        CorporateAction::new(
+            id,
+            date,
+            account_id,
+            CorporateActionKind::ReturnOfCapital {
+                account_id: String::from(cash_id),
+                amount,
+            },
+        )
+    }
+
+    fn new(
+        id: &str,
+        date: NaiveDate,
+        account_id: &str,
+        kind: CorporateActionKind,
+    ) -> CorporateAction {
+        CorporateAction {
+            id: String::from(id),
+            date,
+            account_id: String::from(account_id),
+            kind,
+        }
+    }
+
+    /// Retrieve the id of this `CorporateAction`, which is also the id of the `Transaction`
+    /// recording it, as a `String`.
+    pub fn get_id(&self) -> String {
+        String::clone(&self.id)
+    }
+
+    /// Retrieve the date this `CorporateAction` takes effect.
+    pub fn get_date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// Retrieve the id of the `Account` holding the `Commodity` affected, as a `String`.
+    pub fn get_account_id(&self) -> String {
+        String::clone(&self.account_id)
+    }
+
+    /// Retrieve the `CorporateActionKind` of this `CorporateAction`.
+    pub fn get_kind(&self) -> CorporateActionKind {
+        CorporateActionKind::clone(&self.kind)
+    }
+
+    /// Convert this `CorporateAction` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["id"] = self.id.as_str().into();
+        value["date"] = self.date.format("%Y-%m-%d").to_string().into();
+        value["account_id"] = self.account_id.as_str().into();
+        match self.kind {
+            CorporateActionKind::Split {
+                numerator,
+                denominator,
+            } => {
+                value["kind"] = "split".into();
+                value["numerator"] = numerator.into();
+                value["denominator"] = denominator.into();
+            }
+            CorporateActionKind::Merger {
+                ref account_id,
+                numerator,
+                denominator,
+            } => {
+                value["kind"] = "merger".into();
+                value["into"] = account_id.as_str().into();
+                value["numerator"] = numerator.into();
+                value["denominator"] = denominator.into();
+            }
+            CorporateActionKind::ReturnOfCapital {
+                ref account_id,
+                amount,
+            } => {
+                value["kind"] = "return_of_capital".into();
+                value["cash"] = account_id.as_str().into();
+                value["amount"] = amount.into();
+            }
+        }
+
+        value
+    }
+
+    /// Describe this `CorporateAction` for the `Transaction` recording it.
+    fn describe(&self) -> String {
+        match self.kind {
+            CorporateActionKind::Split {
+                numerator,
+                denominator,
+            } => format!("{}-for-{} split", numerator, denominator),
+            CorporateActionKind::Merger { ref account_id, .. } => {
+                format!("Merger into {}", account_id)
+            }
+            CorporateActionKind::ReturnOfCapital { .. } => String::from("Return of capital"),
+        }
+    }
+
+    /// Apply this `CorporateAction` to the open lots of every `Account` holding a `Commodity`.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the lots were adjusted, or `Error::InvalidCorporateAction` if the ratio is
+    ///   not positive, or the amount of a return of capital is not positive or exceeds the cost
+    ///   of a lot.
+    pub(crate) fn apply_to_lots(
+        &self,
+        holdings: &mut HashMap<String, Vec<Lot>>,
+    ) -> Result<(), Error> {
+        let invalid = Error::InvalidCorporateAction(String::clone(&self.id));
+        match self.kind {
+            CorporateActionKind::Split {
+                numerator,
+                denominator,
+            } => {
+                if numerator <= 0 || denominator <= 0 {
+                    return Err(invalid);
+                }
+
+                let lots = holdings.entry(String::clone(&self.account_id)).or_default();
+                for lot in lots.iter_mut() {
+                    lot.units = get_share(lot.units, numerator, denominator);
+                }
+                lots.retain(|lot| lot.units > 0);
+            }
+            CorporateActionKind::Merger {
+                ref account_id,
+                numerator,
+                denominator,
+            } => {
+                if numerator <= 0 || denominator <= 0 || *account_id == self.account_id {
+                    return Err(invalid);
+                }
+
+                let moved = holdings.remove(&self.account_id).unwrap_or_default();
+                let lots = holdings.entry(String::clone(account_id)).or_default();
+                for mut lot in moved {
+                    lot.units = get_share(lot.units, numerator, denominator);
+                    lot.account_id = String::clone(account_id);
+                    lots.push(lot);
+                }
+                lots.sort_by_key(|lot| lot.date);
+                lots.retain(|lot| lot.units > 0);
+            }
+            CorporateActionKind::ReturnOfCapital { amount, .. } => {
+                let lots = holdings.entry(String::clone(&self.account_id)).or_default();
+                let units: i64 = lots.iter().map(|lot| lot.units).sum();
+                if amount <= 0 || units == 0 {
+                    return Err(invalid);
+                }
+
+                let mut remaining = amount;
+                let last = lots.len() - 1;
+                for (index, lot) in lots.iter_mut().enumerate() {
+                    let share = if index == last {
+                        remaining
+                    } else {
+                        get_share(amount, lot.units, units)
+                    };
+                    if share > lot.cost {
+                        return Err(invalid);
+                    }
+                    lot.cost -= share;
+                    remaining -= share;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Create a `CorporateAction` object from its JSON form, as produced by
+/// `CorporateAction::to_json()`.
+impl<'a> From<&'a JsonValue> for CorporateAction {
+    fn from(value: &'a JsonValue) -> CorporateAction {
+        let numerator = value["numerator"].as_i64().unwrap_or(1);
+        let denominator = value["denominator"].as_i64().unwrap_or(1);
+        let kind = match value["kind"].as_str().unwrap_or("") {
+            "merger" => CorporateActionKind::Merger {
+                account_id: String::from(value["into"].as_str().unwrap_or("")),
+                numerator,
+                denominator,
+            },
+            "return_of_capital" => CorporateActionKind::ReturnOfCapital {
+                account_id: String::from(value["cash"].as_str().unwrap_or("")),
+                amount: value["amount"].as_i64().unwrap_or(0),
+            },
+            _ => CorporateActionKind::Split {
+                numerator,
+                denominator,
+            },
+        };
+
+        CorporateAction::new(
+            value["id"].as_str().unwrap_or(""),
+            parse_date(&value["date"]).unwrap_or_default(),
+            value["account_id"].as_str().unwrap_or(""),
+            kind,
+        )
+    }
+}
+
+impl Ledger {
+    /// Record a `CorporateAction`, and post the `Transaction` adjusting the balances it affects.
+    ///
+    /// The `Transaction` has the id and date of the action and is marked with
+    /// `EntrySource::CorporateAction`. A split posts the change in the number of units held. A
+    /// merger moves the units and cost of the open lots to the `Account` holding the new
+    /// `Commodity`. A return of capital debits the cash `Account` and reduces the value of the
+    /// `Account` holding the commodity by the amount returned. From then on, the action is
+    /// applied to the open `Lot`s whenever they are replayed.
+    ///
+    /// Actions apply to the lots held on their date, so they should be posted after every
+    /// purchase and sale dated on or before them.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the action was posted, or an `Error` if an `Account` it names does not
+    ///   exist, an `Account` it adjusts does not hold a `Commodity`, the action is invalid for
+    ///   the lots held, or its `Transaction` cannot be added.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Commodity,
+    ///#               CommodityKind, CorporateAction, Currency, Ledger, Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Cash", "Cash", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# let mut apple = Account::new("02", "Brokerage (AAPL)", "Brokerage (AAPL)",
+    ///#                              AccountType::Asset, Currency::USDollar, false);
+    ///# apple.set_commodity("AAPL");
+    ///# chart.add_top_level_account(apple);
+    /// let mut ledger = Ledger::new(chart);
+    /// let apple_stock = Commodity::new("AAPL", "Apple Inc.", CommodityKind::Stock, 1);
+    /// ledger.add_commodity(apple_stock).unwrap();
+    ///
+    /// let mut buy = Transaction::new("BUY-1", NaiveDate::from_ymd_opt(2020, 1, 2).unwrap(),
+    ///                                "Buy 10 AAPL");
+    /// buy.add_split(Split::new_commodity("02", 10, 300000));
+    /// buy.add_split(Split::new("01", -300000));
+    /// ledger.add_transaction(buy).unwrap();
+    ///
+    /// let date = NaiveDate::from_ymd_opt(2020, 8, 31).unwrap();
+    /// ledger.post_corporate_action(CorporateAction::new_split("SPLIT-1", date, "02", 4, 1))
+    ///     .unwrap();
+    ///
+    /// let lots = ledger.get_lots("02", &BalanceOptions::new()).unwrap();
+    /// assert_eq!(lots[0].get_units(), 40);
+    /// assert_eq!(lots[0].get_cost(), 300000);
+    /// assert_eq!(ledger.get_balance("02", &BalanceOptions::new()).unwrap(), 40);
+    /// ```
+    pub fn post_corporate_action(&mut self, action: CorporateAction) -> Result<(), Error> {
+        self.check_holds_commodity(&action.account_id)?;
+        match action.kind {
+            CorporateActionKind::Merger { ref account_id, .. } => {
+                self.check_holds_commodity(account_id)?
+            }
+            CorporateActionKind::ReturnOfCapital { ref account_id, .. } => {
+                if self.get_chart().get_account_by_id(account_id).is_none() {
+                    return Err(Error::UnknownAccount(String::clone(account_id)));
+                }
+            }
+            CorporateActionKind::Split { .. } => {}
+        }
+
+        let mut holdings = self.replay_lots(|transaction| transaction.get_date() <= action.date)?;
+        let before = get_holding(&holdings, &action.account_id);
+        let target_before = match action.kind {
+            CorporateActionKind::Merger { ref account_id, .. } => {
+                get_holding(&holdings, account_id).0
+            }
+            _ => 0,
+        };
+        action.apply_to_lots(&mut holdings)?;
+
+        let mut transaction = Transaction::new(&action.id, action.date, &action.describe());
+        transaction.set_source(EntrySource::CorporateAction);
+        match action.kind {
+            CorporateActionKind::Split { .. } => {
+                let (units, _) = get_holding(&holdings, &action.account_id);
+                transaction.add_split(Split::new_commodity(
+                    &action.account_id,
+                    units - before.0,
+                    0,
+                ));
+            }
+            CorporateActionKind::Merger { ref account_id, .. } => {
+                let moved = before.0 - get_holding(&holdings, &action.account_id).0;
+                let received = get_holding(&holdings, account_id).0 - target_before;
+                transaction.add_split(Split::new_commodity(&action.account_id, -moved, -before.1));
+                transaction.add_split(Split::new_commodity(account_id, received, before.1));
+            }
+            CorporateActionKind::ReturnOfCapital {
+                ref account_id,
+                amount,
+            } => {
+                transaction.add_split(Split::new(account_id, amount));
+                transaction.add_split(Split::new_commodity(&action.account_id, 0, -amount));
+            }
+        }
+
+        self.add_transaction(transaction)?;
+        let actions = self.corporate_actions_mut();
+        actions.retain(|existing| existing.id != action.id);
+        actions.push(action);
+
+        Ok(())
+    }
+
+    /// Retrieve every `CorporateAction` posted to this `Ledger`, in the order they were posted.
+    pub fn get_corporate_actions(&self) -> Vec<CorporateAction> {
+        self.corporate_actions().to_vec()
+    }
+
+    /// Find the `CorporateAction` recorded by a `Transaction`, if it records one.
+    pub(crate) fn get_corporate_action_for(
+        &self,
+        transaction: &Transaction,
+    ) -> Option<&CorporateAction> {
+        if transaction.get_source() != EntrySource::CorporateAction {
+            return None;
+        }
+
+        self.corporate_actions()
+            .iter()
+            .find(|action| action.id == transaction.get_id())
+    }
+
+    /// Check that the `Account` with the given id exists and holds a `Commodity`.
+    fn check_holds_commodity(&self, account_id: &str) -> Result<(), Error> {
+        match self.get_chart().get_account_by_id(account_id) {
+            Some(ref account) if account.get_commodity().is_some() => Ok(()),
+            Some(_) => Err(Error::WrongAccountType(String::from(account_id))),
+            None => Err(Error::UnknownAccount(String::from(account_id))),
+        }
+    }
+}
+
+/// Find the total units and cost of the open lots of an `Account`.
+fn get_holding(holdings: &HashMap<String, Vec<Lot>>, account_id: &str) -> (i64, i64) {
+    holdings.get(account_id).map_or((0, 0), |lots| {
+        lots.iter().fold((0, 0), |(units, cost), lot| {
+            (units + lot.units, cost + lot.cost)
+        })
+    })
+}
@@ -0,0 +1,454 @@
+//! Zero-based budgeting, in which income is assigned to envelopes that spending draws down.
+
+use chrono::{Datelike, NaiveDate};
+use json::JsonValue;
+
+use balance::BalanceOptions;
+use error::Error;
+use fiscal::get_next_month_start;
+use ledger::Ledger;
+use transaction::parse_date;
+use AccountType;
+
+/// A spending category of an `EnvelopeBudget`, covering one or more Expense `Account`s.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Envelope {
+    id: String,
+    name: String,
+    account_ids: Vec<String>,
+}
+
+impl Envelope {
+    /// Create a new `Envelope` that covers no `Account`s.
+    pub fn new(id: &str, name: &str) -> Envelope {
+        Envelope {
+            id: String::from(id),
+            name: String::from(name),
+            account_ids: vec![],
+        }
+    }
+
+    /// Retrieve the id of this `Envelope`, as a `String`.
+    pub fn get_id(&self) -> String {
+        String::clone(&self.id)
+    }
+
+    /// Retrieve the name of this `Envelope`, as a `String`.
+    pub fn get_name(&self) -> String {
+        String::clone(&self.name)
+    }
+
+    /// Retrieve the ids of the `Account`s whose spending is drawn from this `Envelope`.
+    pub fn get_account_ids(&self) -> Vec<String> {
+        Vec::clone(&self.account_ids)
+    }
+
+    /// Draw the spending of an Expense `Account`, and of its sub-accounts, from this `Envelope`.
+    pub fn add_account(&mut self, account_id: &str) {
+        if !self.account_ids.iter().any(|id| id == account_id) {
+            self.account_ids.push(String::from(account_id));
+        }
+    }
+
+    /// Convert this `Envelope` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["id"] = self.id.as_str().into();
+        value["name"] = self.name.as_str().into();
+        value["accounts"] = JsonValue::Array(
+            self.account_ids
+                .iter()
+                .map(|id| JsonValue::from(id.as_str()))
+                .collect(),
+        );
+
+        value
+    }
+}
+
+/// Create an `Envelope` object from its JSON form, as produced by `Envelope::to_json()`.
+impl<'a> From<&'a JsonValue> for Envelope {
+    fn from(value: &'a JsonValue) -> Envelope {
+        let mut envelope = Envelope::new(
+            value["id"].as_str().unwrap_or(""),
+            value["name"].as_str().unwrap_or(""),
+        );
+        for account_id in value["accounts"].members() {
+            if let Some(account_id) = account_id.as_str() {
+                envelope.add_account(account_id);
+            }
+        }
+
+        envelope
+    }
+}
+
+/// A zero-based budget, in which income is assigned to `Envelope`s month by month.
+///
+/// Money left in an `Envelope` at the end of a month rolls over into the next month. An
+/// `Envelope` whose spending exceeds the money in it is overspent; it starts the next month
+/// empty, and the overspending is taken from the income still to be assigned instead.
+///
+/// # Examples
+/// ```
+///# extern crate chrono;
+///# extern crate cratchit;
+///# use chrono::NaiveDate;
+///# use cratchit::{Envelope, EnvelopeBudget};
+/// let mut groceries = Envelope::new("groceries", "Groceries");
+/// groceries.add_account("05-01");
+///
+/// let mut budget = EnvelopeBudget::new(NaiveDate::from_ymd_opt(2019, 1, 1).unwrap());
+/// budget.add_envelope(groceries).unwrap();
+/// budget.assign("groceries", NaiveDate::from_ymd_opt(2019, 1, 1).unwrap(), 40000).unwrap();
+/// budget.assign("groceries", NaiveDate::from_ymd_opt(2019, 1, 15).unwrap(), 5000).unwrap();
+///
+/// assert_eq!(budget.get_assigned("groceries", NaiveDate::from_ymd_opt(2019, 1, 31).unwrap()),
+///            45000);
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct EnvelopeBudget {
+    start: NaiveDate,
+    envelopes: Vec<Envelope>,
+    assignments: Vec<(String, NaiveDate, i64)>,
+}
+
+impl EnvelopeBudget {
+    /// Create a new `EnvelopeBudget` with no `Envelope`s, beginning in the month containing
+    /// `start`. Income and spending before that month are not counted.
+    pub fn new(start: NaiveDate) -> EnvelopeBudget {
+        EnvelopeBudget {
+            start: get_month_start(start),
+            envelopes: vec![],
+            assignments: vec![],
+        }
+    }
+
+    /// Retrieve the first day of the first month of this `EnvelopeBudget`.
+    pub fn get_start(&self) -> NaiveDate {
+        self.start
+    }
+
+    /// Add an `Envelope` to this `EnvelopeBudget`.
+    ///
+    /// # Returns
+    /// * `Ok` if the `Envelope` was added, or `Error::DuplicateEnvelope` if an `Envelope` with
+    ///   the same id already exists, or one already covers any of its `Account`s.
+    pub fn add_envelope(&mut self, envelope: Envelope) -> Result<(), Error> {
+        if self.envelopes.iter().any(|existing| {
+            existing.id == envelope.id
+                || existing
+                    .account_ids
+                    .iter()
+                    .any(|id| envelope.account_ids.contains(id))
+        }) {
+            return Err(Error::DuplicateEnvelope(envelope.get_id()));
+        }
+
+        self.envelopes.push(envelope);
+        Ok(())
+    }
+
+    /// Retrieve the `Envelope` with the given id, if it exists.
+    pub fn get_envelope(&self, id: &str) -> Option<Envelope> {
+        self.envelopes
+            .iter()
+            .find(|envelope| envelope.id == id)
+            .cloned()
+    }
+
+    /// Retrieve all of the `Envelope`s, in the order they were added.
+    pub fn get_envelopes(&self) -> Vec<Envelope> {
+        Vec::clone(&self.envelopes)
+    }
+
+    /// Assign money to an `Envelope` in the month containing `date`, adding to anything already
+    /// assigned that month. A negative amount takes money back out of the `Envelope`.
+    ///
+    /// # Returns
+    /// * `Ok` if the money was assigned, or `Error::UnknownEnvelope` if there is no `Envelope`
+    ///   with the given id.
+    pub fn assign(&mut self, envelope_id: &str, date: NaiveDate, amount: i64) -> Result<(), Error> {
+        if self.get_envelope(envelope_id).is_none() {
+            return Err(Error::UnknownEnvelope(String::from(envelope_id)));
+        }
+
+        let month = get_month_start(date);
+        match self
+            .assignments
+            .iter_mut()
+            .find(|entry| entry.0 == envelope_id && entry.1 == month)
+        {
+            Some(entry) => entry.2 += amount,
+            None => self
+                .assignments
+                .push((String::from(envelope_id), month, amount)),
+        }
+
+        Ok(())
+    }
+
+    /// Move money from one `Envelope` to another in the month containing `date`, such as to cover
+    /// overspending.
+    ///
+    /// # Returns
+    /// * `Ok` if the money was moved, or `Error::UnknownEnvelope` if either `Envelope` does not
+    ///   exist, in which case neither is changed.
+    pub fn move_money(
+        &mut self,
+        from_id: &str,
+        to_id: &str,
+        date: NaiveDate,
+        amount: i64,
+    ) -> Result<(), Error> {
+        if self.get_envelope(to_id).is_none() {
+            return Err(Error::UnknownEnvelope(String::from(to_id)));
+        }
+
+        self.assign(from_id, date, -amount)?;
+        self.assign(to_id, date, amount)
+    }
+
+    /// Retrieve the money assigned to an `Envelope` in the month containing `date`.
+    pub fn get_assigned(&self, envelope_id: &str, date: NaiveDate) -> i64 {
+        let month = get_month_start(date);
+        self.assignments
+            .iter()
+            .filter(|entry| entry.0 == envelope_id && entry.1 == month)
+            .map(|entry| entry.2)
+            .sum()
+    }
+
+    /// Convert this `EnvelopeBudget` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["start"] = self.start.format("%Y-%m-%d").to_string().into();
+        value["envelopes"] =
+            JsonValue::Array(self.envelopes.iter().map(Envelope::to_json).collect());
+        value["assignments"] = JsonValue::Array(
+            self.assignments
+                .iter()
+                .map(|&(ref envelope_id, month, amount)| {
+                    let mut entry = JsonValue::new_object();
+                    entry["envelope"] = envelope_id.as_str().into();
+                    entry["month"] = month.format("%Y-%m-%d").to_string().into();
+                    entry["amount"] = amount.into();
+                    entry
+                })
+                .collect(),
+        );
+
+        value
+    }
+}
+
+/// Create an `EnvelopeBudget` object from its JSON form, as produced by
+/// `EnvelopeBudget::to_json()`. `Envelope`s and assignments that are not valid are skipped.
+impl<'a> From<&'a JsonValue> for EnvelopeBudget {
+    fn from(value: &'a JsonValue) -> EnvelopeBudget {
+        let start = parse_date(&value["start"])
+            .unwrap_or_else(|| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap());
+        let mut budget = EnvelopeBudget::new(start);
+        for envelope in value["envelopes"].members() {
+            let _ = budget.add_envelope(Envelope::from(envelope));
+        }
+        for entry in value["assignments"].members() {
+            if let Some(month) = parse_date(&entry["month"]) {
+                let _ = budget.assign(
+                    entry["envelope"].as_str().unwrap_or(""),
+                    month,
+                    entry["amount"].as_i64().unwrap_or(0),
+                );
+            }
+        }
+
+        budget
+    }
+}
+
+/// The state of an `Envelope` in one month of an `EnvelopeBudget`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct EnvelopeStatus {
+    envelope_id: String,
+    carried_over: i64,
+    assigned: i64,
+    spent: i64,
+}
+
+impl EnvelopeStatus {
+    /// Retrieve the id of the `Envelope`, as a `String`.
+    pub fn get_envelope_id(&self) -> String {
+        String::clone(&self.envelope_id)
+    }
+
+    /// Retrieve the money left over from the previous month.
+    pub fn get_carried_over(&self) -> i64 {
+        self.carried_over
+    }
+
+    /// Retrieve the money assigned during the month.
+    pub fn get_assigned(&self) -> i64 {
+        self.assigned
+    }
+
+    /// Retrieve the net spending from the `Envelope`'s `Account`s during the month.
+    pub fn get_spent(&self) -> i64 {
+        self.spent
+    }
+
+    /// Retrieve the money left at the end of the month, which is negative when overspent.
+    pub fn get_available(&self) -> i64 {
+        self.carried_over + self.assigned - self.spent
+    }
+
+    /// Check whether more was spent than the `Envelope` held.
+    pub fn is_overspent(&self) -> bool {
+        self.get_available() < 0
+    }
+}
+
+impl Ledger {
+    /// Compute the state of each `Envelope` of an `EnvelopeBudget` in the month containing
+    /// `date`.
+    ///
+    /// # Returns
+    /// * The state of each `Envelope`, in the order they were added, or an `Error` if an
+    ///   `Envelope` covers an `Account` that does not exist or is not an Expense `Account`.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, Currency, Envelope,
+    ///#               EnvelopeBudget, Ledger, Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("04", "Salary", "Salary", AccountType::Income,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("05", "Groceries", "Groceries",
+    ///#                                          AccountType::Expense, Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// let mut paycheck = Transaction::new("0001", NaiveDate::from_ymd_opt(2019, 1, 1).unwrap(),
+    ///                                     "Paycheck");
+    /// paycheck.add_split(Split::new("01", 200000));
+    /// paycheck.add_split(Split::new("04", -200000));
+    /// ledger.add_transaction(paycheck).unwrap();
+    ///
+    /// let mut groceries = Envelope::new("groceries", "Groceries");
+    /// groceries.add_account("05");
+    /// let mut budget = EnvelopeBudget::new(NaiveDate::from_ymd_opt(2019, 1, 1).unwrap());
+    /// budget.add_envelope(groceries).unwrap();
+    /// budget.assign("groceries", NaiveDate::from_ymd_opt(2019, 1, 1).unwrap(), 50000).unwrap();
+    ///
+    /// let february = NaiveDate::from_ymd_opt(2019, 2, 1).unwrap();
+    /// let statuses = ledger.get_envelope_statuses(&budget, february).unwrap();
+    /// assert_eq!(statuses[0].get_carried_over(), 50000);
+    /// assert_eq!(ledger.get_to_be_assigned(&budget, february), Ok(150000));
+    /// ```
+    pub fn get_envelope_statuses(
+        &self,
+        budget: &EnvelopeBudget,
+        date: NaiveDate,
+    ) -> Result<Vec<EnvelopeStatus>, Error> {
+        self.replay_envelopes(budget, date)
+            .map(|(statuses, _)| statuses)
+    }
+
+    /// Compute the income of an `EnvelopeBudget` that has not been assigned to an `Envelope` as
+    /// of the end of the month containing `date`. Income is the net credit to the Income
+    /// `Account`s, less any overspending in earlier months.
+    ///
+    /// # Returns
+    /// * The money still to be assigned, which is negative if more was assigned than received,
+    ///   or an `Error` if an `Envelope` covers an `Account` that is not an Expense `Account`.
+    pub fn get_to_be_assigned(
+        &self,
+        budget: &EnvelopeBudget,
+        date: NaiveDate,
+    ) -> Result<i64, Error> {
+        self.replay_envelopes(budget, date)
+            .map(|(_, to_be_assigned)| to_be_assigned)
+    }
+
+    /// Work through the months of an `EnvelopeBudget` up to the one containing `date`, rolling
+    /// the money left in each `Envelope` over from month to month. Year-end closing entries are
+    /// neither income nor spending.
+    fn replay_envelopes(
+        &self,
+        budget: &EnvelopeBudget,
+        date: NaiveDate,
+    ) -> Result<(Vec<EnvelopeStatus>, i64), Error> {
+        for envelope in &budget.envelopes {
+            for account_id in &envelope.account_ids {
+                match self.get_chart().get_account_by_id(account_id) {
+                    Some(ref account) if account.get_account_type() == AccountType::Expense => {}
+                    Some(_) => return Err(Error::WrongAccountType(String::clone(account_id))),
+                    None => return Err(Error::UnknownAccount(String::clone(account_id))),
+                }
+            }
+        }
+
+        let income_ids: Vec<String> = self
+            .get_chart()
+            .get_account_ids()
+            .into_iter()
+            .filter(|id| {
+                self.get_chart()
+                    .get_account_by_id(id)
+                    .is_some_and(|account| account.get_account_type() == AccountType::Income)
+            })
+            .collect();
+
+        let mut statuses: Vec<EnvelopeStatus> = budget
+            .envelopes
+            .iter()
+            .map(|envelope| EnvelopeStatus {
+                envelope_id: envelope.get_id(),
+                carried_over: 0,
+                assigned: 0,
+                spent: 0,
+            })
+            .collect();
+        let mut to_be_assigned = 0;
+        let last = get_month_start(date);
+        let mut month = budget.start;
+        while month <= last {
+            let next = get_next_month_start(month);
+            let options = BalanceOptions::new()
+                .since(month)
+                .as_of(next.pred_opt().unwrap())
+                .exclude_closing_entries(true);
+
+            for id in &income_ids {
+                to_be_assigned += self.get_balance(id, &options)?;
+            }
+
+            let options = options.include_sub_accounts(true);
+            for (envelope, status) in budget.envelopes.iter().zip(statuses.iter_mut()) {
+                let available = status.get_available();
+                if month > budget.start {
+                    status.carried_over = available.max(0);
+                    to_be_assigned += available.min(0);
+                }
+
+                status.assigned = budget.get_assigned(&envelope.id, month);
+                to_be_assigned -= status.assigned;
+                status.spent = 0;
+                for account_id in &envelope.account_ids {
+                    status.spent += self.get_balance(account_id, &options)?;
+                }
+            }
+
+            month = next;
+        }
+
+        Ok((statuses, to_be_assigned))
+    }
+}
+
+/// Find the first day of the month containing `date`.
+fn get_month_start(date: NaiveDate) -> NaiveDate {
+    NaiveDate::from_ymd_opt(date.year(), date.month(), 1).unwrap()
+}
@@ -0,0 +1,318 @@
+//! Errors produced when an operation on a `Ledger` cannot be completed.
+
+use std::error;
+use std::fmt;
+
+use chrono::NaiveDate;
+
+use transaction::TransactionStatus;
+
+/// An enumeration of the reasons an operation on a `Ledger` can be rejected.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Error {
+    /// No `Account` with the given id exists in the `AccountsChart`.
+    UnknownAccount(String),
+
+    /// An `Account` with the given id already exists in the `AccountsChart`.
+    DuplicateAccount(String),
+
+    /// A split attempted to post directly to the placeholder `Account` with the given id.
+    PlaceholderAccount(String),
+
+    /// No `Transaction` with the given id exists in the `Ledger`.
+    UnknownTransaction(String),
+
+    /// A `Transaction` with the given id already exists in the `Ledger`.
+    DuplicateTransaction(String),
+
+    /// The debits and credits of the `Transaction` with the given id do not sum to zero.
+    Unbalanced(String),
+
+    /// The `Transaction` with the given id has no `Split` at the given index.
+    UnknownSplit(String, usize),
+
+    /// An item cannot be moved from the first `TransactionStatus` to the second.
+    InvalidStatusTransition(TransactionStatus, TransactionStatus),
+
+    /// A `Reconciliation` cannot be finalized, because its cleared balance differs from the
+    /// statement's ending balance by the given amount.
+    ReconciliationDifference(i64),
+
+    /// The `Split` at the given index of the `Transaction` with the given id cannot be cleared
+    /// in a `Reconciliation`, because it does not post to the `Account` being reconciled on or
+    /// before the statement date.
+    SplitOutsideReconciliation(String, usize),
+
+    /// No `FiscalPeriod` with the given id exists in the `Ledger`.
+    UnknownPeriod(String),
+
+    /// The `FiscalPeriod` with the given id overlaps a period already in the `Ledger`.
+    OverlappingPeriod(String),
+
+    /// A `FiscalCalendar` names the given month, which is not between 1 and 12.
+    InvalidMonth(u32),
+
+    /// The change affects a date within the closed `FiscalPeriod` with the given id.
+    ClosedPeriod(String),
+
+    /// The `FiscalPeriod` with the given id is already open.
+    OpenPeriod(String),
+
+    /// No `FiscalPeriod`s of the given fiscal year exist in the `Ledger`.
+    UnknownFiscalYear(i32),
+
+    /// The given fiscal year has no Income or Expense balances to close.
+    NothingToClose(i32),
+
+    /// The `Account` with the given id has the wrong `AccountType` for the operation.
+    WrongAccountType(String),
+
+    /// No `ScheduledTransaction` with the given id exists in the `Ledger`.
+    UnknownSchedule(String),
+
+    /// A `ScheduledTransaction` with the given id already exists in the `Ledger`.
+    DuplicateSchedule(String),
+
+    /// The `ScheduledTransaction` with the given id does not recur on the given date.
+    UnknownOccurrence(String, NaiveDate),
+
+    /// The `Transaction` with the given id is marked to be automatically reversed on or before
+    /// its own date.
+    InvalidReversalDate(String),
+
+    /// The hash chain over the journal does not match the `Transaction` with the given id.
+    BrokenChain(String),
+
+    /// There are no changes to undo.
+    NothingToUndo,
+
+    /// There are no undone changes to redo.
+    NothingToRedo,
+
+    /// The given text is not valid JSON, or is missing a field required by the object it
+    /// describes.
+    InvalidJson(String),
+
+    /// No exchange rate from the first commodity to the second is known on the given date.
+    UnknownRate(String, String, NaiveDate),
+
+    /// A `Split` posting to the `Account` with the given id does not convert between the
+    /// `Account`'s currency and the currency of its `Transaction` correctly.
+    CurrencyMismatch(String),
+
+    /// No `Commodity` with the given symbol exists in the `Ledger`.
+    UnknownCommodity(String),
+
+    /// A `Commodity` with the given symbol already exists in the `Ledger`.
+    DuplicateCommodity(String),
+
+    /// A sale from the `Account` with the given id is for more units than its open lots hold.
+    InsufficientUnits(String),
+
+    /// No open lot with the given id holds the units a sale names.
+    UnknownLot(String),
+
+    /// The rate of return of the `Account` with the given id cannot be computed, because nothing
+    /// was invested in it over the period.
+    UndefinedReturn(String),
+
+    /// The `CorporateAction` with the given id cannot be applied to the lots held.
+    InvalidCorporateAction(String),
+
+    /// No `Envelope` with the given id exists in the `EnvelopeBudget`.
+    UnknownEnvelope(String),
+
+    /// An `Envelope` with the given id, or covering one of the same `Account`s, already exists in
+    /// the `EnvelopeBudget`.
+    DuplicateEnvelope(String),
+
+    /// No `Contact` with the given id exists in the `Ledger`.
+    UnknownContact(String),
+
+    /// A `Contact` with the given id already exists in the `Ledger`.
+    DuplicateContact(String),
+
+    /// No `TaxCode` with the given code exists in the `Ledger`.
+    UnknownTaxCode(String),
+
+    /// A `TaxCode` with the given code already exists in the `Ledger`.
+    DuplicateTaxCode(String),
+
+    /// No `Invoice` with the given id exists in the `Ledger`.
+    UnknownInvoice(String),
+
+    /// An `Invoice` with the given id already exists in the `Ledger`.
+    DuplicateInvoice(String),
+
+    /// The `Invoice` with the given id is not in a state that allows the operation.
+    InvalidInvoiceState(String),
+
+    /// No `Bill` with the given id exists in the `Ledger`.
+    UnknownBill(String),
+
+    /// A `Bill` with the given id already exists in the `Ledger`.
+    DuplicateBill(String),
+
+    /// The `Bill` with the given id is not in a state that allows the operation.
+    InvalidBillState(String),
+
+    /// A payment is for more than is outstanding on the document with the given id.
+    Overpayment(String),
+
+    /// The `Contact` with the given id has no control `Account` to post receivables or payables
+    /// to.
+    NoControlAccount(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::UnknownAccount(ref id) => write!(f, "no account with id '{}' exists", id),
+            Error::DuplicateAccount(ref id) => {
+                write!(f, "an account with id '{}' already exists", id)
+            }
+            Error::PlaceholderAccount(ref id) => {
+                write!(
+                    f,
+                    "account '{}' is a placeholder and cannot hold splits",
+                    id
+                )
+            }
+            Error::UnknownTransaction(ref id) => {
+                write!(f, "no transaction with id '{}' exists", id)
+            }
+            Error::DuplicateTransaction(ref id) => {
+                write!(f, "a transaction with id '{}' already exists", id)
+            }
+            Error::Unbalanced(ref id) => write!(f, "transaction '{}' does not balance", id),
+            Error::UnknownSplit(ref id, index) => {
+                write!(f, "transaction '{}' has no split at index {}", id, index)
+            }
+            Error::InvalidStatusTransition(from, to) => {
+                write!(f, "cannot change status from {:?} to {:?}", from, to)
+            }
+            Error::ReconciliationDifference(difference) => write!(
+                f,
+                "the cleared balance differs from the statement by {}",
+                difference
+            ),
+            Error::SplitOutsideReconciliation(ref id, index) => write!(
+                f,
+                "split {} of transaction '{}' is not a posting covered by the statement",
+                index, id
+            ),
+            Error::UnknownPeriod(ref id) => write!(f, "no fiscal period with id '{}' exists", id),
+            Error::OverlappingPeriod(ref id) => {
+                write!(f, "fiscal period '{}' overlaps an existing period", id)
+            }
+            Error::InvalidMonth(month) => {
+                write!(f, "month {} is not between 1 and 12", month)
+            }
+            Error::ClosedPeriod(ref id) => write!(f, "fiscal period '{}' is closed", id),
+            Error::OpenPeriod(ref id) => write!(f, "fiscal period '{}' is already open", id),
+            Error::UnknownFiscalYear(year) => write!(f, "no periods of fiscal year {} exist", year),
+            Error::NothingToClose(year) => {
+                write!(f, "fiscal year {} has no income or expenses to close", year)
+            }
+            Error::WrongAccountType(ref id) => {
+                write!(f, "account '{}' has the wrong type for this operation", id)
+            }
+            Error::UnknownSchedule(ref id) => write!(f, "no schedule with id '{}' exists", id),
+            Error::DuplicateSchedule(ref id) => {
+                write!(f, "a schedule with id '{}' already exists", id)
+            }
+            Error::UnknownOccurrence(ref id, date) => {
+                write!(f, "schedule '{}' does not occur on {}", id, date)
+            }
+            Error::InvalidReversalDate(ref id) => write!(
+                f,
+                "transaction '{}' must be reversed after its own date",
+                id
+            ),
+            Error::BrokenChain(ref id) => {
+                write!(
+                    f,
+                    "the journal hash chain is broken at transaction '{}'",
+                    id
+                )
+            }
+            Error::NothingToUndo => write!(f, "there are no changes to undo"),
+            Error::NothingToRedo => write!(f, "there are no undone changes to redo"),
+            Error::InvalidJson(ref reason) => write!(f, "invalid JSON: {}", reason),
+            Error::UnknownRate(ref commodity, ref currency, date) => write!(
+                f,
+                "no rate from {} to {} is known on {}",
+                commodity, currency, date
+            ),
+            Error::CurrencyMismatch(ref id) => write!(
+                f,
+                "the split posting to account '{}' does not convert to its currency",
+                id
+            ),
+            Error::UnknownCommodity(ref symbol) => {
+                write!(f, "no commodity with symbol '{}' exists", symbol)
+            }
+            Error::DuplicateCommodity(ref symbol) => {
+                write!(f, "a commodity with symbol '{}' already exists", symbol)
+            }
+            Error::InsufficientUnits(ref id) => write!(
+                f,
+                "account '{}' does not hold enough units for the sale",
+                id
+            ),
+            Error::UnknownLot(ref id) => {
+                write!(f, "no open lot with id '{}' holds the units named", id)
+            }
+            Error::UndefinedReturn(ref id) => write!(
+                f,
+                "the return of account '{}' cannot be computed for the period",
+                id
+            ),
+            Error::InvalidCorporateAction(ref id) => write!(
+                f,
+                "corporate action '{}' cannot be applied to the lots held",
+                id
+            ),
+            Error::UnknownEnvelope(ref id) => write!(f, "no envelope with id '{}' exists", id),
+            Error::DuplicateEnvelope(ref id) => write!(
+                f,
+                "envelope '{}' or one of its accounts is already in the budget",
+                id
+            ),
+            Error::UnknownContact(ref id) => write!(f, "no contact with id '{}' exists", id),
+            Error::DuplicateContact(ref id) => {
+                write!(f, "a contact with id '{}' already exists", id)
+            }
+            Error::UnknownTaxCode(ref code) => write!(f, "no tax code '{}' exists", code),
+            Error::DuplicateTaxCode(ref code) => write!(f, "tax code '{}' already exists", code),
+            Error::UnknownInvoice(ref id) => write!(f, "no invoice with id '{}' exists", id),
+            Error::DuplicateInvoice(ref id) => {
+                write!(f, "an invoice with id '{}' already exists", id)
+            }
+            Error::InvalidInvoiceState(ref id) => {
+                write!(f, "invoice '{}' does not allow this operation", id)
+            }
+            Error::UnknownBill(ref id) => write!(f, "no bill with id '{}' exists", id),
+            Error::DuplicateBill(ref id) => write!(f, "a bill with id '{}' already exists", id),
+            Error::InvalidBillState(ref id) => {
+                write!(f, "bill '{}' does not allow this operation", id)
+            }
+            Error::Overpayment(ref id) => {
+                write!(f, "the payment is more than is outstanding on '{}'", id)
+            }
+            Error::NoControlAccount(ref id) => {
+                write!(f, "contact '{}' has no control account", id)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// Convert an `Error` to a JavaScript value, so that it can be thrown from the WebAssembly API.
+#[cfg(target_arch = "wasm32")]
+impl From<Error> for wasm_bindgen::JsValue {
+    fn from(error: Error) -> wasm_bindgen::JsValue {
+        wasm_bindgen::JsValue::from_str(&error.to_string())
+    }
+}
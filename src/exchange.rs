@@ -0,0 +1,252 @@
+//! Realized exchange gains and losses on `Account`s held in a foreign currency.
+
+use std::collections::HashMap;
+
+use balance::BalanceOptions;
+use error::Error;
+use ledger::Ledger;
+use transaction::{Split, Transaction};
+use {Account, AccountType, Currency, NormalBalance};
+
+impl Ledger {
+    /// Set the `Account` to which realized exchange gains and losses are posted.
+    ///
+    /// Once set, each `Transaction` added to or edited in this `Ledger` that settles all or part
+    /// of the balance of an `Account` in a foreign currency is completed with two generated
+    /// `Split`s. The first adjusts the value of the foreign `Account` so that the settled part
+    /// is removed at the value it was carried at, and the second posts the difference to this
+    /// `Account`. The carrying value of a foreign `Account` is the total value of the postings
+    /// dated on or before the settlement, and the settled part is removed at its average value.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Account` was set, or an `Error` if it does not exist, is a placeholder,
+    ///   or is not an Income or Expense `Account`.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Currency, Ledger,
+    ///#               Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("02", "Receivable (EUR)", "Receivable (EUR)",
+    ///#                                          AccountType::Asset, Currency::Euro, false));
+    ///# chart.add_top_level_account(Account::new("04", "Sales", "Sales", AccountType::Income,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("05", "Exchange Gain/Loss", "Exchange Gain/Loss",
+    ///#                                          AccountType::Expense, Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// ledger.set_exchange_gain_loss_account("05").unwrap();
+    ///
+    /// let mut invoice = Transaction::new("INV-1", NaiveDate::from_ymd_opt(2018, 3, 1).unwrap(),
+    ///                                    "Consulting for a Berlin client");
+    /// invoice.add_split(Split::new_foreign("02", 10000, 1.25));
+    /// invoice.add_split(Split::new("04", -12500));
+    /// ledger.add_transaction(invoice).unwrap();
+    ///
+    /// let mut payment = Transaction::new("PMT-1", NaiveDate::from_ymd_opt(2018, 4, 1).unwrap(),
+    ///                                    "Payment received");
+    /// payment.add_split(Split::new("01", 12000));
+    /// payment.add_split(Split::new_foreign("02", -10000, 1.2));
+    /// ledger.add_transaction(payment).unwrap();
+    ///
+    /// assert_eq!(ledger.get_balance("05", &BalanceOptions::new()).unwrap(), 500);
+    /// assert_eq!(ledger.get_carrying_value("02", &BalanceOptions::new()).unwrap(), 0);
+    /// ```
+    pub fn set_exchange_gain_loss_account(&mut self, account_id: &str) -> Result<(), Error> {
+        let account = match self.get_chart().get_account_by_id(account_id) {
+            Some(account) => account,
+            None => return Err(Error::UnknownAccount(String::from(account_id))),
+        };
+
+        if account.is_placeholder() {
+            return Err(Error::PlaceholderAccount(String::from(account_id)));
+        }
+
+        match account.get_account_type() {
+            AccountType::Income | AccountType::Expense => {
+                *self.exchange_account_mut() = Some(String::from(account_id));
+                Ok(())
+            }
+            _ => Err(Error::WrongAccountType(String::from(account_id))),
+        }
+    }
+
+    /// Retrieve the id of the `Account` to which realized exchange gains and losses are posted,
+    /// if one has been set.
+    pub fn get_exchange_gain_loss_account(&self) -> Option<String> {
+        self.exchange_account().clone()
+    }
+
+    /// Compute the carrying value of the `Account` with the given id: the total value of its
+    /// postings in the currency of their `Transaction`s, rather than its amount in its own
+    /// `Currency`. Every `Transaction` posting to an `Account` in a foreign currency is in the same
+    /// currency, so the values can be added up.
+    ///
+    /// The carrying value is signed according to the `Account`'s normal balance, in the same way
+    /// as `get_balance()`.
+    pub fn get_carrying_value(
+        &self,
+        account_id: &str,
+        options: &BalanceOptions,
+    ) -> Result<i64, Error> {
+        let account = match self.get_chart().get_account_by_id(account_id) {
+            Some(account) => account,
+            None => return Err(Error::UnknownAccount(String::from(account_id))),
+        };
+
+        let account_ids = if options.includes_sub_accounts() {
+            self.get_chart().get_sub_tree_ids(account_id)
+        } else {
+            vec![account.get_id()]
+        };
+
+        let total: i64 = self
+            .transactions()
+            .iter()
+            .filter(|transaction| options.includes(transaction))
+            .flat_map(|transaction| transaction.splits())
+            .filter(|split| options.includes_split(split))
+            .filter(|split| account_ids.contains(&split.get_account_id()))
+            .map(Split::get_value)
+            .sum();
+
+        match account.get_account_type().get_normal_balance() {
+            NormalBalance::Debit => Ok(total),
+            NormalBalance::Credit => Ok(-total),
+        }
+    }
+
+    /// Complete a `Transaction` with the `Split`s recording the exchange gains and losses it
+    /// realizes, replacing any it already has. The `Transaction` is returned unchanged if no
+    /// exchange gain/loss `Account` has been set.
+    pub(crate) fn realize_exchange(&self, mut transaction: Transaction) -> Transaction {
+        let gain_loss_id = match *self.exchange_account() {
+            Some(ref id) => String::clone(id),
+            None => return transaction,
+        };
+        transaction.remove_exchange_adjustments();
+
+        let mut account_ids: Vec<String> = vec![];
+        let mut postings: HashMap<String, (i64, i64)> = HashMap::new();
+        for split in transaction.splits() {
+            let account_id = split.get_account_id();
+            let is_foreign =
+                self.get_chart()
+                    .get_account_by_id(&account_id)
+                    .is_some_and(|account| {
+                        account.get_commodity().is_none()
+                            && is_foreign_currency(
+                                account.get_currency(),
+                                transaction.get_currency(),
+                            )
+                    });
+            if !is_foreign {
+                continue;
+            }
+
+            let posting = postings
+                .entry(String::clone(&account_id))
+                .or_insert_with(|| {
+                    account_ids.push(String::clone(&account_id));
+                    (0, 0)
+                });
+            posting.0 += split.get_amount();
+            posting.1 += split.get_value();
+        }
+
+        for account_id in account_ids {
+            let (amount, value) = postings[&account_id];
+            let (balance, carrying_value) = self.get_position(&account_id, &transaction);
+            if balance == 0 || amount == 0 || balance.signum() == amount.signum() {
+                continue;
+            }
+
+            let settled = if amount.abs() > balance.abs() {
+                -balance
+            } else {
+                amount
+            };
+            let removed = get_share(carrying_value, settled, balance);
+            let settled_value = if settled == amount {
+                value
+            } else {
+                get_share(value, settled, amount)
+            };
+
+            let difference = removed - settled_value;
+            if difference != 0 {
+                transaction.add_split(Split::new_exchange_adjustment(&account_id, 0, difference));
+                transaction.add_split(Split::new_exchange_adjustment(
+                    &gain_loss_id,
+                    -difference,
+                    -difference,
+                ));
+            }
+        }
+
+        transaction
+    }
+
+    /// Determine if posting `transaction` to `account` would mix currencies in its carrying
+    /// value. An `Account` that is foreign to a `Transaction` posting to it is carried in the
+    /// currency of that `Transaction`, so every `Transaction` posting to it must share that
+    /// currency.
+    pub(crate) fn mixes_carrying_currency(
+        &self,
+        account: &Account,
+        transaction: &Transaction,
+    ) -> bool {
+        let currency = transaction.get_currency();
+        self.transactions()
+            .iter()
+            .filter(|existing| {
+                existing.get_id() != transaction.get_id() && existing.get_currency() != currency
+            })
+            .filter(|existing| {
+                existing
+                    .splits()
+                    .iter()
+                    .any(|split| split.get_account_id() == account.get_id())
+            })
+            .any(|existing| {
+                is_foreign_currency(account.get_currency(), currency)
+                    || is_foreign_currency(account.get_currency(), existing.get_currency())
+            })
+    }
+
+    /// Find the amount and carrying value of an `Account` from the postings of every other
+    /// `Transaction` dated on or before `transaction`.
+    fn get_position(&self, account_id: &str, transaction: &Transaction) -> (i64, i64) {
+        self.transactions()
+            .iter()
+            .filter(|existing| {
+                existing.get_id() != transaction.get_id()
+                    && existing.get_date() <= transaction.get_date()
+            })
+            .flat_map(|existing| existing.splits())
+            .filter(|split| split.get_account_id() == account_id)
+            .fold((0, 0), |(amount, value), split| {
+                (amount + split.get_amount(), value + split.get_value())
+            })
+    }
+}
+
+/// Determine if an `Account` in `account_currency` is foreign to a `Transaction` in
+/// `transaction_currency`. Accounts whose currency is unknown are never treated as foreign.
+pub(crate) fn is_foreign_currency(
+    account_currency: Currency,
+    transaction_currency: Currency,
+) -> bool {
+    account_currency != transaction_currency
+        && account_currency != Currency::Unknown
+        && transaction_currency != Currency::Unknown
+}
+
+/// Compute `total * part / whole`, rounded to the nearest unit.
+pub(crate) fn get_share(total: i64, part: i64, whole: i64) -> i64 {
+    (total as f64 * part as f64 / whole as f64).round() as i64
+}
@@ -0,0 +1,305 @@
+//! Fiscal years and the accounting periods they are divided into.
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use json::JsonValue;
+
+use error::Error;
+use transaction::parse_date;
+
+/// An enumeration of the ways a fiscal year can be laid out.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FiscalCalendar {
+    /// A fiscal year made up of twelve calendar months, beginning on the first day of
+    /// `start_month`. A fiscal year is named for the calendar year in which it ends.
+    Monthly {
+        /// The month (1 to 12) in which each fiscal year begins.
+        start_month: u32,
+    },
+
+    /// A 52/53-week fiscal year ending on the last `end_weekday` of `end_month`, divided into
+    /// thirteen four-week periods. In 53-week years, the extra week is added to the last period.
+    FiftyTwoFiftyThreeWeek {
+        /// The month (1 to 12) in which each fiscal year ends.
+        end_month: u32,
+
+        /// The day of the week on which each fiscal year ends.
+        end_weekday: Weekday,
+    },
+
+    /// A 52/53-week fiscal year ending on the last `end_weekday` of `end_month`, divided into
+    /// four quarters of three periods lasting four, four and five weeks. In 53-week years, the
+    /// extra week is added to the last period.
+    FourFourFive {
+        /// The month (1 to 12) in which each fiscal year ends.
+        end_month: u32,
+
+        /// The day of the week on which each fiscal year ends.
+        end_weekday: Weekday,
+    },
+}
+
+impl FiscalCalendar {
+    /// Compute the accounting periods of a fiscal year.
+    ///
+    /// # Arguments
+    /// * `year`: The fiscal year to compute. Fiscal years are named for the calendar year in
+    ///   which they end.
+    ///
+    /// # Returns
+    /// * The `FiscalPeriod`s of the fiscal year, in date order, all of which are open, or
+    ///   `Error::InvalidMonth` if the calendar's month is not between 1 and 12.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::{NaiveDate, Weekday};
+    ///# use cratchit::FiscalCalendar;
+    /// let july = FiscalCalendar::Monthly { start_month: 7 };
+    /// let periods = july.get_periods(2019).unwrap();
+    /// assert_eq!(periods.len(), 12);
+    /// assert_eq!(periods[0].get_start(), NaiveDate::from_ymd_opt(2018, 7, 1).unwrap());
+    /// assert_eq!(periods[11].get_end(), NaiveDate::from_ymd_opt(2019, 6, 30).unwrap());
+    ///
+    /// let retail = FiscalCalendar::FourFourFive { end_month: 1, end_weekday: Weekday::Sat };
+    /// let periods = retail.get_periods(2019).unwrap();
+    /// assert_eq!(periods[0].get_start(), NaiveDate::from_ymd_opt(2018, 1, 28).unwrap());
+    /// assert_eq!(periods[2].get_end(), NaiveDate::from_ymd_opt(2018, 4, 28).unwrap());
+    /// assert_eq!(periods[11].get_end(), NaiveDate::from_ymd_opt(2019, 1, 26).unwrap());
+    /// ```
+    pub fn get_periods(&self, year: i32) -> Result<Vec<FiscalPeriod>, Error> {
+        let month = match *self {
+            FiscalCalendar::Monthly { start_month } => start_month,
+            FiscalCalendar::FiftyTwoFiftyThreeWeek { end_month, .. }
+            | FiscalCalendar::FourFourFive { end_month, .. } => end_month,
+        };
+        if !(1..=12).contains(&month) {
+            return Err(Error::InvalidMonth(month));
+        }
+
+        Ok(match *self {
+            FiscalCalendar::Monthly { start_month } => {
+                let first_year = if start_month == 1 { year } else { year - 1 };
+                let mut start = NaiveDate::from_ymd_opt(first_year, start_month, 1).unwrap();
+                let mut periods = vec![];
+                for number in 1..13 {
+                    let next = get_next_month_start(start);
+                    periods.push(FiscalPeriod::new(
+                        year,
+                        number,
+                        start,
+                        next.pred_opt().unwrap(),
+                    ));
+                    start = next;
+                }
+
+                periods
+            }
+            FiscalCalendar::FiftyTwoFiftyThreeWeek {
+                end_month,
+                end_weekday,
+            } => get_week_periods(year, end_month, end_weekday, &[4; 13]),
+            FiscalCalendar::FourFourFive {
+                end_month,
+                end_weekday,
+            } => get_week_periods(
+                year,
+                end_month,
+                end_weekday,
+                &[4, 4, 5, 4, 4, 5, 4, 4, 5, 4, 4, 5],
+            ),
+        })
+    }
+}
+
+/// A record of a `FiscalPeriod` being closed or reopened.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PeriodChange {
+    closed: bool,
+    reason: String,
+}
+
+impl PeriodChange {
+    /// Determine if this change closed the `FiscalPeriod`, rather than reopening it.
+    pub fn is_close(&self) -> bool {
+        self.closed
+    }
+
+    /// Retrieve the reason given for the change, as a `String`.
+    pub fn get_reason(&self) -> String {
+        String::clone(&self.reason)
+    }
+}
+
+/// A single accounting period within a fiscal year.
+///
+/// Once a period has been closed, the `Ledger` rejects any change to a `Transaction` dated
+/// within it until it is reopened.
+#[derive(Clone, PartialEq, Debug)]
+pub struct FiscalPeriod {
+    id: String,
+    year: i32,
+    start: NaiveDate,
+    end: NaiveDate,
+    closed: bool,
+    history: Vec<PeriodChange>,
+}
+
+impl FiscalPeriod {
+    fn new(year: i32, number: u32, start: NaiveDate, end: NaiveDate) -> FiscalPeriod {
+        FiscalPeriod {
+            id: format!("FY{}-P{:02}", year, number),
+            year,
+            start,
+            end,
+            closed: false,
+            history: vec![],
+        }
+    }
+
+    /// Retrieve the id of this `FiscalPeriod`, such as `FY2018-P01`, as a `String`.
+    pub fn get_id(&self) -> String {
+        String::clone(&self.id)
+    }
+
+    /// Retrieve the fiscal year this `FiscalPeriod` belongs to.
+    pub fn get_year(&self) -> i32 {
+        self.year
+    }
+
+    /// Retrieve the first day of this `FiscalPeriod`.
+    pub fn get_start(&self) -> NaiveDate {
+        self.start
+    }
+
+    /// Retrieve the last day of this `FiscalPeriod`.
+    pub fn get_end(&self) -> NaiveDate {
+        self.end
+    }
+
+    /// Determine if `date` falls within this `FiscalPeriod`.
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+
+    /// Determine if this `FiscalPeriod` has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Retrieve the history of this `FiscalPeriod` being closed and reopened, oldest first.
+    pub fn get_history(&self) -> Vec<PeriodChange> {
+        Vec::clone(&self.history)
+    }
+
+    /// Convert this `FiscalPeriod`, along with its history, to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["id"] = self.id.as_str().into();
+        value["year"] = self.year.into();
+        value["start"] = self.start.to_string().into();
+        value["end"] = self.end.to_string().into();
+        value["closed"] = self.closed.into();
+        value["history"] = JsonValue::Array(
+            self.history
+                .iter()
+                .map(|change| {
+                    let mut entry = JsonValue::new_object();
+                    entry["closed"] = change.closed.into();
+                    entry["reason"] = change.reason.as_str().into();
+                    entry
+                })
+                .collect(),
+        );
+
+        value
+    }
+
+    pub(crate) fn overlaps(&self, other: &FiscalPeriod) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub(crate) fn set_closed(&mut self, closed: bool, reason: &str) -> Result<(), Error> {
+        if self.closed == closed {
+            return Err(if closed {
+                Error::ClosedPeriod(self.get_id())
+            } else {
+                Error::OpenPeriod(self.get_id())
+            });
+        }
+
+        self.closed = closed;
+        self.history.push(PeriodChange {
+            closed,
+            reason: String::from(reason),
+        });
+
+        Ok(())
+    }
+}
+
+/// Create a `FiscalPeriod` object from its JSON form, as produced by `FiscalPeriod::to_json()`.
+impl<'a> From<&'a JsonValue> for FiscalPeriod {
+    fn from(value: &'a JsonValue) -> FiscalPeriod {
+        FiscalPeriod {
+            id: String::from(value["id"].as_str().unwrap_or("")),
+            year: value["year"].as_i32().unwrap_or(0),
+            start: parse_date(&value["start"]).unwrap_or_default(),
+            end: parse_date(&value["end"]).unwrap_or_default(),
+            closed: value["closed"].as_bool().unwrap_or(false),
+            history: value["history"]
+                .members()
+                .map(|entry| PeriodChange {
+                    closed: entry["closed"].as_bool().unwrap_or(false),
+                    reason: String::from(entry["reason"].as_str().unwrap_or("")),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Find the first day of the month after the one containing `date`.
+pub(crate) fn get_next_month_start(date: NaiveDate) -> NaiveDate {
+    if date.month() == 12 {
+        NaiveDate::from_ymd_opt(date.year() + 1, 1, 1).unwrap()
+    } else {
+        NaiveDate::from_ymd_opt(date.year(), date.month() + 1, 1).unwrap()
+    }
+}
+
+/// Find the last `weekday` in the given month.
+fn get_last_weekday(year: i32, month: u32, weekday: Weekday) -> NaiveDate {
+    let mut date = get_next_month_start(NaiveDate::from_ymd_opt(year, month, 1).unwrap())
+        .pred_opt()
+        .unwrap();
+    while date.weekday() != weekday {
+        date = date.pred_opt().unwrap();
+    }
+
+    date
+}
+
+fn get_week_periods(
+    year: i32,
+    end_month: u32,
+    weekday: Weekday,
+    weeks: &[i64],
+) -> Vec<FiscalPeriod> {
+    let end = get_last_weekday(year, end_month, weekday);
+    let mut start = get_last_weekday(year - 1, end_month, weekday)
+        .succ_opt()
+        .unwrap();
+
+    let mut periods = vec![];
+    for (index, &length) in weeks.iter().enumerate() {
+        let period_end = if index == weeks.len() - 1 {
+            end
+        } else {
+            start + Duration::weeks(length) - Duration::days(1)
+        };
+        periods.push(FiscalPeriod::new(year, index as u32 + 1, start, period_end));
+        start = period_end.succ_opt().unwrap();
+    }
+
+    periods
+}
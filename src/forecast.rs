@@ -0,0 +1,278 @@
+//! Projections of `Account` balances forward from scheduled transactions and past activity.
+
+use chrono::{Duration, NaiveDate};
+
+use balance::BalanceOptions;
+use error::Error;
+use ledger::Ledger;
+use transaction::{EntrySource, Split};
+use NormalBalance;
+
+/// Options controlling how a `Forecast` projects balances.
+///
+/// By default, a `Forecast` projects the occurrences of the `Ledger`'s `ScheduledTransaction`s
+/// that have not been posted, and watches no thresholds.
+///
+/// # Examples
+/// ```
+///# extern crate chrono;
+///# extern crate cratchit;
+///# use chrono::NaiveDate;
+///# use cratchit::ForecastOptions;
+/// let options = ForecastOptions::new()
+///     .trend_since(NaiveDate::from_ymd_opt(2018, 7, 1).unwrap())
+///     .threshold("01", 0);
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct ForecastOptions {
+    include_schedules: bool,
+    trend_start: Option<NaiveDate>,
+    thresholds: Vec<(String, i64)>,
+}
+
+impl Default for ForecastOptions {
+    fn default() -> ForecastOptions {
+        ForecastOptions {
+            include_schedules: true,
+            trend_start: None,
+            thresholds: vec![],
+        }
+    }
+}
+
+impl ForecastOptions {
+    /// Create a new `ForecastOptions` object that projects scheduled occurrences only.
+    pub fn new() -> ForecastOptions {
+        ForecastOptions::default()
+    }
+
+    /// Whether the pending occurrences of `ScheduledTransaction`s should be projected.
+    pub fn include_schedules(mut self, include: bool) -> ForecastOptions {
+        self.include_schedules = include;
+        self
+    }
+
+    /// Add an estimate of unscheduled activity, projecting the average daily change from
+    /// `Transaction`s posted between `date` and the start of the `Forecast`. `Transaction`s
+    /// created from `ScheduledTransaction`s are left out of the average, since their future
+    /// occurrences are projected separately.
+    pub fn trend_since(mut self, date: NaiveDate) -> ForecastOptions {
+        self.trend_start = Some(date);
+        self
+    }
+
+    /// Watch for the balance of an `Account` falling below `amount`.
+    pub fn threshold(mut self, account_id: &str, amount: i64) -> ForecastOptions {
+        self.thresholds.retain(|entry| entry.0 != account_id);
+        self.thresholds.push((String::from(account_id), amount));
+        self
+    }
+}
+
+/// The projected daily balances of a set of `Account`s, as produced by `Ledger::get_forecast()`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Forecast {
+    start: NaiveDate,
+    series: Vec<(String, Vec<i64>)>,
+    crossings: Vec<(String, NaiveDate)>,
+}
+
+impl Forecast {
+    /// Retrieve the first date of the `Forecast`, whose balances are the actual balances.
+    pub fn get_start(&self) -> NaiveDate {
+        self.start
+    }
+
+    /// Retrieve the ids of the `Account`s projected, in the order they were requested.
+    pub fn get_account_ids(&self) -> Vec<String> {
+        self.series.iter().map(|entry| entry.0.clone()).collect()
+    }
+
+    /// Retrieve the projected balance of an `Account` at the end of each day, oldest first.
+    pub fn get_series(&self, account_id: &str) -> Vec<(NaiveDate, i64)> {
+        self.series
+            .iter()
+            .find(|entry| entry.0 == account_id)
+            .map_or(vec![], |entry| {
+                entry
+                    .1
+                    .iter()
+                    .enumerate()
+                    .map(|(day, &balance)| (self.start + Duration::days(day as i64), balance))
+                    .collect()
+            })
+    }
+
+    /// Retrieve the projected balance of an `Account` at the end of `date`, if it is within the
+    /// `Forecast`.
+    pub fn get_balance(&self, account_id: &str, date: NaiveDate) -> Option<i64> {
+        if date < self.start {
+            return None;
+        }
+
+        let day = (date - self.start).num_days() as usize;
+        self.series
+            .iter()
+            .find(|entry| entry.0 == account_id)
+            .and_then(|entry| entry.1.get(day).cloned())
+    }
+
+    /// Retrieve the first date on which the balance of an `Account` is below its threshold.
+    pub fn get_crossing(&self, account_id: &str) -> Option<NaiveDate> {
+        self.crossings
+            .iter()
+            .find(|entry| entry.0 == account_id)
+            .map(|entry| entry.1)
+    }
+
+    /// Retrieve the first date on which any `Account` is below its threshold, along with the id
+    /// of that `Account`.
+    pub fn get_first_crossing(&self) -> Option<(String, NaiveDate)> {
+        self.crossings.iter().min_by_key(|entry| entry.1).cloned()
+    }
+}
+
+impl Ledger {
+    /// Project the balances of `Account`s forward, day by day.
+    ///
+    /// The balance on `start` is the actual balance, including every `Transaction` posted on or
+    /// before it. Each later day adds the pending occurrences of `ScheduledTransaction`s falling
+    /// on that day, and the trend estimated from past activity if one was requested. Balances
+    /// include sub-accounts and are signed by each `Account`'s normal balance.
+    ///
+    /// # Arguments
+    /// * `account_ids`: The `Account`s to project.
+    /// * `start`: The date the projection starts from.
+    /// * `end`: The last date to project.
+    /// * `options`: How balances are projected, and which thresholds to watch.
+    ///
+    /// # Returns
+    /// * The `Forecast`, or `Error::UnknownAccount` if an `Account` does not exist.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, Currency, ForecastOptions, Frequency,
+    ///#               Ledger, Recurrence, ScheduledTransaction, Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("03", "Capital", "Capital", AccountType::Equity,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("05", "Rent", "Rent", AccountType::Expense,
+    ///#                                          Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// let start = NaiveDate::from_ymd_opt(2019, 1, 1).unwrap();
+    /// let mut deposit = Transaction::new("0001", start, "Opening deposit");
+    /// deposit.add_split(Split::new("01", 250000));
+    /// deposit.add_split(Split::new("03", -250000));
+    /// ledger.add_transaction(deposit).unwrap();
+    ///
+    /// let mut rent = Transaction::new("", start, "Office rent");
+    /// rent.add_split(Split::new("05", 100000));
+    /// rent.add_split(Split::new("01", -100000));
+    /// let recurrence = Recurrence::new(Frequency::MonthlyByDay(15), start);
+    /// ledger.add_schedule(ScheduledTransaction::new("RENT", rent, recurrence)).unwrap();
+    ///
+    /// let options = ForecastOptions::new().threshold("01", 0);
+    /// let forecast = ledger.get_forecast(&["01"], start,
+    ///                                    NaiveDate::from_ymd_opt(2019, 6, 30).unwrap(),
+    ///                                    &options)
+    ///     .unwrap();
+    /// assert_eq!(forecast.get_balance("01", NaiveDate::from_ymd_opt(2019, 2, 15).unwrap()),
+    ///            Some(50000));
+    /// assert_eq!(forecast.get_crossing("01"), NaiveDate::from_ymd_opt(2019, 3, 15));
+    /// ```
+    pub fn get_forecast(
+        &self,
+        account_ids: &[&str],
+        start: NaiveDate,
+        end: NaiveDate,
+        options: &ForecastOptions,
+    ) -> Result<Forecast, Error> {
+        let days = (end - start).num_days().max(0) as usize;
+        let mut forecast = Forecast {
+            start,
+            series: vec![],
+            crossings: vec![],
+        };
+
+        for &account_id in account_ids {
+            let balance = self.get_balance(
+                account_id,
+                &BalanceOptions::new()
+                    .as_of(start)
+                    .include_sub_accounts(true),
+            )?;
+            let sign = match self
+                .get_chart()
+                .get_account_by_id(account_id)
+                .map(|account| account.get_account_type().get_normal_balance())
+            {
+                Some(NormalBalance::Credit) => -1,
+                _ => 1,
+            };
+            let member_ids = self.get_chart().get_sub_tree_ids(account_id);
+            let sum = |splits: &[Split]| -> i64 {
+                splits
+                    .iter()
+                    .filter(|split| member_ids.contains(&split.get_account_id()))
+                    .map(|split| split.get_amount())
+                    .sum::<i64>()
+                    * sign
+            };
+
+            let mut changes = vec![0; days + 1];
+            if options.include_schedules {
+                for schedule in self.schedules() {
+                    for date in schedule.get_due_occurrences(end) {
+                        let occurrence = schedule.create_occurrence(date);
+                        let date = occurrence.get_date();
+                        if date > start && date <= end {
+                            changes[(date - start).num_days() as usize] += sum(occurrence.splits());
+                        }
+                    }
+                }
+            }
+
+            let mut rate = 0.0;
+            if let Some(trend_start) = options.trend_start.filter(|&date| date <= start) {
+                let history = BalanceOptions::new().since(trend_start).as_of(start);
+                let total: i64 = self
+                    .transactions()
+                    .iter()
+                    .filter(|transaction| {
+                        history.includes(transaction)
+                            && transaction.get_source() != EntrySource::Scheduled
+                    })
+                    .map(|transaction| sum(transaction.splits()))
+                    .sum();
+                rate = total as f64 / ((start - trend_start).num_days() + 1) as f64;
+            }
+
+            let mut scheduled = balance;
+            let mut series = Vec::with_capacity(days + 1);
+            for (day, change) in changes.iter().enumerate() {
+                scheduled += change;
+                series.push(scheduled + (rate * day as f64).round() as i64);
+            }
+
+            if let Some(&(_, threshold)) = options
+                .thresholds
+                .iter()
+                .find(|entry| entry.0 == account_id)
+            {
+                if let Some(day) = series.iter().position(|&balance| balance < threshold) {
+                    forecast
+                        .crossings
+                        .push((String::from(account_id), start + Duration::days(day as i64)));
+                }
+            }
+            forecast.series.push((String::from(account_id), series));
+        }
+
+        Ok(forecast)
+    }
+}
@@ -0,0 +1,331 @@
+//! The undo/redo history of a `Ledger`, built from the changes recorded in its audit trail.
+
+use std::mem;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use audit::{AuditAction, AuditEvent, AuditValue};
+use error::Error;
+use ledger::Ledger;
+use transaction::Transaction;
+use Account;
+
+/// The undo and redo stacks of a `Ledger`.
+///
+/// Each command on a stack is the list of `AuditEvent`s recorded for a single user action. Events
+/// recorded while a group is open are collected until the outermost group ends.
+#[derive(Clone, Default)]
+pub(crate) struct UndoHistory {
+    undo: Vec<Vec<AuditEvent>>,
+    redo: Vec<Vec<AuditEvent>>,
+    pending: Vec<AuditEvent>,
+    depth: usize,
+    paused: bool,
+}
+
+impl UndoHistory {
+    /// Add a newly recorded `AuditEvent` to the current user action. Any undone actions can no
+    /// longer be redone once the action is complete.
+    pub(crate) fn record(&mut self, event: &AuditEvent) {
+        if self.paused {
+            return;
+        }
+
+        self.pending.push(AuditEvent::clone(event));
+        if self.depth == 0 {
+            self.commit();
+        }
+    }
+
+    pub(crate) fn begin_group(&mut self) {
+        self.depth += 1;
+    }
+
+    pub(crate) fn end_group(&mut self) {
+        if self.depth > 0 {
+            self.depth -= 1;
+            if self.depth == 0 {
+                self.commit();
+            }
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        *self = UndoHistory::default();
+    }
+
+    /// End every open group, so that the current user action is complete.
+    fn close_groups(&mut self) {
+        self.depth = 0;
+        self.commit();
+    }
+
+    fn commit(&mut self) {
+        if !self.pending.is_empty() {
+            self.undo.push(mem::take(&mut self.pending));
+            self.redo.clear();
+        }
+    }
+}
+
+/// A single change to apply when undoing or redoing a command: the id of the `Account` or
+/// `Transaction`, the parent of an `Account`, and its current and target snapshots.
+type Change = (
+    String,
+    Option<String>,
+    Option<AuditValue>,
+    Option<AuditValue>,
+);
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Ledger {
+    /// Start grouping subsequent changes to this `Ledger` into a single user action, so that they
+    /// are undone and redone together.
+    ///
+    /// Groups may be nested. The changes are combined into one action when the outermost group
+    /// is ended with `end_group()`, or when `undo()` or `redo()` is next called.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, Currency, Ledger, Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# let date = NaiveDate::from_ymd_opt(2018, 3, 1).unwrap();
+    /// let mut ledger = Ledger::new(chart);
+    ///
+    /// ledger.begin_group();
+    /// ledger.add_top_level_account(Account::new("04", "Dues", "Dues", AccountType::Income,
+    ///                                           Currency::USDollar, false)).unwrap();
+    /// let mut dues = Transaction::new("0001", date, "Membership dues");
+    /// dues.add_split(Split::new("01", 5000));
+    /// dues.add_split(Split::new("04", -5000));
+    /// ledger.add_transaction(dues).unwrap();
+    /// ledger.end_group();
+    ///
+    /// ledger.undo().unwrap();
+    /// assert_eq!(ledger.get_num_transactions(), 0);
+    /// assert!(ledger.get_chart().get_account_by_id("04").is_none());
+    /// assert!(!ledger.can_undo());
+    /// ```
+    pub fn begin_group(&mut self) {
+        self.audit_mut().history.begin_group();
+    }
+
+    /// End a group of changes started with `begin_group()`.
+    pub fn end_group(&mut self) {
+        self.audit_mut().history.end_group();
+    }
+
+    /// Determine whether there is a change to this `Ledger` that can be undone.
+    pub fn can_undo(&self) -> bool {
+        let history = &self.audit().history;
+        !history.undo.is_empty() || !history.pending.is_empty()
+    }
+
+    /// Determine whether there is an undone change to this `Ledger` that can be redone.
+    pub fn can_redo(&self) -> bool {
+        let history = &self.audit().history;
+        !history.redo.is_empty() && history.pending.is_empty()
+    }
+
+    /// Undo the most recent user action on the `AccountsChart` or `Transaction`s of this
+    /// `Ledger`.
+    ///
+    /// Undoing is itself recorded in the audit trail, as the changes needed to restore the
+    /// previous state. The reconciliation records and posted schedule occurrences of the `Ledger`
+    /// follow the `Transaction`s they cover, but fiscal periods are not part of the undo history.
+    /// A `Transaction` restored by undoing its removal is added to the end of the journal.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the action was undone, or an `Error` if there is nothing to undo or the
+    ///   action touched a `Transaction` dated within a closed `FiscalPeriod`.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, Currency, Ledger, Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("04", "Dues", "Dues", AccountType::Income,
+    ///#                                          Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// let mut dues = Transaction::new("0001", NaiveDate::from_ymd_opt(2018, 3, 1).unwrap(),
+    ///                                 "Membership dues");
+    /// dues.add_split(Split::new("01", 5000));
+    /// dues.add_split(Split::new("04", -5000));
+    /// ledger.add_transaction(dues).unwrap();
+    ///
+    /// ledger.undo().unwrap();
+    /// assert_eq!(ledger.get_num_transactions(), 0);
+    ///
+    /// ledger.redo().unwrap();
+    /// assert_eq!(ledger.get_num_transactions(), 1);
+    /// assert!(ledger.verify_journal().is_ok());
+    /// ```
+    pub fn undo(&mut self) -> Result<(), Error> {
+        self.audit_mut().history.close_groups();
+        let command = match self.audit_mut().history.undo.pop() {
+            Some(command) => command,
+            None => return Err(Error::NothingToUndo),
+        };
+
+        let result = self.apply_command(&command, true);
+        let history = &mut self.audit_mut().history;
+        match result {
+            Ok(()) => history.redo.push(command),
+            Err(_) => history.undo.push(command),
+        }
+
+        result
+    }
+
+    /// Redo the most recently undone user action.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the action was redone, or an `Error` if there is nothing to redo or the
+    ///   action touched a `Transaction` dated within a closed `FiscalPeriod`.
+    pub fn redo(&mut self) -> Result<(), Error> {
+        self.audit_mut().history.close_groups();
+        let command = match self.audit_mut().history.redo.pop() {
+            Some(command) => command,
+            None => return Err(Error::NothingToRedo),
+        };
+
+        let result = self.apply_command(&command, false);
+        let history = &mut self.audit_mut().history;
+        match result {
+            Ok(()) => history.undo.push(command),
+            Err(_) => history.redo.push(command),
+        }
+
+        result
+    }
+}
+
+impl Ledger {
+    /// Apply the changes of a command, either reversing them (when undoing) or repeating them
+    /// (when redoing).
+    fn apply_command(&mut self, command: &[AuditEvent], undo: bool) -> Result<(), Error> {
+        let changes: Vec<Change> = if undo {
+            command
+                .iter()
+                .rev()
+                .map(|event| {
+                    (
+                        event.get_subject_id(),
+                        event.get_parent_id(),
+                        event.get_after(),
+                        event.get_before(),
+                    )
+                })
+                .collect()
+        } else {
+            command
+                .iter()
+                .map(|event| {
+                    (
+                        event.get_subject_id(),
+                        event.get_parent_id(),
+                        event.get_before(),
+                        event.get_after(),
+                    )
+                })
+                .collect()
+        };
+
+        for (_, _, current, target) in &changes {
+            for value in current.iter().chain(target.iter()) {
+                if let AuditValue::Transaction(ref transaction) = *value {
+                    self.check_unlocked(transaction)?;
+                }
+            }
+        }
+
+        self.audit_mut().history.paused = true;
+        for change in changes {
+            self.apply_change(change);
+        }
+        self.audit_mut().history.paused = false;
+        self.seal_journal();
+
+        Ok(())
+    }
+
+    fn apply_change(&mut self, (id, parent_id, current, target): Change) {
+        match (current, target) {
+            (current, Some(AuditValue::Account(account))) => {
+                let before = match current {
+                    Some(AuditValue::Account(before)) => Some(before),
+                    _ => None,
+                };
+                if self.get_chart().get_account_by_id(&id).is_some() {
+                    self.chart_mut().replace_account(&account);
+                    self.audit_mut().record_account(
+                        AuditAction::UpdateAccount,
+                        None,
+                        before,
+                        Some(account),
+                    );
+                } else {
+                    self.chart_mut()
+                        .insert_account(parent_id.as_deref(), Account::clone(&account));
+                    self.audit_mut().record_account(
+                        AuditAction::CreateAccount,
+                        parent_id,
+                        None,
+                        Some(account),
+                    );
+                }
+            }
+            (Some(AuditValue::Account(account)), None) => {
+                self.chart_mut().remove_account(&id);
+                self.audit_mut().record_account(
+                    AuditAction::RemoveAccount,
+                    parent_id,
+                    Some(account),
+                    None,
+                );
+            }
+            (_, Some(AuditValue::Transaction(transaction))) => {
+                let existing = self
+                    .transactions_mut()
+                    .iter_mut()
+                    .find(|existing| existing.get_id() == id);
+                let before = existing
+                    .as_ref()
+                    .map(|existing| Transaction::clone(existing));
+                match existing {
+                    Some(existing) => *existing = Transaction::clone(&transaction),
+                    None => self
+                        .transactions_mut()
+                        .push(Transaction::clone(&transaction)),
+                }
+                let action = if before.is_some() {
+                    AuditAction::UpdateTransaction
+                } else {
+                    AuditAction::CreateTransaction
+                };
+                self.audit_mut()
+                    .record_transaction(action, before, Some(transaction));
+            }
+            (Some(AuditValue::Transaction(transaction)), None) => {
+                self.transactions_mut()
+                    .retain(|existing| existing.get_id() != id);
+                self.audit_mut().record_transaction(
+                    AuditAction::RemoveTransaction,
+                    Some(transaction),
+                    None,
+                );
+            }
+            (None, None) => {}
+        }
+    }
+}
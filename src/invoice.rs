@@ -0,0 +1,705 @@
+//! Invoices billed to customers, and the payments received against them.
+
+use chrono::NaiveDate;
+use json::JsonValue;
+
+use error::Error;
+use ledger::Ledger;
+use transaction::{parse_date, EntrySource, Split, Transaction, TransactionStatus};
+use AccountType;
+
+/// An enumeration of the stages an `Invoice` moves through.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InvoiceStatus {
+    /// The `Invoice` is being prepared, and has not been posted to the `Ledger`.
+    Draft,
+
+    /// The `Invoice` has been issued to the customer, and nothing has been paid.
+    Sent,
+
+    /// Part of the `Invoice` has been paid, and it is not yet due.
+    PartiallyPaid,
+
+    /// The `Invoice` has been paid in full.
+    Paid,
+
+    /// The `Invoice` is past its due date with an amount still outstanding.
+    Overdue,
+
+    /// The `Invoice` was cancelled, and its entry reversed.
+    Void,
+}
+
+/// A line of an `Invoice`, charging for a quantity of goods or services.
+///
+/// # Examples
+/// ```
+///# use cratchit::InvoiceLine;
+/// let line = InvoiceLine::new("04-01", "Ice time (hours)", 2.5, 24000).discount(10.0);
+/// assert_eq!(line.get_gross(), 60000);
+/// assert_eq!(line.get_discount(), 6000);
+/// assert_eq!(line.get_amount(), 54000);
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct InvoiceLine {
+    account_id: String,
+    description: String,
+    quantity: f64,
+    unit_price: i64,
+    discount_percent: f64,
+    tax_code: Option<String>,
+}
+
+impl InvoiceLine {
+    /// Create a new `InvoiceLine` with no discount or tax.
+    ///
+    /// # Arguments
+    /// * `account_id`: The id of the Income `Account` the line is credited to.
+    /// * `description`: A description of what is charged for.
+    /// * `quantity`: The number of units charged for, which may be fractional.
+    /// * `unit_price`: The price of each unit, in the smallest unit of the currency.
+    pub fn new(account_id: &str, description: &str, quantity: f64, unit_price: i64) -> InvoiceLine {
+        InvoiceLine {
+            account_id: String::from(account_id),
+            description: String::from(description),
+            quantity,
+            unit_price,
+            discount_percent: 0.0,
+            tax_code: None,
+        }
+    }
+
+    /// Take `percent` off the price of this line.
+    pub fn discount(mut self, percent: f64) -> InvoiceLine {
+        self.discount_percent = percent;
+        self
+    }
+
+    /// Charge the tax with the given `TaxCode` on this line.
+    pub fn tax_code(mut self, code: &str) -> InvoiceLine {
+        self.tax_code = Some(String::from(code));
+        self
+    }
+
+    /// Retrieve the id of the Income `Account` this line is credited to, as a `String`.
+    pub fn get_account_id(&self) -> String {
+        String::clone(&self.account_id)
+    }
+
+    /// Retrieve the description of this line, as a `String`.
+    pub fn get_description(&self) -> String {
+        String::clone(&self.description)
+    }
+
+    /// Retrieve the number of units charged for.
+    pub fn get_quantity(&self) -> f64 {
+        self.quantity
+    }
+
+    /// Retrieve the price of each unit.
+    pub fn get_unit_price(&self) -> i64 {
+        self.unit_price
+    }
+
+    /// Retrieve the percentage taken off the price of this line.
+    pub fn get_discount_percent(&self) -> f64 {
+        self.discount_percent
+    }
+
+    /// Retrieve the code of the tax charged on this line, if any.
+    pub fn get_tax_code(&self) -> Option<String> {
+        self.tax_code.clone()
+    }
+
+    /// Compute the price of this line before its discount, rounded to the nearest unit.
+    pub fn get_gross(&self) -> i64 {
+        (self.quantity * self.unit_price as f64).round() as i64
+    }
+
+    /// Compute the discount taken off this line, rounded to the nearest unit.
+    pub fn get_discount(&self) -> i64 {
+        (self.get_gross() as f64 * self.discount_percent / 100.0).round() as i64
+    }
+
+    /// Compute the amount charged for this line after its discount, before tax.
+    pub fn get_amount(&self) -> i64 {
+        self.get_gross() - self.get_discount()
+    }
+
+    /// Convert this `InvoiceLine` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["account_id"] = self.account_id.as_str().into();
+        value["description"] = self.description.as_str().into();
+        value["quantity"] = self.quantity.into();
+        value["unit_price"] = self.unit_price.into();
+        value["discount_percent"] = self.discount_percent.into();
+        value["tax_code"] = self.tax_code.clone().into();
+
+        value
+    }
+}
+
+/// Create an `InvoiceLine` object from its JSON form, as produced by `InvoiceLine::to_json()`.
+impl<'a> From<&'a JsonValue> for InvoiceLine {
+    fn from(value: &'a JsonValue) -> InvoiceLine {
+        let mut line = InvoiceLine::new(
+            value["account_id"].as_str().unwrap_or(""),
+            value["description"].as_str().unwrap_or(""),
+            value["quantity"].as_f64().unwrap_or(0.0),
+            value["unit_price"].as_i64().unwrap_or(0),
+        )
+        .discount(value["discount_percent"].as_f64().unwrap_or(0.0));
+        line.tax_code = value["tax_code"].as_str().map(String::from);
+
+        line
+    }
+}
+
+/// A payment received against an `Invoice`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct InvoicePayment {
+    transaction_id: String,
+    date: NaiveDate,
+    amount: i64,
+}
+
+impl InvoicePayment {
+    /// Retrieve the id of the `Transaction` recording the payment, as a `String`.
+    pub fn get_transaction_id(&self) -> String {
+        String::clone(&self.transaction_id)
+    }
+
+    /// Retrieve the date the payment was received.
+    pub fn get_date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// Retrieve the amount paid.
+    pub fn get_amount(&self) -> i64 {
+        self.amount
+    }
+
+    fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["transaction_id"] = self.transaction_id.as_str().into();
+        value["date"] = self.date.to_string().into();
+        value["amount"] = self.amount.into();
+
+        value
+    }
+}
+
+/// A bill for goods or services sold to a customer `Contact`.
+///
+/// An `Invoice` is added to a `Ledger` as a draft, and can be edited until it is issued with
+/// `Ledger::issue_invoice()`, which debits the customer's receivable `Account` with the total,
+/// and credits the Income `Account` of each line and the `Account` of each tax charged.
+/// Payments are then applied against it until nothing is outstanding.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Invoice {
+    id: String,
+    contact_id: String,
+    date: NaiveDate,
+    due_date: Option<NaiveDate>,
+    lines: Vec<InvoiceLine>,
+    tax_rates: Vec<(String, f64, String)>,
+    issued: bool,
+    voided: bool,
+    payments: Vec<InvoicePayment>,
+}
+
+impl Invoice {
+    /// Create a new draft `Invoice` with no lines.
+    ///
+    /// # Arguments
+    /// * `id`: The invoice number. It is also the id of the `Transaction` posted when the
+    ///   `Invoice` is issued.
+    /// * `contact_id`: The id of the customer `Contact` billed.
+    /// * `date`: The date of the `Invoice`.
+    pub fn new(id: &str, contact_id: &str, date: NaiveDate) -> Invoice {
+        Invoice {
+            id: String::from(id),
+            contact_id: String::from(contact_id),
+            date,
+            due_date: None,
+            lines: vec![],
+            tax_rates: vec![],
+            issued: false,
+            voided: false,
+            payments: vec![],
+        }
+    }
+
+    /// Retrieve the invoice number, as a `String`.
+    pub fn get_id(&self) -> String {
+        String::clone(&self.id)
+    }
+
+    /// Retrieve the id of the customer `Contact` billed, as a `String`.
+    pub fn get_contact_id(&self) -> String {
+        String::clone(&self.contact_id)
+    }
+
+    /// Retrieve the date of this `Invoice`.
+    pub fn get_date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// Retrieve the date payment is due. Unless set, an `Invoice` retrieved from a `Ledger` is
+    /// due according to the `PaymentTerms` of its `Contact`, as they were when it was issued.
+    pub fn get_due_date(&self) -> Option<NaiveDate> {
+        self.due_date
+    }
+
+    /// Set the date payment is due, overriding the `PaymentTerms` of the `Contact`.
+    pub fn set_due_date(&mut self, date: NaiveDate) {
+        self.due_date = Some(date);
+    }
+
+    /// Add a line to this `Invoice`.
+    pub fn add_line(&mut self, line: InvoiceLine) {
+        self.lines.push(line);
+    }
+
+    /// Retrieve the lines of this `Invoice`.
+    pub fn get_lines(&self) -> Vec<InvoiceLine> {
+        Vec::clone(&self.lines)
+    }
+
+    /// Compute the amount charged for the lines of this `Invoice`, after discounts and before
+    /// tax.
+    pub fn get_subtotal(&self) -> i64 {
+        self.lines.iter().map(InvoiceLine::get_amount).sum()
+    }
+
+    /// Compute the tax charged on this `Invoice`, from the rates of the `TaxCode`s in the
+    /// `Ledger` it was retrieved from. Each tax is computed on the total of the lines it applies
+    /// to, so rounding is applied once per tax.
+    pub fn get_tax(&self) -> i64 {
+        self.get_tax_amounts().iter().map(|entry| entry.1).sum()
+    }
+
+    /// Compute the total of this `Invoice`, including tax.
+    pub fn get_total(&self) -> i64 {
+        self.get_subtotal() + self.get_tax()
+    }
+
+    /// Retrieve the payments applied against this `Invoice`, oldest first.
+    pub fn get_payments(&self) -> Vec<InvoicePayment> {
+        Vec::clone(&self.payments)
+    }
+
+    /// Compute the total paid against this `Invoice`.
+    pub fn get_paid(&self) -> i64 {
+        self.payments.iter().map(|payment| payment.amount).sum()
+    }
+
+    /// Compute the amount of this `Invoice` still to be paid. Nothing is outstanding on a draft
+    /// or void `Invoice`.
+    pub fn get_outstanding(&self) -> i64 {
+        if self.issued && !self.voided {
+            self.get_total() - self.get_paid()
+        } else {
+            0
+        }
+    }
+
+    /// Determine the `InvoiceStatus` of this `Invoice` on the given date. An issued `Invoice`
+    /// with an amount outstanding after its due date is `Overdue`, even if part of it was paid.
+    pub fn get_status(&self, today: NaiveDate) -> InvoiceStatus {
+        if self.voided {
+            InvoiceStatus::Void
+        } else if !self.issued {
+            InvoiceStatus::Draft
+        } else if self.get_outstanding() <= 0 {
+            InvoiceStatus::Paid
+        } else if self.due_date.is_some_and(|due| today > due) {
+            InvoiceStatus::Overdue
+        } else if self.get_paid() > 0 {
+            InvoiceStatus::PartiallyPaid
+        } else {
+            InvoiceStatus::Sent
+        }
+    }
+
+    /// Convert this `Invoice` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["id"] = self.id.as_str().into();
+        value["contact_id"] = self.contact_id.as_str().into();
+        value["date"] = self.date.to_string().into();
+        value["due_date"] = self.due_date.map(|date| date.to_string()).into();
+        value["lines"] = JsonValue::Array(self.lines.iter().map(InvoiceLine::to_json).collect());
+        value["issued"] = self.issued.into();
+        value["voided"] = self.voided.into();
+        value["payments"] =
+            JsonValue::Array(self.payments.iter().map(InvoicePayment::to_json).collect());
+
+        value
+    }
+
+    /// Compute the tax charged for each `TaxCode`, along with the `Account` it is credited to.
+    fn get_tax_amounts(&self) -> Vec<(String, i64)> {
+        self.tax_rates
+            .iter()
+            .map(|&(ref code, rate, ref account_id)| {
+                let taxable: i64 = self
+                    .lines
+                    .iter()
+                    .filter(|line| line.tax_code.as_ref() == Some(code))
+                    .map(InvoiceLine::get_amount)
+                    .sum();
+                (
+                    String::clone(account_id),
+                    (taxable as f64 * rate / 100.0).round() as i64,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Create an `Invoice` object from its JSON form, as produced by `Invoice::to_json()`.
+impl<'a> From<&'a JsonValue> for Invoice {
+    fn from(value: &'a JsonValue) -> Invoice {
+        let mut invoice = Invoice::new(
+            value["id"].as_str().unwrap_or(""),
+            value["contact_id"].as_str().unwrap_or(""),
+            parse_date(&value["date"]).unwrap_or_default(),
+        );
+        invoice.due_date = parse_date(&value["due_date"]);
+        invoice.lines = value["lines"].members().map(InvoiceLine::from).collect();
+        invoice.issued = value["issued"].as_bool().unwrap_or(false);
+        invoice.voided = value["voided"].as_bool().unwrap_or(false);
+        invoice.payments = value["payments"]
+            .members()
+            .filter_map(|payment| {
+                parse_date(&payment["date"]).map(|date| InvoicePayment {
+                    transaction_id: String::from(payment["transaction_id"].as_str().unwrap_or("")),
+                    date,
+                    amount: payment["amount"].as_i64().unwrap_or(0),
+                })
+            })
+            .collect();
+
+        invoice
+    }
+}
+
+impl Ledger {
+    /// Add a draft `Invoice` to this `Ledger`. Nothing is posted until it is issued.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Invoice` was added, or an `Error` if an `Invoice` with the same id
+    ///   already exists, its `Contact` or a `TaxCode` does not exist, or a line is not credited
+    ///   to an Income `Account`.
+    pub fn add_invoice(&mut self, invoice: Invoice) -> Result<(), Error> {
+        if self.get_invoice(&invoice.id).is_some() {
+            return Err(Error::DuplicateInvoice(invoice.id));
+        }
+        self.check_invoice(&invoice)?;
+
+        self.invoices_mut().push(Invoice {
+            issued: false,
+            voided: false,
+            payments: vec![],
+            tax_rates: vec![],
+            ..invoice
+        });
+
+        Ok(())
+    }
+
+    /// Replace a draft `Invoice` with an edited copy.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Invoice` was replaced, `Error::UnknownInvoice` if it does not exist,
+    ///   `Error::InvalidInvoiceState` if it is no longer a draft, or an `Error` if the edited
+    ///   copy is not valid.
+    pub fn update_invoice(&mut self, invoice: Invoice) -> Result<(), Error> {
+        self.get_draft_invoice(&invoice.id)?;
+        self.check_invoice(&invoice)?;
+
+        let index = self.get_invoice_index(&invoice.id)?;
+        let existing = &mut self.invoices_mut()[index];
+        existing.contact_id = invoice.contact_id;
+        existing.date = invoice.date;
+        existing.due_date = invoice.due_date;
+        existing.lines = invoice.lines;
+
+        Ok(())
+    }
+
+    /// Issue a draft `Invoice`, posting its `Transaction`.
+    ///
+    /// The `Transaction` has the id and date of the `Invoice` and is marked with
+    /// `EntrySource::Invoice`. It debits the total to the control `Account` of the customer
+    /// `Contact`, tagged with the `Contact`, and credits each line and each tax.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Invoice` was issued, or an `Error` if it is not a draft, its `Contact`
+    ///   has no control `Account`, or the `Transaction` cannot be added.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Contact, ContactKind,
+    ///#               Currency, Invoice, InvoiceLine, InvoiceStatus, Ledger, PaymentTerms};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("02", "Receivable", "Receivable",
+    ///#                                          AccountType::Asset, Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("04", "Ice Time", "Ice Time",
+    ///#                                          AccountType::Income, Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// let mut school = Contact::new("LNHS", "Lakeville North", ContactKind::Customer);
+    /// school.set_control_account("02");
+    /// school.set_payment_terms(PaymentTerms::net(30));
+    /// ledger.add_contact(school).unwrap();
+    ///
+    /// let date = NaiveDate::from_ymd_opt(2018, 11, 3).unwrap();
+    /// let mut invoice = Invoice::new("INV-1", "LNHS", date);
+    /// invoice.add_line(InvoiceLine::new("04", "Ice time (hours)", 2.0, 25000));
+    /// ledger.add_invoice(invoice).unwrap();
+    /// ledger.issue_invoice("INV-1").unwrap();
+    /// ledger.record_invoice_payment("INV-1", "PMT-1", date, 20000, "01").unwrap();
+    ///
+    /// let invoice = ledger.get_invoice("INV-1").unwrap();
+    /// assert_eq!(invoice.get_outstanding(), 30000);
+    /// assert_eq!(invoice.get_status(date), InvoiceStatus::PartiallyPaid);
+    /// assert_eq!(ledger.get_balance("02", &BalanceOptions::new()), Ok(30000));
+    /// ```
+    pub fn issue_invoice(&mut self, id: &str) -> Result<(), Error> {
+        let invoice = self.get_draft_invoice(id)?;
+        let receivable_id = match self
+            .get_contact(&invoice.contact_id)
+            .and_then(|contact| contact.get_control_account())
+        {
+            Some(account_id) => account_id,
+            None => return Err(Error::NoControlAccount(invoice.get_contact_id())),
+        };
+
+        let mut transaction = Transaction::new(
+            &invoice.id,
+            invoice.date,
+            &format!("Invoice {}", invoice.id),
+        );
+        transaction.set_source(EntrySource::Invoice);
+        let mut receivable = Split::new(&receivable_id, invoice.get_total());
+        receivable.set_contact(&invoice.contact_id);
+        transaction.add_split(receivable);
+        for line in &invoice.lines {
+            let mut split = Split::new(&line.account_id, -line.get_amount());
+            split.set_memo(&line.description);
+            transaction.add_split(split);
+        }
+        for (account_id, tax) in invoice.get_tax_amounts() {
+            if tax == 0 {
+                continue;
+            }
+            transaction.add_split(Split::new(&account_id, -tax));
+        }
+
+        self.add_transaction(transaction)?;
+        let index = self.get_invoice_index(id)?;
+        let issued = &mut self.invoices_mut()[index];
+        issued.issued = true;
+        issued.due_date = invoice.due_date;
+
+        Ok(())
+    }
+
+    /// Apply a payment received against an issued `Invoice`, posting a `Transaction` that debits
+    /// the deposit `Account` and credits the customer's control `Account`.
+    ///
+    /// # Arguments
+    /// * `id`: The id of the `Invoice` paid.
+    /// * `transaction_id`: The id of the `Transaction` to post.
+    /// * `date`: The date the payment was received.
+    /// * `amount`: The amount paid, which may be less than the amount outstanding.
+    /// * `account_id`: The id of the `Account` the payment was deposited to.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the payment was applied, `Error::InvalidInvoiceState` if the `Invoice` is
+    ///   not issued or the amount is not positive, `Error::Overpayment` if the amount is more than
+    ///   is outstanding, or an `Error` if the `Transaction` cannot be added.
+    pub fn record_invoice_payment(
+        &mut self,
+        id: &str,
+        transaction_id: &str,
+        date: NaiveDate,
+        amount: i64,
+        account_id: &str,
+    ) -> Result<(), Error> {
+        let invoice = self.get_invoice_or_error(id)?;
+        if !invoice.issued || invoice.voided || amount <= 0 {
+            return Err(Error::InvalidInvoiceState(String::from(id)));
+        }
+        if amount > invoice.get_outstanding() {
+            return Err(Error::Overpayment(String::from(id)));
+        }
+        let receivable_id = self
+            .find_invoice_split(id)
+            .map(|split| split.get_account_id())
+            .unwrap_or_default();
+
+        let mut transaction =
+            Transaction::new(transaction_id, date, &format!("Payment for invoice {}", id));
+        transaction.set_source(EntrySource::Invoice);
+        transaction.add_split(Split::new(account_id, amount));
+        let mut receivable = Split::new(&receivable_id, -amount);
+        receivable.set_contact(&invoice.contact_id);
+        transaction.add_split(receivable);
+        self.add_transaction(transaction)?;
+
+        let index = self.get_invoice_index(id)?;
+        self.invoices_mut()[index].payments.push(InvoicePayment {
+            transaction_id: String::from(transaction_id),
+            date,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Void an `Invoice`. If it was issued, its `Transaction` is voided with a reversal dated
+    /// `date`.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Invoice` was voided, `Error::InvalidInvoiceState` if it is already void
+    ///   or payments have been applied against it, or an `Error` if its `Transaction` cannot be
+    ///   voided.
+    pub fn void_invoice(&mut self, id: &str, date: NaiveDate) -> Result<(), Error> {
+        let invoice = self.get_invoice_or_error(id)?;
+        if invoice.voided || !invoice.payments.is_empty() {
+            return Err(Error::InvalidInvoiceState(String::from(id)));
+        }
+
+        if invoice.issued {
+            self.void_transaction(id, date)?;
+        } else {
+            let index = self.get_invoice_index(id)?;
+            self.invoices_mut()[index].voided = true;
+        }
+
+        Ok(())
+    }
+
+    /// Retrieve the `Invoice` with the given id, if it has been added to this `Ledger`.
+    ///
+    /// Whether the `Invoice` is issued or void, and which payments are applied against it,
+    /// follow the `Transaction`s in the `Ledger`, so undoing or voiding one of them is reflected
+    /// here.
+    pub fn get_invoice(&self, id: &str) -> Option<Invoice> {
+        self.invoices()
+            .iter()
+            .find(|invoice| invoice.id == id)
+            .map(|invoice| self.sync_invoice(invoice))
+    }
+
+    /// Retrieve every `Invoice` added to this `Ledger`, in the order they were added.
+    pub fn get_invoices(&self) -> Vec<Invoice> {
+        self.invoices()
+            .iter()
+            .map(|invoice| self.sync_invoice(invoice))
+            .collect()
+    }
+
+    /// Bring the state of a stored `Invoice` up to date with the `Ledger`.
+    fn sync_invoice(&self, invoice: &Invoice) -> Invoice {
+        let get_status = |id: &str| {
+            self.get_transaction_by_id(id)
+                .map(|transaction| transaction.get_status())
+        };
+        let posted =
+            |id: &str| get_status(id).is_some_and(|status| status != TransactionStatus::Voided);
+
+        let mut invoice = Invoice::clone(invoice);
+        if invoice.issued {
+            invoice.voided = get_status(&invoice.id) == Some(TransactionStatus::Voided);
+            invoice.issued = posted(&invoice.id);
+        }
+        invoice
+            .payments
+            .retain(|payment| posted(&payment.transaction_id));
+        if invoice.due_date.is_none() {
+            invoice.due_date = self
+                .get_contact(&invoice.contact_id)
+                .map(|contact| contact.get_payment_terms().get_due_date(invoice.date));
+        }
+
+        let mut codes: Vec<String> = invoice
+            .lines
+            .iter()
+            .filter_map(InvoiceLine::get_tax_code)
+            .collect();
+        codes.sort();
+        codes.dedup();
+        invoice.tax_rates = codes
+            .into_iter()
+            .filter_map(|code| self.get_tax_code(&code))
+            .map(|tax_code| {
+                (
+                    tax_code.get_code(),
+                    tax_code.get_rate(),
+                    tax_code.get_account_id(),
+                )
+            })
+            .collect();
+
+        invoice
+    }
+
+    /// Find the `Split` debiting the receivable `Account` in the `Transaction` issuing an
+    /// `Invoice`.
+    fn find_invoice_split(&self, id: &str) -> Option<Split> {
+        self.get_transaction_by_id(id)
+            .and_then(|transaction| transaction.get_splits().into_iter().next())
+    }
+
+    fn get_invoice_or_error(&self, id: &str) -> Result<Invoice, Error> {
+        self.get_invoice(id)
+            .ok_or_else(|| Error::UnknownInvoice(String::from(id)))
+    }
+
+    fn get_draft_invoice(&self, id: &str) -> Result<Invoice, Error> {
+        let invoice = self.get_invoice_or_error(id)?;
+        if invoice.issued || invoice.voided {
+            return Err(Error::InvalidInvoiceState(String::from(id)));
+        }
+
+        Ok(invoice)
+    }
+
+    fn get_invoice_index(&self, id: &str) -> Result<usize, Error> {
+        self.invoices()
+            .iter()
+            .position(|invoice| invoice.id == id)
+            .ok_or_else(|| Error::UnknownInvoice(String::from(id)))
+    }
+
+    /// Ensure that the `Contact`, `Account`s and `TaxCode`s named by an `Invoice` exist.
+    fn check_invoice(&self, invoice: &Invoice) -> Result<(), Error> {
+        if self.get_contact(&invoice.contact_id).is_none() {
+            return Err(Error::UnknownContact(invoice.get_contact_id()));
+        }
+
+        for line in &invoice.lines {
+            match self.get_chart().get_account_by_id(&line.account_id) {
+                Some(ref account) if account.get_account_type() == AccountType::Income => {}
+                Some(_) => return Err(Error::WrongAccountType(line.get_account_id())),
+                None => return Err(Error::UnknownAccount(line.get_account_id())),
+            }
+            if let Some(ref code) = line.tax_code {
+                if self.get_tax_code(code).is_none() {
+                    return Err(Error::UnknownTaxCode(String::clone(code)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
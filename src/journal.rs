@@ -0,0 +1,519 @@
+//! The tamper-evident hash chain over the journal of a `Ledger`, and the JSON form in which a
+//! `Ledger` is saved.
+
+use std::convert::TryFrom;
+
+use json::JsonValue;
+use sha2::{Digest, Sha256};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use audit::AuditEvent;
+use bill::Bill;
+use commodity::Commodity;
+use contact::Contact;
+use corporate_action::CorporateAction;
+use error::Error;
+use fiscal::FiscalPeriod;
+use invoice::Invoice;
+use ledger::Ledger;
+use price::PriceDatabase;
+use reconciliation::ReconciliationRecord;
+use schedule::ScheduledTransaction;
+use tax::TaxCode;
+use transaction::{parse_date, Transaction};
+use {Account, AccountsChart};
+
+/// The previous hash of the first link in a hash chain.
+pub(crate) const GENESIS_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// A link in the hash chain over the journal of a `Ledger`.
+///
+/// The chain is append-only: each time a `Transaction` is added to, changed in or removed from
+/// the journal, a link is appended recording the SHA-256 digest of the canonical JSON form of
+/// the `Transaction`, or its removal. The hash of each link is the digest of the hash of the
+/// previous link followed by the id and digest it records, so altering, inserting or removing any
+/// link changes the hash of every later link, and the chain holds the history of every entry.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChainLink {
+    transaction_id: String,
+    content_hash: Option<String>,
+    previous_hash: String,
+    hash: String,
+}
+
+impl ChainLink {
+    fn new(previous_hash: &str, transaction_id: &str, content_hash: Option<String>) -> ChainLink {
+        let mut link = ChainLink {
+            transaction_id: String::from(transaction_id),
+            content_hash,
+            previous_hash: String::from(previous_hash),
+            hash: String::new(),
+        };
+        link.hash = link.compute_hash();
+
+        link
+    }
+
+    /// Retrieve the id of the `Transaction` this link covers, as a `String`.
+    pub fn get_transaction_id(&self) -> String {
+        String::clone(&self.transaction_id)
+    }
+
+    /// Retrieve the digest of the `Transaction` as of this link, as a hexadecimal `String`, or
+    /// `None` if the link records its removal from the journal.
+    pub fn get_content_hash(&self) -> Option<String> {
+        self.content_hash.clone()
+    }
+
+    /// Retrieve the hash of the previous link, as a hexadecimal `String`.
+    pub fn get_previous_hash(&self) -> String {
+        String::clone(&self.previous_hash)
+    }
+
+    /// Retrieve the hash of this link, as a hexadecimal `String`.
+    pub fn get_hash(&self) -> String {
+        String::clone(&self.hash)
+    }
+
+    /// Compute the hash of this link from the hash of the previous link and what it records.
+    fn compute_hash(&self) -> String {
+        let mut value = JsonValue::new_object();
+        value["transaction_id"] = self.transaction_id.as_str().into();
+        value["content_hash"] = self.content_hash.clone().into();
+
+        digest(&format!("{}{}", self.previous_hash, value.dump()))
+    }
+
+    /// Convert this `ChainLink` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["transaction_id"] = self.transaction_id.as_str().into();
+        value["content_hash"] = self.content_hash.clone().into();
+        value["previous_hash"] = self.previous_hash.as_str().into();
+        value["hash"] = self.hash.as_str().into();
+
+        value
+    }
+}
+
+/// Create a `ChainLink` object from its JSON form, as produced by `ChainLink::to_json()`.
+///
+/// A link without a `Transaction` id and both hashes is rejected with `Error::InvalidJson`.
+impl<'a> TryFrom<&'a JsonValue> for ChainLink {
+    type Error = Error;
+
+    fn try_from(value: &'a JsonValue) -> Result<ChainLink, Error> {
+        match (
+            value["transaction_id"].as_str(),
+            value["previous_hash"].as_str(),
+            value["hash"].as_str(),
+        ) {
+            (Some(transaction_id), Some(previous_hash), Some(hash)) => Ok(ChainLink {
+                transaction_id: String::from(transaction_id),
+                content_hash: value["content_hash"].as_str().map(String::from),
+                previous_hash: String::from(previous_hash),
+                hash: String::from(hash),
+            }),
+            _ => Err(Error::InvalidJson(String::from(
+                "a chain link has no 'transaction_id', 'previous_hash' or 'hash'",
+            ))),
+        }
+    }
+}
+
+impl Ledger {
+    /// Retrieve the hash chain over the journal of this `Ledger`, with one link for each time a
+    /// `Transaction` was added, changed or removed, oldest first.
+    pub fn get_chain(&self) -> Vec<ChainLink> {
+        self.chain().to_vec()
+    }
+
+    /// Walk the hash chain of this `Ledger`, checking that every link is intact, and that every
+    /// `Transaction` in the journal matches the latest link recorded for it.
+    ///
+    /// Every change made through the `Ledger`, including undoing and redoing, appends to the
+    /// chain rather than rewriting it. Verification therefore fails if the journal or its history
+    /// was altered some other way, e.g. by editing a saved file.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the chain is intact, or `Error::BrokenChain` with the id of the first
+    ///   `Transaction` whose link is broken, which does not match its latest link, or which the
+    ///   chain records as present but is missing from the journal.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# extern crate json;
+    ///# use std::convert::TryFrom;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, Currency, Error, Ledger, Split,
+    ///#               Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("04", "Dues", "Dues", AccountType::Income,
+    ///#                                          Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// let mut dues = Transaction::new("0001", NaiveDate::from_ymd_opt(2018, 3, 1).unwrap(),
+    ///                                 "Membership dues");
+    /// dues.add_split(Split::new("01", 5000));
+    /// dues.add_split(Split::new("04", -5000));
+    /// ledger.add_transaction(dues).unwrap();
+    ///
+    /// let mut saved = ledger.to_json();
+    /// assert!(Ledger::try_from(&saved).unwrap().verify_journal().is_ok());
+    ///
+    /// saved["transactions"][0]["splits"][0]["amount"] = 500.into();
+    /// saved["transactions"][0]["splits"][1]["amount"] = (-500).into();
+    /// assert_eq!(Ledger::try_from(&saved).unwrap().verify_journal(),
+    ///            Err(Error::BrokenChain(String::from("0001"))));
+    /// ```
+    pub fn verify_journal(&self) -> Result<(), Error> {
+        let mut previous_hash = GENESIS_HASH;
+        for link in self.chain() {
+            if link.previous_hash != previous_hash || link.hash != link.compute_hash() {
+                return Err(Error::BrokenChain(link.get_transaction_id()));
+            }
+            previous_hash = &link.hash;
+        }
+
+        let mut sealed = get_sealed(self.chain());
+        for transaction in self.transactions() {
+            match sealed
+                .iter()
+                .position(|(id, _)| *id == transaction.get_id())
+            {
+                Some(index) if sealed[index].1 == Some(hash_transaction(transaction)) => {
+                    sealed.remove(index);
+                }
+                _ => return Err(Error::BrokenChain(transaction.get_id())),
+            }
+        }
+
+        match sealed
+            .into_iter()
+            .find(|(_, content_hash)| content_hash.is_some())
+        {
+            Some((id, _)) => Err(Error::BrokenChain(id)),
+            None => Ok(()),
+        }
+    }
+
+    /// Append a link to the hash chain for each `Transaction` added to, changed in or removed
+    /// from the journal since the chain was last appended to.
+    pub(crate) fn seal_journal(&mut self) {
+        let mut sealed = get_sealed(self.chain());
+        let mut changes = vec![];
+        for transaction in self.transactions() {
+            let content_hash = Some(hash_transaction(transaction));
+            match sealed
+                .iter()
+                .position(|(id, _)| *id == transaction.get_id())
+            {
+                Some(index) if sealed[index].1 == content_hash => {}
+                _ => changes.push((transaction.get_id(), content_hash)),
+            }
+            sealed.retain(|(id, _)| *id != transaction.get_id());
+        }
+        changes.extend(
+            sealed
+                .into_iter()
+                .filter(|(_, content_hash)| content_hash.is_some())
+                .map(|(id, _)| (id, None)),
+        );
+
+        for (id, content_hash) in changes {
+            self.seal_transaction(&id, content_hash);
+        }
+    }
+
+    /// Append a link to the hash chain recording the state of the `Transaction` with the given id,
+    /// as the digest of its canonical JSON form, or `None` if it was removed.
+    pub(crate) fn seal_transaction(&mut self, id: &str, content_hash: Option<String>) {
+        let previous_hash = match self.chain().last() {
+            Some(link) => link.get_hash(),
+            None => String::from(GENESIS_HASH),
+        };
+        self.chain_mut()
+            .push(ChainLink::new(&previous_hash, id, content_hash));
+    }
+
+    /// Convert this `Ledger` to the JSON form in which it is saved.
+    ///
+    /// The JSON form contains the `AccountsChart` (in the same form as `AccountsChart::to_json()`),
+    /// the journal of `Transaction`s, the hash chain over the journal, the audit trail, the
+    /// `FiscalPeriod`s, the `ReconciliationRecord`s, the `ScheduledTransaction`s, the quotes of the
+    /// `PriceDatabase`, the `Commodity` definitions, the `CorporateAction`s, the `Contact`s, the
+    /// `TaxCode`s, the `Invoice`s and the `Bill`s. The undo history is not saved.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = self.get_chart().to_json();
+        value["transactions"] = JsonValue::Array(
+            self.transactions()
+                .iter()
+                .map(Transaction::to_json)
+                .collect(),
+        );
+        value["chain"] = JsonValue::Array(self.chain().iter().map(ChainLink::to_json).collect());
+        value["audit"] = JsonValue::Array(
+            self.get_audit_log()
+                .iter()
+                .map(AuditEvent::to_json)
+                .collect(),
+        );
+        value["periods"] =
+            JsonValue::Array(self.periods().iter().map(FiscalPeriod::to_json).collect());
+        value["reconciliations"] = JsonValue::Array(
+            self.reconciliations()
+                .iter()
+                .filter(|record| record.is_current(self))
+                .map(ReconciliationRecord::to_json)
+                .collect(),
+        );
+        value["schedules"] = JsonValue::Array(
+            self.schedules()
+                .iter()
+                .map(ScheduledTransaction::to_json)
+                .collect(),
+        );
+        value["prices"] = self.prices().to_json();
+        value["commodities"] =
+            JsonValue::Array(self.commodities().iter().map(Commodity::to_json).collect());
+        value["corporate_actions"] = JsonValue::Array(
+            self.corporate_actions()
+                .iter()
+                .map(CorporateAction::to_json)
+                .collect(),
+        );
+        value["contacts"] =
+            JsonValue::Array(self.contacts().iter().map(Contact::to_json).collect());
+        value["tax_codes"] =
+            JsonValue::Array(self.tax_codes().iter().map(TaxCode::to_json).collect());
+        value["invoices"] =
+            JsonValue::Array(self.invoices().iter().map(Invoice::to_json).collect());
+        value["bills"] = JsonValue::Array(self.bills().iter().map(Bill::to_json).collect());
+
+        value
+    }
+}
+
+/// The text form of a `Ledger`, used by the WebAssembly API, where `Account`s and `Transaction`s
+/// are passed in the same JSON forms as `Account::to_json()` and `Transaction::to_json()`.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Ledger {
+    /// Load a `Ledger` from text holding the JSON form produced by `to_json_string()`. This is
+    /// the constructor of a `Ledger` in JavaScript.
+    ///
+    /// # Returns
+    /// * The loaded `Ledger`, or `Error::InvalidJson` if `json` cannot be parsed or does not hold
+    ///   a valid `Ledger`.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate cratchit;
+    ///# use cratchit::Ledger;
+    /// let mut ledger = Ledger::from_json_string(r#"{ "accounts": [] }"#).unwrap();
+    /// ledger.add_account_json(None, r#"{ "id": "01", "name": "Checking",
+    ///     "description": "Checking", "type": 1, "currency": "USD", "placeholder": false,
+    ///     "subaccounts": [] }"#).unwrap();
+    /// ledger.add_account_json(None, r#"{ "id": "04", "name": "Dues", "description": "Dues",
+    ///     "type": 4, "currency": "USD", "placeholder": false, "subaccounts": [] }"#).unwrap();
+    /// ledger.add_transaction_json(r#"{ "id": "0001", "date": "2018-03-01",
+    ///     "description": "Membership dues", "splits": [
+    ///         { "account_id": "01", "amount": 5000 },
+    ///         { "account_id": "04", "amount": -5000 } ] }"#).unwrap();
+    ///
+    /// ledger.undo().unwrap();
+    /// assert_eq!(ledger.get_num_transactions(), 0);
+    ///
+    /// let reloaded = Ledger::from_json_string(&ledger.to_json_string()).unwrap();
+    /// assert_eq!(reloaded.get_chart().get_num_accounts(), 2);
+    /// ```
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen(constructor))]
+    pub fn from_json_string(json: &str) -> Result<Ledger, Error> {
+        Ledger::try_from(&parse_json(json)?)
+    }
+
+    /// Convert this `Ledger` to text holding the JSON form produced by `to_json()`.
+    pub fn to_json_string(&self) -> String {
+        self.to_json().dump()
+    }
+
+    /// Add an `Account` given in JSON form, along with its sub-accounts, as a sub-account of the
+    /// `Account` with id `parent_id`, or as a top-level `Account` if there is no parent.
+    ///
+    /// See `add_sub_account()` and `add_top_level_account()` for details.
+    pub fn add_account_json(&mut self, parent_id: Option<String>, json: &str) -> Result<(), Error> {
+        let account = parse_account(json)?;
+        match parent_id {
+            Some(parent_id) => self.add_sub_account(&parent_id, account),
+            None => self.add_top_level_account(account),
+        }
+    }
+
+    /// Replace the details of an `Account` with those given in JSON form.
+    ///
+    /// See `update_account()` for details.
+    pub fn update_account_json(&mut self, json: &str) -> Result<(), Error> {
+        self.update_account(parse_account(json)?)
+    }
+
+    /// Add a `Transaction` given in JSON form.
+    ///
+    /// See `add_transaction()` for details.
+    pub fn add_transaction_json(&mut self, json: &str) -> Result<(), Error> {
+        self.add_transaction(parse_transaction(json)?)
+    }
+
+    /// Replace the `Transaction` with the same id as one given in JSON form.
+    ///
+    /// See `update_transaction()` for details.
+    pub fn update_transaction_json(&mut self, json: &str) -> Result<(), Error> {
+        self.update_transaction(parse_transaction(json)?)
+    }
+
+    /// Remove the `Transaction` with the given id.
+    ///
+    /// See `remove_transaction()` for details.
+    ///
+    /// # Returns
+    /// * The removed `Transaction` in JSON form, or an `Error` if it could not be removed.
+    pub fn remove_transaction_json(&mut self, id: &str) -> Result<String, Error> {
+        Ok(self.remove_transaction(id)?.to_json().dump())
+    }
+
+    /// Void the `Transaction` with the given id, posting its reversal on `date`, given in
+    /// `YYYY-MM-DD` form.
+    ///
+    /// See `void_transaction()` for details.
+    ///
+    /// # Returns
+    /// * The id of the reversing `Transaction`, or an `Error` if `date` is invalid or the
+    ///   `Transaction` could not be voided.
+    pub fn void_transaction_json(&mut self, id: &str, date: &str) -> Result<String, Error> {
+        match parse_date(&date.into()) {
+            Some(date) => self.void_transaction(id, date),
+            None => Err(Error::InvalidJson(format!("'{}' is not a date", date))),
+        }
+    }
+}
+
+/// Create a `Ledger` object from the JSON form produced by `Ledger::to_json()`.
+///
+/// The hash chain is loaded as saved, rather than recomputed, so that `verify_journal()` can
+/// detect any change made to the saved journal.
+///
+/// An `Account`, `Transaction`, `ChainLink`, `AuditEvent` or `ScheduledTransaction` missing a
+/// required field is rejected with `Error::InvalidJson`.
+impl<'a> TryFrom<&'a JsonValue> for Ledger {
+    type Error = Error;
+
+    fn try_from(value: &'a JsonValue) -> Result<Ledger, Error> {
+        value["accounts"].members().try_for_each(check_account)?;
+        let mut ledger = Ledger::new(AccountsChart::new());
+        ledger.restore(
+            AccountsChart::from(value),
+            value["transactions"]
+                .members()
+                .map(Transaction::try_from)
+                .collect::<Result<_, _>>()?,
+        );
+        *ledger.chain_mut() = value["chain"]
+            .members()
+            .map(ChainLink::try_from)
+            .collect::<Result<_, _>>()?;
+        ledger.audit_mut().restore(
+            value["audit"]
+                .members()
+                .map(AuditEvent::try_from)
+                .collect::<Result<_, _>>()?,
+        );
+        *ledger.periods_mut() = value["periods"].members().map(FiscalPeriod::from).collect();
+        *ledger.reconciliations_mut() = value["reconciliations"]
+            .members()
+            .map(ReconciliationRecord::from)
+            .collect();
+        *ledger.schedules_mut() = value["schedules"]
+            .members()
+            .map(ScheduledTransaction::try_from)
+            .collect::<Result<_, _>>()?;
+        *ledger.prices_mut() = PriceDatabase::from(&value["prices"]);
+        *ledger.commodities_mut() = value["commodities"]
+            .members()
+            .map(Commodity::from)
+            .collect();
+        *ledger.corporate_actions_mut() = value["corporate_actions"]
+            .members()
+            .map(CorporateAction::from)
+            .collect();
+        *ledger.contacts_mut() = value["contacts"].members().map(Contact::from).collect();
+        *ledger.tax_codes_mut() = value["tax_codes"].members().map(TaxCode::from).collect();
+        *ledger.invoices_mut() = value["invoices"].members().map(Invoice::from).collect();
+        *ledger.bills_mut() = value["bills"].members().map(Bill::from).collect();
+
+        Ok(ledger)
+    }
+}
+
+/// Parse text holding a JSON value.
+fn parse_json(json: &str) -> Result<JsonValue, Error> {
+    json::parse(json).map_err(|error| Error::InvalidJson(error.to_string()))
+}
+
+/// Check that the JSON form of an `Account`, and of each of its sub-accounts, has the fields
+/// `Account::from()` requires.
+pub(crate) fn check_account(value: &JsonValue) -> Result<(), Error> {
+    for field in &["id", "name", "description", "currency"] {
+        if !value[*field].is_string() {
+            return Err(Error::InvalidJson(format!("an account has no '{}'", field)));
+        }
+    }
+
+    value["subaccounts"].members().try_for_each(check_account)
+}
+
+/// Parse text holding the JSON form of an `Account`.
+fn parse_account(json: &str) -> Result<Account, Error> {
+    let value = parse_json(json)?;
+    check_account(&value)?;
+
+    Ok(Account::from(&value))
+}
+
+/// Parse text holding the JSON form of a `Transaction`.
+fn parse_transaction(json: &str) -> Result<Transaction, Error> {
+    Transaction::try_from(&parse_json(json)?)
+}
+
+/// Find the latest state the hash chain records for each `Transaction`, as its id and digest, or
+/// `None` if it was removed, in the order the `Transaction`s first appear in the chain.
+fn get_sealed(chain: &[ChainLink]) -> Vec<(String, Option<String>)> {
+    let mut sealed: Vec<(String, Option<String>)> = vec![];
+    for link in chain {
+        match sealed.iter_mut().find(|(id, _)| *id == link.transaction_id) {
+            Some(entry) => entry.1 = link.get_content_hash(),
+            None => sealed.push((link.get_transaction_id(), link.get_content_hash())),
+        }
+    }
+
+    sealed
+}
+
+/// Compute the digest of the canonical JSON form of a `Transaction`.
+pub(crate) fn hash_transaction(transaction: &Transaction) -> String {
+    digest(&transaction.to_canonical_json().dump())
+}
+
+/// Compute the SHA-256 digest of some text, as a hexadecimal `String`.
+fn digest(text: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(text.as_bytes());
+
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
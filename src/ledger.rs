@@ -0,0 +1,1179 @@
+//! The `Ledger`, which records `Transaction`s against an `AccountsChart`.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use audit::{AuditAction, AuditTrail};
+use balance::BalanceOptions;
+use bill::Bill;
+use commodity::Commodity;
+use contact::Contact;
+use corporate_action::CorporateAction;
+use error::Error;
+use exchange::is_foreign_currency;
+use fiscal::{FiscalCalendar, FiscalPeriod};
+use invoice::Invoice;
+use journal::ChainLink;
+use price::PriceDatabase;
+use reconciliation::{Reconciliation, ReconciliationRecord};
+use register::{Register, RegisterOptions};
+use schedule::ScheduledTransaction;
+use tax::TaxCode;
+use transaction::{EntrySource, Transaction, TransactionStatus};
+use {Account, AccountsChart, NormalBalance};
+
+/// A record of all of the `Transaction`s posted to the `Account`s of an `AccountsChart`.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub struct Ledger {
+    chart: AccountsChart,
+    transactions: Vec<Transaction>,
+    reconciliations: Vec<ReconciliationRecord>,
+    periods: Vec<FiscalPeriod>,
+    schedules: Vec<ScheduledTransaction>,
+    audit: AuditTrail,
+    chain: Vec<ChainLink>,
+    prices: PriceDatabase,
+    exchange_account: Option<String>,
+    commodities: Vec<Commodity>,
+    capital_gains_account: Option<String>,
+    corporate_actions: Vec<CorporateAction>,
+    contacts: Vec<Contact>,
+    tax_codes: Vec<TaxCode>,
+    invoices: Vec<Invoice>,
+    bills: Vec<Bill>,
+    purchase_discount_account: Option<String>,
+}
+
+impl Ledger {
+    /// Create a new `Ledger` with no `Transaction`s for the given `AccountsChart`.
+    ///
+    /// The creation of each top-level `Account` in the chart is recorded in the audit trail, but
+    /// cannot be undone.
+    pub fn new(chart: AccountsChart) -> Ledger {
+        let mut audit = AuditTrail::new();
+        for account in &chart.top_level_accounts {
+            audit.record_account(
+                AuditAction::CreateAccount,
+                None,
+                None,
+                Some(Account::clone(account)),
+            );
+        }
+        audit.history.clear();
+
+        Ledger {
+            chart,
+            transactions: vec![],
+            reconciliations: vec![],
+            periods: vec![],
+            schedules: vec![],
+            audit,
+            chain: vec![],
+            prices: PriceDatabase::new(),
+            exchange_account: None,
+            commodities: vec![],
+            capital_gains_account: None,
+            corporate_actions: vec![],
+            contacts: vec![],
+            tax_codes: vec![],
+            invoices: vec![],
+            bills: vec![],
+            purchase_discount_account: None,
+        }
+    }
+
+    /// Retrieve the `AccountsChart` whose `Account`s this `Ledger` records transactions for.
+    pub fn get_chart(&self) -> &AccountsChart {
+        &self.chart
+    }
+
+    /// Add a top-level `Account`, along with any sub-accounts it already has, to the
+    /// `AccountsChart` of this `Ledger`.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Account` was added, or `Error::DuplicateAccount` if it or any of its
+    ///   sub-accounts has the same id as an existing `Account`.
+    pub fn add_top_level_account(&mut self, account: Account) -> Result<(), Error> {
+        self.insert_account(None, account)
+    }
+
+    /// Add an `Account`, along with any sub-accounts it already has, beneath an existing
+    /// `Account` in the `AccountsChart` of this `Ledger`.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Account` was added, or an `Error` if the parent `Account` does not
+    ///   exist, or the new `Account` or any of its sub-accounts has the same id as an existing
+    ///   `Account`.
+    pub fn add_sub_account(&mut self, parent_id: &str, account: Account) -> Result<(), Error> {
+        if self.chart.get_account_by_id(parent_id).is_none() {
+            return Err(Error::UnknownAccount(String::from(parent_id)));
+        }
+
+        self.insert_account(Some(parent_id), account)
+    }
+
+    /// Edit the name, description, type, currency, commodity and placeholder flag of the `Account`
+    /// in the `AccountsChart` of this `Ledger` with the same id as `account`. The sub-accounts of
+    /// the existing `Account` are kept, and those of `account` are ignored.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Account` was edited, or an `Error` if it does not exist, or it would
+    ///   become a placeholder while `Transaction`s post to it.
+    pub fn update_account(&mut self, account: Account) -> Result<(), Error> {
+        let id = account.get_id();
+        let mut before = match self.chart.get_account_by_id(&id) {
+            Some(before) => before,
+            None => return Err(Error::UnknownAccount(id)),
+        };
+
+        if account.is_placeholder()
+            && self.transactions.iter().any(|transaction| {
+                transaction
+                    .splits()
+                    .iter()
+                    .any(|split| split.get_account_id() == id)
+            })
+        {
+            return Err(Error::PlaceholderAccount(id));
+        }
+
+        self.chart.replace_account(&account);
+        let mut after = account;
+        after.sub_accounts = vec![];
+        before.sub_accounts = vec![];
+        self.audit
+            .record_account(AuditAction::UpdateAccount, None, Some(before), Some(after));
+
+        Ok(())
+    }
+
+    /// Add a `Transaction` to this `Ledger`.
+    ///
+    /// If the `Transaction` is marked to be automatically reversed, its reversal is added as
+    /// well. If it settles the balance of an `Account` in a foreign currency, it is completed with
+    /// the realized exchange gain or loss; see `set_exchange_gain_loss_account()`. If it sells
+    /// units of a `Commodity`, it is completed with the realized capital gain or loss; see
+    /// `set_capital_gains_account()`.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Transaction` was added, or an `Error` if its id (or that of its
+    ///   reversal) is already in use, it posts to an `Account` that is unknown or a placeholder,
+    ///   it posts to an `Account` in a foreign currency without a rate or in a currency other
+    ///   than that of the `Account`'s other `Transaction`s, it sells more units of a `Commodity`
+    ///   than are held, it does not balance, or it (or its reversal) is dated within a closed
+    ///   `FiscalPeriod`.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, Currency, Ledger, Split, Transaction};
+    /// let mut chart = AccountsChart::new();
+    /// chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///                                          Currency::USDollar, false));
+    /// chart.add_top_level_account(Account::new("04", "Dues", "Dues", AccountType::Income,
+    ///                                          Currency::USDollar, false));
+    ///
+    /// let mut ledger = Ledger::new(chart);
+    /// let mut transaction = Transaction::new("0001",
+    ///                                        NaiveDate::from_ymd_opt(2018, 11, 3).unwrap(),
+    ///                                        "Membership dues");
+    /// transaction.add_split(Split::new("01", 5000));
+    /// transaction.add_split(Split::new("04", -5000));
+    ///
+    /// assert!(ledger.add_transaction(transaction).is_ok());
+    /// assert_eq!(ledger.get_num_transactions(), 1);
+    /// ```
+    pub fn add_transaction(&mut self, transaction: Transaction) -> Result<(), Error> {
+        let id = transaction.get_id();
+        if self.get_transaction_by_id(&id).is_some() {
+            return Err(Error::DuplicateTransaction(id));
+        }
+
+        let transaction = self.realize_exchange(transaction);
+        let transaction = self.realize_capital_gains(transaction)?;
+        self.validate_transaction(&transaction)?;
+        self.check_unlocked(&transaction)?;
+
+        let reversal = create_auto_reversal(&transaction);
+        if let Some(ref reversal) = reversal {
+            if self.get_transaction_by_id(&reversal.get_id()).is_some() {
+                return Err(Error::DuplicateTransaction(reversal.get_id()));
+            }
+            self.check_unlocked(reversal)?;
+        }
+
+        self.audit.history.begin_group();
+        self.audit.record_transaction(
+            AuditAction::CreateTransaction,
+            None,
+            Some(Transaction::clone(&transaction)),
+        );
+        self.transactions.push(transaction);
+        if let Some(reversal) = reversal {
+            self.audit.record_transaction(
+                AuditAction::CreateTransaction,
+                None,
+                Some(Transaction::clone(&reversal)),
+            );
+            self.transactions.push(reversal);
+        }
+        self.audit.history.end_group();
+        self.seal_journal();
+
+        Ok(())
+    }
+
+    /// Replace a `Transaction` in this `Ledger` with an edited copy having the same id.
+    ///
+    /// Any automatic reversal of the original is replaced with a reversal of the edited copy, or
+    /// removed if the edited copy is no longer marked to be automatically reversed. Any realized
+    /// exchange or capital gain or loss is recomputed for the edited copy.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Transaction` was replaced, `Error::InvalidStatusTransition` if the
+    ///   original has been voided or has reconciled `Split`s, or an `Error` if no `Transaction`
+    ///   with the same id exists, the edited copy is invalid, or either the original or the
+    ///   edited copy (or their reversals) is dated within a closed `FiscalPeriod`.
+    pub fn update_transaction(&mut self, transaction: Transaction) -> Result<(), Error> {
+        let id = transaction.get_id();
+        let original = match self.get_transaction_by_id(&id) {
+            Some(original) => original,
+            None => return Err(Error::UnknownTransaction(id)),
+        };
+        check_editable(&original, transaction.get_status())?;
+
+        let transaction = self.realize_exchange(transaction);
+        let transaction = self.realize_capital_gains(transaction)?;
+        self.validate_transaction(&transaction)?;
+        self.check_unlocked(&original)?;
+        self.check_unlocked(&transaction)?;
+
+        let old_reversal = self.get_auto_reversal(&id);
+        if let Some(ref old_reversal) = old_reversal {
+            self.check_unlocked(old_reversal)?;
+        }
+        let reversal = create_auto_reversal(&transaction);
+        if let Some(ref reversal) = reversal {
+            if old_reversal.is_none() && self.get_transaction_by_id(&reversal.get_id()).is_some() {
+                return Err(Error::DuplicateTransaction(reversal.get_id()));
+            }
+            self.check_unlocked(reversal)?;
+        }
+
+        self.audit.history.begin_group();
+        *self.get_transaction_mut(&id)? = Transaction::clone(&transaction);
+        self.audit.record_transaction(
+            AuditAction::UpdateTransaction,
+            Some(original),
+            Some(transaction),
+        );
+
+        match (old_reversal, reversal) {
+            (Some(old_reversal), Some(reversal)) => {
+                *self.get_transaction_mut(&reversal.get_id())? = Transaction::clone(&reversal);
+                self.audit.record_transaction(
+                    AuditAction::UpdateTransaction,
+                    Some(old_reversal),
+                    Some(reversal),
+                );
+            }
+            (Some(old_reversal), None) => self.delete_transaction(&old_reversal.get_id()),
+            (None, Some(reversal)) => {
+                self.audit.record_transaction(
+                    AuditAction::CreateTransaction,
+                    None,
+                    Some(Transaction::clone(&reversal)),
+                );
+                self.transactions.push(reversal);
+            }
+            (None, None) => {}
+        }
+        self.audit.history.end_group();
+        self.seal_journal();
+
+        Ok(())
+    }
+
+    /// Remove a `Transaction` from this `Ledger`.
+    ///
+    /// Posted transactions should normally be voided with `void_transaction()` rather than
+    /// removed, so that a record of them remains. Any automatic reversal of the `Transaction` is
+    /// removed along with it.
+    ///
+    /// # Returns
+    /// * The removed `Transaction`, `Error::InvalidStatusTransition` if it has been voided (or is
+    ///   the reversal of a voided `Transaction`) or has reconciled `Split`s, or an `Error` if it
+    ///   does not exist or it (or its automatic reversal) is dated within a closed
+    ///   `FiscalPeriod`.
+    pub fn remove_transaction(&mut self, id: &str) -> Result<Transaction, Error> {
+        let position = match self
+            .transactions
+            .iter()
+            .position(|transaction| transaction.get_id() == id)
+        {
+            Some(position) => position,
+            None => return Err(Error::UnknownTransaction(String::from(id))),
+        };
+
+        let original = &self.transactions[position];
+        check_editable(original, original.get_status())?;
+        self.check_unlocked(original)?;
+        let reversal = self.get_auto_reversal(id);
+        if let Some(ref reversal) = reversal {
+            self.check_unlocked(reversal)?;
+        }
+
+        self.audit.history.begin_group();
+        let transaction = self.transactions.remove(position);
+        self.audit.record_transaction(
+            AuditAction::RemoveTransaction,
+            Some(Transaction::clone(&transaction)),
+            None,
+        );
+        if let Some(reversal) = reversal {
+            self.delete_transaction(&reversal.get_id());
+        }
+        self.audit.history.end_group();
+        self.seal_journal();
+
+        Ok(transaction)
+    }
+
+    /// Retrieve the `Transaction` that was generated to automatically reverse the `Transaction`
+    /// with the given id, if it exists.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Currency, Ledger,
+    ///#               Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("02", "Accrued Wages", "Accrued Wages",
+    ///#                                          AccountType::Liability, Currency::USDollar,
+    ///#                                          false));
+    ///# chart.add_top_level_account(Account::new("05", "Wages", "Wages", AccountType::Expense,
+    ///#                                          Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    ///
+    /// let mut accrual = Transaction::new("ACC-01", NaiveDate::from_ymd_opt(2018, 1, 31).unwrap(),
+    ///                                    "Accrue January wages");
+    /// accrual.add_split(Split::new("05", 40000));
+    /// accrual.add_split(Split::new("02", -40000));
+    /// accrual.set_auto_reverse(NaiveDate::from_ymd_opt(2018, 2, 1).unwrap());
+    /// ledger.add_transaction(accrual).unwrap();
+    ///
+    /// let reversal = ledger.get_auto_reversal("ACC-01").unwrap();
+    /// assert_eq!(reversal.get_id(), "ACC-01-rev");
+    /// assert_eq!(reversal.get_reverses(), Some(String::from("ACC-01")));
+    /// assert_eq!(ledger.get_balance("02", &BalanceOptions::new()).unwrap(), 0);
+    /// ```
+    pub fn get_auto_reversal(&self, id: &str) -> Option<Transaction> {
+        self.transactions
+            .iter()
+            .find(|transaction| {
+                transaction.get_source() == EntrySource::AutoReversal
+                    && transaction
+                        .get_reverses()
+                        .is_some_and(|reverses| reverses == id)
+            })
+            .cloned()
+    }
+
+    /// Retrieve a single `Transaction` by its unique identifier.
+    pub fn get_transaction_by_id(&self, id: &str) -> Option<Transaction> {
+        self.transactions
+            .iter()
+            .find(|transaction| transaction.get_id() == id)
+            .cloned()
+    }
+
+    /// Retrieve the number of `Transaction`s in this `Ledger`.
+    pub fn get_num_transactions(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// Change the `TransactionStatus` of a `Transaction` and all of its `Split`s.
+    ///
+    /// # Arguments
+    /// * `id`: The id of the `Transaction` to change.
+    /// * `status`: The new status. This must not be `TransactionStatus::Voided`; use
+    ///   `void_transaction()` instead.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the status was changed, or `Error::InvalidStatusTransition` if the
+    ///   `Transaction` or any of its `Split`s cannot move to the new status.
+    pub fn set_transaction_status(
+        &mut self,
+        id: &str,
+        status: TransactionStatus,
+    ) -> Result<(), Error> {
+        let transaction = self.get_transaction_mut(id)?;
+        let before = Transaction::clone(transaction);
+        let current = transaction.get_status();
+        if status == TransactionStatus::Voided || !current.can_transition_to(status) {
+            return Err(Error::InvalidStatusTransition(current, status));
+        }
+
+        for split in transaction.splits() {
+            let split_status = split.get_status();
+            if split_status != status && !split_status.can_transition_to(status) {
+                return Err(Error::InvalidStatusTransition(split_status, status));
+            }
+        }
+
+        for split in transaction.splits_mut() {
+            split.set_status(status);
+        }
+        transaction.set_status(status);
+
+        let after = Transaction::clone(transaction);
+        self.audit
+            .record_transaction(AuditAction::UpdateTransaction, Some(before), Some(after));
+        self.seal_journal();
+
+        Ok(())
+    }
+
+    /// Change the `TransactionStatus` of a single `Split` of a `Transaction`.
+    ///
+    /// The status of the `Transaction` itself is updated to match its least-advanced `Split`.
+    ///
+    /// # Arguments
+    /// * `id`: The id of the `Transaction` containing the `Split`.
+    /// * `index`: The index of the `Split` within the `Transaction`.
+    /// * `status`: The new status. This must not be `TransactionStatus::Voided`.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, Currency, Ledger, Split, Transaction,
+    ///#               TransactionStatus};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("05", "Ice Time", "Ice Time",
+    ///#                                          AccountType::Expense, Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// let mut check = Transaction::new("1042", NaiveDate::from_ymd_opt(2018, 11, 3).unwrap(),
+    ///                                  "Ice time");
+    /// check.add_split(Split::new("05", 25000));
+    /// check.add_split(Split::new("01", -25000));
+    /// ledger.add_transaction(check).unwrap();
+    ///
+    /// ledger.set_split_status("1042", 1, TransactionStatus::Cleared).unwrap();
+    /// assert_eq!(ledger.get_transaction_by_id("1042").unwrap().get_status(),
+    ///            TransactionStatus::Pending);
+    ///
+    /// ledger.set_split_status("1042", 0, TransactionStatus::Cleared).unwrap();
+    /// assert_eq!(ledger.get_transaction_by_id("1042").unwrap().get_status(),
+    ///            TransactionStatus::Cleared);
+    /// ```
+    pub fn set_split_status(
+        &mut self,
+        id: &str,
+        index: usize,
+        status: TransactionStatus,
+    ) -> Result<(), Error> {
+        let transaction = self.get_transaction_mut(id)?;
+        let before = Transaction::clone(transaction);
+        let split = match transaction.splits_mut().get_mut(index) {
+            Some(split) => split,
+            None => return Err(Error::UnknownSplit(String::from(id), index)),
+        };
+
+        let current = split.get_status();
+        if status == TransactionStatus::Voided || !current.can_transition_to(status) {
+            return Err(Error::InvalidStatusTransition(current, status));
+        }
+
+        split.set_status(status);
+        transaction.update_status_from_splits();
+
+        let after = Transaction::clone(transaction);
+        self.audit
+            .record_transaction(AuditAction::UpdateTransaction, Some(before), Some(after));
+        self.seal_journal();
+
+        Ok(())
+    }
+
+    /// Void a `Transaction`.
+    ///
+    /// Voided transactions are not deleted. Instead, a reversing `Transaction` is added to the
+    /// `Ledger` on the given date, and both the original and the reversal are marked as
+    /// `TransactionStatus::Voided`.
+    ///
+    /// # Arguments
+    /// * `id`: The id of the `Transaction` to void.
+    /// * `date`: The date to post the reversing `Transaction` on.
+    ///
+    /// Voiding only changes the status of the original `Transaction`, so it is permitted even if
+    /// the original is dated within a closed `FiscalPeriod`. The reversal, however, must be
+    /// dated within an open period.
+    ///
+    /// If the original is marked to be automatically reversed, its automatic reversal is removed,
+    /// since the void already offsets it. That reversal must also be dated within an open period.
+    ///
+    /// # Returns
+    /// * The id of the reversing `Transaction`, or an `Error` if the `Transaction` does not exist,
+    ///   has already been voided, or `date` (or the date of its automatic reversal) is within a
+    ///   closed `FiscalPeriod`.
+    pub fn void_transaction(&mut self, id: &str, date: NaiveDate) -> Result<String, Error> {
+        let original = match self.get_transaction_by_id(id) {
+            Some(transaction) => transaction,
+            None => return Err(Error::UnknownTransaction(String::from(id))),
+        };
+
+        if original.get_status() == TransactionStatus::Voided {
+            return Err(Error::InvalidStatusTransition(
+                TransactionStatus::Voided,
+                TransactionStatus::Voided,
+            ));
+        }
+
+        let reversal_id = format!("{}-void", id);
+        if self.get_transaction_by_id(&reversal_id).is_some() {
+            return Err(Error::DuplicateTransaction(reversal_id));
+        }
+
+        let description = format!("Void: {}", original.get_description());
+        let mut reversal = original.create_reversal(&reversal_id, date, &description);
+        self.check_unlocked(&reversal)?;
+        let auto_reversal = self
+            .get_auto_reversal(id)
+            .filter(|auto_reversal| auto_reversal.get_status() != TransactionStatus::Voided);
+        if let Some(ref auto_reversal) = auto_reversal {
+            self.check_unlocked(auto_reversal)?;
+        }
+
+        mark_voided(&mut reversal);
+        self.audit.history.begin_group();
+        let voided = self.get_transaction_mut(id)?;
+        mark_voided(voided);
+        let voided = Transaction::clone(voided);
+        self.audit
+            .record_transaction(AuditAction::VoidTransaction, Some(original), Some(voided));
+        self.audit.record_transaction(
+            AuditAction::CreateTransaction,
+            None,
+            Some(Transaction::clone(&reversal)),
+        );
+        self.transactions.push(reversal);
+        if let Some(auto_reversal) = auto_reversal {
+            self.delete_transaction(&auto_reversal.get_id());
+        }
+        self.audit.history.end_group();
+        self.seal_journal();
+
+        Ok(reversal_id)
+    }
+
+    /// Start a `Reconciliation` session for an `Account` against a bank statement.
+    ///
+    /// Postings that are already `TransactionStatus::Cleared` start out marked as cleared in the
+    /// session.
+    ///
+    /// # Arguments
+    /// * `account_id`: The id of the `Account` to reconcile.
+    /// * `statement_date`: The end date of the statement. Postings after this date are ignored.
+    /// * `ending_balance`: The ending balance shown on the statement, signed according to the
+    ///   `Account`'s normal balance.
+    pub fn start_reconciliation(
+        &self,
+        account_id: &str,
+        statement_date: NaiveDate,
+        ending_balance: i64,
+    ) -> Result<Reconciliation, Error> {
+        Reconciliation::new(self, account_id, statement_date, ending_balance)
+    }
+
+    /// Finalize a `Reconciliation` session.
+    ///
+    /// Every posting marked as cleared in the session is marked as
+    /// `TransactionStatus::Reconciled`, and a `ReconciliationRecord` is added to the `Account`'s
+    /// reconciliation history.
+    ///
+    /// # Returns
+    /// * The `ReconciliationRecord` of the session, `Error::ReconciliationDifference` if the
+    ///   cleared balance does not agree with the statement's ending balance,
+    ///   `Error::SplitOutsideReconciliation` if a cleared `Split` does not post to the `Account`
+    ///   on or before the statement date, or `Error::InvalidStatusTransition` if its
+    ///   `Transaction` has been voided since it was marked.
+    pub fn finalize_reconciliation(
+        &mut self,
+        session: &Reconciliation,
+    ) -> Result<ReconciliationRecord, Error> {
+        for &(ref transaction_id, split_index) in &session.get_cleared() {
+            session.check_posting(self, transaction_id, split_index)?;
+        }
+
+        let difference = session.get_difference(self);
+        if difference != 0 {
+            return Err(Error::ReconciliationDifference(difference));
+        }
+
+        let cleared = session.get_cleared();
+        let mut statuses = vec![];
+        for &(ref transaction_id, split_index) in &cleared {
+            let transaction = match self.get_transaction_by_id(transaction_id) {
+                Some(transaction) => transaction,
+                None => return Err(Error::UnknownTransaction(String::clone(transaction_id))),
+            };
+            match transaction.splits().get(split_index) {
+                Some(split) if split.get_status() < TransactionStatus::Reconciled => {
+                    statuses.push(split.get_status())
+                }
+                Some(split) => {
+                    return Err(Error::InvalidStatusTransition(
+                        split.get_status(),
+                        TransactionStatus::Reconciled,
+                    ))
+                }
+                None => {
+                    return Err(Error::UnknownSplit(
+                        String::clone(transaction_id),
+                        split_index,
+                    ))
+                }
+            }
+        }
+
+        // Drop the records of undone reconciliations before their postings can be reconciled
+        // again, as they can no longer be redone.
+        let current = self
+            .reconciliations
+            .iter()
+            .filter(|existing| existing.is_current(self))
+            .cloned()
+            .collect();
+        self.reconciliations = current;
+
+        self.audit.history.begin_group();
+        let result = cleared.iter().zip(statuses).try_for_each(
+            |(&(ref transaction_id, split_index), status)| {
+                if status == TransactionStatus::Pending {
+                    self.set_split_status(transaction_id, split_index, TransactionStatus::Cleared)?;
+                }
+                self.set_split_status(transaction_id, split_index, TransactionStatus::Reconciled)
+            },
+        );
+        self.audit.history.end_group();
+        result?;
+
+        let record = session.create_record();
+        self.reconciliations
+            .push(ReconciliationRecord::clone(&record));
+
+        Ok(record)
+    }
+
+    /// Retrieve the history of completed reconciliations for the `Account` with the given id, in
+    /// the order they were finalized. Reconciliations which have been undone are not included.
+    pub fn get_reconciliations(&self, account_id: &str) -> Vec<ReconciliationRecord> {
+        self.reconciliations
+            .iter()
+            .filter(|record| record.get_account_id() == account_id && record.is_current(self))
+            .cloned()
+            .collect()
+    }
+
+    /// Add the accounting periods of a fiscal year to this `Ledger`.
+    ///
+    /// # Arguments
+    /// * `calendar`: The `FiscalCalendar` describing how fiscal years are laid out.
+    /// * `year`: The fiscal year to add, named for the calendar year in which it ends.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the periods were added, `Error::OverlappingPeriod` if any of them overlap
+    ///   a period that is already part of this `Ledger`, or `Error::InvalidMonth` if the
+    ///   calendar's month is not between 1 and 12.
+    pub fn add_fiscal_year(&mut self, calendar: &FiscalCalendar, year: i32) -> Result<(), Error> {
+        let periods = calendar.get_periods(year)?;
+        for period in &periods {
+            if self
+                .periods
+                .iter()
+                .any(|existing| existing.overlaps(period))
+            {
+                return Err(Error::OverlappingPeriod(period.get_id()));
+            }
+        }
+
+        self.periods.extend(periods);
+        self.periods.sort_by_key(|period| period.get_start());
+
+        Ok(())
+    }
+
+    /// Retrieve all of the `FiscalPeriod`s of this `Ledger`, in date order.
+    pub fn get_periods(&self) -> Vec<FiscalPeriod> {
+        Vec::clone(&self.periods)
+    }
+
+    /// Retrieve the `FiscalPeriod` containing `date`, if there is one.
+    pub fn get_period_for_date(&self, date: NaiveDate) -> Option<FiscalPeriod> {
+        self.periods
+            .iter()
+            .find(|period| period.contains(date))
+            .cloned()
+    }
+
+    /// Close a `FiscalPeriod`, so that no `Transaction` dated within it can be added, edited or
+    /// removed.
+    ///
+    /// # Arguments
+    /// * `id`: The id of the `FiscalPeriod` to close.
+    /// * `reason`: The reason for closing the period, e.g. "Q3 sales tax return filed".
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, Currency, Error, FiscalCalendar,
+    ///#               Ledger, Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("04", "Dues", "Dues", AccountType::Income,
+    ///#                                          Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// ledger.add_fiscal_year(&FiscalCalendar::Monthly { start_month: 1 }, 2018).unwrap();
+    /// ledger.close_period("FY2018-P01", "January books filed").unwrap();
+    ///
+    /// let mut late_dues = Transaction::new("0001", NaiveDate::from_ymd_opt(2018, 1, 15).unwrap(),
+    ///                                      "Late dues");
+    /// late_dues.add_split(Split::new("01", 5000));
+    /// late_dues.add_split(Split::new("04", -5000));
+    ///
+    /// assert_eq!(ledger.add_transaction(late_dues),
+    ///            Err(Error::ClosedPeriod(String::from("FY2018-P01"))));
+    /// ```
+    pub fn close_period(&mut self, id: &str, reason: &str) -> Result<(), Error> {
+        self.get_period_mut(id)?.set_closed(true, reason)
+    }
+
+    /// Reopen a closed `FiscalPeriod`.
+    ///
+    /// # Arguments
+    /// * `id`: The id of the `FiscalPeriod` to reopen.
+    /// * `reason`: The reason for reopening the period, e.g. "Amended return for missed invoice".
+    pub fn reopen_period(&mut self, id: &str, reason: &str) -> Result<(), Error> {
+        self.get_period_mut(id)?.set_closed(false, reason)
+    }
+
+    /// Build a check-register view of the `Account` with the given id.
+    ///
+    /// # Arguments
+    /// * `account_id`: The id of the `Account` to build the register for.
+    /// * `options`: A `RegisterOptions` object restricting the dates and page of entries returned.
+    ///
+    /// # Returns
+    /// * A `Register` for the `Account`, or `Error::UnknownAccount` if it does not exist.
+    pub fn get_register(
+        &self,
+        account_id: &str,
+        options: &RegisterOptions,
+    ) -> Result<Register, Error> {
+        Register::build(self, account_id, options)
+    }
+
+    /// Compute the balance of the `Account` with the given id.
+    ///
+    /// The balance is signed according to the `Account`'s normal balance, so a positive value
+    /// means a debit balance for an Asset or Expense `Account`, and a credit balance for an
+    /// Equity, Income or Liability `Account`. It is expressed in the `Account`'s `Currency`,
+    /// unless the options translate it into a reporting currency.
+    ///
+    /// # Arguments
+    /// * `account_id`: The id of the `Account` to compute the balance of.
+    /// * `options`: A `BalanceOptions` object restricting which postings are included.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Currency, DateBasis,
+    ///#               Ledger, Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Prepaid Ice Time", "Prepaid Ice Time",
+    ///#                                          AccountType::Asset, Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("05", "Ice Time", "Ice Time",
+    ///#                                          AccountType::Expense, Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    ///
+    /// let mut adjustment = Transaction::new("ADJ-12",
+    ///                                       NaiveDate::from_ymd_opt(2019, 1, 5).unwrap(),
+    ///                                       "Expense December ice time");
+    /// adjustment.set_effective_date(NaiveDate::from_ymd_opt(2018, 12, 31).unwrap());
+    /// adjustment.add_split(Split::new("05", 40000));
+    /// adjustment.add_split(Split::new("01", -40000));
+    /// ledger.add_transaction(adjustment).unwrap();
+    ///
+    /// let year_end = NaiveDate::from_ymd_opt(2018, 12, 31).unwrap();
+    /// let posted = BalanceOptions::new().as_of(year_end);
+    /// let effective = BalanceOptions::new().as_of(year_end).date_basis(DateBasis::Effective);
+    ///
+    /// assert_eq!(ledger.get_balance("05", &posted).unwrap(), 0);
+    /// assert_eq!(ledger.get_balance("05", &effective).unwrap(), 40000);
+    /// ```
+    pub fn get_balance(&self, account_id: &str, options: &BalanceOptions) -> Result<i64, Error> {
+        let account = match self.chart.get_account_by_id(account_id) {
+            Some(account) => account,
+            None => return Err(Error::UnknownAccount(String::from(account_id))),
+        };
+
+        if let Some(translation) = options.get_translation() {
+            return self.get_translated_balance(&account, options, translation);
+        }
+
+        let account_ids = if options.includes_sub_accounts() {
+            self.chart.get_sub_tree_ids(account_id)
+        } else {
+            vec![account.get_id()]
+        };
+
+        let total: i64 = self
+            .transactions
+            .iter()
+            .filter(|transaction| options.includes(transaction))
+            .flat_map(|transaction| transaction.splits())
+            .filter(|split| options.includes_split(split))
+            .filter(|split| account_ids.contains(&split.get_account_id()))
+            .map(|split| split.get_amount())
+            .sum();
+
+        match account.get_account_type().get_normal_balance() {
+            NormalBalance::Debit => Ok(total),
+            NormalBalance::Credit => Ok(-total),
+        }
+    }
+
+    /// Compute the balance of every `Account` in the `AccountsChart`.
+    ///
+    /// # Returns
+    /// * A `HashMap` from each `Account`'s id to its balance, as computed by `get_balance()`.
+    pub fn get_balances(&self, options: &BalanceOptions) -> HashMap<String, i64> {
+        let mut balances = HashMap::new();
+        for account_id in self.chart.get_account_ids() {
+            if let Ok(balance) = self.get_balance(&account_id, options) {
+                balances.insert(account_id, balance);
+            }
+        }
+
+        balances
+    }
+
+    pub(crate) fn transactions(&self) -> &[Transaction] {
+        &self.transactions
+    }
+
+    pub(crate) fn transactions_mut(&mut self) -> &mut Vec<Transaction> {
+        &mut self.transactions
+    }
+
+    pub(crate) fn chart_mut(&mut self) -> &mut AccountsChart {
+        &mut self.chart
+    }
+
+    pub(crate) fn audit(&self) -> &AuditTrail {
+        &self.audit
+    }
+
+    pub(crate) fn audit_mut(&mut self) -> &mut AuditTrail {
+        &mut self.audit
+    }
+
+    /// Replace the `AccountsChart` and `Transaction`s of this `Ledger`, without recording the
+    /// change in the audit trail or updating the hash chain.
+    pub(crate) fn restore(&mut self, chart: AccountsChart, transactions: Vec<Transaction>) {
+        self.chart = chart;
+        self.transactions = transactions;
+    }
+
+    pub(crate) fn chain(&self) -> &[ChainLink] {
+        &self.chain
+    }
+
+    pub(crate) fn chain_mut(&mut self) -> &mut Vec<ChainLink> {
+        &mut self.chain
+    }
+
+    pub(crate) fn prices(&self) -> &PriceDatabase {
+        &self.prices
+    }
+
+    pub(crate) fn prices_mut(&mut self) -> &mut PriceDatabase {
+        &mut self.prices
+    }
+
+    pub(crate) fn exchange_account(&self) -> &Option<String> {
+        &self.exchange_account
+    }
+
+    pub(crate) fn exchange_account_mut(&mut self) -> &mut Option<String> {
+        &mut self.exchange_account
+    }
+
+    pub(crate) fn commodities(&self) -> &[Commodity] {
+        &self.commodities
+    }
+
+    pub(crate) fn commodities_mut(&mut self) -> &mut Vec<Commodity> {
+        &mut self.commodities
+    }
+
+    pub(crate) fn capital_gains_account(&self) -> &Option<String> {
+        &self.capital_gains_account
+    }
+
+    pub(crate) fn capital_gains_account_mut(&mut self) -> &mut Option<String> {
+        &mut self.capital_gains_account
+    }
+
+    pub(crate) fn corporate_actions(&self) -> &[CorporateAction] {
+        &self.corporate_actions
+    }
+
+    pub(crate) fn corporate_actions_mut(&mut self) -> &mut Vec<CorporateAction> {
+        &mut self.corporate_actions
+    }
+
+    pub(crate) fn contacts(&self) -> &[Contact] {
+        &self.contacts
+    }
+
+    pub(crate) fn contacts_mut(&mut self) -> &mut Vec<Contact> {
+        &mut self.contacts
+    }
+
+    pub(crate) fn tax_codes(&self) -> &[TaxCode] {
+        &self.tax_codes
+    }
+
+    pub(crate) fn tax_codes_mut(&mut self) -> &mut Vec<TaxCode> {
+        &mut self.tax_codes
+    }
+
+    pub(crate) fn invoices(&self) -> &[Invoice] {
+        &self.invoices
+    }
+
+    pub(crate) fn invoices_mut(&mut self) -> &mut Vec<Invoice> {
+        &mut self.invoices
+    }
+
+    pub(crate) fn bills(&self) -> &[Bill] {
+        &self.bills
+    }
+
+    pub(crate) fn bills_mut(&mut self) -> &mut Vec<Bill> {
+        &mut self.bills
+    }
+
+    pub(crate) fn purchase_discount_account(&self) -> &Option<String> {
+        &self.purchase_discount_account
+    }
+
+    pub(crate) fn purchase_discount_account_mut(&mut self) -> &mut Option<String> {
+        &mut self.purchase_discount_account
+    }
+
+    pub(crate) fn schedules(&self) -> &[ScheduledTransaction] {
+        &self.schedules
+    }
+
+    pub(crate) fn schedules_mut(&mut self) -> &mut Vec<ScheduledTransaction> {
+        &mut self.schedules
+    }
+
+    pub(crate) fn periods(&self) -> &[FiscalPeriod] {
+        &self.periods
+    }
+
+    pub(crate) fn periods_mut(&mut self) -> &mut Vec<FiscalPeriod> {
+        &mut self.periods
+    }
+
+    pub(crate) fn reconciliations(&self) -> &[ReconciliationRecord] {
+        &self.reconciliations
+    }
+
+    pub(crate) fn reconciliations_mut(&mut self) -> &mut Vec<ReconciliationRecord> {
+        &mut self.reconciliations
+    }
+
+    fn get_period_mut(&mut self, id: &str) -> Result<&mut FiscalPeriod, Error> {
+        match self.periods.iter_mut().find(|period| period.get_id() == id) {
+            Some(period) => Ok(period),
+            None => Err(Error::UnknownPeriod(String::from(id))),
+        }
+    }
+
+    /// Ensure that neither the posting date nor the effective date of a `Transaction` falls
+    /// within a closed `FiscalPeriod`.
+    pub(crate) fn check_unlocked(&self, transaction: &Transaction) -> Result<(), Error> {
+        for date in &[transaction.get_date(), transaction.get_effective_date()] {
+            if let Some(period) = self
+                .periods
+                .iter()
+                .find(|period| period.is_closed() && period.contains(*date))
+            {
+                return Err(Error::ClosedPeriod(period.get_id()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add an `Account` to the `AccountsChart` beneath an existing parent, which must exist, or as
+    /// a top-level `Account`.
+    fn insert_account(&mut self, parent_id: Option<&str>, account: Account) -> Result<(), Error> {
+        let mut ids = vec![account.get_id()];
+        ids.extend(account.get_sub_tree().iter().map(Account::get_id));
+        if let Some(id) = ids
+            .into_iter()
+            .find(|id| self.chart.get_account_by_id(id).is_some())
+        {
+            return Err(Error::DuplicateAccount(id));
+        }
+
+        self.chart
+            .insert_account(parent_id, Account::clone(&account));
+        self.audit.record_account(
+            AuditAction::CreateAccount,
+            parent_id.map(String::from),
+            None,
+            Some(account),
+        );
+
+        Ok(())
+    }
+
+    /// Remove a `Transaction` as a side effect of another change, recording its removal in the
+    /// audit trail.
+    fn delete_transaction(&mut self, id: &str) {
+        if let Some(position) = self
+            .transactions
+            .iter()
+            .position(|transaction| transaction.get_id() == id)
+        {
+            let transaction = self.transactions.remove(position);
+            self.audit
+                .record_transaction(AuditAction::RemoveTransaction, Some(transaction), None);
+        }
+    }
+
+    fn get_transaction_mut(&mut self, id: &str) -> Result<&mut Transaction, Error> {
+        match self
+            .transactions
+            .iter_mut()
+            .find(|transaction| transaction.get_id() == id)
+        {
+            Some(transaction) => Ok(transaction),
+            None => Err(Error::UnknownTransaction(String::from(id))),
+        }
+    }
+
+    pub(crate) fn validate_transaction(&self, transaction: &Transaction) -> Result<(), Error> {
+        if transaction
+            .get_auto_reverse()
+            .is_some_and(|date| date <= transaction.get_date())
+        {
+            return Err(Error::InvalidReversalDate(transaction.get_id()));
+        }
+
+        for split in transaction.splits() {
+            if let Some(contact_id) = split.get_contact() {
+                if self.get_contact(&contact_id).is_none() {
+                    return Err(Error::UnknownContact(contact_id));
+                }
+            }
+
+            let account_id = split.get_account_id();
+            match self.chart.get_account_by_id(&account_id) {
+                Some(ref account) if account.is_placeholder() => {
+                    return Err(Error::PlaceholderAccount(account_id));
+                }
+                Some(ref account) => {
+                    if let Some(symbol) = account.get_commodity() {
+                        if self.get_commodity(&symbol).is_none() {
+                            return Err(Error::UnknownCommodity(symbol));
+                        }
+                        continue;
+                    }
+
+                    let mismatched = if is_foreign_currency(
+                        account.get_currency(),
+                        transaction.get_currency(),
+                    ) {
+                        split.get_rate().is_none() && split.get_amount() != 0
+                    } else {
+                        split.get_value() != split.get_amount()
+                    };
+                    if mismatched || self.mixes_carrying_currency(account, transaction) {
+                        return Err(Error::CurrencyMismatch(account_id));
+                    }
+                }
+                None => return Err(Error::UnknownAccount(account_id)),
+            }
+        }
+
+        if !transaction.is_balanced() {
+            return Err(Error::Unbalanced(transaction.get_id()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Create the automatic reversal of a `Transaction`, if it is marked to be automatically
+/// reversed.
+fn create_auto_reversal(transaction: &Transaction) -> Option<Transaction> {
+    transaction.get_auto_reverse().map(|date| {
+        let mut reversal = transaction.create_reversal(
+            &format!("{}-rev", transaction.get_id()),
+            date,
+            &format!("Reversal: {}", transaction.get_description()),
+        );
+        reversal.set_source(EntrySource::AutoReversal);
+        reversal
+    })
+}
+
+/// Ensure that a `Transaction` can be edited or removed, which it cannot once it has been voided
+/// or any of its `Split`s has been reconciled.
+fn check_editable(original: &Transaction, status: TransactionStatus) -> Result<(), Error> {
+    for voided_or_reconciled in &[TransactionStatus::Voided, TransactionStatus::Reconciled] {
+        if original
+            .splits()
+            .iter()
+            .any(|split| split.get_status() == *voided_or_reconciled)
+            || original.get_status() == *voided_or_reconciled
+        {
+            return Err(Error::InvalidStatusTransition(
+                *voided_or_reconciled,
+                status,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn mark_voided(transaction: &mut Transaction) {
+    for split in transaction.splits_mut() {
+        split.set_status(TransactionStatus::Voided);
+    }
+    transaction.set_status(TransactionStatus::Voided);
+}
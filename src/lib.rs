@@ -10,10 +10,68 @@ use cfg_if::cfg_if;
 
 use std::collections::HashMap;
 
+extern crate chrono;
 extern crate json;
+extern crate sha2;
+
+mod audit;
+mod balance;
+mod bill;
+mod budget;
+mod closing;
+mod commodity;
+mod contact;
+mod corporate_action;
+mod envelope;
+mod error;
+mod exchange;
+mod fiscal;
+mod forecast;
+mod history;
+mod invoice;
+mod journal;
+mod ledger;
+mod lot;
+mod opening;
+mod performance;
+mod price;
+mod reconciliation;
+mod register;
+mod revaluation;
+mod schedule;
+mod tax;
+mod transaction;
+mod translation;
+
+pub use audit::{AuditAction, AuditEvent, AuditValue};
+pub use balance::{BalanceOptions, StatusFilter};
+pub use bill::{Bill, BillLine, BillPayment, BillStatus};
+pub use budget::{Budget, BudgetPeriod, BudgetVariance};
+pub use commodity::{Commodity, CommodityKind};
+pub use contact::{Contact, ContactKind, PaymentTerms};
+pub use corporate_action::{CorporateAction, CorporateActionKind};
+pub use envelope::{Envelope, EnvelopeBudget, EnvelopeStatus};
+pub use error::Error;
+pub use fiscal::{FiscalCalendar, FiscalPeriod, PeriodChange};
+pub use forecast::{Forecast, ForecastOptions};
+pub use invoice::{Invoice, InvoiceLine, InvoicePayment, InvoiceStatus};
+pub use journal::ChainLink;
+pub use ledger::Ledger;
+pub use lot::{Lot, LotSelection};
+pub use performance::UnrealizedGain;
+pub use price::{Price, PriceDatabase, PriceFallback};
+pub use reconciliation::{
+    MatchTolerance, Reconciliation, ReconciliationRecord, StatementLine, StatementMatch,
+};
+pub use register::{Register, RegisterEntry, RegisterOptions};
+pub use schedule::{Frequency, Recurrence, ScheduledTransaction};
+pub use tax::TaxCode;
+pub use transaction::{DateBasis, EntrySource, Split, Transaction, TransactionStatus};
+pub use translation::{RatePolicy, Translation};
 
 cfg_if! {
     if #[cfg(target_arch = "wasm32")] {
+        extern crate js_sys;
         extern crate wasm_bindgen;
 
         use wasm_bindgen::prelude::*;
@@ -41,8 +99,6 @@ cfg_if! {
 }
 
 /// An enumeration for controlling the type of currency.
-///
-/// Currently, only US Dollars are supported.
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
 #[repr(u8)]
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -50,6 +106,15 @@ pub enum Currency {
     /// United States Dollars
     USDollar,
 
+    /// Euros
+    Euro,
+
+    /// Canadian Dollars
+    CanadianDollar,
+
+    /// British Pounds Sterling
+    BritishPound,
+
     /// Default currency type. This is used when deserialization didn't resolve to a known value.
     Unknown,
 }
@@ -67,11 +132,34 @@ impl<'a> From<&'a str> for Currency {
     fn from(abbrev: &'a str) -> Currency {
         match abbrev {
             "USD" => Currency::USDollar,
+            "EUR" => Currency::Euro,
+            "CAD" => Currency::CanadianDollar,
+            "GBP" => Currency::BritishPound,
             _ => Currency::Unknown,
         }
     }
 }
 
+impl Currency {
+    /// Retrieve the abbreviation for this `Currency`, such as `USD`, as used in JSON form.
+    ///
+    /// # Examples
+    /// ```
+    ///# use cratchit::Currency;
+    /// assert_eq!(Currency::USDollar.get_code(), "USD");
+    /// assert_eq!(Currency::from(Currency::USDollar.get_code()), Currency::USDollar);
+    /// ```
+    pub fn get_code(self) -> &'static str {
+        match self {
+            Currency::USDollar => "USD",
+            Currency::Euro => "EUR",
+            Currency::CanadianDollar => "CAD",
+            Currency::BritishPound => "GBP",
+            Currency::Unknown => "",
+        }
+    }
+}
+
 /// An enumeration for specifying the type of an `Account`.
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
 #[repr(u8)]
@@ -80,7 +168,7 @@ pub enum AccountType {
     /// An Asset account, Accounts of this type should normally have a debit balance.
     Asset = 1,
 
-    /// An Equity account. Accounts of this type should normally have a debit balance.
+    /// An Equity account. Accounts of this type should normally have a credit balance.
     Equity = 2,
 
     /// An Expense account. Accounts of this type should normally have a debit balance.
@@ -96,6 +184,61 @@ pub enum AccountType {
     Other = 6,
 }
 
+/// An enumeration for specifying which side of the ledger increases an `Account`'s balance.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NormalBalance {
+    /// Debits increase the balance of the `Account`, and credits decrease it.
+    Debit,
+
+    /// Credits increase the balance of the `Account`, and debits decrease it.
+    Credit,
+}
+
+impl AccountType {
+    /// Retrieve the side of the ledger on which accounts of this type normally carry a balance.
+    ///
+    /// # Examples
+    /// ```
+    /// use cratchit::{AccountType, NormalBalance};
+    ///
+    /// assert_eq!(AccountType::Asset.get_normal_balance(), NormalBalance::Debit);
+    /// assert_eq!(AccountType::Income.get_normal_balance(), NormalBalance::Credit);
+    /// ```
+    pub fn get_normal_balance(self) -> NormalBalance {
+        match self {
+            AccountType::Equity | AccountType::Income | AccountType::Liability => {
+                NormalBalance::Credit
+            }
+            _ => NormalBalance::Debit,
+        }
+    }
+}
+
+/// Convert from an integer to an `AccountType` value.
+///
+/// The integer values correspond to the discriminants of `AccountType`, which is how account types
+/// are stored in JSON form.
+///
+/// # Examples
+/// ```
+/// use cratchit::AccountType;
+///
+/// assert_eq!(AccountType::from(4), AccountType::Income);
+/// assert_eq!(AccountType::from(42), AccountType::Other);
+/// ```
+impl From<u8> for AccountType {
+    fn from(value: u8) -> AccountType {
+        match value {
+            1 => AccountType::Asset,
+            2 => AccountType::Equity,
+            3 => AccountType::Expense,
+            4 => AccountType::Income,
+            5 => AccountType::Liability,
+            _ => AccountType::Other,
+        }
+    }
+}
+
 /// Convert from a string slice to an `AccountType` value.
 ///
 /// # Examples
@@ -125,7 +268,7 @@ impl<'a> From<&'a str> for AccountType {
 ///
 /// The resource type for an Account is determined by the `Currency` enumeration.
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct Account {
     id: String,
     name: String,
@@ -133,6 +276,7 @@ pub struct Account {
     account_type: AccountType,
     currency: Currency,
     placeholder: bool,
+    commodity: Option<String>,
     sub_accounts: Vec<Account>,
 }
 
@@ -171,7 +315,10 @@ impl<'a> From<&'a json::JsonValue> for Account {
         let name = value["name"].as_str().unwrap();
         let id = value["id"].as_str().unwrap();
         let description = value["description"].as_str().unwrap();
-        let act_type = AccountType::Asset;
+        let act_type = match value["type"].as_u8() {
+            Some(type_value) => AccountType::from(type_value),
+            None => AccountType::from(value["type"].as_str().unwrap_or("")),
+        };
         let currency_str = value["currency"].as_str().unwrap();
         let placeholder: bool = value["placeholder"].as_bool().unwrap_or(false);
 
@@ -183,6 +330,7 @@ impl<'a> From<&'a json::JsonValue> for Account {
             Currency::from(currency_str),
             placeholder,
         );
+        result_account.commodity = value["commodity"].as_str().map(String::from);
         result_account.parse_and_add_sub_accounts(sub_accounts_json);
 
         result_account
@@ -204,6 +352,7 @@ impl Account {
     /// * placeholder: A boolean value indicating whether this `Account` will be a placeholder
     ///   `Account`. Placeholder accounts serve only as a grouping of other sub-accounts, and do
     ///   not have transactions assigned to them directly.
+    #[allow(clippy::redundant_field_names)]
     pub fn new(
         id: &str,
         name: &str,
@@ -222,6 +371,7 @@ impl Account {
             account_type: account_type,
             currency: currency,
             placeholder: placeholder,
+            commodity: None,
             sub_accounts: vec![],
         }
     }
@@ -267,6 +417,21 @@ impl Account {
         self.placeholder
     }
 
+    /// Retrieve the symbol of the `Commodity` this `Account` holds, if it holds one.
+    pub fn get_commodity(&self) -> Option<String> {
+        self.commodity.clone()
+    }
+
+    /// Make this `Account` hold units of the `Commodity` with the given symbol, such as shares of
+    /// a stock, rather than amounts of its `Currency`.
+    ///
+    /// The balance of an `Account` holding a commodity is the number of units it holds, and its
+    /// carrying value (see `Ledger::get_carrying_value()`) is the cost of those units in its
+    /// `Currency`. Purchases and sales are posted with `Split::new_commodity()`.
+    pub fn set_commodity(&mut self, symbol: &str) {
+        self.commodity = Some(String::from(symbol));
+    }
+
     /// Add a new sub-account to this `Account`'s sub-tree.
     ///
     /// This adds a new child `Account` to this `Account`. This also effectively adds the child
@@ -294,6 +459,38 @@ impl Account {
         self.sub_accounts.push(account);
     }
 
+    /// Find the `Account` with the given id within this `Account`'s sub-tree, including this
+    /// `Account` itself.
+    fn find_mut(&mut self, id: &str) -> Option<&mut Account> {
+        if self.id == id {
+            return Some(self);
+        }
+
+        for sub_account in &mut self.sub_accounts {
+            if let Some(account) = sub_account.find_mut(id) {
+                return Some(account);
+            }
+        }
+
+        None
+    }
+
+    /// Remove the `Account` with the given id from this `Account`'s sub-tree.
+    fn remove_sub_account(&mut self, id: &str) -> bool {
+        if let Some(position) = self
+            .sub_accounts
+            .iter()
+            .position(|account| account.id == id)
+        {
+            self.sub_accounts.remove(position);
+            return true;
+        }
+
+        self.sub_accounts
+            .iter_mut()
+            .any(|account| account.remove_sub_account(id))
+    }
+
     fn get_sub_accounts(&self) -> Vec<Account> {
         Vec::clone(&self.sub_accounts)
     }
@@ -313,6 +510,27 @@ impl Account {
     }
 }
 
+impl Account {
+    /// Convert this `Account`, along with its sub-accounts, to its JSON form, as accepted by
+    /// `Account::from()`.
+    pub fn to_json(&self) -> json::JsonValue {
+        let mut value = json::JsonValue::new_object();
+        value["name"] = self.name.as_str().into();
+        value["description"] = self.description.as_str().into();
+        value["id"] = self.id.as_str().into();
+        value["type"] = (self.account_type as u8).into();
+        value["currency"] = self.currency.get_code().into();
+        value["placeholder"] = self.placeholder.into();
+        if let Some(ref commodity) = self.commodity {
+            value["commodity"] = commodity.as_str().into();
+        }
+        value["subaccounts"] =
+            json::JsonValue::Array(self.sub_accounts.iter().map(Account::to_json).collect());
+
+        value
+    }
+}
+
 /// A data structure representing a Chart of Accounts.
 ///
 /// A Chart of Accounts is the listing of all of the `Account`s in a ledger. The `AccountsChart`
@@ -320,6 +538,7 @@ impl Account {
 /// contain individual transactions, as well as a balance.
 
 //#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[allow(clippy::empty_line_after_doc_comments)]
 pub struct AccountsChart {
     top_level_accounts: Vec<Account>,
 }
@@ -399,6 +618,7 @@ impl<'a> From<&'a json::JsonValue> for AccountsChart {
     ///assert_eq!(account.is_placeholder(), false);
     ///assert_eq!(account.get_currency(), Currency::USDollar);
     ///  ```
+    #[allow(clippy::assign_op_pattern)]
     fn from(value: &'a json::JsonValue) -> AccountsChart {
         let mut top_level_accounts = vec![];
         let accounts_json = &value["accounts"];
@@ -415,6 +635,12 @@ impl<'a> From<&'a json::JsonValue> for AccountsChart {
     }
 }
 
+impl Default for AccountsChart {
+    fn default() -> AccountsChart {
+        AccountsChart::new()
+    }
+}
+
 // #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
 impl AccountsChart {
     /// Create a new `AccountsChart` object.
@@ -535,11 +761,136 @@ impl AccountsChart {
     ///
     ///    assert_eq!(accounts_chart.get_account_ids(), vec!["01"]);
     /// ```
+    #[allow(clippy::redundant_closure)]
     pub fn get_account_ids(&self) -> Vec<String> {
         let mapping = self.get_account_map();
         mapping.keys().map(|x| String::clone(x)).collect()
     }
 
+    /// Convert this `AccountsChart` to its JSON form, as accepted by `AccountsChart::from()`.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate json;
+    ///# extern crate cratchit;
+    ///# use cratchit::{Account, AccountsChart, AccountType, Currency};
+    /// let mut accounts_chart = AccountsChart::new();
+    /// accounts_chart.add_top_level_account(Account::new("01", "Assets", "Assets",
+    ///                                                   AccountType::Asset, Currency::USDollar,
+    ///                                                   true));
+    ///
+    /// let value = accounts_chart.to_json();
+    /// assert_eq!(value["accounts"][0]["type"], 1);
+    /// assert_eq!(AccountsChart::from(&value).get_account_ids(), vec!["01"]);
+    /// ```
+    pub fn to_json(&self) -> json::JsonValue {
+        let mut value = json::JsonValue::new_object();
+        value["accounts"] = json::JsonValue::Array(
+            self.top_level_accounts
+                .iter()
+                .map(Account::to_json)
+                .collect(),
+        );
+
+        value
+    }
+
+    /// Add an `Account` beneath the `Account` with the given parent id, or as a top-level
+    /// `Account` if there is no parent.
+    ///
+    /// # Returns
+    /// * `true` if the `Account` was added, or `false` if the parent does not exist.
+    fn insert_account(&mut self, parent_id: Option<&str>, account: Account) -> bool {
+        match parent_id {
+            Some(parent_id) => match self.get_account_mut(parent_id) {
+                Some(parent) => {
+                    parent.add_sub_account(account);
+                    true
+                }
+                None => false,
+            },
+            None => {
+                self.add_top_level_account(account);
+                true
+            }
+        }
+    }
+
+    /// Replace the details of the `Account` with the same id as `account`, keeping its
+    /// sub-accounts.
+    ///
+    /// # Returns
+    /// * `true` if the `Account` was replaced, or `false` if it does not exist.
+    fn replace_account(&mut self, account: &Account) -> bool {
+        match self.get_account_mut(&account.id) {
+            Some(existing) => {
+                existing.name = String::clone(&account.name);
+                existing.description = String::clone(&account.description);
+                existing.account_type = account.account_type;
+                existing.currency = account.currency;
+                existing.commodity = account.commodity.clone();
+                existing.placeholder = account.placeholder;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the `Account` with the given id, along with its sub-accounts.
+    ///
+    /// # Returns
+    /// * `true` if the `Account` was removed, or `false` if it does not exist.
+    fn remove_account(&mut self, id: &str) -> bool {
+        if let Some(position) = self
+            .top_level_accounts
+            .iter()
+            .position(|account| account.id == id)
+        {
+            self.top_level_accounts.remove(position);
+            return true;
+        }
+
+        self.top_level_accounts
+            .iter_mut()
+            .any(|account| account.remove_sub_account(id))
+    }
+
+    fn get_account_mut(&mut self, id: &str) -> Option<&mut Account> {
+        for account in &mut self.top_level_accounts {
+            if let Some(account) = account.find_mut(id) {
+                return Some(account);
+            }
+        }
+
+        None
+    }
+
+    /// Retrieve the ids of the `Account` with the given id and every `Account` beneath it.
+    fn get_sub_tree_ids(&self, id: &str) -> Vec<String> {
+        match self.get_account_by_id(id) {
+            Some(account) => {
+                let mut ids = vec![account.get_id()];
+                for sub_account in account.get_sub_tree() {
+                    ids.push(sub_account.get_id());
+                }
+
+                ids
+            }
+            None => vec![],
+        }
+    }
+
+    /// Retrieve the ids of the `Account` with the given id and every `Account` above it.
+    fn get_ancestor_ids(&self, id: &str) -> Vec<String> {
+        self.get_account_map()
+            .values()
+            .filter(|account| {
+                account.id == id || account.get_sub_tree().iter().any(|sub| sub.id == id)
+            })
+            .map(Account::get_id)
+            .collect()
+    }
+
     fn get_account_map(&self) -> HashMap<String, Account> {
         let mut mapping: HashMap<String, Account> = HashMap::new();
         let accounts_to_process = Vec::clone(&self.top_level_accounts);
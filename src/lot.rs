@@ -0,0 +1,449 @@
+//! Purchase lots of commodities, and the capital gains realized when they are sold.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use json::JsonValue;
+
+use balance::BalanceOptions;
+use error::Error;
+use exchange::get_share;
+use ledger::Ledger;
+use transaction::{EntrySource, Split, Transaction, TransactionStatus};
+use AccountType;
+
+/// An enumeration of the ways the lots sold by a `Split` are chosen.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum LotSelection {
+    /// Sell the oldest lots first.
+    #[default]
+    Fifo,
+
+    /// Sell the newest lots first.
+    Lifo,
+
+    /// Sell the lots with the highest cost per unit first.
+    HighestCost,
+
+    /// Sell the given number of units from each of the named lots, in order. Any units sold
+    /// beyond those named are taken from the oldest lots first.
+    Specific(Vec<(String, i64)>),
+}
+
+impl LotSelection {
+    /// Convert this `LotSelection` to its JSON form. Specific identification is stored as an
+    /// array of the lots named, and every other selection as a string.
+    pub fn to_json(&self) -> JsonValue {
+        match *self {
+            LotSelection::Fifo => "fifo".into(),
+            LotSelection::Lifo => "lifo".into(),
+            LotSelection::HighestCost => "highest_cost".into(),
+            LotSelection::Specific(ref lots) => JsonValue::Array(
+                lots.iter()
+                    .map(|(id, units)| {
+                        let mut value = JsonValue::new_object();
+                        value["lot"] = id.as_str().into();
+                        value["units"] = (*units).into();
+                        value
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Create a `LotSelection` from its JSON form, as produced by `LotSelection::to_json()`. Unknown
+/// values are treated as `Fifo`.
+impl<'a> From<&'a JsonValue> for LotSelection {
+    fn from(value: &'a JsonValue) -> LotSelection {
+        if value.is_array() {
+            return LotSelection::Specific(
+                value
+                    .members()
+                    .map(|lot| {
+                        (
+                            String::from(lot["lot"].as_str().unwrap_or("")),
+                            lot["units"].as_i64().unwrap_or(0),
+                        )
+                    })
+                    .collect(),
+            );
+        }
+
+        match value.as_str().unwrap_or("") {
+            "lifo" => LotSelection::Lifo,
+            "highest_cost" => LotSelection::HighestCost,
+            _ => LotSelection::Fifo,
+        }
+    }
+}
+
+/// The units of a commodity bought by a single purchase, and still held, along with their cost.
+///
+/// A lot is opened by each `Split` that posts a positive number of units to an `Account` holding
+/// a `Commodity`. The first lot opened in an `Account` by a `Transaction` has the id of the
+/// `Transaction`, and any further lots it opens in the same `Account` have the ids `{id}/2`,
+/// `{id}/3` and so on.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Lot {
+    pub(crate) id: String,
+    pub(crate) account_id: String,
+    pub(crate) date: NaiveDate,
+    pub(crate) units: i64,
+    pub(crate) cost: i64,
+}
+
+impl Lot {
+    /// Retrieve the id of this `Lot`, as a `String`.
+    pub fn get_id(&self) -> String {
+        String::clone(&self.id)
+    }
+
+    /// Retrieve the id of the `Account` holding this `Lot`, as a `String`.
+    pub fn get_account_id(&self) -> String {
+        String::clone(&self.account_id)
+    }
+
+    /// Retrieve the date the units of this `Lot` were bought.
+    pub fn get_date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// Retrieve the number of units of this `Lot` still held.
+    pub fn get_units(&self) -> i64 {
+        self.units
+    }
+
+    /// Retrieve the cost basis of the units of this `Lot` still held, in the currency of the
+    /// `Transaction` that bought them.
+    pub fn get_cost(&self) -> i64 {
+        self.cost
+    }
+
+    /// Compare the cost per unit of two `Lot`s.
+    fn cmp_unit_cost(&self, other: &Lot) -> Ordering {
+        (i128::from(self.cost) * i128::from(other.units))
+            .cmp(&(i128::from(other.cost) * i128::from(self.units)))
+    }
+}
+
+impl Ledger {
+    /// Set the `Account` to which realized capital gains and losses are posted.
+    ///
+    /// Once set, each `Transaction` added to or edited in this `Ledger` that sells units of a
+    /// `Commodity` is completed with two generated `Split`s. The first adjusts the value of the
+    /// `Account` holding the commodity so that the units sold are removed at the cost of the
+    /// lots they were taken from, and the second posts the difference between that cost and the
+    /// proceeds of the sale to this `Account`.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Account` was set, or an `Error` if it does not exist, is a placeholder,
+    ///   or is not an Income or Expense `Account`.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Commodity,
+    ///#               CommodityKind, Currency, Ledger, Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Cash", "Cash", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    /// let mut apple = Account::new("02", "Brokerage (AAPL)", "Brokerage (AAPL)",
+    ///                              AccountType::Asset, Currency::USDollar, false);
+    /// apple.set_commodity("AAPL");
+    /// chart.add_top_level_account(apple);
+    ///# chart.add_top_level_account(Account::new("04", "Capital Gains", "Capital Gains",
+    ///#                                          AccountType::Income, Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// let apple_stock = Commodity::new("AAPL", "Apple Inc.", CommodityKind::Stock, 1);
+    /// ledger.add_commodity(apple_stock).unwrap();
+    /// ledger.set_capital_gains_account("04").unwrap();
+    ///
+    /// let mut buy = Transaction::new("BUY-1", NaiveDate::from_ymd_opt(2018, 1, 2).unwrap(),
+    ///                                "Buy 10 AAPL");
+    /// buy.add_split(Split::new_commodity("02", 10, 170000));
+    /// buy.add_split(Split::new("01", -170000));
+    /// ledger.add_transaction(buy).unwrap();
+    ///
+    /// let mut sell = Transaction::new("SELL-1", NaiveDate::from_ymd_opt(2018, 6, 1).unwrap(),
+    ///                                 "Sell 4 AAPL");
+    /// sell.add_split(Split::new_commodity("02", -4, -76000));
+    /// sell.add_split(Split::new("01", 76000));
+    /// ledger.add_transaction(sell).unwrap();
+    ///
+    /// let options = BalanceOptions::new();
+    /// assert_eq!(ledger.get_balance("02", &options).unwrap(), 6);
+    /// assert_eq!(ledger.get_carrying_value("02", &options).unwrap(), 102000);
+    /// assert_eq!(ledger.get_balance("04", &options).unwrap(), 8000);
+    /// ```
+    pub fn set_capital_gains_account(&mut self, account_id: &str) -> Result<(), Error> {
+        let account = match self.get_chart().get_account_by_id(account_id) {
+            Some(account) => account,
+            None => return Err(Error::UnknownAccount(String::from(account_id))),
+        };
+
+        if account.is_placeholder() {
+            return Err(Error::PlaceholderAccount(String::from(account_id)));
+        }
+
+        match account.get_account_type() {
+            AccountType::Income | AccountType::Expense => {
+                *self.capital_gains_account_mut() = Some(String::from(account_id));
+                Ok(())
+            }
+            _ => Err(Error::WrongAccountType(String::from(account_id))),
+        }
+    }
+
+    /// Retrieve the id of the `Account` to which realized capital gains and losses are posted,
+    /// if one has been set.
+    pub fn get_capital_gains_account(&self) -> Option<String> {
+        self.capital_gains_account().clone()
+    }
+
+    /// Retrieve the open `Lot`s of the `Account` with the given id, oldest first.
+    ///
+    /// The `Transaction`s included by `options` are replayed in date order, opening a `Lot` for
+    /// each purchase, removing units from the `Lot`s chosen by the `LotSelection` of each sale,
+    /// and applying each `CorporateAction` to the `Lot`s it affects. Voided `Transaction`s and
+    /// their reversals are skipped, since together they leave the lots unchanged. Sub-accounts are
+    /// never included, and an `Account` that does not hold a `Commodity` has no `Lot`s.
+    ///
+    /// # Returns
+    /// * The `Lot`s still holding units, or an `Error` if the `Account` does not exist or the
+    ///   recorded sales cannot be matched to the lots held.
+    pub fn get_lots(&self, account_id: &str, options: &BalanceOptions) -> Result<Vec<Lot>, Error> {
+        if self.get_chart().get_account_by_id(account_id).is_none() {
+            return Err(Error::UnknownAccount(String::from(account_id)));
+        }
+
+        let mut holdings = self.replay_lots(|transaction| options.includes(transaction))?;
+
+        Ok(holdings.remove(account_id).unwrap_or_default())
+    }
+
+    /// Check the sales in a `Transaction` against the lots held, and complete it with the
+    /// `Split`s recording the capital gains and losses it realizes, replacing any it already has.
+    /// Gains and losses are only recorded once a capital gains `Account` has been set.
+    pub(crate) fn realize_capital_gains(
+        &self,
+        mut transaction: Transaction,
+    ) -> Result<Transaction, Error> {
+        if transaction.get_source() == EntrySource::CorporateAction {
+            return Ok(transaction);
+        }
+
+        let gains_id = self.capital_gains_account().clone();
+        if gains_id.is_some() {
+            transaction.remove_capital_gains();
+        }
+
+        let mut account_ids: Vec<String> = vec![];
+        for split in transaction.splits() {
+            let account_id = split.get_account_id();
+            let holds_commodity = self
+                .get_chart()
+                .get_account_by_id(&account_id)
+                .is_some_and(|account| account.get_commodity().is_some());
+            if holds_commodity && split.get_amount() < 0 && !account_ids.contains(&account_id) {
+                account_ids.push(account_id);
+            }
+        }
+
+        if account_ids.is_empty() {
+            return Ok(transaction);
+        }
+
+        let mut holdings = self.replay_lots(|existing| {
+            existing.get_id() != transaction.get_id()
+                && existing.get_date() <= transaction.get_date()
+        })?;
+        for account_id in account_ids {
+            let mut lots = holdings.remove(&account_id).unwrap_or_default();
+            let (cost, proceeds) = post_to_lots(&mut lots, &account_id, &transaction)?;
+
+            let difference = -cost - proceeds;
+            if let Some(ref gains_id) = gains_id {
+                if difference != 0 {
+                    transaction.add_split(Split::new_capital_gain(&account_id, 0, difference));
+                    transaction.add_split(Split::new_capital_gain(
+                        gains_id,
+                        -difference,
+                        -difference,
+                    ));
+                }
+            }
+        }
+
+        Ok(transaction)
+    }
+
+    /// Find the open lots of every `Account` holding a `Commodity` after replaying the
+    /// `Transaction`s accepted by `include`, in date order, skipping voided `Transaction`s and
+    /// their reversals.
+    pub(crate) fn replay_lots<F>(&self, include: F) -> Result<HashMap<String, Vec<Lot>>, Error>
+    where
+        F: Fn(&Transaction) -> bool,
+    {
+        let mut account_ids: Vec<String> = self
+            .get_chart()
+            .get_account_ids()
+            .into_iter()
+            .filter(|account_id| {
+                self.get_chart()
+                    .get_account_by_id(account_id)
+                    .is_some_and(|account| account.get_commodity().is_some())
+            })
+            .collect();
+        account_ids.sort();
+
+        let mut transactions: Vec<&Transaction> = self
+            .transactions()
+            .iter()
+            .filter(|transaction| {
+                transaction.get_status() != TransactionStatus::Voided && include(transaction)
+            })
+            .collect();
+        transactions.sort_by_key(|transaction| transaction.get_date());
+
+        let mut holdings: HashMap<String, Vec<Lot>> = HashMap::new();
+        for transaction in transactions {
+            if let Some(action) = self.get_corporate_action_for(transaction) {
+                action.apply_to_lots(&mut holdings)?;
+                continue;
+            }
+
+            for account_id in &account_ids {
+                if transaction
+                    .splits()
+                    .iter()
+                    .any(|split| split.get_account_id() == *account_id)
+                {
+                    let lots = holdings.entry(String::clone(account_id)).or_default();
+                    post_to_lots(lots, account_id, transaction)?;
+                }
+            }
+        }
+
+        Ok(holdings)
+    }
+}
+
+/// Post the units a `Transaction` buys and sells in an `Account` to its open lots.
+///
+/// # Returns
+/// * The total cost of the units sold and the total value of the sales (negative, as credits),
+///   or an `Error` if the units sold cannot be taken from the open lots.
+fn post_to_lots(
+    lots: &mut Vec<Lot>,
+    account_id: &str,
+    transaction: &Transaction,
+) -> Result<(i64, i64), Error> {
+    let mut opened = 0;
+    let mut cost = 0;
+    let mut proceeds = 0;
+    for split in transaction.splits() {
+        if split.get_account_id() != account_id {
+            continue;
+        }
+
+        if split.get_amount() > 0 {
+            opened += 1;
+            let id = if opened == 1 {
+                transaction.get_id()
+            } else {
+                format!("{}/{}", transaction.get_id(), opened)
+            };
+            lots.push(Lot {
+                id,
+                account_id: String::from(account_id),
+                date: transaction.get_date(),
+                units: split.get_amount(),
+                cost: split.get_value(),
+            });
+        } else if split.get_amount() < 0 {
+            cost += take_units(
+                lots,
+                account_id,
+                -split.get_amount(),
+                &split.get_lot_selection(),
+            )?;
+            proceeds += split.get_value();
+        }
+    }
+
+    Ok((cost, proceeds))
+}
+
+/// Remove a number of units from the open lots chosen by a `LotSelection`, dropping any lots
+/// left empty.
+///
+/// # Returns
+/// * The cost of the units removed, or an `Error` if a named lot is not open or does not hold
+///   enough units, or the lots do not hold enough units in total.
+fn take_units(
+    lots: &mut Vec<Lot>,
+    account_id: &str,
+    units: i64,
+    selection: &LotSelection,
+) -> Result<i64, Error> {
+    let mut remaining = units;
+    let mut cost = 0;
+
+    if let LotSelection::Specific(ref named) = *selection {
+        for (lot_id, lot_units) in named {
+            let wanted = (*lot_units).min(remaining);
+            if wanted <= 0 {
+                continue;
+            }
+
+            match lots.iter_mut().find(|lot| lot.id == *lot_id) {
+                Some(lot) if lot.units >= wanted => {
+                    cost += take_from_lot(lot, wanted);
+                    remaining -= wanted;
+                }
+                _ => return Err(Error::UnknownLot(String::clone(lot_id))),
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..lots.len()).collect();
+    match *selection {
+        LotSelection::Lifo => order.reverse(),
+        LotSelection::HighestCost => order.sort_by(|&a, &b| lots[b].cmp_unit_cost(&lots[a])),
+        _ => {}
+    }
+
+    for index in order {
+        if remaining == 0 {
+            break;
+        }
+
+        let wanted = remaining.min(lots[index].units);
+        cost += take_from_lot(&mut lots[index], wanted);
+        remaining -= wanted;
+    }
+
+    lots.retain(|lot| lot.units > 0);
+    if remaining > 0 {
+        return Err(Error::InsufficientUnits(String::from(account_id)));
+    }
+
+    Ok(cost)
+}
+
+/// Remove a number of units from a lot, returning their share of its cost.
+fn take_from_lot(lot: &mut Lot, units: i64) -> i64 {
+    let cost = if units == lot.units {
+        lot.cost
+    } else {
+        get_share(lot.cost, units, lot.units)
+    };
+    lot.units -= units;
+    lot.cost -= cost;
+
+    cost
+}
@@ -0,0 +1,148 @@
+//! Opening balances for books migrated into a `Ledger` part way through their history.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+
+use error::Error;
+use ledger::Ledger;
+use transaction::{EntrySource, Split, Transaction};
+use {AccountType, NormalBalance};
+
+impl Ledger {
+    /// Generate, without posting, an opening balance entry.
+    ///
+    /// Each balance is posted to its `Account`. If the balances form a complete trial balance,
+    /// the entry balances on its own. Otherwise, the difference between the total debits and
+    /// total credits can be posted to an Opening Balances equity account, so that the entry as a
+    /// whole balances.
+    ///
+    /// # Arguments
+    /// * `date`: The date the balances were taken on.
+    /// * `balances`: A map from the id of each `Account` to its balance on `date`, signed
+    ///   according to the `Account`'s normal balance.
+    /// * `opening_balances_id`: The id of the Equity `Account` to post any difference to, or
+    ///   `None` if the balances must form a complete trial balance.
+    ///
+    /// # Returns
+    /// * The opening balance `Transaction`, `Error::Unbalanced` if the balances do not balance
+    ///   and no Opening Balances account was given, or an `Error` if any `Account` is unknown or
+    ///   a placeholder, or the Opening Balances account is not an Equity account.
+    pub fn preview_opening_balances(
+        &self,
+        date: NaiveDate,
+        balances: &HashMap<String, i64>,
+        opening_balances_id: Option<&str>,
+    ) -> Result<Transaction, Error> {
+        let chart = self.get_chart();
+        if let Some(opening_balances_id) = opening_balances_id {
+            match chart.get_account_by_id(opening_balances_id) {
+                Some(ref account) if account.is_placeholder() => {
+                    return Err(Error::PlaceholderAccount(String::from(opening_balances_id)));
+                }
+                Some(ref account) if account.get_account_type() != AccountType::Equity => {
+                    return Err(Error::WrongAccountType(String::from(opening_balances_id)));
+                }
+                Some(_) => {}
+                None => return Err(Error::UnknownAccount(String::from(opening_balances_id))),
+            }
+        }
+
+        let mut account_ids: Vec<_> = balances.keys().cloned().collect();
+        account_ids.sort();
+
+        let mut entry = Transaction::new(&format!("OPENING-{}", date), date, "Opening balances");
+        entry.set_source(EntrySource::OpeningBalance);
+
+        let mut total = 0;
+        for account_id in account_ids {
+            let account = match chart.get_account_by_id(&account_id) {
+                Some(account) => account,
+                None => return Err(Error::UnknownAccount(account_id)),
+            };
+            if account.is_placeholder() {
+                return Err(Error::PlaceholderAccount(account_id));
+            }
+            if opening_balances_id == Some(account_id.as_str()) {
+                return Err(Error::WrongAccountType(account_id));
+            }
+
+            let balance = balances[&account_id];
+            if balance == 0 {
+                continue;
+            }
+
+            let amount = match account.get_account_type().get_normal_balance() {
+                NormalBalance::Debit => balance,
+                NormalBalance::Credit => -balance,
+            };
+            total += amount;
+
+            let mut split = Split::new(&account_id, amount);
+            split.set_memo("Opening balance");
+            entry.add_split(split);
+        }
+
+        if total != 0 {
+            let opening_balances_id = match opening_balances_id {
+                Some(opening_balances_id) => opening_balances_id,
+                None => return Err(Error::Unbalanced(entry.get_id())),
+            };
+            let mut split = Split::new(opening_balances_id, -total);
+            split.set_memo("Opening balance offset");
+            entry.add_split(split);
+        }
+
+        Ok(entry)
+    }
+
+    /// Generate and post an opening balance entry.
+    ///
+    /// See `preview_opening_balances()` for how the entry is computed. The entry is marked with
+    /// `EntrySource::OpeningBalance`.
+    ///
+    /// # Returns
+    /// * The id of the opening balance `Transaction`, or an `Error` if it could not be generated,
+    ///   opening balances have already been posted on `date`, or `date` is within a closed
+    ///   `FiscalPeriod`.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use std::collections::HashMap;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Currency, Ledger};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("02", "Credit Card", "Credit Card",
+    ///#                                          AccountType::Liability, Currency::USDollar,
+    ///#                                          false));
+    ///# chart.add_top_level_account(Account::new("03", "Opening Balances", "Opening Balances",
+    ///#                                          AccountType::Equity, Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    ///
+    /// let mut balances = HashMap::new();
+    /// balances.insert(String::from("01"), 125000);
+    /// balances.insert(String::from("02"), 25000);
+    ///
+    /// let date = NaiveDate::from_ymd_opt(2018, 7, 1).unwrap();
+    /// ledger.post_opening_balances(date, &balances, Some("03")).unwrap();
+    ///
+    /// assert_eq!(ledger.get_balance("02", &BalanceOptions::new()).unwrap(), 25000);
+    /// assert_eq!(ledger.get_balance("03", &BalanceOptions::new()).unwrap(), 100000);
+    /// ```
+    pub fn post_opening_balances(
+        &mut self,
+        date: NaiveDate,
+        balances: &HashMap<String, i64>,
+        opening_balances_id: Option<&str>,
+    ) -> Result<String, Error> {
+        let entry = self.preview_opening_balances(date, balances, opening_balances_id)?;
+        let id = entry.get_id();
+        self.add_transaction(entry)?;
+
+        Ok(id)
+    }
+}
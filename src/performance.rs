@@ -0,0 +1,412 @@
+//! Performance of investment `Account`s: market values, returns, unrealized gains and income.
+
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
+
+use balance::BalanceOptions;
+use error::Error;
+use ledger::Ledger;
+use lot::Lot;
+use {Account, AccountType, NormalBalance};
+
+/// The gain or loss, not yet realized by a sale, on the units of a `Lot` still held.
+#[derive(Clone, PartialEq, Debug)]
+pub struct UnrealizedGain {
+    lot: Lot,
+    market_value: i64,
+}
+
+impl UnrealizedGain {
+    /// Retrieve the `Lot` the gain is on.
+    pub fn get_lot(&self) -> Lot {
+        Lot::clone(&self.lot)
+    }
+
+    /// Retrieve the market value of the units of the `Lot` still held.
+    pub fn get_market_value(&self) -> i64 {
+        self.market_value
+    }
+
+    /// Retrieve the gain, which is the market value less the cost basis. A loss is negative.
+    pub fn get_gain(&self) -> i64 {
+        self.market_value - self.lot.get_cost()
+    }
+}
+
+impl Ledger {
+    /// Compute the market value of an `Account`, including its sub-accounts, at the end of a
+    /// date, in the `Currency` of that `Account`.
+    ///
+    /// The units held by each `Account` holding a `Commodity` are valued at the price of the
+    /// commodity in the `PriceDatabase`, which is quoted as the value of one whole unit in the
+    /// smallest unit of the `Account`'s `Currency` (e.g. a share worth 170.00 is quoted as
+    /// 17000). Balances of other `Account`s, such as cash held in a brokerage account, are
+    /// converted from their `Currency` where it differs.
+    ///
+    /// # Returns
+    /// * The market value, signed according to the `Account`'s normal balance, or an `Error` if
+    ///   the `Account` does not exist or a price or rate is unknown.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, Commodity, CommodityKind, Currency,
+    ///#               Ledger, Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    /// let mut apple = Account::new("02", "Brokerage (AAPL)", "Brokerage (AAPL)",
+    ///                              AccountType::Asset, Currency::USDollar, false);
+    /// apple.set_commodity("AAPL");
+    /// chart.add_top_level_account(apple);
+    /// let mut ledger = Ledger::new(chart);
+    /// let apple_stock = Commodity::new("AAPL", "Apple Inc.", CommodityKind::Stock, 1);
+    /// ledger.add_commodity(apple_stock).unwrap();
+    ///
+    /// let mut buy = Transaction::new("BUY-1", NaiveDate::from_ymd_opt(2018, 1, 2).unwrap(),
+    ///                                "Buy 10 AAPL");
+    /// buy.add_split(Split::new_commodity("02", 10, 170000));
+    /// buy.add_split(Split::new("01", -170000));
+    /// ledger.add_transaction(buy).unwrap();
+    ///
+    /// let date = NaiveDate::from_ymd_opt(2018, 12, 31).unwrap();
+    /// ledger.get_price_database_mut().add_price("AAPL", "USD", date, 15750.0);
+    /// assert_eq!(ledger.get_market_value("02", date).unwrap(), 157500);
+    /// ```
+    pub fn get_market_value(&self, account_id: &str, date: NaiveDate) -> Result<i64, Error> {
+        let account = match self.get_chart().get_account_by_id(account_id) {
+            Some(account) => account,
+            None => return Err(Error::UnknownAccount(String::from(account_id))),
+        };
+
+        let options = BalanceOptions::new().as_of(date);
+        let mut total = 0.0;
+        for member_id in self.get_chart().get_sub_tree_ids(account_id) {
+            let member = self.get_chart().get_account_by_id(&member_id).unwrap();
+            let amount: i64 = self
+                .transactions()
+                .iter()
+                .filter(|transaction| options.includes(transaction))
+                .flat_map(|transaction| transaction.splits())
+                .filter(|split| split.get_account_id() == member_id)
+                .map(|split| split.get_amount())
+                .sum();
+            if amount == 0 {
+                continue;
+            }
+
+            let value = match member.get_commodity() {
+                Some(symbol) => {
+                    let fraction = self
+                        .get_commodity(&symbol)
+                        .map_or(1, |commodity| commodity.get_fraction());
+                    let price = self.get_price_database().get_rate(
+                        &symbol,
+                        member.get_currency().get_code(),
+                        date,
+                    )?;
+                    amount as f64 * price / f64::from(fraction)
+                }
+                None => amount as f64,
+            };
+            let rate = self.get_price_database().get_rate(
+                member.get_currency().get_code(),
+                account.get_currency().get_code(),
+                date,
+            )?;
+            total += value * rate;
+        }
+
+        let total = total.round() as i64;
+        match account.get_account_type().get_normal_balance() {
+            NormalBalance::Debit => Ok(total),
+            NormalBalance::Credit => Ok(-total),
+        }
+    }
+
+    /// Compute the money-weighted return of an `Account`, including its sub-accounts, over a
+    /// period, as an annual rate (XIRR).
+    ///
+    /// The market value at the end of `start` is treated as invested on that date, and the
+    /// market value at the end of `end` as withdrawn on that date. In between, every
+    /// `Transaction` moving value between the `Account` and an Asset, Liability or Equity
+    /// `Account` outside it is a contribution or withdrawal. Postings from Income and Expense
+    /// `Account`s, such as dividends and fees, are part of the return.
+    ///
+    /// # Returns
+    /// * The annual rate of return, e.g. 0.05 for 5%, or an `Error` if the `Account` does not
+    ///   exist, a price or rate is unknown, or nothing was invested over the period.
+    pub fn get_money_weighted_return(
+        &self,
+        account_id: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<f64, Error> {
+        let mut flows = vec![(start, -self.get_market_value(account_id, start)?)];
+        for (date, flow) in self.get_external_flows(account_id, start, end)? {
+            flows.push((date, -flow));
+        }
+        flows.push((end, self.get_market_value(account_id, end)?));
+
+        let present_value = |rate: f64| -> f64 {
+            flows
+                .iter()
+                .map(|&(date, flow)| {
+                    let years = date.signed_duration_since(start).num_days() as f64 / 365.0;
+                    flow as f64 / (1.0 + rate).powf(years)
+                })
+                .sum()
+        };
+
+        let mut low = -0.9999;
+        let mut high = 1.0;
+        while present_value(low).signum() == present_value(high).signum() {
+            if high > 1e9 {
+                return Err(Error::UndefinedReturn(String::from(account_id)));
+            }
+            high = high * 2.0 + 1.0;
+        }
+
+        let low_sign = present_value(low).signum();
+        for _ in 0..200 {
+            let middle = (low + high) / 2.0;
+            if present_value(middle).signum() == low_sign {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+
+        Ok((low + high) / 2.0)
+    }
+
+    /// Compute the time-weighted return of an `Account`, including its sub-accounts, over a
+    /// period. Unlike the money-weighted return, it is not annualized.
+    ///
+    /// The period is divided at each contribution or withdrawal (see
+    /// `get_money_weighted_return()`), which is taken to happen at the end of its day, and the
+    /// returns of the sub-periods are compounded. Sub-periods that begin with no market value
+    /// are skipped.
+    ///
+    /// # Returns
+    /// * The rate of return, e.g. 0.05 for 5%, or an `Error` if the `Account` does not exist, a
+    ///   price or rate is unknown, or nothing was invested over the period.
+    pub fn get_time_weighted_return(
+        &self,
+        account_id: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<f64, Error> {
+        let mut flows: Vec<(NaiveDate, i64)> = vec![];
+        for (date, flow) in self.get_external_flows(account_id, start, end)? {
+            match flows.last_mut() {
+                Some(last) if last.0 == date => last.1 += flow,
+                _ => flows.push((date, flow)),
+            }
+        }
+        if flows.last().is_none_or(|&(date, _)| date != end) {
+            flows.push((end, 0));
+        }
+
+        let mut growth = 1.0;
+        let mut measured = false;
+        let mut previous = self.get_market_value(account_id, start)?;
+        for (date, flow) in flows {
+            let value = self.get_market_value(account_id, date)?;
+            if previous != 0 {
+                growth *= (value - flow) as f64 / previous as f64;
+                measured = true;
+            }
+            previous = value;
+        }
+
+        if !measured {
+            return Err(Error::UndefinedReturn(String::from(account_id)));
+        }
+
+        Ok(growth - 1.0)
+    }
+
+    /// Compute the unrealized gain or loss on each open `Lot` held by an `Account` or its
+    /// sub-accounts at the end of a date, using the prices in the `PriceDatabase`. See
+    /// `get_market_value()` for how prices are quoted.
+    ///
+    /// # Returns
+    /// * The gains, ordered by `Account` id and then oldest `Lot` first, or an `Error` if the
+    ///   `Account` does not exist or a price is unknown.
+    pub fn get_unrealized_gains(
+        &self,
+        account_id: &str,
+        date: NaiveDate,
+    ) -> Result<Vec<UnrealizedGain>, Error> {
+        if self.get_chart().get_account_by_id(account_id).is_none() {
+            return Err(Error::UnknownAccount(String::from(account_id)));
+        }
+
+        let options = BalanceOptions::new().as_of(date);
+        let mut member_ids = self.get_chart().get_sub_tree_ids(account_id);
+        member_ids.sort();
+
+        let mut gains = vec![];
+        for member_id in member_ids {
+            let member = self.get_chart().get_account_by_id(&member_id).unwrap();
+            let symbol = match member.get_commodity() {
+                Some(symbol) => symbol,
+                None => continue,
+            };
+
+            let lots = self.get_lots(&member_id, &options)?;
+            if lots.is_empty() {
+                continue;
+            }
+
+            let fraction = self
+                .get_commodity(&symbol)
+                .map_or(1, |commodity| commodity.get_fraction());
+            let price = self.get_price_database().get_rate(
+                &symbol,
+                member.get_currency().get_code(),
+                date,
+            )?;
+            for lot in lots {
+                let market_value =
+                    (lot.get_units() as f64 * price / f64::from(fraction)).round() as i64;
+                gains.push(UnrealizedGain { lot, market_value });
+            }
+        }
+
+        Ok(gains)
+    }
+
+    /// Summarize the income, such as dividends and interest, earned by an `Account` or its
+    /// sub-accounts.
+    ///
+    /// Income is every posting to an Income `Account` made by a `Transaction` that also posts to
+    /// the `Account` or its sub-accounts. Realized capital gains are not included.
+    ///
+    /// # Returns
+    /// * A `HashMap` from the id of each Income `Account` to the income posted to it, or an
+    ///   `Error` if the `Account` does not exist.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Currency, Ledger,
+    ///#               Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("02", "Brokerage", "Brokerage",
+    ///#                                          AccountType::Asset, Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("04", "Dividends", "Dividends",
+    ///#                                          AccountType::Income, Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// let mut dividend = Transaction::new("DIV-1", NaiveDate::from_ymd_opt(2018, 3, 15).unwrap(),
+    ///                                     "Quarterly dividend");
+    /// dividend.add_split(Split::new("02", 1250));
+    /// dividend.add_split(Split::new("04", -1250));
+    /// ledger.add_transaction(dividend).unwrap();
+    ///
+    /// let income = ledger.get_investment_income("02", &BalanceOptions::new()).unwrap();
+    /// assert_eq!(income["04"], 1250);
+    /// ```
+    pub fn get_investment_income(
+        &self,
+        account_id: &str,
+        options: &BalanceOptions,
+    ) -> Result<HashMap<String, i64>, Error> {
+        if self.get_chart().get_account_by_id(account_id).is_none() {
+            return Err(Error::UnknownAccount(String::from(account_id)));
+        }
+
+        let member_ids = self.get_chart().get_sub_tree_ids(account_id);
+        let mut income = HashMap::new();
+        for transaction in self
+            .transactions()
+            .iter()
+            .filter(|transaction| options.includes(transaction))
+        {
+            let splits = transaction.splits();
+            if !splits
+                .iter()
+                .any(|split| member_ids.contains(&split.get_account_id()))
+            {
+                continue;
+            }
+
+            for split in splits {
+                if !options.includes_split(split) || split.is_capital_gain() {
+                    continue;
+                }
+
+                let account_id = split.get_account_id();
+                if self.get_account_type(&account_id) == Some(AccountType::Income) {
+                    *income.entry(account_id).or_insert(0) -= split.get_value();
+                }
+            }
+        }
+
+        Ok(income)
+    }
+
+    /// Find the contributions to (positive) and withdrawals from (negative) an `Account` and
+    /// its sub-accounts, dated after `start` and up to `end`, in date order.
+    fn get_external_flows(
+        &self,
+        account_id: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, i64)>, Error> {
+        if self.get_chart().get_account_by_id(account_id).is_none() {
+            return Err(Error::UnknownAccount(String::from(account_id)));
+        }
+
+        let member_ids = self.get_chart().get_sub_tree_ids(account_id);
+        let options = BalanceOptions::new()
+            .since(start + Duration::days(1))
+            .as_of(end);
+        let mut flows = vec![];
+        for transaction in self
+            .transactions()
+            .iter()
+            .filter(|transaction| options.includes(transaction))
+        {
+            let splits = transaction.splits();
+            if !splits
+                .iter()
+                .any(|split| member_ids.contains(&split.get_account_id()))
+            {
+                continue;
+            }
+
+            let flow: i64 = splits
+                .iter()
+                .filter(|split| !member_ids.contains(&split.get_account_id()))
+                .filter(|split| {
+                    !matches!(
+                        self.get_account_type(&split.get_account_id()),
+                        Some(AccountType::Income) | Some(AccountType::Expense)
+                    )
+                })
+                .map(|split| -split.get_value())
+                .sum();
+            if flow != 0 {
+                flows.push((transaction.get_date(), flow));
+            }
+        }
+        flows.sort_by_key(|&(date, _)| date);
+
+        Ok(flows)
+    }
+
+    /// Find the `AccountType` of the `Account` with the given id, if it exists.
+    fn get_account_type(&self, account_id: &str) -> Option<AccountType> {
+        self.get_chart()
+            .get_account_by_id(account_id)
+            .as_ref()
+            .map(Account::get_account_type)
+    }
+}
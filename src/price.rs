@@ -0,0 +1,335 @@
+//! A database of dated exchange rates between currencies and other commodities.
+
+use std::convert::TryFrom;
+
+use chrono::NaiveDate;
+use json::JsonValue;
+
+use error::Error;
+use ledger::Ledger;
+use transaction::parse_date;
+
+/// An enumeration controlling which quote is used when no price was recorded on the requested
+/// date.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum PriceFallback {
+    /// Only use a quote recorded on the requested date.
+    Exact,
+
+    /// Use the most recent quote recorded on or before the requested date.
+    #[default]
+    NearestEarlier,
+
+    /// Use the most recent quote recorded on or before the requested date, as long as it is no
+    /// more than the given number of days old.
+    NearestEarlierWithin(u32),
+}
+
+/// A single exchange rate quote: the number of units of one commodity that one unit of another
+/// was worth on a given date.
+///
+/// Commodities are identified by code, such as the code of a `Currency` (e.g. `USD`).
+#[derive(Clone, PartialEq, Debug)]
+pub struct Price {
+    commodity: String,
+    currency: String,
+    date: NaiveDate,
+    rate: f64,
+}
+
+impl Price {
+    /// Create a new `Price` object.
+    ///
+    /// # Arguments
+    /// * `commodity`: The code of the commodity being priced, e.g. `EUR`.
+    /// * `currency`: The code of the commodity the price is expressed in, e.g. `USD`.
+    /// * `date`: The date of the quote.
+    /// * `rate`: The number of units of `currency` that one unit of `commodity` was worth.
+    pub fn new(commodity: &str, currency: &str, date: NaiveDate, rate: f64) -> Price {
+        Price {
+            commodity: String::from(commodity),
+            currency: String::from(currency),
+            date,
+            rate,
+        }
+    }
+
+    /// Retrieve the code of the commodity being priced, as a `String`.
+    pub fn get_commodity(&self) -> String {
+        String::clone(&self.commodity)
+    }
+
+    /// Retrieve the code of the commodity the price is expressed in, as a `String`.
+    pub fn get_currency(&self) -> String {
+        String::clone(&self.currency)
+    }
+
+    /// Retrieve the date of the quote.
+    pub fn get_date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// Retrieve the number of units of the currency that one unit of the commodity was worth.
+    pub fn get_rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Convert this `Price` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["commodity"] = self.commodity.as_str().into();
+        value["currency"] = self.currency.as_str().into();
+        value["date"] = self.date.format("%Y-%m-%d").to_string().into();
+        value["rate"] = self.rate.into();
+
+        value
+    }
+}
+
+/// Create a `Price` object from its JSON form, as produced by `Price::to_json()`.
+///
+/// A quote without both commodity codes, a valid date and a positive rate is rejected with
+/// `Error::InvalidJson`, rather than being read as a quote that would distort conversions.
+impl<'a> TryFrom<&'a JsonValue> for Price {
+    type Error = Error;
+
+    fn try_from(value: &'a JsonValue) -> Result<Price, Error> {
+        let (commodity, currency) = match (value["commodity"].as_str(), value["currency"].as_str())
+        {
+            (Some(commodity), Some(currency)) => (commodity, currency),
+            _ => {
+                return Err(Error::InvalidJson(String::from(
+                    "a price has no 'commodity' or 'currency'",
+                )))
+            }
+        };
+        let date = match parse_date(&value["date"]) {
+            Some(date) => date,
+            None => return Err(Error::InvalidJson(String::from("a price has no 'date'"))),
+        };
+        match value["rate"].as_f64() {
+            Some(rate) if rate > 0.0 && rate.is_finite() => {
+                Ok(Price::new(commodity, currency, date, rate))
+            }
+            _ => Err(Error::InvalidJson(String::from(
+                "a price has no positive 'rate'",
+            ))),
+        }
+    }
+}
+
+/// A database of dated exchange rates between pairs of commodities.
+///
+/// Rates are derived from the recorded quotes directly, from the inverse of a quote for the
+/// opposite pair, or by triangulating through a third commodity quoted against both.
+///
+/// # Examples
+/// ```
+///# extern crate chrono;
+///# extern crate cratchit;
+///# use chrono::NaiveDate;
+///# use cratchit::PriceDatabase;
+/// let mut prices = PriceDatabase::new();
+/// prices.add_price("EUR", "USD", NaiveDate::from_ymd_opt(2018, 3, 1).unwrap(), 1.25);
+/// prices.add_price("CAD", "USD", NaiveDate::from_ymd_opt(2018, 3, 1).unwrap(), 0.8);
+///
+/// let date = NaiveDate::from_ymd_opt(2018, 3, 15).unwrap();
+/// assert_eq!(prices.get_rate("EUR", "USD", date).unwrap(), 1.25);
+/// assert_eq!(prices.get_rate("USD", "EUR", date).unwrap(), 0.8);
+/// assert_eq!(prices.get_rate("EUR", "CAD", date).unwrap(), 1.5625);
+/// ```
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct PriceDatabase {
+    prices: Vec<Price>,
+    fallback: PriceFallback,
+}
+
+impl PriceDatabase {
+    /// Create a new, empty `PriceDatabase` which falls back to the nearest earlier quote.
+    pub fn new() -> PriceDatabase {
+        PriceDatabase::default()
+    }
+
+    /// Set how a rate is found when no quote was recorded on the requested date.
+    pub fn set_fallback(&mut self, fallback: PriceFallback) {
+        self.fallback = fallback;
+    }
+
+    /// Retrieve how a rate is found when no quote was recorded on the requested date.
+    pub fn get_fallback(&self) -> PriceFallback {
+        self.fallback
+    }
+
+    /// Record a quote, replacing any quote already recorded for the same pair on the same date.
+    ///
+    /// # Arguments
+    /// * `commodity`: The code of the commodity being priced, e.g. `EUR`.
+    /// * `currency`: The code of the commodity the price is expressed in, e.g. `USD`.
+    /// * `date`: The date of the quote.
+    /// * `rate`: The number of units of `currency` that one unit of `commodity` was worth.
+    pub fn add_price(&mut self, commodity: &str, currency: &str, date: NaiveDate, rate: f64) {
+        self.remove_price(commodity, currency, date);
+        self.prices
+            .push(Price::new(commodity, currency, date, rate));
+        self.prices.sort_by(|a, b| {
+            (&a.commodity, &a.currency, a.date).cmp(&(&b.commodity, &b.currency, b.date))
+        });
+    }
+
+    /// Remove the quote recorded for a pair on a given date.
+    ///
+    /// # Returns
+    /// * The removed `Price`, or `None` if no such quote was recorded.
+    pub fn remove_price(
+        &mut self,
+        commodity: &str,
+        currency: &str,
+        date: NaiveDate,
+    ) -> Option<Price> {
+        let position = self.prices.iter().position(|price| {
+            price.commodity == commodity && price.currency == currency && price.date == date
+        })?;
+
+        Some(self.prices.remove(position))
+    }
+
+    /// Retrieve every recorded quote, ordered by pair and then by date.
+    pub fn get_prices(&self) -> Vec<Price> {
+        Vec::clone(&self.prices)
+    }
+
+    /// Retrieve the quotes recorded for a single pair, in date order. Quotes for the opposite
+    /// pair are not included.
+    pub fn get_prices_for(&self, commodity: &str, currency: &str) -> Vec<Price> {
+        self.prices
+            .iter()
+            .filter(|price| price.commodity == commodity && price.currency == currency)
+            .cloned()
+            .collect()
+    }
+
+    /// Find the rate at which one unit of `commodity` converts to `currency` on `date`.
+    ///
+    /// A quote for the pair itself is preferred, followed by the inverse of a quote for the
+    /// opposite pair, and finally a rate triangulated through a third commodity. Which quotes
+    /// are eligible is controlled by the `PriceFallback` of this database.
+    ///
+    /// # Returns
+    /// * The rate, or `Error::UnknownRate` if no eligible quotes exist. The rate between a
+    ///   commodity and itself is always 1.
+    pub fn get_rate(&self, commodity: &str, currency: &str, date: NaiveDate) -> Result<f64, Error> {
+        if commodity == currency {
+            return Ok(1.0);
+        }
+
+        if let Some(rate) = self.get_quoted_rate(commodity, currency, date) {
+            return Ok(rate);
+        }
+
+        let mut intermediates: Vec<&str> = self
+            .prices
+            .iter()
+            .flat_map(|price| vec![price.commodity.as_str(), price.currency.as_str()])
+            .filter(|code| *code != commodity && *code != currency)
+            .collect();
+        intermediates.sort();
+        intermediates.dedup();
+        for intermediate in intermediates {
+            if let (Some(first), Some(second)) = (
+                self.get_quoted_rate(commodity, intermediate, date),
+                self.get_quoted_rate(intermediate, currency, date),
+            ) {
+                return Ok(first * second);
+            }
+        }
+
+        Err(Error::UnknownRate(
+            String::from(commodity),
+            String::from(currency),
+            date,
+        ))
+    }
+
+    /// Convert this `PriceDatabase` to its JSON form, an array of the JSON forms of its quotes.
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::Array(self.prices.iter().map(Price::to_json).collect())
+    }
+
+    /// Find the rate between a pair from a quote for the pair, or the inverse of a quote for the
+    /// opposite pair.
+    fn get_quoted_rate(&self, commodity: &str, currency: &str, date: NaiveDate) -> Option<f64> {
+        if let Some(price) = self.get_eligible_price(commodity, currency, date) {
+            return Some(price.rate);
+        }
+
+        self.get_eligible_price(currency, commodity, date)
+            .filter(|price| price.rate != 0.0)
+            .map(|price| 1.0 / price.rate)
+    }
+
+    /// Find the most recent quote for a pair on or before `date` permitted by the fallback.
+    fn get_eligible_price(
+        &self,
+        commodity: &str,
+        currency: &str,
+        date: NaiveDate,
+    ) -> Option<&Price> {
+        self.prices
+            .iter()
+            .rev()
+            .find(|price| {
+                price.commodity == commodity && price.currency == currency && price.date <= date
+            })
+            .filter(|price| match self.fallback {
+                PriceFallback::Exact => price.date == date,
+                PriceFallback::NearestEarlier => true,
+                PriceFallback::NearestEarlierWithin(days) => {
+                    date.signed_duration_since(price.date).num_days() <= i64::from(days)
+                }
+            })
+    }
+}
+
+/// Create a `PriceDatabase` object from its JSON form, as produced by `PriceDatabase::to_json()`.
+/// The fallback is not saved, so the database falls back to the nearest earlier quote. Malformed
+/// quotes are skipped.
+impl<'a> From<&'a JsonValue> for PriceDatabase {
+    fn from(value: &'a JsonValue) -> PriceDatabase {
+        let mut prices = PriceDatabase::new();
+        for price in value
+            .members()
+            .filter_map(|price| Price::try_from(price).ok())
+        {
+            prices.add_price(&price.commodity, &price.currency, price.date, price.rate);
+        }
+
+        prices
+    }
+}
+
+impl Ledger {
+    /// Retrieve the `PriceDatabase` of exchange rates used by this `Ledger`.
+    pub fn get_price_database(&self) -> &PriceDatabase {
+        self.prices()
+    }
+
+    /// Retrieve the `PriceDatabase` of this `Ledger` for recording new quotes.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use std::convert::TryFrom;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{AccountsChart, Ledger};
+    /// let mut ledger = Ledger::new(AccountsChart::new());
+    /// let date = NaiveDate::from_ymd_opt(2018, 3, 1).unwrap();
+    /// ledger.get_price_database_mut().add_price("EUR", "USD", date, 1.25);
+    ///
+    /// let saved = Ledger::try_from(&ledger.to_json()).unwrap();
+    /// assert_eq!(saved.get_price_database().get_rate("EUR", "USD", date).unwrap(), 1.25);
+    /// ```
+    pub fn get_price_database_mut(&mut self) -> &mut PriceDatabase {
+        self.prices_mut()
+    }
+}
@@ -0,0 +1,494 @@
+//! Reconciliation of an `Account` against a bank statement.
+
+use chrono::NaiveDate;
+use json::JsonValue;
+
+use error::Error;
+use ledger::Ledger;
+use transaction::{parse_date, TransactionStatus};
+use NormalBalance;
+
+/// A single line from a bank statement, used to automatically match postings in a
+/// `Reconciliation`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct StatementLine {
+    date: NaiveDate,
+    amount: i64,
+    payee: String,
+}
+
+impl StatementLine {
+    /// Construct a new `StatementLine` object.
+    ///
+    /// * date: The date the line cleared the bank.
+    /// * amount: The amount of the line, signed according to the normal balance of the `Account`
+    ///   being reconciled. For a checking account, deposits are positive and withdrawals are
+    ///   negative.
+    /// * payee: The payee or description printed on the statement.
+    pub fn new(date: NaiveDate, amount: i64, payee: &str) -> StatementLine {
+        StatementLine {
+            date,
+            amount,
+            payee: String::from(payee),
+        }
+    }
+
+    /// Retrieve the date of this `StatementLine`.
+    pub fn get_date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// Retrieve the amount of this `StatementLine`.
+    pub fn get_amount(&self) -> i64 {
+        self.amount
+    }
+
+    /// Retrieve the payee of this `StatementLine`, as a `String`.
+    pub fn get_payee(&self) -> String {
+        String::clone(&self.payee)
+    }
+}
+
+/// The tolerances allowed when automatically matching `StatementLine`s to postings.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MatchTolerance {
+    days: i64,
+    amount: i64,
+}
+
+impl MatchTolerance {
+    /// Construct a new `MatchTolerance` object.
+    ///
+    /// * days: The number of days a posting's date may differ from the statement line's date.
+    /// * amount: The amount a posting may differ from the statement line's amount.
+    pub fn new(days: i64, amount: i64) -> MatchTolerance {
+        MatchTolerance { days, amount }
+    }
+}
+
+/// A pairing of a `StatementLine` with the posting it was matched to.
+#[derive(Clone, PartialEq, Debug)]
+pub struct StatementMatch {
+    line_index: usize,
+    transaction_id: String,
+    split_index: usize,
+}
+
+impl StatementMatch {
+    /// Retrieve the index of the matched line within the statement lines passed to
+    /// `Reconciliation::auto_match()`.
+    pub fn get_line_index(&self) -> usize {
+        self.line_index
+    }
+
+    /// Retrieve the id of the `Transaction` containing the matched posting.
+    pub fn get_transaction_id(&self) -> String {
+        String::clone(&self.transaction_id)
+    }
+
+    /// Retrieve the index of the matched `Split` within its `Transaction`.
+    pub fn get_split_index(&self) -> usize {
+        self.split_index
+    }
+}
+
+/// The permanent record of a completed `Reconciliation`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ReconciliationRecord {
+    account_id: String,
+    statement_date: NaiveDate,
+    ending_balance: i64,
+    splits: Vec<(String, usize)>,
+}
+
+impl ReconciliationRecord {
+    /// Retrieve the id of the `Account` that was reconciled.
+    pub fn get_account_id(&self) -> String {
+        String::clone(&self.account_id)
+    }
+
+    /// Retrieve the end date of the statement that was reconciled against.
+    pub fn get_statement_date(&self) -> NaiveDate {
+        self.statement_date
+    }
+
+    /// Retrieve the ending balance of the statement that was reconciled against.
+    pub fn get_ending_balance(&self) -> i64 {
+        self.ending_balance
+    }
+
+    /// Retrieve the `(transaction id, split index)` pairs of the postings that were reconciled.
+    pub fn get_splits(&self) -> Vec<(String, usize)> {
+        Vec::clone(&self.splits)
+    }
+
+    /// Determine if every posting covered by this record is still reconciled in `ledger`. A
+    /// record stops being current when its reconciliation is undone.
+    pub(crate) fn is_current(&self, ledger: &Ledger) -> bool {
+        self.splits.iter().all(|&(ref transaction_id, index)| {
+            ledger
+                .get_transaction_by_id(transaction_id)
+                .and_then(|transaction| {
+                    transaction
+                        .splits()
+                        .get(index)
+                        .map(|split| split.get_status())
+                })
+                .is_some_and(|status| status == TransactionStatus::Reconciled)
+        })
+    }
+
+    /// Convert this `ReconciliationRecord` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["account_id"] = self.account_id.as_str().into();
+        value["statement_date"] = self.statement_date.to_string().into();
+        value["ending_balance"] = self.ending_balance.into();
+        value["splits"] = JsonValue::Array(
+            self.splits
+                .iter()
+                .map(|&(ref transaction_id, index)| {
+                    let mut entry = JsonValue::new_object();
+                    entry["transaction_id"] = transaction_id.as_str().into();
+                    entry["index"] = index.into();
+                    entry
+                })
+                .collect(),
+        );
+
+        value
+    }
+}
+
+/// Create a `ReconciliationRecord` object from its JSON form, as produced by
+/// `ReconciliationRecord::to_json()`.
+impl<'a> From<&'a JsonValue> for ReconciliationRecord {
+    fn from(value: &'a JsonValue) -> ReconciliationRecord {
+        ReconciliationRecord {
+            account_id: String::from(value["account_id"].as_str().unwrap_or("")),
+            statement_date: parse_date(&value["statement_date"]).unwrap_or_default(),
+            ending_balance: value["ending_balance"].as_i64().unwrap_or(0),
+            splits: value["splits"]
+                .members()
+                .map(|entry| {
+                    (
+                        String::from(entry["transaction_id"].as_str().unwrap_or("")),
+                        entry["index"].as_usize().unwrap_or(0),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A single posting to the `Account` being reconciled, with its amount signed according to the
+/// `Account`'s normal balance.
+struct Posting {
+    transaction_id: String,
+    split_index: usize,
+    date: NaiveDate,
+    amount: i64,
+    status: TransactionStatus,
+    description: String,
+}
+
+/// A reconciliation session for a single `Account` against one bank statement.
+///
+/// Postings are marked as cleared within the session, either by hand or by matching them
+/// against `StatementLine`s, until the cleared balance agrees with the statement's ending
+/// balance. The session is then finalized with `Ledger::finalize_reconciliation()`, which marks
+/// every cleared posting as `TransactionStatus::Reconciled`.
+///
+/// # Examples
+/// ```
+///# extern crate chrono;
+///# extern crate cratchit;
+///# use chrono::NaiveDate;
+///# use cratchit::{Account, AccountType, AccountsChart, Currency, Ledger, Split, Transaction};
+///# let mut chart = AccountsChart::new();
+///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+///#                                          Currency::USDollar, false));
+///# chart.add_top_level_account(Account::new("04", "Dues", "Dues", AccountType::Income,
+///#                                          Currency::USDollar, false));
+/// let mut ledger = Ledger::new(chart);
+/// let mut deposit = Transaction::new("0001", NaiveDate::from_ymd_opt(2018, 11, 3).unwrap(),
+///                                    "Membership dues");
+/// deposit.add_split(Split::new("01", 5000));
+/// deposit.add_split(Split::new("04", -5000));
+/// ledger.add_transaction(deposit).unwrap();
+///
+/// let statement_date = NaiveDate::from_ymd_opt(2018, 11, 30).unwrap();
+/// let mut session = ledger.start_reconciliation("01", statement_date, 5000).unwrap();
+/// assert_eq!(session.get_difference(&ledger), 5000);
+///
+/// session.mark_cleared(&ledger, "0001", 0).unwrap();
+/// assert_eq!(session.get_difference(&ledger), 0);
+///
+/// ledger.finalize_reconciliation(&session).unwrap();
+/// assert_eq!(ledger.get_reconciliations("01").len(), 1);
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct Reconciliation {
+    account_id: String,
+    statement_date: NaiveDate,
+    ending_balance: i64,
+    cleared: Vec<(String, usize)>,
+}
+
+impl Reconciliation {
+    pub(crate) fn new(
+        ledger: &Ledger,
+        account_id: &str,
+        statement_date: NaiveDate,
+        ending_balance: i64,
+    ) -> Result<Reconciliation, Error> {
+        match ledger.get_chart().get_account_by_id(account_id) {
+            Some(ref account) if account.is_placeholder() => {
+                return Err(Error::PlaceholderAccount(String::from(account_id)));
+            }
+            Some(_) => {}
+            None => return Err(Error::UnknownAccount(String::from(account_id))),
+        }
+
+        let mut session = Reconciliation {
+            account_id: String::from(account_id),
+            statement_date,
+            ending_balance,
+            cleared: vec![],
+        };
+
+        for posting in session.get_postings(ledger) {
+            if posting.status == TransactionStatus::Cleared {
+                session
+                    .cleared
+                    .push((posting.transaction_id, posting.split_index));
+            }
+        }
+
+        Ok(session)
+    }
+
+    /// Retrieve the id of the `Account` being reconciled.
+    pub fn get_account_id(&self) -> String {
+        String::clone(&self.account_id)
+    }
+
+    /// Retrieve the end date of the statement being reconciled against.
+    pub fn get_statement_date(&self) -> NaiveDate {
+        self.statement_date
+    }
+
+    /// Retrieve the ending balance of the statement being reconciled against.
+    pub fn get_ending_balance(&self) -> i64 {
+        self.ending_balance
+    }
+
+    /// Mark the `Split` at `split_index` of the given `Transaction` as cleared in this session.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `Split` was marked, `Error::SplitOutsideReconciliation` if it does not
+    ///   post to the `Account` being reconciled on or before the statement date,
+    ///   `Error::InvalidStatusTransition` if the `Transaction` has been voided, or an `Error` if
+    ///   the `Transaction` or `Split` does not exist.
+    pub fn mark_cleared(
+        &mut self,
+        ledger: &Ledger,
+        transaction_id: &str,
+        split_index: usize,
+    ) -> Result<(), Error> {
+        self.check_posting(ledger, transaction_id, split_index)?;
+        if !self.is_cleared(transaction_id, split_index) {
+            self.cleared
+                .push((String::from(transaction_id), split_index));
+        }
+
+        Ok(())
+    }
+
+    /// Remove the cleared mark from the `Split` at `split_index` of the given `Transaction`.
+    pub fn unmark_cleared(&mut self, transaction_id: &str, split_index: usize) {
+        self.cleared
+            .retain(|&(ref id, index)| id != transaction_id || index != split_index);
+    }
+
+    /// Determine if the `Split` at `split_index` of the given `Transaction` is marked as cleared.
+    pub fn is_cleared(&self, transaction_id: &str, split_index: usize) -> bool {
+        self.cleared
+            .iter()
+            .any(|&(ref id, index)| id == transaction_id && index == split_index)
+    }
+
+    /// Retrieve the `(transaction id, split index)` pairs marked as cleared in this session.
+    pub fn get_cleared(&self) -> Vec<(String, usize)> {
+        Vec::clone(&self.cleared)
+    }
+
+    /// Compute the balance of the `Account` counting only previously reconciled postings and
+    /// postings marked as cleared in this session.
+    pub fn get_cleared_balance(&self, ledger: &Ledger) -> i64 {
+        self.get_postings(ledger)
+            .into_iter()
+            .filter(|posting| {
+                posting.status == TransactionStatus::Reconciled
+                    || self.is_cleared(&posting.transaction_id, posting.split_index)
+            })
+            .map(|posting| posting.amount)
+            .sum()
+    }
+
+    /// Compute the difference between the statement's ending balance and the cleared balance.
+    /// The session can be finalized once this is zero.
+    pub fn get_difference(&self, ledger: &Ledger) -> i64 {
+        self.ending_balance - self.get_cleared_balance(ledger)
+    }
+
+    /// Match `StatementLine`s against the postings that have not yet been reconciled, and mark
+    /// every matched posting as cleared.
+    ///
+    /// A posting matches a line if its amount and date are within the given `MatchTolerance`.
+    /// When several postings match, the one whose `Transaction` description contains the
+    /// line's payee is preferred, followed by the one closest in amount and then in date. Each
+    /// posting is matched to at most one line.
+    ///
+    /// # Returns
+    /// * A `StatementMatch` for each line that was matched to a posting.
+    pub fn auto_match(
+        &mut self,
+        ledger: &Ledger,
+        lines: &[StatementLine],
+        tolerance: MatchTolerance,
+    ) -> Vec<StatementMatch> {
+        let postings: Vec<_> = self
+            .get_postings(ledger)
+            .into_iter()
+            .filter(|posting| posting.status != TransactionStatus::Reconciled)
+            .collect();
+
+        let mut matches: Vec<StatementMatch> = vec![];
+        for (line_index, line) in lines.iter().enumerate() {
+            let payee = line.payee.to_lowercase();
+            let best = postings
+                .iter()
+                .filter(|posting| {
+                    !matches.iter().any(|m| {
+                        m.transaction_id == posting.transaction_id
+                            && m.split_index == posting.split_index
+                    })
+                })
+                .filter(|posting| {
+                    (posting.amount - line.amount).abs() <= tolerance.amount
+                        && (posting.date - line.date).num_days().abs() <= tolerance.days
+                })
+                .min_by_key(|posting| {
+                    (
+                        payee.is_empty() || !posting.description.contains(&payee),
+                        (posting.amount - line.amount).abs(),
+                        (posting.date - line.date).num_days().abs(),
+                    )
+                });
+
+            if let Some(posting) = best {
+                matches.push(StatementMatch {
+                    line_index,
+                    transaction_id: String::clone(&posting.transaction_id),
+                    split_index: posting.split_index,
+                });
+            }
+        }
+
+        for statement_match in &matches {
+            let posting = (
+                String::clone(&statement_match.transaction_id),
+                statement_match.split_index,
+            );
+            if !self.cleared.contains(&posting) {
+                self.cleared.push(posting);
+            }
+        }
+
+        matches
+    }
+
+    pub(crate) fn create_record(&self) -> ReconciliationRecord {
+        ReconciliationRecord {
+            account_id: String::clone(&self.account_id),
+            statement_date: self.statement_date,
+            ending_balance: self.ending_balance,
+            splits: Vec::clone(&self.cleared),
+        }
+    }
+
+    /// Check that the `Split` at `split_index` of the given `Transaction` posts to the `Account`
+    /// being reconciled on or before the statement date, and that the `Transaction` has not been
+    /// voided.
+    pub(crate) fn check_posting(
+        &self,
+        ledger: &Ledger,
+        transaction_id: &str,
+        split_index: usize,
+    ) -> Result<(), Error> {
+        let transaction = match ledger.get_transaction_by_id(transaction_id) {
+            Some(transaction) => transaction,
+            None => return Err(Error::UnknownTransaction(String::from(transaction_id))),
+        };
+        if transaction.get_status() == TransactionStatus::Voided {
+            return Err(Error::InvalidStatusTransition(
+                TransactionStatus::Voided,
+                TransactionStatus::Cleared,
+            ));
+        }
+        match transaction.splits().get(split_index) {
+            Some(split)
+                if split.get_account_id() == self.account_id
+                    && transaction.get_date() <= self.statement_date =>
+            {
+                Ok(())
+            }
+            Some(_) => Err(Error::SplitOutsideReconciliation(
+                String::from(transaction_id),
+                split_index,
+            )),
+            None => Err(Error::UnknownSplit(
+                String::from(transaction_id),
+                split_index,
+            )),
+        }
+    }
+
+    /// Retrieve every posting to the `Account` on or before the statement date that has not been
+    /// voided.
+    fn get_postings(&self, ledger: &Ledger) -> Vec<Posting> {
+        let sign = match ledger.get_chart().get_account_by_id(&self.account_id) {
+            Some(ref account)
+                if account.get_account_type().get_normal_balance() == NormalBalance::Credit =>
+            {
+                -1
+            }
+            _ => 1,
+        };
+
+        let mut postings = vec![];
+        for transaction in ledger.transactions() {
+            if transaction.get_date() > self.statement_date {
+                continue;
+            }
+
+            for (index, split) in transaction.splits().iter().enumerate() {
+                if split.get_account_id() == self.account_id
+                    && split.get_status() != TransactionStatus::Voided
+                {
+                    postings.push(Posting {
+                        transaction_id: transaction.get_id(),
+                        split_index: index,
+                        date: transaction.get_date(),
+                        amount: sign * split.get_amount(),
+                        status: split.get_status(),
+                        description: transaction.get_description().to_lowercase(),
+                    });
+                }
+            }
+        }
+
+        postings
+    }
+}
@@ -0,0 +1,256 @@
+//! A check-register view of the postings to a single `Account`.
+
+use std::ptr;
+
+use chrono::NaiveDate;
+
+use error::Error;
+use ledger::Ledger;
+use transaction::DateBasis;
+use NormalBalance;
+
+/// Options controlling which entries are included in a `Register`.
+///
+/// By default, a register includes every posting made directly to its `Account`, on any date.
+///
+/// # Examples
+/// ```
+///# extern crate chrono;
+///# extern crate cratchit;
+///# use chrono::NaiveDate;
+///# use cratchit::RegisterOptions;
+/// let options = RegisterOptions::new()
+///     .starting(NaiveDate::from_ymd_opt(2018, 1, 1).unwrap())
+///     .ending(NaiveDate::from_ymd_opt(2018, 12, 31).unwrap())
+///     .page(0, 50)
+///     .include_sub_accounts(true);
+/// ```
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RegisterOptions {
+    start: Option<NaiveDate>,
+    end: Option<NaiveDate>,
+    offset: usize,
+    limit: Option<usize>,
+    date_basis: DateBasis,
+    include_sub_accounts: bool,
+}
+
+impl RegisterOptions {
+    /// Create a new `RegisterOptions` object including every posting to the `Account`.
+    pub fn new() -> RegisterOptions {
+        RegisterOptions::default()
+    }
+
+    /// Exclude entries dated before `date`. Their amounts are still included in the running
+    /// balance.
+    pub fn starting(mut self, date: NaiveDate) -> RegisterOptions {
+        self.start = Some(date);
+        self
+    }
+
+    /// Exclude entries dated after `date`.
+    pub fn ending(mut self, date: NaiveDate) -> RegisterOptions {
+        self.end = Some(date);
+        self
+    }
+
+    /// Return at most `limit` entries, skipping the first `offset` entries in the date range.
+    pub fn page(mut self, offset: usize, limit: usize) -> RegisterOptions {
+        self.offset = offset;
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Choose which of each `Transaction`'s dates is used to order and filter the entries.
+    pub fn date_basis(mut self, basis: DateBasis) -> RegisterOptions {
+        self.date_basis = basis;
+        self
+    }
+
+    /// Whether postings to sub-accounts of the `Account` should also be included. This is
+    /// primarily useful for placeholder accounts, which have no postings of their own.
+    pub fn include_sub_accounts(mut self, include: bool) -> RegisterOptions {
+        self.include_sub_accounts = include;
+        self
+    }
+}
+
+/// A single line of a `Register`, representing one posting to the `Account`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct RegisterEntry {
+    date: NaiveDate,
+    transaction_id: String,
+    reference: Option<String>,
+    account_id: String,
+    description: String,
+    memo: String,
+    counterparts: Vec<String>,
+    amount: i64,
+    balance: i64,
+}
+
+impl RegisterEntry {
+    /// Retrieve the date of the posting, according to the `DateBasis` of the `RegisterOptions`.
+    pub fn get_date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// Retrieve the id of the `Transaction` the posting belongs to.
+    pub fn get_transaction_id(&self) -> String {
+        String::clone(&self.transaction_id)
+    }
+
+    /// Retrieve the reference (e.g. check number) of the `Transaction`, if it has one.
+    pub fn get_reference(&self) -> Option<String> {
+        self.reference.clone()
+    }
+
+    /// Retrieve the id of the `Account` that was posted to. This differs from the `Register`'s
+    /// `Account` only when sub-account postings are included.
+    pub fn get_account_id(&self) -> String {
+        String::clone(&self.account_id)
+    }
+
+    /// Retrieve the description of the `Transaction` the posting belongs to.
+    pub fn get_description(&self) -> String {
+        String::clone(&self.description)
+    }
+
+    /// Retrieve the memo of the `Split` that made the posting.
+    pub fn get_memo(&self) -> String {
+        String::clone(&self.memo)
+    }
+
+    /// Retrieve the ids of the other `Account`s posted to by the same `Transaction`.
+    pub fn get_counterparts(&self) -> Vec<String> {
+        Vec::clone(&self.counterparts)
+    }
+
+    /// Retrieve the amount of the posting. Amounts that increase the `Account`'s normal balance
+    /// are positive, and amounts that decrease it are negative.
+    pub fn get_amount(&self) -> i64 {
+        self.amount
+    }
+
+    /// Retrieve the balance of the `Account` after this posting, with the same sign convention as
+    /// `get_amount()`.
+    pub fn get_balance(&self) -> i64 {
+        self.balance
+    }
+}
+
+/// A check-register view of an `Account`: every posting in date order, with a running balance.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Register {
+    account_id: String,
+    opening_balance: i64,
+    entries: Vec<RegisterEntry>,
+    total_entries: usize,
+}
+
+impl Register {
+    /// Retrieve the id of the `Account` this `Register` is for.
+    pub fn get_account_id(&self) -> String {
+        String::clone(&self.account_id)
+    }
+
+    /// Retrieve the balance of the `Account` immediately before the first entry of this page.
+    pub fn get_opening_balance(&self) -> i64 {
+        self.opening_balance
+    }
+
+    /// Retrieve the entries of this page of the `Register`, in date order.
+    pub fn get_entries(&self) -> Vec<RegisterEntry> {
+        Vec::clone(&self.entries)
+    }
+
+    /// Retrieve the number of entries in the requested date range, across all pages.
+    pub fn get_total_entries(&self) -> usize {
+        self.total_entries
+    }
+
+    pub(crate) fn build(
+        ledger: &Ledger,
+        account_id: &str,
+        options: &RegisterOptions,
+    ) -> Result<Register, Error> {
+        let chart = ledger.get_chart();
+        let account = match chart.get_account_by_id(account_id) {
+            Some(account) => account,
+            None => return Err(Error::UnknownAccount(String::from(account_id))),
+        };
+
+        let account_ids = if options.include_sub_accounts {
+            chart.get_sub_tree_ids(account_id)
+        } else {
+            vec![account.get_id()]
+        };
+
+        let sign = match account.get_account_type().get_normal_balance() {
+            NormalBalance::Debit => 1,
+            NormalBalance::Credit => -1,
+        };
+
+        let mut transactions: Vec<_> = ledger.transactions().iter().collect();
+        transactions.sort_by_key(|transaction| transaction.get_date_for(options.date_basis));
+
+        let mut balance = 0;
+        let mut entries = vec![];
+        for transaction in transactions {
+            let date = transaction.get_date_for(options.date_basis);
+            if options.end.is_some_and(|end| date > end) {
+                break;
+            }
+
+            for split in transaction.splits() {
+                if !account_ids.contains(&split.get_account_id()) {
+                    continue;
+                }
+
+                let amount = sign * split.get_amount();
+                balance += amount;
+                if options.start.is_some_and(|start| date < start) {
+                    continue;
+                }
+
+                let counterparts = transaction
+                    .splits()
+                    .iter()
+                    .filter(|other| !ptr::eq(*other, split))
+                    .map(|other| other.get_account_id())
+                    .collect();
+
+                entries.push(RegisterEntry {
+                    date,
+                    transaction_id: transaction.get_id(),
+                    reference: transaction.get_reference(),
+                    account_id: split.get_account_id(),
+                    description: transaction.get_description(),
+                    memo: split.get_memo(),
+                    counterparts,
+                    amount,
+                    balance,
+                });
+            }
+        }
+
+        let total_entries = entries.len();
+        let limit = options.limit.unwrap_or(total_entries);
+        let page: Vec<RegisterEntry> = entries
+            .into_iter()
+            .skip(options.offset)
+            .take(limit)
+            .collect();
+        let opening_balance = match page.first() {
+            Some(entry) => entry.balance - entry.amount,
+            None => balance,
+        };
+
+        Ok(Register {
+            account_id: account.get_id(),
+            opening_balance,
+            entries: page,
+            total_entries,
+        })
+    }
+}
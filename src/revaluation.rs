@@ -0,0 +1,165 @@
+//! Period-end revaluation of foreign-currency balances at the closing rate.
+
+use chrono::NaiveDate;
+
+use balance::BalanceOptions;
+use error::Error;
+use exchange::is_foreign_currency;
+use ledger::Ledger;
+use transaction::{EntrySource, Split, Transaction};
+use {AccountType, Currency, NormalBalance};
+
+impl Ledger {
+    /// Generate, without posting, the entry revaluing foreign-currency balances on a date.
+    ///
+    /// Every non-placeholder Asset and Liability `Account` whose `Currency` differs from
+    /// `currency` is revalued, except those holding a `Commodity`. Its balance on `date` is
+    /// converted at that date's rate from the `PriceDatabase`, and the difference from its
+    /// carrying value (see `get_carrying_value()`) is posted to the `Account` as a change in value
+    /// only. The total of the differences is posted to the unrealized gain/loss `Account`.
+    ///
+    /// # Arguments
+    /// * `date`: The date to revalue balances on, e.g. the last day of a period.
+    /// * `currency`: The home currency, in which carrying values are expressed.
+    /// * `unrealized_id`: The id of the Income or Expense `Account` for unrealized exchange gains
+    ///   and losses.
+    ///
+    /// # Returns
+    /// * The revaluation `Transaction`, with the id `REVAL-{date}`, or an `Error` if the
+    ///   unrealized gain/loss account is not a non-placeholder Income or Expense account, or no
+    ///   rate is known for the currency of an `Account` with a balance.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Currency, Ledger,
+    ///#               Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking (EUR)", "Checking (EUR)",
+    ///#                                          AccountType::Asset, Currency::Euro, false));
+    ///# chart.add_top_level_account(Account::new("03", "Capital", "Capital", AccountType::Equity,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("04", "Unrealized FX", "Unrealized FX",
+    ///#                                          AccountType::Income, Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    /// let mut deposit = Transaction::new("0001", NaiveDate::from_ymd_opt(2018, 1, 5).unwrap(),
+    ///                                    "Opening deposit");
+    /// deposit.add_split(Split::new_foreign("01", 100000, 1.2));
+    /// deposit.add_split(Split::new("03", -120000));
+    /// ledger.add_transaction(deposit).unwrap();
+    ///
+    /// let month_end = NaiveDate::from_ymd_opt(2018, 1, 31).unwrap();
+    /// ledger.get_price_database_mut().add_price("EUR", "USD", month_end, 1.25);
+    ///
+    /// let revaluation = ledger.preview_revaluation(month_end, Currency::USDollar, "04").unwrap();
+    /// assert_eq!(revaluation.get_id(), "REVAL-2018-01-31");
+    /// assert_eq!(revaluation.get_total_debits(), 5000);
+    ///
+    /// ledger.post_revaluation(month_end, Currency::USDollar, "04").unwrap();
+    /// assert_eq!(ledger.get_carrying_value("01", &BalanceOptions::new()).unwrap(), 125000);
+    /// assert_eq!(ledger.get_balance("04", &BalanceOptions::new()).unwrap(), 5000);
+    /// ```
+    pub fn preview_revaluation(
+        &self,
+        date: NaiveDate,
+        currency: Currency,
+        unrealized_id: &str,
+    ) -> Result<Transaction, Error> {
+        match self.get_chart().get_account_by_id(unrealized_id) {
+            Some(ref account) if account.is_placeholder() => {
+                return Err(Error::PlaceholderAccount(String::from(unrealized_id)));
+            }
+            Some(ref account)
+                if account.get_account_type() != AccountType::Income
+                    && account.get_account_type() != AccountType::Expense =>
+            {
+                return Err(Error::WrongAccountType(String::from(unrealized_id)));
+            }
+            Some(_) => {}
+            None => return Err(Error::UnknownAccount(String::from(unrealized_id))),
+        }
+
+        let options = BalanceOptions::new().as_of(date);
+        let mut account_ids = self.get_chart().get_account_ids();
+        account_ids.sort();
+
+        let mut revaluation = Transaction::new(
+            &format!("REVAL-{}", date),
+            date,
+            "Unrealized exchange revaluation",
+        );
+        revaluation.set_currency(currency);
+        revaluation.set_source(EntrySource::Revaluation);
+
+        let mut total = 0;
+        for account_id in account_ids {
+            let account = self.get_chart().get_account_by_id(&account_id).unwrap();
+            let account_type = account.get_account_type();
+            if account.is_placeholder()
+                || account.get_commodity().is_some()
+                || (account_type != AccountType::Asset && account_type != AccountType::Liability)
+                || !is_foreign_currency(account.get_currency(), currency)
+            {
+                continue;
+            }
+
+            let balance = self.get_balance(&account_id, &options)?;
+            let carrying_value = self.get_carrying_value(&account_id, &options)?;
+            let rate = if balance == 0 {
+                0.0
+            } else {
+                self.get_price_database().get_rate(
+                    account.get_currency().get_code(),
+                    currency.get_code(),
+                    date,
+                )?
+            };
+
+            let difference = (balance as f64 * rate).round() as i64 - carrying_value;
+            let value = match account_type.get_normal_balance() {
+                NormalBalance::Debit => difference,
+                NormalBalance::Credit => -difference,
+            };
+            if value == 0 {
+                continue;
+            }
+            total += value;
+
+            let mut split = Split::new(&account_id, 0);
+            split.set_value(value);
+            split.set_memo(&format!("Revalue {} at {}", account.get_name(), rate));
+            revaluation.add_split(split);
+        }
+
+        if total != 0 {
+            let mut split = Split::new(unrealized_id, -total);
+            split.set_memo("Unrealized exchange gain/loss");
+            revaluation.add_split(split);
+        }
+
+        Ok(revaluation)
+    }
+
+    /// Generate and post the entry revaluing foreign-currency balances on a date.
+    ///
+    /// See `preview_revaluation()` for how the entry is computed. The entry is marked with
+    /// `EntrySource::Revaluation`.
+    ///
+    /// # Returns
+    /// * The id of the posted `Transaction`, or an `Error` if it could not be generated or
+    ///   posted, e.g. because balances were already revalued on the same date.
+    pub fn post_revaluation(
+        &mut self,
+        date: NaiveDate,
+        currency: Currency,
+        unrealized_id: &str,
+    ) -> Result<String, Error> {
+        let revaluation = self.preview_revaluation(date, currency, unrealized_id)?;
+        let id = revaluation.get_id();
+        self.add_transaction(revaluation)?;
+
+        Ok(id)
+    }
+}
@@ -0,0 +1,610 @@
+//! Scheduled transactions, which are posted to a `Ledger` according to a `Recurrence`.
+
+use std::convert::TryFrom;
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use json::JsonValue;
+
+use error::Error;
+use fiscal::get_next_month_start;
+use ledger::Ledger;
+use transaction::{parse_date, EntrySource, Transaction};
+
+/// An enumeration of the ways a `Recurrence` can repeat.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Frequency {
+    /// Repeat every day.
+    Daily,
+
+    /// Repeat every week, on the same day of the week as the first occurrence.
+    Weekly,
+
+    /// Repeat every month, on the given day of the month (1 to 31). In months with fewer days,
+    /// the last day of the month is used.
+    MonthlyByDay(u32),
+
+    /// Repeat every month, on the given occurrence (1 to 5) of the given day of the week, e.g.
+    /// the second Tuesday. In months with fewer such days, the last one is used, so 5 always
+    /// means the last such day of the month.
+    MonthlyByWeekday(u32, Weekday),
+
+    /// Repeat on the last day of every month.
+    EndOfMonth,
+
+    /// Repeat every year, on the same month and day as the first occurrence. A first occurrence
+    /// on February 29 falls on February 28 in other years.
+    Yearly,
+}
+
+/// A rule describing the dates on which a `ScheduledTransaction` occurs.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Recurrence {
+    frequency: Frequency,
+    interval: u32,
+    start: NaiveDate,
+    until: Option<NaiveDate>,
+    count: Option<u32>,
+}
+
+impl Recurrence {
+    /// Create a new `Recurrence` which repeats without end at the given `Frequency`.
+    ///
+    /// # Arguments
+    /// * `frequency`: How often the `Recurrence` repeats.
+    /// * `start`: The earliest date on which the `Recurrence` can occur. For `Weekly` and
+    ///   `Yearly` recurrences, this is also the date of the first occurrence.
+    pub fn new(frequency: Frequency, start: NaiveDate) -> Recurrence {
+        Recurrence {
+            frequency,
+            interval: 1,
+            start,
+            until: None,
+            count: None,
+        }
+    }
+
+    /// Repeat only on every `interval`th day, week, month or year, e.g. an `interval` of 2 with a
+    /// `Weekly` frequency repeats every other week.
+    pub fn every(mut self, interval: u32) -> Self {
+        self.interval = interval.max(1);
+        self
+    }
+
+    /// Stop repeating after the given date.
+    pub fn until(mut self, date: NaiveDate) -> Self {
+        self.until = Some(date);
+        self
+    }
+
+    /// Stop repeating after the given number of occurrences.
+    pub fn count(mut self, count: u32) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    /// Retrieve the `Frequency` at which this `Recurrence` repeats.
+    pub fn get_frequency(&self) -> Frequency {
+        self.frequency
+    }
+
+    /// Retrieve the number of days, weeks, months or years between occurrences.
+    pub fn get_interval(&self) -> u32 {
+        self.interval
+    }
+
+    /// Retrieve the earliest date on which this `Recurrence` can occur.
+    pub fn get_start(&self) -> NaiveDate {
+        self.start
+    }
+
+    /// Retrieve the last date on which this `Recurrence` can occur, if it has one.
+    pub fn get_until(&self) -> Option<NaiveDate> {
+        self.until
+    }
+
+    /// Retrieve the maximum number of occurrences of this `Recurrence`, if it has one.
+    pub fn get_count(&self) -> Option<u32> {
+        self.count
+    }
+
+    /// Compute the dates on which this `Recurrence` occurs, up to and including `through`.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::{NaiveDate, Weekday};
+    ///# use cratchit::{Frequency, Recurrence};
+    /// let start = NaiveDate::from_ymd_opt(2018, 1, 1).unwrap();
+    /// let board_meeting = Recurrence::new(Frequency::MonthlyByWeekday(2, Weekday::Tue), start)
+    ///     .count(3);
+    ///
+    /// let dates = board_meeting.get_occurrences(NaiveDate::from_ymd_opt(2018, 12, 31).unwrap());
+    /// assert_eq!(dates, vec![NaiveDate::from_ymd_opt(2018, 1, 9).unwrap(),
+    ///                        NaiveDate::from_ymd_opt(2018, 2, 13).unwrap(),
+    ///                        NaiveDate::from_ymd_opt(2018, 3, 13).unwrap()]);
+    /// ```
+    pub fn get_occurrences(&self, through: NaiveDate) -> Vec<NaiveDate> {
+        let last = match self.until {
+            Some(until) if until < through => until,
+            _ => through,
+        };
+
+        let mut occurrences = vec![];
+        let mut index = 0;
+        while self
+            .count
+            .is_none_or(|count| occurrences.len() < count as usize)
+        {
+            let date = self.get_candidate(index);
+            index += 1;
+            if date > last {
+                break;
+            }
+            if date >= self.start {
+                occurrences.push(date);
+            }
+        }
+
+        occurrences
+    }
+
+    /// Determine if this `Recurrence` occurs on `date`.
+    pub fn occurs_on(&self, date: NaiveDate) -> bool {
+        self.get_occurrences(date).last() == Some(&date)
+    }
+
+    /// Convert this `Recurrence` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        match self.frequency {
+            Frequency::Daily => value["frequency"] = "daily".into(),
+            Frequency::Weekly => value["frequency"] = "weekly".into(),
+            Frequency::MonthlyByDay(day) => {
+                value["frequency"] = "monthly_by_day".into();
+                value["day"] = day.into();
+            }
+            Frequency::MonthlyByWeekday(week, weekday) => {
+                value["frequency"] = "monthly_by_weekday".into();
+                value["week"] = week.into();
+                value["weekday"] = weekday.to_string().into();
+            }
+            Frequency::EndOfMonth => value["frequency"] = "end_of_month".into(),
+            Frequency::Yearly => value["frequency"] = "yearly".into(),
+        }
+        value["interval"] = self.interval.into();
+        value["start"] = self.start.to_string().into();
+        value["until"] = self.until.map(|date| date.to_string()).into();
+        value["count"] = self.count.into();
+
+        value
+    }
+
+    /// Compute the `index`th candidate date of this `Recurrence`. Candidates are in increasing
+    /// order, but those in the first month may fall before the start date.
+    fn get_candidate(&self, index: u32) -> NaiveDate {
+        let steps = i64::from(index) * i64::from(self.interval);
+        match self.frequency {
+            Frequency::Daily => self.start + Duration::days(steps),
+            Frequency::Weekly => self.start + Duration::weeks(steps),
+            Frequency::MonthlyByDay(day) => get_day_of_month(self.add_months(steps), day),
+            Frequency::MonthlyByWeekday(week, weekday) => {
+                let first = self.add_months(steps);
+                let offset = (7 + weekday.num_days_from_monday()
+                    - first.weekday().num_days_from_monday())
+                    % 7;
+                let mut date = first
+                    + Duration::days(i64::from(offset))
+                    + Duration::weeks(i64::from(week.max(1)) - 1);
+                while date.month() != first.month() {
+                    date -= Duration::weeks(1);
+                }
+
+                date
+            }
+            Frequency::EndOfMonth => get_next_month_start(self.add_months(steps))
+                .pred_opt()
+                .unwrap(),
+            Frequency::Yearly => get_day_of_month(self.add_months(steps * 12), self.start.day()),
+        }
+    }
+
+    /// Find the first day of the month `months` months after the month of the start date.
+    fn add_months(&self, months: i64) -> NaiveDate {
+        let total = i64::from(self.start.year()) * 12 + i64::from(self.start.month0()) + months;
+        NaiveDate::from_ymd_opt((total / 12) as i32, (total % 12) as u32 + 1, 1).unwrap()
+    }
+}
+
+/// Create a `Recurrence` object from its JSON form, as produced by `Recurrence::to_json()`.
+impl<'a> From<&'a JsonValue> for Recurrence {
+    fn from(value: &'a JsonValue) -> Recurrence {
+        let frequency = match value["frequency"].as_str().unwrap_or("") {
+            "weekly" => Frequency::Weekly,
+            "monthly_by_day" => Frequency::MonthlyByDay(value["day"].as_u32().unwrap_or(1)),
+            "monthly_by_weekday" => Frequency::MonthlyByWeekday(
+                value["week"].as_u32().unwrap_or(1),
+                value["weekday"]
+                    .as_str()
+                    .and_then(|weekday| weekday.parse().ok())
+                    .unwrap_or(Weekday::Mon),
+            ),
+            "end_of_month" => Frequency::EndOfMonth,
+            "yearly" => Frequency::Yearly,
+            _ => Frequency::Daily,
+        };
+
+        Recurrence {
+            frequency,
+            interval: value["interval"].as_u32().unwrap_or(1).max(1),
+            start: parse_date(&value["start"]).unwrap_or_default(),
+            until: parse_date(&value["until"]),
+            count: value["count"].as_u32(),
+        }
+    }
+}
+
+/// Find the given day of the month beginning on `first`, or the last day of the month if it has
+/// fewer days.
+fn get_day_of_month(first: NaiveDate, day: u32) -> NaiveDate {
+    let last = get_next_month_start(first).pred_opt().unwrap();
+    first.with_day(day.max(1).min(last.day())).unwrap()
+}
+
+/// A template for a `Transaction` that is posted to a `Ledger` on each date of a `Recurrence`,
+/// such as rent or a subscription.
+///
+/// The `Transaction` created for each occurrence has the id `{schedule id}-{date}`, e.g.
+/// `RENT-2018-02-01`, and is marked with `EntrySource::Scheduled`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ScheduledTransaction {
+    id: String,
+    template: Transaction,
+    recurrence: Recurrence,
+    created: Vec<NaiveDate>,
+    skipped: Vec<NaiveDate>,
+    edited: Vec<(NaiveDate, Transaction)>,
+}
+
+impl ScheduledTransaction {
+    /// Create a new `ScheduledTransaction`.
+    ///
+    /// # Arguments
+    /// * `id`: An identifier for this schedule. This must be unique within a `Ledger`.
+    /// * `template`: The `Transaction` to post on each occurrence. Its id and date are replaced
+    ///   with those of each occurrence. If it is automatically reversed, each occurrence is
+    ///   reversed the same number of days after its own date.
+    /// * `recurrence`: The `Recurrence` describing when the `Transaction` is posted.
+    pub fn new(id: &str, template: Transaction, recurrence: Recurrence) -> ScheduledTransaction {
+        ScheduledTransaction {
+            id: String::from(id),
+            template,
+            recurrence,
+            created: vec![],
+            skipped: vec![],
+            edited: vec![],
+        }
+    }
+
+    /// Retrieve the id of this `ScheduledTransaction`, as a `String`.
+    pub fn get_id(&self) -> String {
+        String::clone(&self.id)
+    }
+
+    /// Retrieve the template `Transaction` posted on each occurrence.
+    pub fn get_template(&self) -> Transaction {
+        Transaction::clone(&self.template)
+    }
+
+    /// Retrieve the `Recurrence` describing when this `ScheduledTransaction` occurs.
+    pub fn get_recurrence(&self) -> Recurrence {
+        Recurrence::clone(&self.recurrence)
+    }
+
+    /// Retrieve the dates of the occurrences that have been posted to the `Ledger`, oldest first.
+    ///
+    /// An occurrence counts as posted while its `Transaction` is in the `Ledger`, even if it has
+    /// been voided, so undoing `Ledger::materialize_schedules()` makes its occurrences due again.
+    pub fn get_created(&self) -> Vec<NaiveDate> {
+        Vec::clone(&self.created)
+    }
+
+    /// Retrieve the dates of the occurrences that have been skipped, oldest first.
+    pub fn get_skipped(&self) -> Vec<NaiveDate> {
+        Vec::clone(&self.skipped)
+    }
+
+    /// Retrieve the id of the `Transaction` created for the occurrence on `date`.
+    pub fn get_occurrence_id(&self, date: NaiveDate) -> String {
+        format!("{}-{}", self.id, date)
+    }
+
+    /// Convert this `ScheduledTransaction`, along with the occurrences skipped and edited so
+    /// far, to its JSON form. The posted occurrences are found from the journal when it is
+    /// loaded.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["id"] = self.id.as_str().into();
+        value["template"] = self.template.to_json();
+        value["recurrence"] = self.recurrence.to_json();
+        value["skipped"] = JsonValue::Array(
+            self.skipped
+                .iter()
+                .map(|date| date.to_string().into())
+                .collect(),
+        );
+        value["edited"] = JsonValue::Array(
+            self.edited
+                .iter()
+                .map(|&(date, ref transaction)| {
+                    let mut entry = JsonValue::new_object();
+                    entry["date"] = date.to_string().into();
+                    entry["transaction"] = transaction.to_json();
+                    entry
+                })
+                .collect(),
+        );
+
+        value
+    }
+
+    /// Compute the dates of the occurrences up to and including `through` that have been neither
+    /// posted nor skipped.
+    pub fn get_due_occurrences(&self, through: NaiveDate) -> Vec<NaiveDate> {
+        self.recurrence
+            .get_occurrences(through)
+            .into_iter()
+            .filter(|date| !self.created.contains(date) && !self.skipped.contains(date))
+            .collect()
+    }
+
+    /// Build the `Transaction` to post for the occurrence on `date`, using the edited copy of the
+    /// template if there is one. An occurrence of an auto-reversing template is reversed as long
+    /// after its own date as the template is.
+    pub(crate) fn create_occurrence(&self, date: NaiveDate) -> Transaction {
+        let mut transaction = match self.edited.iter().find(|&&(edited, _)| edited == date) {
+            Some((_, transaction)) => Transaction::clone(transaction),
+            None => {
+                let mut transaction = Transaction::clone(&self.template);
+                transaction.set_date(date);
+                if let Some(reverse) = self.template.get_auto_reverse() {
+                    transaction.set_auto_reverse(date + (reverse - self.template.get_date()));
+                }
+                transaction
+            }
+        };
+        transaction.set_id(&self.get_occurrence_id(date));
+        transaction.set_source(EntrySource::Scheduled);
+
+        transaction
+    }
+
+    /// Ensure that `date` is an occurrence that has not yet been posted.
+    fn check_pending(&self, date: NaiveDate) -> Result<(), Error> {
+        if !self.recurrence.occurs_on(date) {
+            return Err(Error::UnknownOccurrence(self.get_id(), date));
+        }
+        if self.created.contains(&date) {
+            return Err(Error::DuplicateTransaction(self.get_occurrence_id(date)));
+        }
+
+        Ok(())
+    }
+}
+
+/// Create a `ScheduledTransaction` object from its JSON form, as produced by
+/// `ScheduledTransaction::to_json()`.
+///
+/// A schedule whose template or edited occurrences are not valid `Transaction`s is rejected
+/// with `Error::InvalidJson`.
+impl<'a> TryFrom<&'a JsonValue> for ScheduledTransaction {
+    type Error = Error;
+
+    fn try_from(value: &'a JsonValue) -> Result<ScheduledTransaction, Error> {
+        let mut schedule = ScheduledTransaction::new(
+            value["id"].as_str().unwrap_or(""),
+            Transaction::try_from(&value["template"])?,
+            Recurrence::from(&value["recurrence"]),
+        );
+        schedule.skipped = value["skipped"].members().filter_map(parse_date).collect();
+        for entry in value["edited"].members() {
+            if let Some(date) = parse_date(&entry["date"]) {
+                schedule
+                    .edited
+                    .push((date, Transaction::try_from(&entry["transaction"])?));
+            }
+        }
+
+        Ok(schedule)
+    }
+}
+
+impl Ledger {
+    /// Add a `ScheduledTransaction` to this `Ledger`. No occurrences are posted until
+    /// `materialize_schedules()` is called.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the schedule was added, or an `Error` if its id is already in use, or its
+    ///   template posts to an `Account` that is unknown or a placeholder, or does not balance.
+    pub fn add_schedule(&mut self, schedule: ScheduledTransaction) -> Result<(), Error> {
+        if self.get_schedule_by_id(&schedule.id).is_some() {
+            return Err(Error::DuplicateSchedule(schedule.get_id()));
+        }
+
+        self.validate_transaction(&schedule.template)?;
+        self.schedules_mut().push(schedule);
+
+        Ok(())
+    }
+
+    /// Remove a `ScheduledTransaction` from this `Ledger`. Occurrences which have already been
+    /// posted are not affected.
+    pub fn remove_schedule(&mut self, id: &str) -> Result<ScheduledTransaction, Error> {
+        match self
+            .schedules()
+            .iter()
+            .position(|schedule| schedule.id == id)
+        {
+            Some(index) => Ok(self.schedules_mut().remove(index)),
+            None => Err(Error::UnknownSchedule(String::from(id))),
+        }
+    }
+
+    /// Retrieve all of the `ScheduledTransaction`s in this `Ledger`.
+    pub fn get_schedules(&self) -> Vec<ScheduledTransaction> {
+        self.schedules()
+            .iter()
+            .map(|schedule| self.sync_schedule(schedule))
+            .collect()
+    }
+
+    /// Retrieve the `ScheduledTransaction` with the given id, if it exists.
+    pub fn get_schedule_by_id(&self, id: &str) -> Option<ScheduledTransaction> {
+        self.schedules()
+            .iter()
+            .find(|schedule| schedule.id == id)
+            .map(|schedule| self.sync_schedule(schedule))
+    }
+
+    /// Skip a single occurrence of a `ScheduledTransaction`, so that it is never posted.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the occurrence was skipped, or an `Error` if the schedule is unknown, does
+    ///   not occur on `date`, or the occurrence has already been posted.
+    pub fn skip_occurrence(&mut self, id: &str, date: NaiveDate) -> Result<(), Error> {
+        self.check_pending(id, date)?;
+        let schedule = self.get_schedule_mut(id)?;
+        if !schedule.skipped.contains(&date) {
+            schedule.skipped.push(date);
+            schedule.skipped.sort();
+        }
+
+        Ok(())
+    }
+
+    /// Replace the `Transaction` posted for a single occurrence of a `ScheduledTransaction`.
+    ///
+    /// The occurrence keeps its id, but its date, description and `Split`s are taken from
+    /// `transaction`. If the occurrence was skipped, it is no longer skipped. Occurrences which
+    /// have already been posted should be changed with `update_transaction()` instead.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the occurrence was changed, or an `Error` if the schedule is unknown, does
+    ///   not occur on `date`, the occurrence has already been posted, or `transaction` is
+    ///   invalid.
+    pub fn edit_occurrence(
+        &mut self,
+        id: &str,
+        date: NaiveDate,
+        transaction: Transaction,
+    ) -> Result<(), Error> {
+        self.validate_transaction(&transaction)?;
+        self.check_pending(id, date)?;
+
+        let schedule = self.get_schedule_mut(id)?;
+        schedule.skipped.retain(|&skipped| skipped != date);
+        schedule.edited.retain(|&(edited, _)| edited != date);
+        schedule.edited.push((date, transaction));
+
+        Ok(())
+    }
+
+    /// Post every occurrence of every `ScheduledTransaction`, up to and including `through`,
+    /// which has been neither posted nor skipped.
+    ///
+    /// # Returns
+    /// * The ids of the posted `Transaction`s, or an `Error` if an occurrence could not be
+    ///   posted. Occurrences posted before the error remain in the `Ledger`, and are not posted
+    ///   again.
+    ///
+    /// The posted occurrences are undone together as a single action by `Ledger::undo()`.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Account, AccountType, AccountsChart, Currency, Frequency, Ledger,
+    ///#               Recurrence, ScheduledTransaction, Split, Transaction};
+    ///# let mut chart = AccountsChart::new();
+    ///# chart.add_top_level_account(Account::new("01", "Checking", "Checking", AccountType::Asset,
+    ///#                                          Currency::USDollar, false));
+    ///# chart.add_top_level_account(Account::new("05", "Rent", "Rent", AccountType::Expense,
+    ///#                                          Currency::USDollar, false));
+    /// let mut ledger = Ledger::new(chart);
+    ///
+    /// let start = NaiveDate::from_ymd_opt(2018, 1, 1).unwrap();
+    /// let mut rent = Transaction::new("", start, "Office rent");
+    /// rent.add_split(Split::new("05", 120000));
+    /// rent.add_split(Split::new("01", -120000));
+    /// let recurrence = Recurrence::new(Frequency::MonthlyByDay(1), start);
+    /// ledger.add_schedule(ScheduledTransaction::new("RENT", rent, recurrence)).unwrap();
+    ///
+    /// let posted = ledger.materialize_schedules(NaiveDate::from_ymd_opt(2018, 3, 15).unwrap());
+    /// assert_eq!(posted.unwrap(), vec!["RENT-2018-01-01", "RENT-2018-02-01", "RENT-2018-03-01"]);
+    ///
+    /// let posted = ledger.materialize_schedules(NaiveDate::from_ymd_opt(2018, 3, 31).unwrap());
+    /// assert!(posted.unwrap().is_empty());
+    /// ```
+    pub fn materialize_schedules(&mut self, through: NaiveDate) -> Result<Vec<String>, Error> {
+        self.begin_group();
+        let result = self.post_due_occurrences(through);
+        self.end_group();
+
+        result
+    }
+
+    fn post_due_occurrences(&mut self, through: NaiveDate) -> Result<Vec<String>, Error> {
+        let mut posted = vec![];
+        for schedule in self.get_schedules() {
+            for date in schedule.get_due_occurrences(through) {
+                let transaction = schedule.create_occurrence(date);
+                let id = transaction.get_id();
+                self.add_transaction(transaction)?;
+                posted.push(id);
+            }
+        }
+
+        Ok(posted)
+    }
+
+    /// Bring the posted occurrences of a stored `ScheduledTransaction` up to date with the
+    /// journal.
+    fn sync_schedule(&self, schedule: &ScheduledTransaction) -> ScheduledTransaction {
+        let prefix = format!("{}-", schedule.id);
+        let mut schedule = ScheduledTransaction::clone(schedule);
+        schedule.created = self
+            .transactions()
+            .iter()
+            .filter_map(|transaction| {
+                let id = transaction.get_id();
+                id.strip_prefix(&prefix)
+                    .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+                    .filter(|&date| schedule.recurrence.occurs_on(date))
+            })
+            .collect();
+        schedule.created.sort();
+
+        schedule
+    }
+
+    /// Ensure that `date` is an occurrence of the schedule with the given id that has not yet
+    /// been posted.
+    fn check_pending(&self, id: &str, date: NaiveDate) -> Result<(), Error> {
+        match self.get_schedule_by_id(id) {
+            Some(schedule) => schedule.check_pending(date),
+            None => Err(Error::UnknownSchedule(String::from(id))),
+        }
+    }
+
+    fn get_schedule_mut(&mut self, id: &str) -> Result<&mut ScheduledTransaction, Error> {
+        match self
+            .schedules_mut()
+            .iter_mut()
+            .find(|schedule| schedule.id == id)
+        {
+            Some(schedule) => Ok(schedule),
+            None => Err(Error::UnknownSchedule(String::from(id))),
+        }
+    }
+}
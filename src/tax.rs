@@ -0,0 +1,126 @@
+//! Sales taxes that can be charged on the lines of an `Invoice`.
+
+use json::JsonValue;
+
+use error::Error;
+use ledger::Ledger;
+use AccountType;
+
+/// A sales tax, charged at a percentage of the amounts it applies to, and owed to the tax
+/// authority through a Liability `Account`.
+///
+/// # Examples
+/// ```
+///# use cratchit::TaxCode;
+/// let tax = TaxCode::new("MN", "Minnesota sales tax", 6.875, "02-01");
+/// assert_eq!(tax.get_tax(40000), 2750);
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct TaxCode {
+    code: String,
+    name: String,
+    rate: f64,
+    account_id: String,
+}
+
+impl TaxCode {
+    /// Create a new `TaxCode` object.
+    ///
+    /// # Arguments
+    /// * `code`: The code identifying the tax on invoice lines.
+    /// * `name`: The human-readable name of the tax.
+    /// * `rate`: The percentage of the taxable amount charged, e.g. 6.875.
+    /// * `account_id`: The id of the Liability `Account` the tax collected is owed through.
+    pub fn new(code: &str, name: &str, rate: f64, account_id: &str) -> TaxCode {
+        TaxCode {
+            code: String::from(code),
+            name: String::from(name),
+            rate,
+            account_id: String::from(account_id),
+        }
+    }
+
+    /// Retrieve the code identifying this `TaxCode`, as a `String`.
+    pub fn get_code(&self) -> String {
+        String::clone(&self.code)
+    }
+
+    /// Retrieve the name of this `TaxCode`, as a `String`.
+    pub fn get_name(&self) -> String {
+        String::clone(&self.name)
+    }
+
+    /// Retrieve the percentage of the taxable amount charged.
+    pub fn get_rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Retrieve the id of the Liability `Account` the tax collected is owed through.
+    pub fn get_account_id(&self) -> String {
+        String::clone(&self.account_id)
+    }
+
+    /// Compute the tax on a taxable amount, rounded to the nearest unit.
+    pub fn get_tax(&self, amount: i64) -> i64 {
+        (amount as f64 * self.rate / 100.0).round() as i64
+    }
+
+    /// Convert this `TaxCode` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["code"] = self.code.as_str().into();
+        value["name"] = self.name.as_str().into();
+        value["rate"] = self.rate.into();
+        value["account_id"] = self.account_id.as_str().into();
+
+        value
+    }
+}
+
+/// Create a `TaxCode` object from its JSON form, as produced by `TaxCode::to_json()`.
+impl<'a> From<&'a JsonValue> for TaxCode {
+    fn from(value: &'a JsonValue) -> TaxCode {
+        TaxCode::new(
+            value["code"].as_str().unwrap_or(""),
+            value["name"].as_str().unwrap_or(""),
+            value["rate"].as_f64().unwrap_or(0.0),
+            value["account_id"].as_str().unwrap_or(""),
+        )
+    }
+}
+
+impl Ledger {
+    /// Add a `TaxCode` that the lines of `Invoice`s in this `Ledger` can be charged.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the `TaxCode` was added, `Error::DuplicateTaxCode` if one with the same code
+    ///   already exists, or an `Error` if its `Account` does not exist or is not a Liability
+    ///   `Account`.
+    pub fn add_tax_code(&mut self, tax_code: TaxCode) -> Result<(), Error> {
+        if self.get_tax_code(&tax_code.code).is_some() {
+            return Err(Error::DuplicateTaxCode(tax_code.code));
+        }
+        match self.get_chart().get_account_by_id(&tax_code.account_id) {
+            Some(ref account) if account.get_account_type() == AccountType::Liability => {}
+            Some(_) => return Err(Error::WrongAccountType(tax_code.account_id)),
+            None => return Err(Error::UnknownAccount(tax_code.account_id)),
+        }
+
+        self.tax_codes_mut().push(tax_code);
+
+        Ok(())
+    }
+
+    /// Retrieve the `TaxCode` with the given code, if it has been added to this `Ledger`.
+    pub fn get_tax_code(&self, code: &str) -> Option<TaxCode> {
+        self.tax_codes()
+            .iter()
+            .find(|tax_code| tax_code.code == code)
+            .cloned()
+    }
+
+    /// Retrieve every `TaxCode` added to this `Ledger`, in the order they were added.
+    pub fn get_tax_codes(&self) -> Vec<TaxCode> {
+        self.tax_codes().to_vec()
+    }
+}
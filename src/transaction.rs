@@ -0,0 +1,802 @@
+//! Transactions and the splits that post them to individual `Account`s.
+
+use std::convert::TryFrom;
+
+use chrono::NaiveDate;
+use json::JsonValue;
+
+use error::Error;
+use lot::LotSelection;
+use Currency;
+
+/// An enumeration for choosing which of a `Transaction`'s dates is used when filtering by date.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum DateBasis {
+    /// Use the date the `Transaction` was posted to the books.
+    #[default]
+    Posting,
+
+    /// Use the date the `Transaction` takes economic effect. This is the same as the posting
+    /// date unless an effective date was set on the `Transaction`.
+    Effective,
+}
+
+/// An enumeration for tracking whether a `Transaction` or `Split` has cleared the bank.
+///
+/// A status may only move forward from `Pending` to `Cleared` to `Reconciled`, except that a
+/// `Cleared` item may be returned to `Pending` if it was marked by mistake. Any item may be
+/// voided, but only by voiding its `Transaction` within a `Ledger`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub enum TransactionStatus {
+    /// The item has been entered, but has not yet been seen on a bank statement.
+    #[default]
+    Pending,
+
+    /// The item has been seen on a bank statement.
+    Cleared,
+
+    /// The item has been matched against a bank statement during a reconciliation.
+    Reconciled,
+
+    /// The item was cancelled, and has been offset by a reversing `Transaction`.
+    Voided,
+}
+
+impl TransactionStatus {
+    /// Determine if an item with this status may be moved directly to the `next` status.
+    ///
+    /// # Examples
+    /// ```
+    /// use cratchit::TransactionStatus;
+    ///
+    /// assert!(TransactionStatus::Pending.can_transition_to(TransactionStatus::Cleared));
+    /// assert!(!TransactionStatus::Pending.can_transition_to(TransactionStatus::Reconciled));
+    /// assert!(!TransactionStatus::Reconciled.can_transition_to(TransactionStatus::Pending));
+    /// ```
+    pub fn can_transition_to(self, next: TransactionStatus) -> bool {
+        match (self, next) {
+            (TransactionStatus::Pending, TransactionStatus::Cleared)
+            | (TransactionStatus::Cleared, TransactionStatus::Pending)
+            | (TransactionStatus::Cleared, TransactionStatus::Reconciled) => true,
+            (TransactionStatus::Voided, _) => false,
+            (_, TransactionStatus::Voided) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Convert from a string slice to a `TransactionStatus` value, ignoring case. Unknown values are
+/// treated as `Pending`.
+impl<'a> From<&'a str> for TransactionStatus {
+    fn from(value: &'a str) -> TransactionStatus {
+        match value.to_lowercase().as_str() {
+            "cleared" => TransactionStatus::Cleared,
+            "reconciled" => TransactionStatus::Reconciled,
+            "voided" => TransactionStatus::Voided,
+            _ => TransactionStatus::Pending,
+        }
+    }
+}
+
+/// An enumeration describing how a `Transaction` came to be in the `Ledger`.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum EntrySource {
+    /// The `Transaction` was entered by a user.
+    #[default]
+    Manual,
+
+    /// The `Transaction` was generated by the `Ledger` to close Income and Expense accounts into
+    /// retained earnings at the end of a fiscal year.
+    YearEndClose,
+
+    /// The `Transaction` was generated by the `Ledger` to record the balances of accounts
+    /// migrated from another set of books.
+    OpeningBalance,
+
+    /// The `Transaction` was generated by the `Ledger` as an occurrence of a
+    /// `ScheduledTransaction`.
+    Scheduled,
+
+    /// The `Transaction` was generated by the `Ledger` to reverse an entry marked with
+    /// `Transaction::set_auto_reverse()`.
+    AutoReversal,
+
+    /// The `Transaction` was generated by the `Ledger` to revalue foreign-currency balances at
+    /// the closing rate.
+    Revaluation,
+
+    /// The `Transaction` was generated by the `Ledger` to record a `CorporateAction` affecting
+    /// the units or cost of a `Commodity` held.
+    CorporateAction,
+
+    /// The `Transaction` was generated by the `Ledger` to issue an `Invoice`, or to record a
+    /// payment against one.
+    Invoice,
+
+    /// The `Transaction` was generated by the `Ledger` to post a `Bill`, or to record a payment
+    /// against one or more.
+    Bill,
+}
+
+/// Convert from a string slice to an `EntrySource` value, ignoring case. Unknown values are
+/// treated as `Manual`.
+impl<'a> From<&'a str> for EntrySource {
+    fn from(value: &'a str) -> EntrySource {
+        match value.to_lowercase().as_str() {
+            "yearendclose" => EntrySource::YearEndClose,
+            "openingbalance" => EntrySource::OpeningBalance,
+            "scheduled" => EntrySource::Scheduled,
+            "autoreversal" => EntrySource::AutoReversal,
+            "revaluation" => EntrySource::Revaluation,
+            "corporateaction" => EntrySource::CorporateAction,
+            "invoice" => EntrySource::Invoice,
+            "bill" => EntrySource::Bill,
+            _ => EntrySource::Manual,
+        }
+    }
+}
+
+/// A single line of a `Transaction`, posting an amount to one `Account`.
+///
+/// Amounts are expressed in the smallest unit of the `Account`'s `Currency` (e.g. cents). A
+/// positive amount is a debit to the `Account`, and a negative amount is a credit.
+///
+/// Each `Split` also has a value in the currency of its `Transaction`, which is what must
+/// balance. For an `Account` in the transaction currency, the value is the same as the amount.
+/// For an `Account` in a foreign currency, the value is the amount converted at an explicit
+/// rate; see `Split::new_foreign()`. For an `Account` holding a `Commodity`, the amount is a
+/// number of units and the value is their price; see `Split::new_commodity()`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Split {
+    account_id: String,
+    amount: i64,
+    value: Option<i64>,
+    rate: Option<f64>,
+    exchange_adjustment: bool,
+    capital_gain: bool,
+    lot_selection: Option<LotSelection>,
+    contact: Option<String>,
+    memo: String,
+    status: TransactionStatus,
+}
+
+impl Split {
+    /// Construct a new `Split` object.
+    ///
+    /// * account_id: The id of the `Account` this `Split` posts to.
+    /// * amount: The amount to post, in the smallest unit of the `Account`'s `Currency`. Debits
+    ///   are positive, and credits are negative.
+    pub fn new(account_id: &str, amount: i64) -> Split {
+        Split {
+            account_id: String::from(account_id),
+            amount,
+            value: None,
+            rate: None,
+            exchange_adjustment: false,
+            capital_gain: false,
+            lot_selection: None,
+            contact: None,
+            memo: String::new(),
+            status: TransactionStatus::Pending,
+        }
+    }
+
+    /// Construct a new `Split` object posting to an `Account` in a currency other than that of
+    /// the `Transaction`.
+    ///
+    /// * account_id: The id of the `Account` this `Split` posts to.
+    /// * amount: The amount to post, in the smallest unit of the `Account`'s `Currency`.
+    /// * rate: The number of units of the transaction currency that one unit of the `Account`'s
+    ///   `Currency` is worth. The value of the `Split` is the amount converted at this rate,
+    ///   rounded to the nearest unit.
+    ///
+    /// # Examples
+    /// ```
+    ///# use cratchit::Split;
+    /// let split = Split::new_foreign("01-02", 10000, 1.2345);
+    /// assert_eq!(split.get_amount(), 10000);
+    /// assert_eq!(split.get_value(), 12345);
+    /// assert_eq!(split.get_rate(), Some(1.2345));
+    /// ```
+    pub fn new_foreign(account_id: &str, amount: i64, rate: f64) -> Split {
+        let mut split = Split::new(account_id, amount);
+        split.value = Some((amount as f64 * rate).round() as i64);
+        split.rate = Some(rate);
+
+        split
+    }
+
+    /// Construct a new `Split` object buying or selling units of the `Commodity` held by an
+    /// `Account`.
+    ///
+    /// * account_id: The id of the `Account` this `Split` posts to.
+    /// * units: The number of units bought (positive) or sold (negative), in the smallest
+    ///   fraction of the `Commodity` that can be held.
+    /// * value: The price of the units in the transaction currency: their cost for a purchase,
+    ///   or the proceeds (negative) for a sale.
+    ///
+    /// # Examples
+    /// ```
+    ///# use cratchit::Split;
+    /// let split = Split::new_commodity("01-05", 10, 170000);
+    /// assert_eq!(split.get_amount(), 10);
+    /// assert_eq!(split.get_value(), 170000);
+    /// ```
+    pub fn new_commodity(account_id: &str, units: i64, value: i64) -> Split {
+        let mut split = Split::new(account_id, units);
+        split.value = Some(value);
+
+        split
+    }
+
+    /// Retrieve the id of the `Account` this `Split` posts to, as a `String`.
+    pub fn get_account_id(&self) -> String {
+        String::clone(&self.account_id)
+    }
+
+    /// Retrieve the amount of this `Split`. Debits are positive, and credits are negative.
+    pub fn get_amount(&self) -> i64 {
+        self.amount
+    }
+
+    /// Determine if this `Split` is a debit to its `Account`.
+    pub fn is_debit(&self) -> bool {
+        self.amount > 0
+    }
+
+    /// Retrieve the value of this `Split` in the currency of its `Transaction`. Debits are
+    /// positive, and credits are negative.
+    pub fn get_value(&self) -> i64 {
+        self.value.unwrap_or(self.amount)
+    }
+
+    /// Retrieve the rate at which the amount of this `Split` was converted to the transaction
+    /// currency, if it posts to an `Account` in a foreign currency.
+    pub fn get_rate(&self) -> Option<f64> {
+        self.rate
+    }
+
+    /// Determine if this `Split` was generated by the `Ledger` to record a realized exchange
+    /// gain or loss.
+    pub fn is_exchange_adjustment(&self) -> bool {
+        self.exchange_adjustment
+    }
+
+    /// Determine if this `Split` was generated by the `Ledger` to record a realized capital gain
+    /// or loss.
+    pub fn is_capital_gain(&self) -> bool {
+        self.capital_gain
+    }
+
+    /// Retrieve how the lots sold by this `Split` are chosen, if it sells units of a
+    /// `Commodity`. Unless set, the oldest lots are sold first.
+    pub fn get_lot_selection(&self) -> LotSelection {
+        self.lot_selection.clone().unwrap_or_default()
+    }
+
+    /// Set how the lots sold by this `Split` are chosen.
+    pub fn set_lot_selection(&mut self, selection: LotSelection) {
+        self.lot_selection = Some(selection);
+    }
+
+    /// Retrieve the id of the `Contact` this `Split` is tagged with, if any.
+    pub fn get_contact(&self) -> Option<String> {
+        self.contact.clone()
+    }
+
+    /// Tag this `Split` with the `Contact` it is owed by or to, such as the customer of a
+    /// receivable.
+    pub fn set_contact(&mut self, contact_id: &str) {
+        self.contact = Some(String::from(contact_id));
+    }
+
+    /// Retrieve the memo describing this line of the `Transaction`, as a `String`.
+    pub fn get_memo(&self) -> String {
+        String::clone(&self.memo)
+    }
+
+    /// Set the memo describing this line of the `Transaction`.
+    pub fn set_memo(&mut self, memo: &str) {
+        self.memo = String::from(memo);
+    }
+
+    /// Retrieve the `TransactionStatus` of this `Split`.
+    pub fn get_status(&self) -> TransactionStatus {
+        self.status
+    }
+
+    /// Convert this `Split` to its JSON form.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["account_id"] = self.account_id.as_str().into();
+        value["amount"] = self.amount.into();
+        value["value"] = self.value.into();
+        value["rate"] = self.rate.into();
+        value["exchange_adjustment"] = self.exchange_adjustment.into();
+        value["capital_gain"] = self.capital_gain.into();
+        if let Some(ref selection) = self.lot_selection {
+            value["lot_selection"] = selection.to_json();
+        }
+        if let Some(ref contact) = self.contact {
+            value["contact"] = contact.as_str().into();
+        }
+        value["memo"] = self.memo.as_str().into();
+        value["status"] = format!("{:?}", self.status).into();
+
+        value
+    }
+
+    /// Convert this `Split` to the canonical JSON form covered by the hash chain.
+    ///
+    /// The fields present when the hash chain was introduced always appear, in their original
+    /// order. Later fields follow them, and only appear when they differ from their defaults, so
+    /// that adding a field never changes the hash of a `Split` that does not use it.
+    pub(crate) fn to_canonical_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["account_id"] = self.account_id.as_str().into();
+        value["amount"] = self.amount.into();
+        value["memo"] = self.memo.as_str().into();
+        value["status"] = format!("{:?}", self.status).into();
+        if let Some(split_value) = self.value {
+            value["value"] = split_value.into();
+        }
+        if let Some(rate) = self.rate {
+            value["rate"] = rate.into();
+        }
+        if self.exchange_adjustment {
+            value["exchange_adjustment"] = true.into();
+        }
+        if self.capital_gain {
+            value["capital_gain"] = true.into();
+        }
+        if let Some(ref selection) = self.lot_selection {
+            value["lot_selection"] = selection.to_json();
+        }
+        if let Some(ref contact) = self.contact {
+            value["contact"] = contact.as_str().into();
+        }
+
+        value
+    }
+
+    pub(crate) fn set_status(&mut self, status: TransactionStatus) {
+        self.status = status;
+    }
+
+    /// Set the value of this `Split` in the transaction currency, without changing its amount.
+    pub(crate) fn set_value(&mut self, value: i64) {
+        self.value = Some(value);
+    }
+
+    /// Create a `Split` generated to record a realized exchange gain or loss, which changes the
+    /// value of an `Account` without changing its amount.
+    pub(crate) fn new_exchange_adjustment(account_id: &str, amount: i64, value: i64) -> Split {
+        let mut split = Split::new(account_id, amount);
+        if value != amount {
+            split.value = Some(value);
+        }
+        split.exchange_adjustment = true;
+        split.memo = String::from("Realized exchange gain/loss");
+
+        split
+    }
+
+    /// Create a `Split` generated to record a realized capital gain or loss, which changes the
+    /// value of an `Account` without changing its units.
+    pub(crate) fn new_capital_gain(account_id: &str, amount: i64, value: i64) -> Split {
+        let mut split = Split::new(account_id, amount);
+        if value != amount {
+            split.value = Some(value);
+        }
+        split.capital_gain = true;
+        split.memo = String::from("Realized capital gain/loss");
+
+        split
+    }
+}
+
+/// Create a `Split` object from its JSON form, as produced by `Split::to_json()`.
+///
+/// A `Split` without an `Account` id or an amount is rejected with `Error::InvalidJson`.
+impl<'a> TryFrom<&'a JsonValue> for Split {
+    type Error = Error;
+
+    fn try_from(value: &'a JsonValue) -> Result<Split, Error> {
+        let mut split = match (value["account_id"].as_str(), value["amount"].as_i64()) {
+            (Some(account_id), Some(amount)) => Split::new(account_id, amount),
+            _ => {
+                return Err(Error::InvalidJson(String::from(
+                    "a split has no 'account_id' or 'amount'",
+                )))
+            }
+        };
+        split.value = value["value"].as_i64();
+        split.rate = value["rate"].as_f64();
+        split.exchange_adjustment = value["exchange_adjustment"].as_bool().unwrap_or(false);
+        split.capital_gain = value["capital_gain"].as_bool().unwrap_or(false);
+        if !value["lot_selection"].is_null() {
+            split.lot_selection = Some(LotSelection::from(&value["lot_selection"]));
+        }
+        split.contact = value["contact"].as_str().map(String::from);
+        split.memo = String::from(value["memo"].as_str().unwrap_or(""));
+        split.status = TransactionStatus::from(value["status"].as_str().unwrap_or(""));
+
+        Ok(split)
+    }
+}
+
+/// A dated exchange of value between two or more `Account`s, i.e. a general journal entry.
+///
+/// A `Transaction` is made up of any number of `Split`s, each of which posts an amount to a
+/// single `Account`. In order to be added to a `Ledger`, the values of all of the `Split`s in a
+/// `Transaction`, expressed in its `Currency`, must sum to zero.
+///
+/// Every `Transaction` has a posting date, which is the date it was entered into the books. It may
+/// also have a separate effective date, for entries such as adjustments that are posted after the
+/// period they apply to.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Transaction {
+    id: String,
+    date: NaiveDate,
+    effective_date: Option<NaiveDate>,
+    reference: Option<String>,
+    description: String,
+    status: TransactionStatus,
+    reverses: Option<String>,
+    auto_reverse: Option<NaiveDate>,
+    source: EntrySource,
+    currency: Currency,
+    splits: Vec<Split>,
+}
+
+impl Transaction {
+    /// Construct a new `Transaction` object with no `Split`s.
+    ///
+    /// * id: An identifier for this transaction. This must be unique within a `Ledger`.
+    /// * date: The date on which this transaction was posted.
+    /// * description: A human-readable description of this transaction.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Split, Transaction};
+    /// let mut transaction = Transaction::new("0001",
+    ///                                        NaiveDate::from_ymd_opt(2018, 11, 3).unwrap(),
+    ///                                        "Ice time for Lakeville North");
+    /// transaction.add_split(Split::new("01-0101", 25000));
+    /// transaction.add_split(Split::new("04-01", -25000));
+    ///
+    /// assert_eq!(transaction.get_splits().len(), 2);
+    /// assert!(transaction.is_balanced());
+    /// ```
+    pub fn new(id: &str, date: NaiveDate, description: &str) -> Transaction {
+        Transaction {
+            id: String::from(id),
+            date,
+            effective_date: None,
+            reference: None,
+            description: String::from(description),
+            status: TransactionStatus::Pending,
+            reverses: None,
+            auto_reverse: None,
+            source: EntrySource::Manual,
+            currency: Currency::USDollar,
+            splits: vec![],
+        }
+    }
+
+    /// Retrieve the id for this `Transaction`, as a `String`.
+    pub fn get_id(&self) -> String {
+        String::clone(&self.id)
+    }
+
+    /// Retrieve the date on which this `Transaction` was posted.
+    pub fn get_date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// Retrieve the date on which this `Transaction` takes effect.
+    ///
+    /// If no effective date has been set, this is the same as the posting date.
+    pub fn get_effective_date(&self) -> NaiveDate {
+        self.effective_date.unwrap_or(self.date)
+    }
+
+    /// Set the date on which this `Transaction` takes effect, if it differs from the posting date.
+    pub fn set_effective_date(&mut self, date: NaiveDate) {
+        self.effective_date = Some(date);
+    }
+
+    /// Retrieve the date of this `Transaction` according to the given `DateBasis`.
+    pub fn get_date_for(&self, basis: DateBasis) -> NaiveDate {
+        match basis {
+            DateBasis::Posting => self.get_date(),
+            DateBasis::Effective => self.get_effective_date(),
+        }
+    }
+
+    /// Retrieve the reference for this `Transaction`, such as a check number or the number of
+    /// the source document, if it has one.
+    pub fn get_reference(&self) -> Option<String> {
+        self.reference.clone()
+    }
+
+    /// Set the reference for this `Transaction`, such as a check number or the number of the
+    /// source document.
+    pub fn set_reference(&mut self, reference: &str) {
+        self.reference = Some(String::from(reference));
+    }
+
+    /// Retrieve the description of this `Transaction`, as a `String`.
+    pub fn get_description(&self) -> String {
+        String::clone(&self.description)
+    }
+
+    /// Retrieve the `TransactionStatus` of this `Transaction`.
+    ///
+    /// Once a `Transaction` has been added to a `Ledger`, its status is never further along than
+    /// the status of its least-advanced `Split`.
+    pub fn get_status(&self) -> TransactionStatus {
+        self.status
+    }
+
+    /// Retrieve the id of the `Transaction` that this `Transaction` reverses, if it is a
+    /// reversing entry.
+    pub fn get_reverses(&self) -> Option<String> {
+        self.reverses.clone()
+    }
+
+    /// Retrieve the date on which this `Transaction` is automatically reversed, if it is an
+    /// auto-reversing entry such as a month-end accrual.
+    pub fn get_auto_reverse(&self) -> Option<NaiveDate> {
+        self.auto_reverse
+    }
+
+    /// Mark this `Transaction` to be automatically reversed on the given date.
+    ///
+    /// When the `Transaction` is added to a `Ledger`, a reversing `Transaction` with the id
+    /// `{id}-rev` is posted on `date`, which must be after the date of the `Transaction`. The
+    /// reversal is kept up to date when the original is edited, and removed when the original is
+    /// removed or voided.
+    pub fn set_auto_reverse(&mut self, date: NaiveDate) {
+        self.auto_reverse = Some(date);
+    }
+
+    /// Stop this `Transaction` from being automatically reversed.
+    pub fn clear_auto_reverse(&mut self) {
+        self.auto_reverse = None;
+    }
+
+    /// Retrieve the `EntrySource` describing how this `Transaction` was created.
+    pub fn get_source(&self) -> EntrySource {
+        self.source
+    }
+
+    /// Retrieve the `Currency` in which the values of the `Split`s of this `Transaction` are
+    /// expressed. This is `Currency::USDollar` unless another currency has been set.
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Set the `Currency` in which the values of the `Split`s of this `Transaction` are
+    /// expressed.
+    pub fn set_currency(&mut self, currency: Currency) {
+        self.currency = currency;
+    }
+
+    /// Determine if this `Transaction` was generated by the `Ledger`, rather than entered by a
+    /// user.
+    pub fn is_system_generated(&self) -> bool {
+        self.source != EntrySource::Manual
+    }
+
+    /// Create a new `Transaction` that exactly offsets this one.
+    ///
+    /// The reversal has the given id and date, and a `Split` with the negated amount for each
+    /// `Split` of this `Transaction`.
+    pub fn create_reversal(&self, id: &str, date: NaiveDate, description: &str) -> Transaction {
+        let mut reversal = Transaction::new(id, date, description);
+        reversal.reference = self.reference.clone();
+        reversal.reverses = Some(self.get_id());
+        reversal.currency = self.currency;
+        for split in &self.splits {
+            let mut reversed_split = Split::new(&split.account_id, -split.amount);
+            reversed_split.value = split.value.map(|value| -value);
+            reversed_split.rate = split.rate;
+            reversed_split.contact = split.contact.clone();
+            reversed_split.memo = String::clone(&split.memo);
+            reversal.add_split(reversed_split);
+        }
+
+        reversal
+    }
+
+    /// Add a new `Split` to this `Transaction`.
+    pub fn add_split(&mut self, split: Split) {
+        self.splits.push(split);
+    }
+
+    /// Retrieve the `Split`s that make up this `Transaction`.
+    pub fn get_splits(&self) -> Vec<Split> {
+        Vec::clone(&self.splits)
+    }
+
+    /// Retrieve the total value of the debit `Split`s of this `Transaction`, in its `Currency`.
+    pub fn get_total_debits(&self) -> i64 {
+        self.splits
+            .iter()
+            .map(Split::get_value)
+            .filter(|value| *value > 0)
+            .sum()
+    }
+
+    /// Retrieve the total value of the credit `Split`s of this `Transaction`, in its `Currency`,
+    /// as a positive number.
+    pub fn get_total_credits(&self) -> i64 {
+        -self
+            .splits
+            .iter()
+            .map(Split::get_value)
+            .filter(|value| *value < 0)
+            .sum::<i64>()
+    }
+
+    /// Determine if the values of the debits and credits of this `Transaction` sum to zero.
+    pub fn is_balanced(&self) -> bool {
+        self.splits.iter().map(Split::get_value).sum::<i64>() == 0
+    }
+
+    /// Convert this `Transaction` to its JSON form. Dates are written as `YYYY-MM-DD` strings.
+    ///
+    /// # Examples
+    /// ```
+    ///# extern crate chrono;
+    ///# extern crate cratchit;
+    ///# use std::convert::TryFrom;
+    ///# use chrono::NaiveDate;
+    ///# use cratchit::{Split, Transaction};
+    /// let mut transaction = Transaction::new("0001",
+    ///                                        NaiveDate::from_ymd_opt(2018, 11, 3).unwrap(),
+    ///                                        "Ice time for Lakeville North");
+    /// transaction.add_split(Split::new("01-0101", 25000));
+    /// transaction.add_split(Split::new("04-01", -25000));
+    ///
+    /// let value = transaction.to_json();
+    /// assert_eq!(value["date"], "2018-11-03");
+    /// assert_eq!(value["splits"][1]["amount"], -25000);
+    /// assert_eq!(Transaction::try_from(&value), Ok(transaction));
+    /// ```
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["id"] = self.id.as_str().into();
+        value["date"] = self.date.to_string().into();
+        value["effective_date"] = self.effective_date.map(|date| date.to_string()).into();
+        value["reference"] = self.reference.clone().into();
+        value["description"] = self.description.as_str().into();
+        value["status"] = format!("{:?}", self.status).into();
+        value["reverses"] = self.reverses.clone().into();
+        value["auto_reverse"] = self.auto_reverse.map(|date| date.to_string()).into();
+        value["source"] = format!("{:?}", self.source).into();
+        value["currency"] = self.currency.get_code().into();
+        value["splits"] = JsonValue::Array(self.splits.iter().map(Split::to_json).collect());
+
+        value
+    }
+
+    /// Convert this `Transaction` to the canonical JSON form covered by the hash chain.
+    ///
+    /// See `Split::to_canonical_json()` for how later fields are added.
+    pub(crate) fn to_canonical_json(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        value["id"] = self.id.as_str().into();
+        value["date"] = self.date.to_string().into();
+        value["effective_date"] = self.effective_date.map(|date| date.to_string()).into();
+        value["reference"] = self.reference.clone().into();
+        value["description"] = self.description.as_str().into();
+        value["status"] = format!("{:?}", self.status).into();
+        value["reverses"] = self.reverses.clone().into();
+        value["auto_reverse"] = self.auto_reverse.map(|date| date.to_string()).into();
+        value["source"] = format!("{:?}", self.source).into();
+        value["splits"] =
+            JsonValue::Array(self.splits.iter().map(Split::to_canonical_json).collect());
+        if self.currency != Currency::USDollar {
+            value["currency"] = self.currency.get_code().into();
+        }
+
+        value
+    }
+
+    pub(crate) fn splits(&self) -> &[Split] {
+        &self.splits
+    }
+
+    pub(crate) fn splits_mut(&mut self) -> &mut [Split] {
+        &mut self.splits
+    }
+
+    /// Remove the `Split`s generated to record realized exchange gains or losses.
+    pub(crate) fn remove_exchange_adjustments(&mut self) {
+        self.splits.retain(|split| !split.exchange_adjustment);
+    }
+
+    /// Remove the `Split`s generated to record realized capital gains or losses.
+    pub(crate) fn remove_capital_gains(&mut self) {
+        self.splits.retain(|split| !split.capital_gain);
+    }
+
+    pub(crate) fn set_id(&mut self, id: &str) {
+        self.id = String::from(id);
+    }
+
+    pub(crate) fn set_date(&mut self, date: NaiveDate) {
+        self.date = date;
+    }
+
+    pub(crate) fn set_status(&mut self, status: TransactionStatus) {
+        self.status = status;
+    }
+
+    pub(crate) fn set_source(&mut self, source: EntrySource) {
+        self.source = source;
+    }
+
+    /// Set the status of this `Transaction` to that of its least-advanced `Split`.
+    pub(crate) fn update_status_from_splits(&mut self) {
+        if let Some(status) = self.splits.iter().map(|split| split.status).min() {
+            self.status = status;
+        }
+    }
+}
+
+/// Create a `Transaction` object from its JSON form, as produced by `Transaction::to_json()`.
+///
+/// A `Transaction` without an id, a description or a valid date, or with a `Split` that is not
+/// valid, is rejected with `Error::InvalidJson`.
+impl<'a> TryFrom<&'a JsonValue> for Transaction {
+    type Error = Error;
+
+    fn try_from(value: &'a JsonValue) -> Result<Transaction, Error> {
+        let (id, description) = match (value["id"].as_str(), value["description"].as_str()) {
+            (Some(id), Some(description)) => (id, description),
+            _ => {
+                return Err(Error::InvalidJson(String::from(
+                    "a transaction has no 'id' or 'description'",
+                )))
+            }
+        };
+        let date = match parse_date(&value["date"]) {
+            Some(date) => date,
+            None => {
+                return Err(Error::InvalidJson(String::from(
+                    "a transaction has no 'date'",
+                )))
+            }
+        };
+        let mut transaction = Transaction::new(id, date, description);
+        transaction.effective_date = parse_date(&value["effective_date"]);
+        transaction.reference = value["reference"].as_str().map(String::from);
+        transaction.status = TransactionStatus::from(value["status"].as_str().unwrap_or(""));
+        transaction.reverses = value["reverses"].as_str().map(String::from);
+        transaction.auto_reverse = parse_date(&value["auto_reverse"]);
+        transaction.source = EntrySource::from(value["source"].as_str().unwrap_or(""));
+        transaction.currency = value["currency"]
+            .as_str()
+            .map(Currency::from)
+            .unwrap_or(Currency::USDollar);
+        transaction.splits = value["splits"]
+            .members()
+            .map(Split::try_from)
+            .collect::<Result<_, _>>()?;
+
+        Ok(transaction)
+    }
+}
+
+/// Parse a date written as a `YYYY-MM-DD` string, if the value is one.
+pub(crate) fn parse_date(value: &JsonValue) -> Option<NaiveDate> {
+    value
+        .as_str()
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+}
@@ -0,0 +1,304 @@
+//! Translation of `Account` balances into a single reporting currency.
+
+use chrono::{Duration, NaiveDate};
+
+use balance::BalanceOptions;
+use error::Error;
+use exchange::is_foreign_currency;
+use ledger::Ledger;
+use transaction::Transaction;
+use {Account, AccountType, Currency, NormalBalance};
+
+/// An enumeration of the rates at which a balance can be translated.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RatePolicy {
+    /// The rate on the closing date of the report, i.e. the `as_of()` date of the
+    /// `BalanceOptions`, or the date of the latest `Transaction` if none is set.
+    Closing,
+
+    /// The mean of the daily rates over the period of the report, from the `since()` date of the
+    /// `BalanceOptions` (or the date of the earliest `Transaction`) to the closing date.
+    Average,
+
+    /// The rate on the date of each posting. Postings made in a `Transaction` whose currency is
+    /// the reporting currency use the value they were recorded at.
+    Historical,
+}
+
+/// Options for translating balances into a reporting currency, using the rates in the
+/// `PriceDatabase` of the `Ledger`.
+///
+/// By default, Asset and Liability balances are translated at the closing rate, Income and
+/// Expense balances at the average rate, and Equity balances at historical rates. The
+/// difference that arises from translating at different rates can be reported as the balance of
+/// a cumulative translation adjustment (CTA) Equity `Account`.
+///
+/// # Examples
+/// ```
+///# extern crate chrono;
+///# extern crate cratchit;
+///# use chrono::NaiveDate;
+///# use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Currency, Ledger,
+///#               Split, Transaction, Translation};
+///# let mut chart = AccountsChart::new();
+///# chart.add_top_level_account(Account::new("01", "Checking (CAD)", "Checking (CAD)",
+///#                                          AccountType::Asset, Currency::CanadianDollar, false));
+///# chart.add_top_level_account(Account::new("03", "Capital", "Capital", AccountType::Equity,
+///#                                          Currency::CanadianDollar, false));
+///# chart.add_top_level_account(Account::new("03-CTA", "Translation Adjustment",
+///#                                          "Translation Adjustment", AccountType::Equity,
+///#                                          Currency::USDollar, false));
+/// let mut ledger = Ledger::new(chart);
+/// let mut deposit = Transaction::new("0001", NaiveDate::from_ymd_opt(2018, 1, 1).unwrap(),
+///                                    "Opening deposit");
+/// deposit.set_currency(Currency::CanadianDollar);
+/// deposit.add_split(Split::new("01", 100000));
+/// deposit.add_split(Split::new("03", -100000));
+/// ledger.add_transaction(deposit).unwrap();
+///
+/// let prices = ledger.get_price_database_mut();
+/// prices.add_price("CAD", "USD", NaiveDate::from_ymd_opt(2018, 1, 1).unwrap(), 0.8);
+/// prices.add_price("CAD", "USD", NaiveDate::from_ymd_opt(2018, 12, 31).unwrap(), 0.75);
+///
+/// let options = BalanceOptions::new()
+///     .as_of(NaiveDate::from_ymd_opt(2018, 12, 31).unwrap())
+///     .translate(Translation::new(Currency::USDollar).adjustment_account("03-CTA"));
+/// assert_eq!(ledger.get_balance("01", &options).unwrap(), 75000);
+/// assert_eq!(ledger.get_balance("03", &options).unwrap(), 80000);
+/// assert_eq!(ledger.get_balance("03-CTA", &options).unwrap(), -5000);
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct Translation {
+    currency: Currency,
+    balance_sheet_policy: RatePolicy,
+    equity_policy: RatePolicy,
+    income_statement_policy: RatePolicy,
+    adjustment_account_id: Option<String>,
+}
+
+impl Translation {
+    /// Create a new `Translation` object into the given reporting currency, using the default
+    /// rate policies.
+    pub fn new(currency: Currency) -> Translation {
+        Translation {
+            currency,
+            balance_sheet_policy: RatePolicy::Closing,
+            equity_policy: RatePolicy::Historical,
+            income_statement_policy: RatePolicy::Average,
+            adjustment_account_id: None,
+        }
+    }
+
+    /// Set the `RatePolicy` for Asset and Liability balances.
+    pub fn balance_sheet_rate(mut self, policy: RatePolicy) -> Translation {
+        self.balance_sheet_policy = policy;
+        self
+    }
+
+    /// Set the `RatePolicy` for Equity balances.
+    pub fn equity_rate(mut self, policy: RatePolicy) -> Translation {
+        self.equity_policy = policy;
+        self
+    }
+
+    /// Set the `RatePolicy` for Income and Expense balances.
+    pub fn income_statement_rate(mut self, policy: RatePolicy) -> Translation {
+        self.income_statement_policy = policy;
+        self
+    }
+
+    /// Report the cumulative translation adjustment as part of the balance of the Equity
+    /// `Account` with the given id.
+    pub fn adjustment_account(mut self, account_id: &str) -> Translation {
+        self.adjustment_account_id = Some(String::from(account_id));
+        self
+    }
+
+    /// Retrieve the reporting currency.
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Retrieve the `RatePolicy` used for balances of the given `AccountType`.
+    pub fn get_rate_policy(&self, account_type: AccountType) -> RatePolicy {
+        match account_type {
+            AccountType::Equity => self.equity_policy,
+            AccountType::Income | AccountType::Expense => self.income_statement_policy,
+            _ => self.balance_sheet_policy,
+        }
+    }
+
+    /// Retrieve the id of the `Account` that reports the cumulative translation adjustment, if
+    /// one has been set.
+    pub fn get_adjustment_account_id(&self) -> Option<String> {
+        self.adjustment_account_id.clone()
+    }
+}
+
+impl Ledger {
+    /// Compute the cumulative translation adjustment: the amount, in the reporting currency,
+    /// needed to keep the translated debits and credits of every `Account` in balance.
+    ///
+    /// The adjustment is signed as the balance of an Equity `Account`, so a positive value is a
+    /// credit. It does not depend on the `Account` set with `Translation::adjustment_account()`.
+    ///
+    /// # Returns
+    /// * The adjustment, which is 0 if no `Translation` was set on `options`, or an `Error` if a
+    ///   rate is unknown.
+    pub fn get_translation_adjustment(&self, options: &BalanceOptions) -> Result<i64, Error> {
+        let translation = match options.get_translation() {
+            Some(translation) => translation,
+            None => return Ok(0),
+        };
+
+        let mut total = 0;
+        for account_id in self.get_chart().get_account_ids() {
+            let account = self.get_chart().get_account_by_id(&account_id).unwrap();
+            if !account.is_placeholder() {
+                total += self.get_translated_total(&account, options, translation)?;
+            }
+        }
+
+        Ok(total)
+    }
+
+    /// Compute the balance of an `Account`, and optionally its sub-accounts, translated into the
+    /// reporting currency of `translation`.
+    pub(crate) fn get_translated_balance(
+        &self,
+        account: &Account,
+        options: &BalanceOptions,
+        translation: &Translation,
+    ) -> Result<i64, Error> {
+        let account_ids = if options.includes_sub_accounts() {
+            self.get_chart().get_sub_tree_ids(&account.get_id())
+        } else {
+            vec![account.get_id()]
+        };
+
+        let mut total = 0;
+        for account_id in account_ids {
+            let member = self.get_chart().get_account_by_id(&account_id).unwrap();
+            total += self.get_translated_total(&member, options, translation)?;
+            if translation.adjustment_account_id.as_ref() == Some(&account_id) {
+                total -= self.get_translation_adjustment(options)?;
+            }
+        }
+
+        match account.get_account_type().get_normal_balance() {
+            NormalBalance::Debit => Ok(total),
+            NormalBalance::Credit => Ok(-total),
+        }
+    }
+
+    /// Translate the postings made directly to an `Account`, returning the total with debits
+    /// positive.
+    fn get_translated_total(
+        &self,
+        account: &Account,
+        options: &BalanceOptions,
+        translation: &Translation,
+    ) -> Result<i64, Error> {
+        let account_id = account.get_id();
+        let postings: Vec<(&Transaction, i64, i64)> = self
+            .transactions()
+            .iter()
+            .filter(|transaction| options.includes(transaction))
+            .flat_map(|transaction| {
+                transaction
+                    .splits()
+                    .iter()
+                    .filter(|split| {
+                        options.includes_split(split) && split.get_account_id() == account_id
+                    })
+                    .map(move |split| (transaction, split.get_amount(), split.get_value()))
+            })
+            .collect();
+        let amount: i64 = postings.iter().map(|&(_, amount, _)| amount).sum();
+
+        let from = account.get_currency();
+        let to = translation.currency;
+        if !is_foreign_currency(from, to) {
+            return Ok(amount);
+        }
+
+        let (start, end) = self.get_report_period(options);
+        let rate = match translation.get_rate_policy(account.get_account_type()) {
+            RatePolicy::Closing if amount != 0 => {
+                self.get_price_database()
+                    .get_rate(from.get_code(), to.get_code(), end)?
+            }
+            RatePolicy::Average if amount != 0 => self.get_average_rate(from, to, start, end)?,
+            RatePolicy::Historical => {
+                let mut total = 0;
+                for (transaction, amount, value) in postings {
+                    total += if transaction.get_currency() == to {
+                        value
+                    } else {
+                        let rate = self.get_price_database().get_rate(
+                            from.get_code(),
+                            to.get_code(),
+                            transaction.get_date(),
+                        )?;
+                        (amount as f64 * rate).round() as i64
+                    };
+                }
+                return Ok(total);
+            }
+            _ => 0.0,
+        };
+
+        Ok((amount as f64 * rate).round() as i64)
+    }
+
+    /// Find the first and last dates of the period covered by a report.
+    fn get_report_period(&self, options: &BalanceOptions) -> (NaiveDate, NaiveDate) {
+        let dates: Vec<NaiveDate> = self
+            .transactions()
+            .iter()
+            .map(Transaction::get_date)
+            .collect();
+        let end = options
+            .get_end()
+            .or_else(|| dates.iter().max().cloned())
+            .unwrap_or_default();
+        let start = options
+            .get_start()
+            .or_else(|| dates.iter().min().cloned())
+            .unwrap_or(end);
+
+        (start, end)
+    }
+
+    /// Compute the mean of the daily rates between two currencies over a period, ignoring days
+    /// for which no rate is known.
+    fn get_average_rate(
+        &self,
+        from: Currency,
+        to: Currency,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<f64, Error> {
+        let prices = self.get_price_database();
+        let mut total = 0.0;
+        let mut days = 0;
+        let mut date = start;
+        while date <= end {
+            if let Ok(rate) = prices.get_rate(from.get_code(), to.get_code(), date) {
+                total += rate;
+                days += 1;
+            }
+            date += Duration::days(1);
+        }
+
+        if days == 0 {
+            return Err(Error::UnknownRate(
+                String::from(from.get_code()),
+                String::from(to.get_code()),
+                end,
+            ));
+        }
+
+        Ok(total / f64::from(days))
+    }
+}
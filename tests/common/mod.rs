@@ -0,0 +1,36 @@
+//! Fixtures shared by the integration tests.
+
+#![allow(dead_code)]
+
+use chrono::NaiveDate;
+use cratchit::{Account, AccountType, Currency, Error, Ledger, Split, Transaction};
+
+pub fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+pub fn account(id: &str, name: &str, account_type: AccountType) -> Account {
+    Account::new(id, name, name, account_type, Currency::USDollar, false)
+}
+
+/// An account that only groups its sub-accounts.
+pub fn placeholder(id: &str, name: &str, account_type: AccountType) -> Account {
+    Account::new(id, name, name, account_type, Currency::USDollar, true)
+}
+
+/// Post a transaction with the given splits, described by its id.
+pub fn post(ledger: &mut Ledger, id: &str, on: NaiveDate, splits: Vec<Split>) -> Result<(), Error> {
+    let mut transaction = Transaction::new(id, on, id);
+    for split in splits {
+        transaction.add_split(split);
+    }
+    ledger.add_transaction(transaction)
+}
+
+/// Post `amount` to an account, balanced against the checking account "01".
+pub fn post_to(ledger: &mut Ledger, id: &str, on: NaiveDate, account_id: &str, amount: i64) {
+    let mut transaction = Transaction::new(id, on, id);
+    transaction.add_split(Split::new(account_id, amount));
+    transaction.add_split(Split::new("01", -amount));
+    ledger.add_transaction(transaction).unwrap();
+}
@@ -0,0 +1,161 @@
+extern crate chrono;
+extern crate cratchit;
+
+mod common;
+
+use common::date;
+use cratchit::{
+    Account, AccountType, AccountsChart, BalanceOptions, Currency, EntrySource, Error,
+    FiscalCalendar, Frequency, Ledger, Recurrence, ScheduledTransaction, Split, Transaction,
+    TransactionStatus,
+};
+
+fn accrual(amount: i64) -> Transaction {
+    let mut transaction = Transaction::new("ACC-01", date(2018, 1, 31), "Accrue referee fees");
+    transaction.add_split(Split::new("05", amount));
+    transaction.add_split(Split::new("02", -amount));
+    transaction.set_auto_reverse(date(2018, 2, 1));
+
+    transaction
+}
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(Account::new(
+        "02",
+        "Accrued Liabilities",
+        "Accrued Liabilities",
+        AccountType::Liability,
+        Currency::USDollar,
+        false,
+    ));
+    chart.add_top_level_account(Account::new(
+        "05",
+        "Referee Fees",
+        "Referee Fees",
+        AccountType::Expense,
+        Currency::USDollar,
+        false,
+    ));
+
+    let mut ledger = Ledger::new(chart);
+    ledger
+        .add_fiscal_year(&FiscalCalendar::Monthly { start_month: 1 }, 2018)
+        .unwrap();
+    ledger.add_transaction(accrual(15000)).unwrap();
+
+    ledger
+}
+
+#[test]
+fn accrual_is_reversed_on_chosen_date() {
+    let ledger = get_ledger();
+    let reversal = ledger.get_auto_reversal("ACC-01").unwrap();
+
+    assert_eq!(ledger.get_num_transactions(), 2);
+    assert_eq!(reversal.get_id(), "ACC-01-rev");
+    assert_eq!(reversal.get_date(), date(2018, 2, 1));
+    assert_eq!(reversal.get_source(), EntrySource::AutoReversal);
+    assert_eq!(reversal.get_reverses(), Some(String::from("ACC-01")));
+    assert_eq!(reversal.get_description(), "Reversal: Accrue referee fees");
+
+    let january = BalanceOptions::new().as_of(date(2018, 1, 31));
+    assert_eq!(ledger.get_balance("02", &january).unwrap(), 15000);
+    assert_eq!(ledger.get_balance("02", &BalanceOptions::new()).unwrap(), 0);
+}
+
+#[test]
+fn editing_accrual_updates_reversal() {
+    let mut ledger = get_ledger();
+    ledger.update_transaction(accrual(18000)).unwrap();
+
+    let reversal = ledger.get_auto_reversal("ACC-01").unwrap();
+    assert_eq!(reversal.get_total_credits(), 18000);
+    assert_eq!(ledger.get_num_transactions(), 2);
+
+    let mut unflagged = accrual(18000);
+    unflagged.clear_auto_reverse();
+    ledger.update_transaction(unflagged).unwrap();
+    assert!(ledger.get_auto_reversal("ACC-01").is_none());
+    assert_eq!(ledger.get_num_transactions(), 1);
+}
+
+#[test]
+fn voiding_or_removing_accrual_removes_reversal() {
+    let mut ledger = get_ledger();
+    ledger.void_transaction("ACC-01", date(2018, 2, 5)).unwrap();
+
+    assert!(ledger.get_auto_reversal("ACC-01").is_none());
+    assert_eq!(
+        ledger
+            .get_transaction_by_id("ACC-01-void")
+            .unwrap()
+            .get_status(),
+        TransactionStatus::Voided
+    );
+    assert_eq!(ledger.get_balance("05", &BalanceOptions::new()).unwrap(), 0);
+
+    let mut ledger = get_ledger();
+    ledger.remove_transaction("ACC-01").unwrap();
+    assert_eq!(ledger.get_num_transactions(), 0);
+}
+
+#[test]
+fn reversal_in_closed_period_is_locked() {
+    let mut ledger = get_ledger();
+    ledger
+        .close_period("FY2018-P02", "February closed")
+        .unwrap();
+    let locked = Err(Error::ClosedPeriod(String::from("FY2018-P02")));
+
+    assert_eq!(ledger.update_transaction(accrual(18000)), locked);
+    assert_eq!(ledger.remove_transaction("ACC-01").map(|_| ()), locked);
+
+    let mut late = Transaction::new("ACC-02", date(2018, 1, 31), "Accrue rink rental");
+    late.add_split(Split::new("05", 500));
+    late.add_split(Split::new("02", -500));
+    late.set_auto_reverse(date(2018, 2, 28));
+    assert_eq!(ledger.add_transaction(late), locked);
+    assert_eq!(ledger.get_num_transactions(), 2);
+}
+
+#[test]
+fn reversal_must_follow_the_accrual() {
+    let mut ledger = get_ledger();
+    let mut early = accrual(15000);
+    early.set_auto_reverse(date(2018, 1, 31));
+    assert_eq!(
+        ledger.update_transaction(early),
+        Err(Error::InvalidReversalDate(String::from("ACC-01")))
+    );
+
+    let mut early = Transaction::new("ACC-02", date(2018, 2, 28), "Accrue referee fees");
+    early.add_split(Split::new("05", 5000));
+    early.add_split(Split::new("02", -5000));
+    early.set_auto_reverse(date(2018, 2, 1));
+    assert_eq!(
+        ledger.add_transaction(early),
+        Err(Error::InvalidReversalDate(String::from("ACC-02")))
+    );
+    assert_eq!(ledger.get_num_transactions(), 2);
+}
+
+#[test]
+fn scheduled_accruals_are_each_reversed_the_next_day() {
+    let mut ledger = get_ledger();
+    let recurrence = Recurrence::new(Frequency::EndOfMonth, date(2018, 2, 28));
+    ledger
+        .add_schedule(ScheduledTransaction::new("REF", accrual(5000), recurrence))
+        .unwrap();
+    ledger.materialize_schedules(date(2018, 4, 30)).unwrap();
+
+    for &(id, reversed) in &[
+        ("REF-2018-02-28", date(2018, 3, 1)),
+        ("REF-2018-03-31", date(2018, 4, 1)),
+        ("REF-2018-04-30", date(2018, 5, 1)),
+    ] {
+        let occurrence = ledger.get_transaction_by_id(id).unwrap();
+        assert_eq!(occurrence.get_auto_reverse(), Some(reversed));
+        assert_eq!(ledger.get_auto_reversal(id).unwrap().get_date(), reversed);
+    }
+}
@@ -0,0 +1,179 @@
+extern crate chrono;
+extern crate cratchit;
+
+mod common;
+
+use std::cell::Cell;
+
+use chrono::{Duration, NaiveDateTime};
+use common::{account, date, placeholder};
+use cratchit::{
+    AccountType, AccountsChart, AuditAction, AuditValue, BalanceOptions, Error, Ledger, Split,
+    Transaction, TransactionStatus,
+};
+
+thread_local! {
+    static MINUTES: Cell<u32> = const { Cell::new(0) };
+}
+
+/// A clock which starts at 9:00 on 2018-03-01 and advances by one minute each time it is read.
+fn tick() -> NaiveDateTime {
+    let minutes = MINUTES.with(|minutes| {
+        let current = minutes.get();
+        minutes.set(current + 1);
+        current
+    });
+    at(minutes)
+}
+
+fn at(minutes: u32) -> NaiveDateTime {
+    date(2018, 3, 1)
+        .and_hms_opt(9 + minutes / 60, minutes % 60, 0)
+        .unwrap()
+}
+
+fn dues(amount: i64) -> Transaction {
+    let mut transaction = Transaction::new("0001", date(2018, 3, 1), "Membership dues");
+    transaction.add_split(Split::new("01-01", amount));
+    transaction.add_split(Split::new("04", -amount));
+
+    transaction
+}
+
+/// Build a `Ledger` whose audit trail contains, in order:
+/// 1. Create 01, 2. Create 01-01, 3. Create 04, 4. Create 0001, 5. Update 0001, 6. Update 04.
+fn get_ledger() -> Ledger {
+    MINUTES.with(|minutes| minutes.set(0));
+
+    let mut ledger = Ledger::new(AccountsChart::new());
+    ledger.set_clock(tick);
+    ledger.set_actor("treasurer");
+    ledger
+        .add_top_level_account(placeholder("01", "Assets", AccountType::Asset))
+        .unwrap();
+    ledger
+        .add_sub_account("01", account("01-01", "Checking", AccountType::Asset))
+        .unwrap();
+    ledger
+        .add_top_level_account(account("04", "Dues", AccountType::Income))
+        .unwrap();
+    ledger.add_transaction(dues(5000)).unwrap();
+
+    ledger.set_actor("auditor");
+    ledger.update_transaction(dues(7500)).unwrap();
+    ledger
+        .update_account(account("04", "Membership Dues", AccountType::Income))
+        .unwrap();
+
+    ledger
+}
+
+#[test]
+fn mutations_are_recorded() {
+    let ledger = get_ledger();
+    let log = ledger.get_audit_log();
+
+    assert_eq!(log.len(), 6);
+    assert_eq!(log[0].get_sequence(), 1);
+    assert_eq!(log[1].get_action(), AuditAction::CreateAccount);
+    assert_eq!(log[1].get_parent_id(), Some(String::from("01")));
+    assert_eq!(log[3].get_timestamp(), at(3));
+    assert_eq!(log[3].get_actor(), "treasurer");
+    assert_eq!(log[4].get_actor(), "auditor");
+
+    let history = ledger.get_transaction_history("0001");
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[1].get_action(), AuditAction::UpdateTransaction);
+    assert_eq!(
+        history[1].get_before(),
+        Some(AuditValue::Transaction(dues(5000)))
+    );
+    assert_eq!(
+        history[1].get_after(),
+        Some(AuditValue::Transaction(dues(7500)))
+    );
+
+    let history = ledger.get_account_history("04");
+    assert_eq!(history.len(), 2);
+    match history[1].get_after() {
+        Some(AuditValue::Account(account)) => assert_eq!(account.get_name(), "Membership Dues"),
+        other => panic!("unexpected snapshot {:?}", other),
+    }
+}
+
+#[test]
+fn void_and_status_changes_are_recorded() {
+    let mut ledger = get_ledger();
+    ledger
+        .set_transaction_status("0001", TransactionStatus::Cleared)
+        .unwrap();
+    ledger.void_transaction("0001", date(2018, 3, 2)).unwrap();
+
+    let history = ledger.get_transaction_history("0001");
+    assert_eq!(history.len(), 4);
+    assert_eq!(history[3].get_action(), AuditAction::VoidTransaction);
+
+    let history = ledger.get_transaction_history("0001-void");
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].get_action(), AuditAction::CreateTransaction);
+}
+
+#[test]
+fn replaying_reconstructs_past_state() {
+    let mut ledger = get_ledger();
+    ledger.remove_transaction("0001").unwrap();
+
+    let before_edits = ledger.replay(at(3));
+    let transaction = before_edits.get_transaction_by_id("0001").unwrap();
+    assert_eq!(transaction.get_total_debits(), 5000);
+    assert_eq!(before_edits.get_chart().get_num_accounts(), 3);
+    assert_eq!(
+        before_edits
+            .get_chart()
+            .get_account_by_id("04")
+            .unwrap()
+            .get_name(),
+        "Dues"
+    );
+    assert_eq!(before_edits.get_audit_log().len(), 4);
+
+    let after_edits = ledger.replay_through(6);
+    assert_eq!(
+        after_edits
+            .get_balance("04", &BalanceOptions::new())
+            .unwrap(),
+        7500
+    );
+    assert_eq!(
+        after_edits
+            .get_chart()
+            .get_account_by_id("04")
+            .unwrap()
+            .get_name(),
+        "Membership Dues"
+    );
+
+    let now = ledger.replay(at(60));
+    assert_eq!(now.get_num_transactions(), 0);
+    let empty = ledger.replay(at(0) - Duration::minutes(1));
+    assert_eq!(empty.get_chart().get_num_accounts(), 0);
+}
+
+#[test]
+fn invalid_account_changes_are_rejected() {
+    let mut ledger = get_ledger();
+
+    assert_eq!(
+        ledger.add_top_level_account(account("01-01", "Savings", AccountType::Asset)),
+        Err(Error::DuplicateAccount(String::from("01-01")))
+    );
+    assert_eq!(
+        ledger.add_sub_account("09", account("09-01", "Savings", AccountType::Asset)),
+        Err(Error::UnknownAccount(String::from("09")))
+    );
+    assert_eq!(
+        ledger.update_account(placeholder("04", "Dues", AccountType::Income)),
+        Err(Error::PlaceholderAccount(String::from("04")))
+    );
+    assert_eq!(ledger.get_audit_log().len(), 6);
+}
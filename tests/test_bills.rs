@@ -0,0 +1,355 @@
+extern crate chrono;
+extern crate cratchit;
+extern crate json;
+
+mod common;
+
+use std::convert::TryFrom;
+
+use chrono::NaiveDate;
+use common::{account, date};
+use cratchit::{
+    AccountType, AccountsChart, BalanceOptions, Bill, BillLine, BillStatus, Contact, ContactKind,
+    EntrySource, Error, FiscalCalendar, Ledger, PaymentTerms,
+};
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(account("01", "Checking", AccountType::Asset));
+    chart.add_top_level_account(account("02", "Accounts Payable", AccountType::Liability));
+    chart.add_top_level_account(account("04", "Purchase Discounts", AccountType::Income));
+    chart.add_top_level_account(account("05", "Rink Rental", AccountType::Expense));
+    chart.add_top_level_account(account("06", "Equipment", AccountType::Asset));
+    chart.add_top_level_account(account("07", "Ice Time", AccountType::Income));
+
+    let mut ledger = Ledger::new(chart);
+    let mut arena = Contact::new("ARENA", "Hasse Arena", ContactKind::Vendor);
+    arena.set_control_account("02");
+    arena.set_payment_terms(PaymentTerms::net(30));
+    ledger.add_contact(arena).unwrap();
+    let mut acme = Contact::new("ACME", "Acme Hockey Supply", ContactKind::Vendor);
+    acme.set_control_account("02");
+    acme.set_payment_terms(PaymentTerms::net(30).discount(2.0, 10));
+    ledger.add_contact(acme).unwrap();
+    ledger
+        .add_contact(Contact::new("ZAM", "Zamboni Service", ContactKind::Vendor))
+        .unwrap();
+
+    ledger
+}
+
+/// A bill for 800.00 of ice rental and 200.00 of nets, due in 30 days.
+fn get_bill(id: &str) -> Bill {
+    let mut bill = Bill::new(id, "ARENA", date(2019, 1, 5));
+    bill.set_reference("HA-5521");
+    bill.add_line(BillLine::new("05", "January ice", 80000));
+    bill.add_line(BillLine::new("06", "Nets", 20000));
+    bill
+}
+
+/// A bill for supplies from a vendor offering 2% off if paid within 10 days.
+fn get_supply_bill(id: &str, date: NaiveDate, amount: i64) -> Bill {
+    let mut bill = Bill::new(id, "ACME", date);
+    bill.add_line(BillLine::new("05", "Supplies", amount));
+    bill
+}
+
+#[test]
+fn entering_a_bill_posts_expenses_and_payables() {
+    let mut ledger = get_ledger();
+    ledger.add_bill(get_bill("B-1")).unwrap();
+
+    let bill = ledger.get_bill("B-1").unwrap();
+    assert_eq!(bill.get_total(), 100000);
+    assert_eq!(bill.get_outstanding(), 100000);
+    assert_eq!(bill.get_due_date(), Some(date(2019, 2, 4)));
+    assert_eq!(bill.get_discount_date(), None);
+    assert_eq!(bill.get_status(date(2019, 2, 4)), BillStatus::Open);
+    assert_eq!(bill.get_status(date(2019, 2, 5)), BillStatus::Overdue);
+
+    let transaction = ledger.get_transaction_by_id("B-1").unwrap();
+    assert_eq!(transaction.get_source(), EntrySource::Bill);
+    assert_eq!(transaction.get_description(), "Bill B-1 (HA-5521)");
+
+    let options = BalanceOptions::new();
+    assert_eq!(ledger.get_balance("02", &options), Ok(100000));
+    assert_eq!(ledger.get_balance("05", &options), Ok(80000));
+    assert_eq!(ledger.get_balance("06", &options), Ok(20000));
+    assert_eq!(
+        ledger.get_contact_balances("02", &options).unwrap()["ARENA"],
+        100000
+    );
+
+    let mut early = get_supply_bill("B-2", date(2019, 1, 10), 50000);
+    early.set_due_date(date(2019, 1, 25));
+    ledger.add_bill(early).unwrap();
+    ledger
+        .add_bill(get_supply_bill("B-3", date(2019, 1, 28), 10000))
+        .unwrap();
+
+    let bill = ledger.get_bill("B-2").unwrap();
+    assert_eq!(bill.get_due_date(), Some(date(2019, 1, 25)));
+    assert_eq!(bill.get_discount_date(), Some(date(2019, 1, 20)));
+    assert_eq!(bill.get_available_discount(date(2019, 1, 20)), 1000);
+    assert_eq!(bill.get_available_discount(date(2019, 1, 21)), 0);
+
+    let ids = |bills: Vec<Bill>| bills.iter().map(Bill::get_id).collect::<Vec<String>>();
+    assert_eq!(
+        ids(ledger.get_bills_to_pay(date(2019, 1, 31))),
+        vec!["B-2", "B-3"]
+    );
+    assert_eq!(
+        ids(ledger.get_bills_to_pay(date(2019, 2, 4))),
+        vec!["B-2", "B-1", "B-3"]
+    );
+}
+
+#[test]
+fn partial_payments_are_tracked_against_the_outstanding_balance() {
+    let mut ledger = get_ledger();
+    ledger.add_bill(get_bill("B-1")).unwrap();
+    ledger
+        .record_bill_payment("B-1", "CHK-101", date(2019, 1, 20), 40000, "01")
+        .unwrap();
+
+    let bill = ledger.get_bill("B-1").unwrap();
+    assert_eq!(bill.get_paid(), 40000);
+    assert_eq!(bill.get_outstanding(), 60000);
+    assert_eq!(bill.get_status(date(2019, 2, 4)), BillStatus::PartiallyPaid);
+    assert_eq!(bill.get_status(date(2019, 2, 5)), BillStatus::Overdue);
+
+    assert_eq!(
+        ledger.record_bill_payment("B-1", "CHK-102", date(2019, 2, 1), 60001, "01"),
+        Err(Error::Overpayment(String::from("B-1")))
+    );
+    assert_eq!(
+        ledger.record_bill_payment("B-1", "CHK-102", date(2019, 2, 1), -5, "01"),
+        Err(Error::InvalidBillState(String::from("B-1")))
+    );
+    ledger
+        .record_bill_payment("B-1", "CHK-102", date(2019, 2, 1), 60000, "01")
+        .unwrap();
+
+    let bill = ledger.get_bill("B-1").unwrap();
+    assert_eq!(bill.get_status(date(2019, 3, 1)), BillStatus::Paid);
+    assert_eq!(bill.get_payments().len(), 2);
+    assert_eq!(
+        ledger.record_bill_payment("B-1", "CHK-103", date(2019, 2, 1), 100, "01"),
+        Err(Error::InvalidBillState(String::from("B-1")))
+    );
+
+    let options = BalanceOptions::new();
+    assert_eq!(ledger.get_balance("01", &options), Ok(-100000));
+    assert_eq!(ledger.get_balance("02", &options), Ok(0));
+
+    ledger.undo().unwrap();
+    let bill = ledger.get_bill("B-1").unwrap();
+    assert_eq!(bill.get_outstanding(), 60000);
+}
+
+#[test]
+fn batch_payments_take_early_payment_discounts() {
+    let mut ledger = get_ledger();
+    ledger.add_bill(get_bill("B-1")).unwrap();
+    ledger
+        .add_bill(get_supply_bill("B-2", date(2019, 1, 2), 30000))
+        .unwrap();
+    ledger
+        .add_bill(get_supply_bill("B-3", date(2019, 1, 10), 50000))
+        .unwrap();
+    ledger
+        .add_bill(get_supply_bill("B-4", date(2019, 1, 12), 20000))
+        .unwrap();
+
+    // No discount is taken until an account for them is set.
+    ledger
+        .record_bill_payment("B-4", "CHK-100", date(2019, 1, 14), 20000, "01")
+        .unwrap();
+    assert_eq!(ledger.get_bill("B-4").unwrap().get_discount_taken(), 0);
+
+    ledger.set_purchase_discount_account("04").unwrap();
+    let paid = ledger
+        .pay_bills(&["B-1", "B-2", "B-3"], "CHK-101", date(2019, 1, 15), "01")
+        .unwrap();
+    assert_eq!(paid, 100000 + 30000 + 49000);
+
+    let bill = ledger.get_bill("B-3").unwrap();
+    assert_eq!(bill.get_paid(), 49000);
+    assert_eq!(bill.get_discount_taken(), 1000);
+    assert_eq!(bill.get_status(date(2019, 1, 15)), BillStatus::Paid);
+    assert_eq!(ledger.get_bill("B-2").unwrap().get_discount_taken(), 0);
+
+    let transaction = ledger.get_transaction_by_id("CHK-101").unwrap();
+    assert_eq!(transaction.get_source(), EntrySource::Bill);
+    assert_eq!(transaction.get_splits().len(), 5);
+
+    let options = BalanceOptions::new();
+    assert_eq!(ledger.get_balance("01", &options), Ok(-199000));
+    assert_eq!(ledger.get_balance("02", &options), Ok(0));
+    assert_eq!(ledger.get_balance("04", &options), Ok(1000));
+    assert_eq!(
+        ledger.get_contact_balances("02", &options).unwrap()["ACME"],
+        0
+    );
+
+    assert_eq!(
+        ledger.pay_bills(&["B-2"], "CHK-102", date(2019, 1, 16), "01"),
+        Err(Error::InvalidBillState(String::from("B-2")))
+    );
+    assert_eq!(
+        ledger.pay_bills(&["B-9"], "CHK-102", date(2019, 1, 16), "01"),
+        Err(Error::UnknownBill(String::from("B-9")))
+    );
+
+    // A partial payment forfeits the discount.
+    ledger
+        .add_bill(get_supply_bill("B-5", date(2019, 1, 20), 10000))
+        .unwrap();
+    ledger
+        .record_bill_payment("B-5", "CHK-103", date(2019, 1, 21), 5000, "01")
+        .unwrap();
+    ledger
+        .pay_bills(&["B-5"], "CHK-104", date(2019, 1, 22), "01")
+        .unwrap();
+    let bill = ledger.get_bill("B-5").unwrap();
+    assert_eq!(bill.get_paid(), 10000);
+    assert_eq!(bill.get_discount_taken(), 0);
+}
+
+#[test]
+fn bills_are_validated_voided_and_saved() {
+    let mut ledger = get_ledger();
+    assert_eq!(
+        ledger.add_bill(Bill::new("B-1", "EHS", date(2019, 1, 5))),
+        Err(Error::UnknownContact(String::from("EHS")))
+    );
+    assert_eq!(
+        ledger.add_bill(Bill::new("B-1", "ZAM", date(2019, 1, 5))),
+        Err(Error::NoControlAccount(String::from("ZAM")))
+    );
+    let mut rink = Contact::new("RINK", "Rink Rentals", ContactKind::Customer);
+    rink.set_control_account("01");
+    ledger.add_contact(rink).unwrap();
+    let mut refund = Bill::new("B-1", "RINK", date(2019, 1, 5));
+    refund.add_line(BillLine::new("05", "Ice refund", 10000));
+    assert_eq!(
+        ledger.add_bill(refund),
+        Err(Error::WrongAccountType(String::from("01")))
+    );
+
+    let mut bill = Bill::new("B-1", "ARENA", date(2019, 1, 5));
+    bill.add_line(BillLine::new("07", "Ice time", 10000));
+    assert_eq!(
+        ledger.add_bill(bill),
+        Err(Error::WrongAccountType(String::from("07")))
+    );
+    assert_eq!(
+        ledger.set_purchase_discount_account("06"),
+        Err(Error::WrongAccountType(String::from("06")))
+    );
+
+    ledger.add_bill(get_bill("B-1")).unwrap();
+    assert_eq!(
+        ledger.add_bill(get_bill("B-1")),
+        Err(Error::DuplicateBill(String::from("B-1")))
+    );
+    ledger.add_bill(get_bill("B-2")).unwrap();
+    ledger
+        .record_bill_payment("B-2", "CHK-101", date(2019, 1, 20), 40000, "01")
+        .unwrap();
+    assert_eq!(
+        ledger.void_bill("B-2", date(2019, 1, 31)),
+        Err(Error::InvalidBillState(String::from("B-2")))
+    );
+
+    ledger.void_bill("B-1", date(2019, 1, 31)).unwrap();
+    let bill = ledger.get_bill("B-1").unwrap();
+    assert_eq!(bill.get_status(date(2019, 1, 31)), BillStatus::Void);
+    assert_eq!(bill.get_outstanding(), 0);
+    assert_eq!(
+        ledger.void_bill("B-1", date(2019, 1, 31)),
+        Err(Error::InvalidBillState(String::from("B-1")))
+    );
+    assert_eq!(
+        ledger.record_bill_payment("B-1", "CHK-102", date(2019, 2, 1), 100, "01"),
+        Err(Error::InvalidBillState(String::from("B-1")))
+    );
+    assert_eq!(
+        ledger.get_balance("02", &BalanceOptions::new()),
+        Ok(100000 - 40000)
+    );
+
+    let saved = json::parse(&ledger.to_json().dump()).unwrap();
+    assert_eq!(saved["bills"][1]["payments"][0]["amount"], 40000);
+
+    let restored = Ledger::try_from(&saved).unwrap();
+    assert_eq!(restored.get_bills(), ledger.get_bills());
+    assert_eq!(
+        restored
+            .get_bill("B-1")
+            .unwrap()
+            .get_status(date(2019, 2, 1)),
+        BillStatus::Void
+    );
+}
+
+#[test]
+fn voids_follow_the_ledger_and_terms_are_fixed_when_entered() {
+    let mut ledger = get_ledger();
+    ledger
+        .add_fiscal_year(&FiscalCalendar::Monthly { start_month: 1 }, 2019)
+        .unwrap();
+    ledger.add_bill(get_bill("B-1")).unwrap();
+    ledger.close_period("FY2019-P01", "January closed").unwrap();
+    assert_eq!(
+        ledger.void_bill("B-1", date(2019, 1, 31)),
+        Err(Error::ClosedPeriod(String::from("FY2019-P01")))
+    );
+    assert_eq!(
+        ledger.get_bill("B-1").unwrap().get_status(date(2019, 2, 1)),
+        BillStatus::Open
+    );
+
+    // Undoing the void would change the bill's transaction in the closed period.
+    ledger.void_bill("B-1", date(2019, 2, 1)).unwrap();
+    assert_eq!(
+        ledger.undo(),
+        Err(Error::ClosedPeriod(String::from("FY2019-P01")))
+    );
+    assert_eq!(
+        ledger.get_bill("B-1").unwrap().get_status(date(2019, 2, 1)),
+        BillStatus::Void
+    );
+    ledger.reopen_period("FY2019-P01", "Restore B-1").unwrap();
+    ledger.undo().unwrap();
+    let bill = ledger.get_bill("B-1").unwrap();
+    assert_eq!(bill.get_status(date(2019, 2, 1)), BillStatus::Open);
+    assert_eq!(bill.get_outstanding(), 100000);
+    assert_eq!(ledger.get_balance("02", &BalanceOptions::new()), Ok(100000));
+    ledger.redo().unwrap();
+    assert_eq!(
+        ledger.get_bill("B-1").unwrap().get_status(date(2019, 2, 1)),
+        BillStatus::Void
+    );
+
+    ledger.set_purchase_discount_account("04").unwrap();
+    ledger
+        .add_bill(get_supply_bill("B-2", date(2019, 2, 2), 50000))
+        .unwrap();
+    assert_eq!(
+        ledger.pay_bills(&["B-2", "B-2"], "CHK-101", date(2019, 2, 5), "01"),
+        Err(Error::InvalidBillState(String::from("B-2")))
+    );
+    assert_eq!(ledger.get_transaction_by_id("CHK-101"), None);
+
+    let mut acme = ledger.get_contact("ACME").unwrap();
+    acme.set_payment_terms(PaymentTerms::net(60));
+    ledger.update_contact(acme).unwrap();
+    let bill = ledger.get_bill("B-2").unwrap();
+    assert_eq!(bill.get_due_date(), Some(date(2019, 3, 4)));
+    assert_eq!(bill.get_available_discount(date(2019, 2, 12)), 1000);
+    assert_eq!(
+        ledger.pay_bills(&["B-2"], "CHK-101", date(2019, 2, 5), "01"),
+        Ok(49000)
+    );
+}
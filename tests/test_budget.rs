@@ -0,0 +1,159 @@
+extern crate chrono;
+extern crate cratchit;
+extern crate json;
+
+mod common;
+
+use common::{account, date, placeholder, post_to};
+use cratchit::{AccountType, AccountsChart, Budget, BudgetPeriod, Error, FiscalCalendar, Ledger};
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(account("01", "Checking", AccountType::Asset));
+    chart.add_top_level_account(account("04", "Donations", AccountType::Income));
+    let mut programs = placeholder("05", "Programs", AccountType::Expense);
+    programs.add_sub_account(account("05-01", "Supplies", AccountType::Expense));
+    programs.add_sub_account(account("05-02", "Venue", AccountType::Expense));
+    chart.add_top_level_account(programs);
+
+    let mut ledger = Ledger::new(chart);
+    post_to(&mut ledger, "0001", date(2019, 1, 5), "04", -200000);
+    post_to(&mut ledger, "0002", date(2019, 1, 10), "05-01", 30000);
+    post_to(&mut ledger, "0003", date(2019, 1, 20), "05-02", 80000);
+    post_to(&mut ledger, "0004", date(2019, 2, 10), "05-01", 45000);
+    post_to(&mut ledger, "0005", date(2019, 4, 2), "05-02", 70000);
+
+    ledger
+}
+
+fn get_budget() -> Budget {
+    let mut budget = Budget::new("2019", "Operating budget", BudgetPeriod::Monthly);
+    for month in 1..13 {
+        budget.set_amount("04", date(2019, month, 1), 150000);
+        budget.set_amount("05-01", date(2019, month, 1), 40000);
+        budget.set_amount("05-02", date(2019, month, 1), 60000);
+    }
+
+    budget
+}
+
+fn summarize(report: &[cratchit::BudgetVariance]) -> Vec<(String, i64, i64, i64)> {
+    report
+        .iter()
+        .map(|line| {
+            (
+                line.get_account_id(),
+                line.get_budget(),
+                line.get_actual(),
+                line.get_difference(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn variances_roll_up_through_placeholder_accounts() {
+    let ledger = get_ledger();
+    let report = ledger
+        .get_budget_variance(&get_budget(), date(2019, 1, 1), date(2019, 1, 31))
+        .unwrap();
+
+    assert_eq!(
+        summarize(&report),
+        vec![
+            (String::from("04"), 150000, 200000, 50000),
+            (String::from("05"), 100000, 110000, 10000),
+            (String::from("05-01"), 40000, 30000, -10000),
+            (String::from("05-02"), 60000, 80000, 20000),
+        ]
+    );
+    assert_eq!(report[1].get_percent(), Some(10.0));
+    assert_eq!(report[2].get_percent(), Some(-25.0));
+}
+
+#[test]
+fn partial_periods_are_pro_rated() {
+    let ledger = get_ledger();
+    let budget = get_budget();
+    assert_eq!(
+        budget.get_amount_between("05-01", date(2019, 1, 1), date(2019, 3, 31)),
+        120000
+    );
+    assert_eq!(
+        budget.get_amount_between("05-01", date(2019, 2, 15), date(2019, 2, 28)),
+        20000
+    );
+
+    let report = ledger
+        .get_budget_variance(&budget, date(2019, 1, 16), date(2019, 2, 28))
+        .unwrap();
+    assert_eq!(
+        summarize(&report)[3],
+        (String::from("05-02"), 30968 + 60000, 80000, -10968)
+    );
+    assert_eq!(report[0].get_actual(), 0);
+    assert_eq!(report[0].get_percent(), Some(-100.0));
+}
+
+#[test]
+fn quarters_follow_the_fiscal_year() {
+    let mut budget =
+        Budget::new("FY2019", "Fiscal budget", BudgetPeriod::Quarterly).fiscal_year_start(2);
+    budget.set_amount("05-02", date(2019, 4, 30), 150000);
+    budget.set_amount("05-02", date(2019, 1, 15), 90000);
+
+    assert_eq!(budget.get_amount("05-02", date(2019, 2, 1)), 150000);
+    assert_eq!(budget.get_amount("05-02", date(2019, 1, 31)), 90000);
+    assert_eq!(budget.get_amount("05-02", date(2018, 11, 1)), 90000);
+    assert_eq!(budget.get_amount("05-02", date(2019, 5, 1)), 0);
+
+    let report = get_ledger()
+        .get_budget_variance(&budget, date(2019, 2, 1), date(2019, 4, 30))
+        .unwrap();
+    assert_eq!(
+        summarize(&report),
+        vec![
+            (String::from("05"), 150000, 115000, -35000),
+            (String::from("05-02"), 150000, 70000, -80000),
+        ]
+    );
+
+    budget.set_amount("05-02", date(2019, 3, 1), 0);
+    assert_eq!(budget.get_amount("05-02", date(2019, 2, 1)), 0);
+}
+
+#[test]
+fn budgets_are_saved_and_checked_against_the_chart() {
+    let mut budget = get_budget().fiscal_year_start(7);
+    let value = json::parse(&budget.to_json().dump()).unwrap();
+    assert_eq!(Budget::from(&value), budget);
+    assert_eq!(value["period"], "Monthly");
+
+    budget.set_amount("99", date(2019, 1, 1), 1000);
+    assert_eq!(
+        get_ledger().get_budget_variance(&budget, date(2019, 1, 1), date(2019, 12, 31)),
+        Err(Error::UnknownAccount(String::from("99")))
+    );
+}
+
+#[test]
+fn closing_entries_are_not_counted_as_activity() {
+    let mut ledger = get_ledger();
+    let before = ledger
+        .get_budget_variance(&get_budget(), date(2019, 1, 1), date(2019, 12, 31))
+        .unwrap();
+
+    ledger
+        .add_top_level_account(account("03", "Net Assets", AccountType::Equity))
+        .unwrap();
+    ledger
+        .add_fiscal_year(&FiscalCalendar::Monthly { start_month: 1 }, 2019)
+        .unwrap();
+    ledger.post_closing_entry(2019, "03").unwrap();
+
+    let after = ledger
+        .get_budget_variance(&get_budget(), date(2019, 1, 1), date(2019, 12, 31))
+        .unwrap();
+    assert_eq!(summarize(&after), summarize(&before));
+    assert_eq!(after[0].get_actual(), 200000);
+}
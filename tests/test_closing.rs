@@ -0,0 +1,183 @@
+extern crate chrono;
+extern crate cratchit;
+
+mod common;
+
+use common::date;
+use cratchit::{
+    Account, AccountType, AccountsChart, BalanceOptions, Currency, EntrySource, Error,
+    FiscalCalendar, Ledger, Split, Transaction,
+};
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    let accounts = [
+        ("01", "Checking", AccountType::Asset, false),
+        ("03", "Equity", AccountType::Equity, true),
+        ("03-01", "Retained Earnings", AccountType::Equity, false),
+        ("04", "Ice Time Income", AccountType::Income, false),
+        ("04-02", "Tournament Fees", AccountType::Income, false),
+        ("05", "Rink Rental", AccountType::Expense, false),
+    ];
+    for &(id, name, account_type, placeholder) in &accounts {
+        chart.add_top_level_account(Account::new(
+            id,
+            name,
+            name,
+            account_type,
+            Currency::USDollar,
+            placeholder,
+        ));
+    }
+
+    let mut ledger = Ledger::new(chart);
+    ledger
+        .add_fiscal_year(&FiscalCalendar::Monthly { start_month: 7 }, 2019)
+        .unwrap();
+
+    let entries = [
+        ("t1", date(2018, 6, 30), "04", -1000),
+        ("t2", date(2018, 8, 1), "04", -50000),
+        ("t3", date(2018, 9, 1), "04-02", -7500),
+        ("t4", date(2019, 2, 1), "05", 30000),
+        ("t5", date(2019, 7, 1), "04", -2000),
+    ];
+    for &(id, date, account, amount) in &entries {
+        let mut transaction = Transaction::new(id, date, id);
+        transaction.add_split(Split::new(account, amount));
+        transaction.add_split(Split::new("01", -amount));
+        ledger.add_transaction(transaction).unwrap();
+    }
+
+    ledger
+}
+
+#[test]
+fn preview_closing_entry() {
+    let ledger = get_ledger();
+    let closing = ledger.preview_closing_entry(2019, "03-01").unwrap();
+    let splits = closing.get_splits();
+
+    assert_eq!(closing.get_date(), date(2019, 6, 30));
+    assert_eq!(closing.get_source(), EntrySource::YearEndClose);
+    assert!(closing.is_system_generated());
+    assert!(closing.is_balanced());
+    assert_eq!(splits.len(), 4);
+    assert_eq!(splits[0].get_account_id(), "04");
+    assert_eq!(splits[0].get_amount(), 50000);
+    assert_eq!(splits[1].get_amount(), 7500);
+    assert_eq!(splits[2].get_account_id(), "05");
+    assert_eq!(splits[2].get_amount(), -30000);
+    assert_eq!(splits[3].get_account_id(), "03-01");
+    assert_eq!(splits[3].get_amount(), -27500);
+
+    assert_eq!(ledger.get_num_transactions(), 5);
+}
+
+#[test]
+fn posting_closing_entry_zeroes_income_and_expenses() {
+    let mut ledger = get_ledger();
+    ledger.post_closing_entry(2019, "03-01").unwrap();
+
+    let year_end = BalanceOptions::new().as_of(date(2019, 6, 30));
+    assert_eq!(ledger.get_balance("04", &year_end).unwrap(), 1000);
+    assert_eq!(ledger.get_balance("04-02", &year_end).unwrap(), 0);
+    assert_eq!(ledger.get_balance("05", &year_end).unwrap(), 0);
+    assert_eq!(ledger.get_balance("03-01", &year_end).unwrap(), 27500);
+
+    let result = ledger.post_closing_entry(2019, "03-01");
+    assert_eq!(
+        result,
+        Err(Error::DuplicateTransaction(String::from("CLOSE-FY2019")))
+    );
+}
+
+#[test]
+fn closing_into_invalid_accounts() {
+    let ledger = get_ledger();
+
+    assert_eq!(
+        ledger.preview_closing_entry(2019, "03"),
+        Err(Error::PlaceholderAccount(String::from("03")))
+    );
+    assert_eq!(
+        ledger.preview_closing_entry(2019, "01"),
+        Err(Error::WrongAccountType(String::from("01")))
+    );
+    assert_eq!(
+        ledger.preview_closing_entry(2017, "03-01"),
+        Err(Error::UnknownFiscalYear(2017))
+    );
+}
+
+#[test]
+fn undoing_closing_entry_requires_reopened_year() {
+    let mut ledger = get_ledger();
+    let id = ledger.post_closing_entry(2019, "03-01").unwrap();
+    ledger.close_period("FY2019-P12", "Year filed").unwrap();
+
+    assert_eq!(
+        ledger.undo_closing_entry(2019).map(|_| ()),
+        Err(Error::ClosedPeriod(String::from("FY2019-P12")))
+    );
+
+    ledger
+        .reopen_period("FY2019-P12", "Late invoice found")
+        .unwrap();
+    let closing = ledger.undo_closing_entry(2019).unwrap();
+    assert_eq!(closing.get_id(), id);
+    assert!(ledger.get_transaction_by_id(&id).is_none());
+    assert_eq!(
+        ledger.get_balance("05", &BalanceOptions::new()).unwrap(),
+        30000
+    );
+}
+
+#[test]
+fn foreign_accounts_are_closed_at_their_carrying_value() {
+    let mut ledger = get_ledger();
+    ledger
+        .add_top_level_account(Account::new(
+            "04-03",
+            "European Camps",
+            "European Camps",
+            AccountType::Income,
+            Currency::Euro,
+            false,
+        ))
+        .unwrap();
+    let mut camp = Transaction::new("t6", date(2019, 3, 1), "Berlin camp");
+    camp.add_split(Split::new("01", 12500));
+    camp.add_split(Split::new_foreign("04-03", -10000, 1.25));
+    ledger.add_transaction(camp).unwrap();
+
+    let closing = ledger.preview_closing_entry(2019, "03-01").unwrap();
+    let splits = closing.get_splits();
+    assert_eq!(closing.get_currency(), Currency::USDollar);
+    assert_eq!(splits[2].get_account_id(), "04-03");
+    assert_eq!(splits[2].get_amount(), 10000);
+    assert_eq!(splits[2].get_value(), 12500);
+    assert_eq!(splits[4].get_amount(), -40000);
+
+    ledger.post_closing_entry(2019, "03-01").unwrap();
+    let year_end = BalanceOptions::new().as_of(date(2019, 6, 30));
+    assert_eq!(ledger.get_balance("04-03", &year_end).unwrap(), 0);
+    assert_eq!(ledger.get_carrying_value("04-03", &year_end).unwrap(), 0);
+    assert_eq!(ledger.get_balance("03-01", &year_end).unwrap(), 40000);
+}
+
+#[test]
+fn years_without_income_or_expenses_are_not_closed() {
+    let mut ledger = get_ledger();
+    ledger
+        .add_fiscal_year(&FiscalCalendar::Monthly { start_month: 7 }, 2021)
+        .unwrap();
+
+    let closing = ledger.preview_closing_entry(2021, "03-01").unwrap();
+    assert!(closing.get_splits().is_empty());
+    assert_eq!(
+        ledger.post_closing_entry(2021, "03-01"),
+        Err(Error::NothingToClose(2021))
+    );
+    assert!(ledger.get_transaction_by_id("CLOSE-FY2021").is_none());
+}
@@ -0,0 +1,230 @@
+extern crate chrono;
+extern crate cratchit;
+extern crate json;
+
+mod common;
+
+use std::convert::TryFrom;
+
+use common::{account, date, placeholder, post};
+use cratchit::{
+    AccountType, AccountsChart, BalanceOptions, Contact, ContactKind, Error, Ledger, PaymentTerms,
+    Split,
+};
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    let mut assets = placeholder("01", "Assets", AccountType::Asset);
+    assets.add_sub_account(account("01-01", "Checking", AccountType::Asset));
+    assets.add_sub_account(account("01-02", "Accounts Receivable", AccountType::Asset));
+    chart.add_top_level_account(assets);
+    chart.add_top_level_account(account("02", "Accounts Payable", AccountType::Liability));
+    chart.add_top_level_account(account("04", "Ice Time", AccountType::Income));
+    chart.add_top_level_account(account("05", "Rink Rental", AccountType::Expense));
+
+    let mut ledger = Ledger::new(chart);
+    let mut north = Contact::new("LNHS", "Lakeville North High School", ContactKind::Customer);
+    north.set_control_account("01-02");
+    north.set_default_account("04");
+    ledger.add_contact(north).unwrap();
+    ledger
+        .add_contact(Contact::new(
+            "LSHS",
+            "Lakeville South High School",
+            ContactKind::Customer,
+        ))
+        .unwrap();
+    let mut arena = Contact::new("ARENA", "Hasse Arena", ContactKind::Vendor);
+    arena.set_payment_terms(PaymentTerms::net(30).discount(1.5, 10));
+    ledger.add_contact(arena).unwrap();
+
+    ledger
+}
+
+fn tagged(account_id: &str, amount: i64, contact_id: &str) -> Split {
+    let mut split = Split::new(account_id, amount);
+    split.set_contact(contact_id);
+    split
+}
+
+#[test]
+fn contacts_hold_party_details() {
+    let mut ledger = get_ledger();
+    assert_eq!(
+        ledger
+            .get_contacts()
+            .iter()
+            .map(Contact::get_id)
+            .collect::<Vec<String>>(),
+        vec!["LNHS", "LSHS", "ARENA"]
+    );
+
+    let north = ledger.get_contact("LNHS").unwrap();
+    assert_eq!(north.get_control_account(), Some(String::from("01-02")));
+    assert_eq!(north.get_default_account(), Some(String::from("04")));
+    assert_eq!(north.get_payment_terms(), PaymentTerms::default());
+    assert_eq!(
+        north.get_payment_terms().get_due_date(date(2019, 1, 5)),
+        date(2019, 1, 5)
+    );
+
+    let arena = ledger.get_contact("ARENA").unwrap();
+    assert_eq!(arena.get_kind(), ContactKind::Vendor);
+    assert_eq!(
+        arena.get_payment_terms().get_due_date(date(2019, 1, 5)),
+        date(2019, 2, 4)
+    );
+    assert_eq!(
+        arena
+            .get_payment_terms()
+            .get_discount_date(date(2019, 1, 5)),
+        Some(date(2019, 1, 15))
+    );
+    assert_eq!(arena.get_payment_terms().get_discount(80000), 1200);
+
+    let mut updated = Contact::clone(&north);
+    updated.set_address("19600 Ipava Ave\nLakeville, MN 55044");
+    updated.set_tax_id("41-6001234");
+    ledger.update_contact(updated).unwrap();
+    assert_eq!(
+        ledger.get_contact("LNHS").unwrap().get_tax_id(),
+        "41-6001234"
+    );
+
+    let mut missing = Contact::new("EHS", "Eagan High School", ContactKind::Customer);
+    assert_eq!(
+        ledger.update_contact(Contact::clone(&missing)),
+        Err(Error::UnknownContact(String::from("EHS")))
+    );
+    missing.set_control_account("99");
+    assert_eq!(
+        ledger.add_contact(missing),
+        Err(Error::UnknownAccount(String::from("99")))
+    );
+    assert_eq!(
+        ledger.add_contact(Contact::new("LNHS", "Duplicate", ContactKind::Customer)),
+        Err(Error::DuplicateContact(String::from("LNHS")))
+    );
+
+    let mut arena = ledger.get_contact("ARENA").unwrap();
+    arena.set_control_account("01-02");
+    assert_eq!(
+        ledger.update_contact(Contact::clone(&arena)),
+        Err(Error::WrongAccountType(String::from("01-02")))
+    );
+    arena.set_control_account("02");
+    ledger.update_contact(arena).unwrap();
+}
+
+#[test]
+fn receivables_and_payables_are_reported_per_party() {
+    let mut ledger = get_ledger();
+    post(
+        &mut ledger,
+        "0001",
+        date(2019, 1, 5),
+        vec![tagged("01-02", 25000, "LNHS"), Split::new("04", -25000)],
+    )
+    .unwrap();
+    post(
+        &mut ledger,
+        "0002",
+        date(2019, 1, 12),
+        vec![tagged("01-02", 40000, "LSHS"), Split::new("04", -40000)],
+    )
+    .unwrap();
+    post(
+        &mut ledger,
+        "0003",
+        date(2019, 1, 20),
+        vec![Split::new("01-01", 10000), tagged("01-02", -10000, "LNHS")],
+    )
+    .unwrap();
+    post(
+        &mut ledger,
+        "0004",
+        date(2019, 1, 25),
+        vec![Split::new("05", 80000), tagged("02", -80000, "ARENA")],
+    )
+    .unwrap();
+    post(
+        &mut ledger,
+        "0005",
+        date(2019, 1, 26),
+        vec![Split::new("01-02", 5000), Split::new("04", -5000)],
+    )
+    .unwrap();
+
+    let options = BalanceOptions::new();
+    let receivables = ledger.get_contact_balances("01-02", &options).unwrap();
+    assert_eq!(receivables.len(), 2);
+    assert_eq!(receivables["LNHS"], 15000);
+    assert_eq!(receivables["LSHS"], 40000);
+    assert_eq!(ledger.get_balance("01-02", &options), Ok(60000));
+
+    let payables = ledger.get_contact_balances("02", &options).unwrap();
+    assert_eq!(payables["ARENA"], 80000);
+
+    let assets = ledger
+        .get_contact_balances("01", &options.clone().include_sub_accounts(true))
+        .unwrap();
+    assert_eq!(assets["LNHS"], 15000);
+    assert!(ledger
+        .get_contact_balances("01", &options)
+        .unwrap()
+        .is_empty());
+
+    let early = BalanceOptions::new().as_of(date(2019, 1, 15));
+    assert_eq!(
+        ledger.get_contact_balances("01-02", &early).unwrap()["LNHS"],
+        25000
+    );
+
+    ledger.void_transaction("0002", date(2019, 1, 31)).unwrap();
+    let receivables = ledger.get_contact_balances("01-02", &options).unwrap();
+    assert_eq!(receivables["LSHS"], 0);
+}
+
+#[test]
+fn splits_must_name_known_contacts() {
+    let mut ledger = get_ledger();
+    assert_eq!(
+        post(
+            &mut ledger,
+            "0001",
+            date(2019, 1, 5),
+            vec![tagged("01-02", 25000, "EHS"), Split::new("04", -25000)],
+        ),
+        Err(Error::UnknownContact(String::from("EHS")))
+    );
+    assert_eq!(ledger.get_num_transactions(), 0);
+    assert_eq!(
+        ledger.get_contact_balances("99", &BalanceOptions::new()),
+        Err(Error::UnknownAccount(String::from("99")))
+    );
+}
+
+#[test]
+fn contacts_are_saved_with_the_ledger() {
+    let mut ledger = get_ledger();
+    post(
+        &mut ledger,
+        "0001",
+        date(2019, 1, 5),
+        vec![tagged("01-02", 25000, "LNHS"), Split::new("04", -25000)],
+    )
+    .unwrap();
+
+    let saved = json::parse(&ledger.to_json().dump()).unwrap();
+    assert_eq!(saved["contacts"][2]["kind"], "Vendor");
+    assert_eq!(saved["transactions"][0]["splits"][0]["contact"], "LNHS");
+    assert!(saved["transactions"][0]["splits"][1]["contact"].is_null());
+
+    let loaded = Ledger::try_from(&saved).unwrap();
+    assert_eq!(loaded.get_contacts(), ledger.get_contacts());
+    assert_eq!(
+        loaded.get_contact_balances("01-02", &BalanceOptions::new()),
+        ledger.get_contact_balances("01-02", &BalanceOptions::new())
+    );
+    assert!(loaded.verify_journal().is_ok());
+}
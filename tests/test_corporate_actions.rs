@@ -0,0 +1,258 @@
+extern crate chrono;
+extern crate cratchit;
+extern crate json;
+
+mod common;
+
+use std::convert::TryFrom;
+
+use chrono::NaiveDate;
+use common::date;
+use cratchit::{
+    Account, AccountType, AccountsChart, BalanceOptions, Commodity, CommodityKind, CorporateAction,
+    Currency, EntrySource, Error, Ledger, Split, Transaction,
+};
+
+fn holding(id: &str, symbol: &str) -> Account {
+    let mut account = Account::new(
+        id,
+        symbol,
+        symbol,
+        AccountType::Asset,
+        Currency::USDollar,
+        false,
+    );
+    account.set_commodity(symbol);
+    account
+}
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(Account::new(
+        "01",
+        "Cash",
+        "Cash",
+        AccountType::Asset,
+        Currency::USDollar,
+        false,
+    ));
+    chart.add_top_level_account(holding("02", "OLD"));
+    chart.add_top_level_account(holding("03", "NEW"));
+    chart.add_top_level_account(Account::new(
+        "04",
+        "Capital Gains",
+        "Capital Gains",
+        AccountType::Income,
+        Currency::USDollar,
+        false,
+    ));
+
+    let mut ledger = Ledger::new(chart);
+    for symbol in &["OLD", "NEW"] {
+        ledger
+            .add_commodity(Commodity::new(symbol, symbol, CommodityKind::Stock, 1))
+            .unwrap();
+    }
+    ledger.set_capital_gains_account("04").unwrap();
+
+    trade(&mut ledger, "BUY-1", date(2018, 1, 2), 10, 100000);
+    trade(&mut ledger, "BUY-2", date(2018, 3, 1), 30, 360000);
+
+    ledger
+}
+
+fn trade(ledger: &mut Ledger, id: &str, on: NaiveDate, units: i64, value: i64) {
+    let mut transaction = Transaction::new(id, on, id);
+    transaction.add_split(Split::new_commodity("02", units, value));
+    transaction.add_split(Split::new("01", -value));
+    ledger.add_transaction(transaction).unwrap();
+}
+
+fn summarize(ledger: &Ledger, account_id: &str) -> Vec<(String, NaiveDate, i64, i64)> {
+    ledger
+        .get_lots(account_id, &BalanceOptions::new())
+        .unwrap()
+        .iter()
+        .map(|lot| {
+            (
+                lot.get_id(),
+                lot.get_date(),
+                lot.get_units(),
+                lot.get_cost(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn splits_scale_the_units_of_every_open_lot() {
+    let mut ledger = get_ledger();
+    ledger
+        .post_corporate_action(CorporateAction::new_split(
+            "SPLIT-1",
+            date(2018, 6, 1),
+            "02",
+            4,
+            1,
+        ))
+        .unwrap();
+
+    assert_eq!(
+        summarize(&ledger, "02"),
+        vec![
+            (String::from("BUY-1"), date(2018, 1, 2), 40, 100000),
+            (String::from("BUY-2"), date(2018, 3, 1), 120, 360000),
+        ]
+    );
+    assert_eq!(ledger.get_balance("02", &BalanceOptions::new()), Ok(160));
+    assert_eq!(
+        ledger
+            .get_transaction_by_id("SPLIT-1")
+            .unwrap()
+            .get_source(),
+        EntrySource::CorporateAction
+    );
+
+    // Selling 8 post-split units takes a fifth of the first lot.
+    trade(&mut ledger, "SELL-1", date(2018, 7, 1), -8, -25000);
+    assert_eq!(
+        ledger.get_balance("04", &BalanceOptions::new()),
+        Ok(25000 - 20000)
+    );
+
+    ledger
+        .post_corporate_action(CorporateAction::new_split(
+            "SPLIT-2",
+            date(2018, 8, 1),
+            "02",
+            1,
+            8,
+        ))
+        .unwrap();
+    assert_eq!(ledger.get_balance("02", &BalanceOptions::new()), Ok(19));
+    assert_eq!(
+        summarize(&ledger, "02")
+            .iter()
+            .map(|lot| lot.2)
+            .collect::<Vec<i64>>(),
+        vec![4, 15]
+    );
+}
+
+#[test]
+fn mergers_move_lots_into_the_new_commodity() {
+    let mut ledger = get_ledger();
+    ledger
+        .post_corporate_action(CorporateAction::new_merger(
+            "MERGE-1",
+            date(2018, 6, 1),
+            "02",
+            "03",
+            3,
+            2,
+        ))
+        .unwrap();
+
+    let options = BalanceOptions::new();
+    assert!(ledger.get_lots("02", &options).unwrap().is_empty());
+    assert_eq!(
+        summarize(&ledger, "03"),
+        vec![
+            (String::from("BUY-1"), date(2018, 1, 2), 15, 100000),
+            (String::from("BUY-2"), date(2018, 3, 1), 45, 360000),
+        ]
+    );
+    assert_eq!(ledger.get_balance("02", &options), Ok(0));
+    assert_eq!(ledger.get_balance("03", &options), Ok(60));
+    assert_eq!(ledger.get_carrying_value("02", &options), Ok(0));
+    assert_eq!(ledger.get_carrying_value("03", &options), Ok(460000));
+
+    let mut sale = Transaction::new("SELL-1", date(2018, 7, 1), "Sell NEW");
+    sale.add_split(Split::new_commodity("03", -15, -120000));
+    sale.add_split(Split::new("01", 120000));
+    ledger.add_transaction(sale).unwrap();
+    assert_eq!(ledger.get_balance("04", &options), Ok(20000));
+}
+
+#[test]
+fn returns_of_capital_reduce_the_cost_basis() {
+    let mut ledger = get_ledger();
+    ledger
+        .post_corporate_action(CorporateAction::new_return_of_capital(
+            "ROC-1",
+            date(2018, 6, 1),
+            "02",
+            "01",
+            20000,
+        ))
+        .unwrap();
+
+    let options = BalanceOptions::new();
+    assert_eq!(
+        summarize(&ledger, "02")
+            .iter()
+            .map(|lot| lot.3)
+            .collect::<Vec<i64>>(),
+        vec![95000, 345000]
+    );
+    assert_eq!(ledger.get_balance("01", &options), Ok(-460000 + 20000));
+    assert_eq!(ledger.get_balance("02", &options), Ok(40));
+    assert_eq!(ledger.get_carrying_value("02", &options), Ok(440000));
+    assert_eq!(ledger.get_balance("04", &options), Ok(0));
+
+    assert_eq!(
+        ledger.post_corporate_action(CorporateAction::new_return_of_capital(
+            "ROC-2",
+            date(2018, 7, 1),
+            "02",
+            "01",
+            500000,
+        )),
+        Err(Error::InvalidCorporateAction(String::from("ROC-2")))
+    );
+    assert!(ledger.get_transaction_by_id("ROC-2").is_none());
+    assert_eq!(
+        ledger.post_corporate_action(CorporateAction::new_split(
+            "SPLIT-1",
+            date(2018, 7, 1),
+            "01",
+            2,
+            1,
+        )),
+        Err(Error::WrongAccountType(String::from("01")))
+    );
+}
+
+#[test]
+fn corporate_actions_are_saved_and_undone_with_the_ledger() {
+    let mut ledger = get_ledger();
+    ledger
+        .post_corporate_action(CorporateAction::new_split(
+            "SPLIT-1",
+            date(2018, 6, 1),
+            "02",
+            2,
+            1,
+        ))
+        .unwrap();
+
+    let saved = json::parse(&ledger.to_json().dump()).unwrap();
+    assert_eq!(saved["corporate_actions"][0]["kind"], "split");
+
+    let loaded = Ledger::try_from(&saved).unwrap();
+    assert_eq!(
+        loaded.get_corporate_actions(),
+        ledger.get_corporate_actions()
+    );
+    assert_eq!(summarize(&loaded, "02"), summarize(&ledger, "02"));
+
+    ledger.undo().unwrap();
+    assert_eq!(ledger.get_balance("02", &BalanceOptions::new()), Ok(40));
+    assert_eq!(
+        summarize(&ledger, "02")
+            .iter()
+            .map(|lot| lot.2)
+            .collect::<Vec<i64>>(),
+        vec![10, 30]
+    );
+}
@@ -5,8 +5,10 @@ use cratchit::Currency;
 #[test]
 fn currency_translation_from_string() {
     let usd = Currency::from("USD");
-    let unknown = Currency::from("EUR");
+    let eur = Currency::from("EUR");
+    let unknown = Currency::from("XYZ");
 
     assert_eq!(usd, Currency::USDollar);
+    assert_eq!(eur, Currency::Euro);
     assert_eq!(unknown, Currency::Unknown);
 }
@@ -0,0 +1,207 @@
+extern crate chrono;
+extern crate cratchit;
+extern crate json;
+
+mod common;
+
+use chrono::NaiveDate;
+use common::{account, date, post_to};
+use cratchit::{
+    AccountType, AccountsChart, Envelope, EnvelopeBudget, Error, FiscalCalendar, Ledger,
+};
+
+/// 3,000.00 of salary arrives in each of January and February 2019. Groceries cost 420.00 and
+/// 600.00, dining out 260.00 and 100.00, and rent 1,500.00 is paid in January.
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(account("01", "Checking", AccountType::Asset));
+    chart.add_top_level_account(account("04", "Salary", AccountType::Income));
+    chart.add_top_level_account(account("05", "Groceries", AccountType::Expense));
+    chart.add_top_level_account(account("06", "Dining Out", AccountType::Expense));
+    chart.add_top_level_account(account("07", "Rent", AccountType::Expense));
+
+    let mut ledger = Ledger::new(chart);
+    post_to(&mut ledger, "PAY-1", date(2019, 1, 1), "04", -300000);
+    post_to(&mut ledger, "RENT-1", date(2019, 1, 1), "07", 150000);
+    post_to(&mut ledger, "FOOD-1", date(2019, 1, 12), "05", 42000);
+    post_to(&mut ledger, "DINE-1", date(2019, 1, 20), "06", 26000);
+    post_to(&mut ledger, "PAY-2", date(2019, 2, 1), "04", -300000);
+    post_to(&mut ledger, "FOOD-2", date(2019, 2, 9), "05", 60000);
+    post_to(&mut ledger, "DINE-2", date(2019, 2, 14), "06", 10000);
+
+    ledger
+}
+
+fn envelope(id: &str, account_id: &str) -> Envelope {
+    let mut envelope = Envelope::new(id, id);
+    envelope.add_account(account_id);
+    envelope
+}
+
+fn get_budget() -> EnvelopeBudget {
+    let mut budget = EnvelopeBudget::new(date(2019, 1, 1));
+    budget.add_envelope(envelope("groceries", "05")).unwrap();
+    budget.add_envelope(envelope("dining", "06")).unwrap();
+    budget.add_envelope(envelope("rent", "07")).unwrap();
+
+    budget.assign("groceries", date(2019, 1, 1), 50000).unwrap();
+    budget.assign("dining", date(2019, 1, 1), 20000).unwrap();
+    budget.assign("rent", date(2019, 1, 1), 150000).unwrap();
+    budget.assign("groceries", date(2019, 2, 1), 45000).unwrap();
+    budget.assign("dining", date(2019, 2, 1), 20000).unwrap();
+
+    budget
+}
+
+fn summarize(ledger: &Ledger, budget: &EnvelopeBudget, month: NaiveDate) -> Vec<[i64; 4]> {
+    ledger
+        .get_envelope_statuses(budget, month)
+        .unwrap()
+        .iter()
+        .map(|status| {
+            [
+                status.get_carried_over(),
+                status.get_assigned(),
+                status.get_spent(),
+                status.get_available(),
+            ]
+        })
+        .collect()
+}
+
+#[test]
+fn leftover_money_rolls_over_month_to_month() {
+    let ledger = get_ledger();
+    let budget = get_budget();
+
+    assert_eq!(
+        summarize(&ledger, &budget, date(2019, 1, 31)),
+        vec![
+            [0, 50000, 42000, 8000],
+            [0, 20000, 26000, -6000],
+            [0, 150000, 150000, 0],
+        ]
+    );
+    assert_eq!(
+        summarize(&ledger, &budget, date(2019, 2, 15)),
+        vec![
+            [8000, 45000, 60000, -7000],
+            [0, 20000, 10000, 10000],
+            [0, 0, 0, 0],
+        ]
+    );
+    assert_eq!(
+        summarize(&ledger, &budget, date(2019, 3, 1)),
+        vec![[0, 0, 0, 0], [10000, 0, 0, 10000], [0, 0, 0, 0]]
+    );
+}
+
+#[test]
+fn overspending_is_flagged_and_taken_from_income() {
+    let ledger = get_ledger();
+    let budget = get_budget();
+
+    let january = ledger
+        .get_envelope_statuses(&budget, date(2019, 1, 1))
+        .unwrap();
+    assert_eq!(
+        january
+            .iter()
+            .filter(|status| status.is_overspent())
+            .map(|status| status.get_envelope_id())
+            .collect::<Vec<String>>(),
+        vec![String::from("dining")]
+    );
+
+    assert_eq!(
+        ledger.get_to_be_assigned(&budget, date(2019, 1, 1)),
+        Ok(80000)
+    );
+    assert_eq!(
+        ledger.get_to_be_assigned(&budget, date(2019, 2, 1)),
+        Ok(80000 + 300000 - 65000 - 6000)
+    );
+    assert_eq!(
+        ledger.get_to_be_assigned(&budget, date(2019, 3, 1)),
+        Ok(309000 - 7000)
+    );
+    assert_eq!(ledger.get_to_be_assigned(&budget, date(2018, 12, 1)), Ok(0));
+}
+
+#[test]
+fn money_moves_between_envelopes() {
+    let ledger = get_ledger();
+    let mut budget = get_budget();
+
+    budget
+        .move_money("dining", "groceries", date(2019, 2, 20), 7000)
+        .unwrap();
+    assert_eq!(budget.get_assigned("groceries", date(2019, 2, 1)), 52000);
+    assert_eq!(
+        summarize(&ledger, &budget, date(2019, 2, 1)),
+        vec![
+            [8000, 52000, 60000, 0],
+            [0, 13000, 10000, 3000],
+            [0, 0, 0, 0],
+        ]
+    );
+    assert_eq!(
+        ledger.get_to_be_assigned(&budget, date(2019, 3, 1)),
+        Ok(309000)
+    );
+
+    assert_eq!(
+        budget.move_money("dining", "vacation", date(2019, 2, 1), 1000),
+        Err(Error::UnknownEnvelope(String::from("vacation")))
+    );
+    assert_eq!(budget.get_assigned("dining", date(2019, 2, 1)), 13000);
+    assert_eq!(
+        budget.assign("vacation", date(2019, 2, 1), 1000),
+        Err(Error::UnknownEnvelope(String::from("vacation")))
+    );
+}
+
+#[test]
+fn envelopes_cover_distinct_expense_accounts() {
+    let ledger = get_ledger();
+    let mut budget = get_budget();
+    assert_eq!(
+        budget.add_envelope(envelope("food", "05")),
+        Err(Error::DuplicateEnvelope(String::from("food")))
+    );
+    assert_eq!(
+        budget.add_envelope(envelope("rent", "99")),
+        Err(Error::DuplicateEnvelope(String::from("rent")))
+    );
+
+    let value = json::parse(&budget.to_json().dump()).unwrap();
+    assert_eq!(EnvelopeBudget::from(&value), budget);
+
+    budget.add_envelope(envelope("salary", "04")).unwrap();
+    assert_eq!(
+        ledger.get_envelope_statuses(&budget, date(2019, 1, 1)),
+        Err(Error::WrongAccountType(String::from("04")))
+    );
+}
+
+#[test]
+fn closing_entries_are_neither_income_nor_spending() {
+    let mut ledger = get_ledger();
+    let budget = get_budget();
+    let before = summarize(&ledger, &budget, date(2019, 2, 1));
+    let to_be_assigned = ledger.get_to_be_assigned(&budget, date(2019, 2, 1));
+
+    ledger
+        .add_top_level_account(account("03", "Savings", AccountType::Equity))
+        .unwrap();
+    ledger
+        .add_fiscal_year(&FiscalCalendar::Monthly { start_month: 2 }, 2019)
+        .unwrap();
+    ledger.post_closing_entry(2019, "03").unwrap();
+
+    assert_eq!(summarize(&ledger, &budget, date(2019, 2, 1)), before);
+    assert_eq!(
+        ledger.get_to_be_assigned(&budget, date(2019, 2, 1)),
+        to_be_assigned
+    );
+}
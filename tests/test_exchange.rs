@@ -0,0 +1,187 @@
+extern crate chrono;
+extern crate cratchit;
+
+mod common;
+
+use std::convert::TryFrom;
+
+use common::date;
+use cratchit::{
+    Account, AccountType, AccountsChart, BalanceOptions, Currency, Error, Ledger, Split,
+    Transaction,
+};
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    for &(id, name, account_type, currency) in &[
+        ("01", "Checking", AccountType::Asset, Currency::USDollar),
+        ("02", "Receivable (EUR)", AccountType::Asset, Currency::Euro),
+        ("04", "Sales", AccountType::Income, Currency::USDollar),
+        (
+            "04-FX",
+            "Exchange Gain",
+            AccountType::Income,
+            Currency::USDollar,
+        ),
+        (
+            "05",
+            "Bank Fees (EUR)",
+            AccountType::Expense,
+            Currency::Euro,
+        ),
+    ] {
+        chart.add_top_level_account(Account::new(id, name, name, account_type, currency, false));
+    }
+
+    let mut ledger = Ledger::new(chart);
+    let mut invoice = Transaction::new("INV-1", date(2018, 3, 1), "Consulting");
+    invoice.add_split(Split::new_foreign("02", 10000, 1.25));
+    invoice.add_split(Split::new("04", -12500));
+    ledger.add_transaction(invoice).unwrap();
+
+    ledger
+}
+
+fn payment(amount: i64, rate: f64) -> Transaction {
+    let mut payment = Transaction::new("PMT-1", date(2018, 4, 1), "Partial payment");
+    let split = Split::new_foreign("02", -amount, rate);
+    payment.add_split(Split::new("01", -split.get_value()));
+    payment.add_split(split);
+
+    payment
+}
+
+fn get_balance(ledger: &Ledger, id: &str) -> i64 {
+    ledger.get_balance(id, &BalanceOptions::new()).unwrap()
+}
+
+#[test]
+fn splits_must_convert_to_their_account_currency() {
+    let mut ledger = get_ledger();
+
+    let mut missing_rate = Transaction::new("0002", date(2018, 3, 2), "Missing rate");
+    missing_rate.add_split(Split::new("02", 10000));
+    missing_rate.add_split(Split::new("04", -10000));
+    assert_eq!(
+        ledger.add_transaction(missing_rate),
+        Err(Error::CurrencyMismatch(String::from("02")))
+    );
+
+    let mut domestic_rate = Transaction::new("0003", date(2018, 3, 2), "Domestic rate");
+    domestic_rate.add_split(Split::new_foreign("01", 10000, 1.1));
+    domestic_rate.add_split(Split::new("04", -11000));
+    assert_eq!(
+        ledger.add_transaction(domestic_rate),
+        Err(Error::CurrencyMismatch(String::from("01")))
+    );
+
+    let mut unbalanced = Transaction::new("0004", date(2018, 3, 2), "Unbalanced in USD");
+    unbalanced.add_split(Split::new_foreign("02", 10000, 1.25));
+    unbalanced.add_split(Split::new("04", -10000));
+    assert_eq!(
+        ledger.add_transaction(unbalanced),
+        Err(Error::Unbalanced(String::from("0004")))
+    );
+
+    // The receivable is carried in dollars, so a fee charged in euros would be subtracted from
+    // its carrying value as if it were dollars.
+    let mut fee = Transaction::new("0005", date(2018, 3, 2), "Bank fee in EUR");
+    fee.set_currency(Currency::Euro);
+    fee.add_split(Split::new("05", 1000));
+    fee.add_split(Split::new("02", -1000));
+    assert_eq!(
+        ledger.add_transaction(fee),
+        Err(Error::CurrencyMismatch(String::from("02")))
+    );
+
+    let mut fee = Transaction::new("0005", date(2018, 3, 2), "Bank fee in USD");
+    fee.add_split(Split::new_foreign("05", 1000, 1.2));
+    fee.add_split(Split::new_foreign("02", -1000, 1.2));
+    ledger.add_transaction(fee).unwrap();
+    assert_eq!(
+        ledger.get_carrying_value("02", &BalanceOptions::new()),
+        Ok(12500 - 1200)
+    );
+}
+
+#[test]
+fn settlements_realize_exchange_gains() {
+    let mut ledger = get_ledger();
+    ledger.set_exchange_gain_loss_account("04-FX").unwrap();
+    ledger.add_transaction(payment(4000, 1.3)).unwrap();
+
+    let settled = ledger.get_transaction_by_id("PMT-1").unwrap();
+    let adjustments: Vec<Split> = settled
+        .get_splits()
+        .into_iter()
+        .filter(Split::is_exchange_adjustment)
+        .collect();
+    assert_eq!(adjustments.len(), 2);
+    assert_eq!(adjustments[0].get_amount(), 0);
+    assert_eq!(adjustments[0].get_value(), 200);
+    assert!(settled.is_balanced());
+
+    assert_eq!(get_balance(&ledger, "04-FX"), 200);
+    assert_eq!(get_balance(&ledger, "02"), 6000);
+    assert_eq!(
+        ledger
+            .get_carrying_value("02", &BalanceOptions::new())
+            .unwrap(),
+        7500
+    );
+}
+
+#[test]
+fn edited_settlements_are_recomputed() {
+    let mut ledger = get_ledger();
+    ledger.set_exchange_gain_loss_account("04-FX").unwrap();
+    ledger.add_transaction(payment(4000, 1.3)).unwrap();
+
+    ledger.update_transaction(payment(4000, 1.2)).unwrap();
+
+    assert_eq!(
+        ledger
+            .get_transaction_by_id("PMT-1")
+            .unwrap()
+            .get_splits()
+            .len(),
+        4
+    );
+    assert_eq!(get_balance(&ledger, "04-FX"), -200);
+
+    ledger.remove_transaction("PMT-1").unwrap();
+    assert_eq!(get_balance(&ledger, "04-FX"), 0);
+    assert_eq!(
+        ledger
+            .get_carrying_value("02", &BalanceOptions::new())
+            .unwrap(),
+        12500
+    );
+}
+
+#[test]
+fn gains_are_not_realized_without_an_account() {
+    let mut ledger = get_ledger();
+    assert_eq!(
+        ledger.set_exchange_gain_loss_account("02"),
+        Err(Error::WrongAccountType(String::from("02")))
+    );
+    assert_eq!(ledger.get_exchange_gain_loss_account(), None);
+
+    ledger.add_transaction(payment(10000, 1.2)).unwrap();
+    assert_eq!(get_balance(&ledger, "02"), 0);
+    assert_eq!(
+        ledger
+            .get_carrying_value("02", &BalanceOptions::new())
+            .unwrap(),
+        500
+    );
+
+    let saved = ledger.get_transaction_by_id("PMT-1").unwrap().to_json();
+    assert_eq!(saved["currency"], "USD");
+    assert_eq!(saved["splits"][1]["rate"], 1.2);
+    assert_eq!(
+        Transaction::try_from(&saved).unwrap(),
+        ledger.get_transaction_by_id("PMT-1").unwrap()
+    );
+}
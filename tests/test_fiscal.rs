@@ -0,0 +1,167 @@
+extern crate chrono;
+extern crate cratchit;
+
+mod common;
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use common::date;
+use cratchit::{
+    Account, AccountType, AccountsChart, Currency, Error, FiscalCalendar, Ledger, Split,
+    Transaction,
+};
+
+fn dues(id: &str, date: NaiveDate) -> Transaction {
+    let mut transaction = Transaction::new(id, date, "Membership dues");
+    transaction.add_split(Split::new("01", 5000));
+    transaction.add_split(Split::new("04", -5000));
+
+    transaction
+}
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(Account::new(
+        "01",
+        "Checking",
+        "Checking",
+        AccountType::Asset,
+        Currency::USDollar,
+        false,
+    ));
+    chart.add_top_level_account(Account::new(
+        "04",
+        "Dues",
+        "Dues",
+        AccountType::Income,
+        Currency::USDollar,
+        false,
+    ));
+
+    let mut ledger = Ledger::new(chart);
+    ledger
+        .add_fiscal_year(&FiscalCalendar::Monthly { start_month: 1 }, 2018)
+        .unwrap();
+    ledger
+        .add_transaction(dues("t1", date(2018, 3, 15)))
+        .unwrap();
+
+    ledger
+}
+
+#[test]
+fn calendar_year_periods() {
+    let periods = FiscalCalendar::Monthly { start_month: 1 }
+        .get_periods(2018)
+        .unwrap();
+
+    assert_eq!(periods.len(), 12);
+    assert_eq!(periods[1].get_id(), "FY2018-P02");
+    assert_eq!(periods[1].get_start(), date(2018, 2, 1));
+    assert_eq!(periods[1].get_end(), date(2018, 2, 28));
+    assert_eq!(periods[11].get_end(), date(2018, 12, 31));
+}
+
+#[test]
+fn fifty_three_week_year() {
+    let calendar = FiscalCalendar::FiftyTwoFiftyThreeWeek {
+        end_month: 12,
+        end_weekday: Weekday::Sat,
+    };
+    let periods = calendar.get_periods(2016).unwrap();
+
+    assert_eq!(periods.len(), 13);
+    assert_eq!(periods[0].get_start(), date(2015, 12, 27));
+    assert_eq!(periods[12].get_end(), date(2016, 12, 31));
+    assert_eq!(periods[12].get_end().weekday(), Weekday::Sat);
+
+    let last = &periods[12];
+    assert_eq!((last.get_end() - last.get_start()).num_days() + 1, 35);
+
+    let next_year = calendar.get_periods(2017).unwrap();
+    assert_eq!(next_year[0].get_start(), date(2017, 1, 1));
+    let last = &next_year[12];
+    assert_eq!((last.get_end() - last.get_start()).num_days() + 1, 28);
+}
+
+#[test]
+fn calendars_with_invalid_months_are_rejected() {
+    assert_eq!(
+        FiscalCalendar::Monthly { start_month: 13 }.get_periods(2018),
+        Err(Error::InvalidMonth(13))
+    );
+
+    let mut ledger = get_ledger();
+    let calendar = FiscalCalendar::FourFourFive {
+        end_month: 0,
+        end_weekday: Weekday::Sat,
+    };
+    assert_eq!(
+        ledger.add_fiscal_year(&calendar, 2019),
+        Err(Error::InvalidMonth(0))
+    );
+    assert_eq!(ledger.get_periods().len(), 12);
+}
+
+#[test]
+fn overlapping_fiscal_years_are_rejected() {
+    let mut ledger = get_ledger();
+    let result = ledger.add_fiscal_year(&FiscalCalendar::Monthly { start_month: 7 }, 2019);
+
+    assert_eq!(
+        result,
+        Err(Error::OverlappingPeriod(String::from("FY2019-P01")))
+    );
+    assert_eq!(ledger.get_periods().len(), 12);
+}
+
+#[test]
+fn closed_periods_lock_transactions() {
+    let mut ledger = get_ledger();
+    ledger
+        .close_period("FY2018-P03", "Q1 return filed")
+        .unwrap();
+    let locked = Err(Error::ClosedPeriod(String::from("FY2018-P03")));
+
+    assert_eq!(ledger.add_transaction(dues("t2", date(2018, 3, 1))), locked);
+    assert_eq!(
+        ledger.update_transaction(dues("t1", date(2018, 4, 1))),
+        locked
+    );
+    assert_eq!(ledger.remove_transaction("t1").map(|_| ()), locked);
+
+    let mut backdated = dues("t3", date(2018, 4, 2));
+    backdated.set_effective_date(date(2018, 3, 31));
+    assert_eq!(ledger.add_transaction(backdated), locked);
+
+    assert!(ledger.add_transaction(dues("t4", date(2018, 4, 2))).is_ok());
+    assert!(ledger.void_transaction("t1", date(2018, 4, 3)).is_ok());
+}
+
+#[test]
+fn reopening_a_period() {
+    let mut ledger = get_ledger();
+    ledger
+        .close_period("FY2018-P03", "Q1 return filed")
+        .unwrap();
+    assert_eq!(
+        ledger.close_period("FY2018-P03", "Again"),
+        Err(Error::ClosedPeriod(String::from("FY2018-P03")))
+    );
+
+    ledger
+        .reopen_period("FY2018-P03", "Amended return")
+        .unwrap();
+    assert!(ledger.remove_transaction("t1").is_ok());
+
+    let period = ledger.get_period_for_date(date(2018, 3, 15)).unwrap();
+    let history = period.get_history();
+    assert!(!period.is_closed());
+    assert_eq!(history.len(), 2);
+    assert!(history[0].is_close());
+    assert_eq!(history[1].get_reason(), "Amended return");
+
+    assert_eq!(
+        ledger.reopen_period("FY2018-P13", "Missing"),
+        Err(Error::UnknownPeriod(String::from("FY2018-P13")))
+    );
+}
@@ -0,0 +1,161 @@
+extern crate chrono;
+extern crate cratchit;
+
+mod common;
+
+use chrono::NaiveDate;
+use common::{account, date};
+use cratchit::{
+    AccountType, AccountsChart, Error, ForecastOptions, Frequency, Ledger, Recurrence,
+    ScheduledTransaction, Split, Transaction,
+};
+
+fn transaction(id: &str, on: NaiveDate, debit: &str, credit: &str, amount: i64) -> Transaction {
+    let mut transaction = Transaction::new(id, on, id);
+    transaction.add_split(Split::new(debit, amount));
+    transaction.add_split(Split::new(credit, -amount));
+    transaction
+}
+
+/// Checking opens with 5,000.00 on 2019-01-01 and pays 2,000.00 of rent on the first of every
+/// month. Sales of 450.00 and supplies of 150.00 during January make a trend of 10.00 a day.
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(account("01", "Checking", AccountType::Asset));
+    chart.add_top_level_account(account("02", "Savings", AccountType::Asset));
+    chart.add_top_level_account(account("03", "Capital", AccountType::Equity));
+    chart.add_top_level_account(account("04", "Sales", AccountType::Income));
+    chart.add_top_level_account(account("05", "Rent", AccountType::Expense));
+    chart.add_top_level_account(account("06", "Supplies", AccountType::Expense));
+
+    let mut ledger = Ledger::new(chart);
+    let start = date(2019, 1, 1);
+    ledger
+        .add_transaction(transaction("0001", start, "01", "03", 500000))
+        .unwrap();
+    ledger
+        .add_transaction(transaction("0002", date(2019, 1, 10), "01", "04", 45000))
+        .unwrap();
+    ledger
+        .add_transaction(transaction("0003", date(2019, 1, 20), "06", "01", 15000))
+        .unwrap();
+
+    let rent = transaction("", start, "05", "01", 200000);
+    let recurrence = Recurrence::new(Frequency::MonthlyByDay(1), start);
+    ledger
+        .add_schedule(ScheduledTransaction::new("RENT", rent, recurrence))
+        .unwrap();
+    ledger.materialize_schedules(date(2019, 1, 31)).unwrap();
+
+    ledger
+}
+
+#[test]
+fn scheduled_occurrences_are_projected() {
+    let mut ledger = get_ledger();
+    let options = ForecastOptions::new().threshold("01", 0);
+    let forecast = ledger
+        .get_forecast(
+            &["01", "05"],
+            date(2019, 1, 31),
+            date(2019, 3, 31),
+            &options,
+        )
+        .unwrap();
+
+    let series = forecast.get_series("01");
+    assert_eq!(series.len(), 60);
+    assert_eq!(series[0], (date(2019, 1, 31), 330000));
+    assert_eq!(series[1], (date(2019, 2, 1), 130000));
+    assert_eq!(series[59], (date(2019, 3, 31), -70000));
+    assert_eq!(forecast.get_balance("05", date(2019, 3, 1)), Some(600000));
+    assert_eq!(forecast.get_balance("05", date(2019, 4, 1)), None);
+    assert_eq!(forecast.get_crossing("01"), Some(date(2019, 3, 1)));
+    assert_eq!(forecast.get_crossing("05"), None);
+
+    ledger.skip_occurrence("RENT", date(2019, 3, 1)).unwrap();
+    let forecast = ledger
+        .get_forecast(&["01"], date(2019, 1, 31), date(2019, 3, 31), &options)
+        .unwrap();
+    assert_eq!(forecast.get_balance("01", date(2019, 3, 31)), Some(130000));
+    assert_eq!(forecast.get_first_crossing(), None);
+}
+
+#[test]
+fn trends_are_estimated_from_unscheduled_history() {
+    let ledger = get_ledger();
+    let options = ForecastOptions::new()
+        .trend_since(date(2019, 1, 2))
+        .threshold("01", 0);
+    let forecast = ledger
+        .get_forecast(&["01"], date(2019, 1, 31), date(2019, 3, 31), &options)
+        .unwrap();
+
+    assert_eq!(forecast.get_balance("01", date(2019, 1, 31)), Some(330000));
+    assert_eq!(forecast.get_balance("01", date(2019, 2, 1)), Some(131000));
+    assert_eq!(forecast.get_balance("01", date(2019, 2, 28)), Some(158000));
+    assert_eq!(forecast.get_balance("01", date(2019, 3, 1)), Some(-41000));
+    assert_eq!(forecast.get_crossing("01"), Some(date(2019, 3, 1)));
+
+    let trend_only = ForecastOptions::new()
+        .include_schedules(false)
+        .trend_since(date(2019, 1, 2));
+    let forecast = ledger
+        .get_forecast(&["01"], date(2019, 1, 31), date(2019, 3, 31), &trend_only)
+        .unwrap();
+    assert_eq!(forecast.get_balance("01", date(2019, 3, 31)), Some(389000));
+}
+
+#[test]
+fn the_first_crossing_of_any_threshold_is_reported() {
+    let mut ledger = get_ledger();
+    ledger
+        .add_transaction(transaction("0004", date(2019, 1, 31), "02", "01", 100000))
+        .unwrap();
+    let transfer = transaction("", date(2019, 2, 4), "01", "02", 30000);
+    let recurrence = Recurrence::new(Frequency::Weekly, date(2019, 2, 4));
+    ledger
+        .add_schedule(ScheduledTransaction::new("TOP-UP", transfer, recurrence))
+        .unwrap();
+
+    let options = ForecastOptions::new()
+        .threshold("01", 0)
+        .threshold("02", 25000);
+    let forecast = ledger
+        .get_forecast(
+            &["01", "02"],
+            date(2019, 1, 31),
+            date(2019, 4, 30),
+            &options,
+        )
+        .unwrap();
+
+    assert_eq!(forecast.get_account_ids(), vec!["01", "02"]);
+    assert_eq!(forecast.get_crossing("02"), Some(date(2019, 2, 18)));
+    assert_eq!(forecast.get_crossing("01"), Some(date(2019, 3, 1)));
+    assert_eq!(
+        forecast.get_first_crossing(),
+        Some((String::from("02"), date(2019, 2, 18)))
+    );
+}
+
+#[test]
+fn forecasts_require_known_accounts() {
+    let ledger = get_ledger();
+    let options = ForecastOptions::new();
+    assert_eq!(
+        ledger.get_forecast(
+            &["01", "99"],
+            date(2019, 1, 31),
+            date(2019, 2, 28),
+            &options
+        ),
+        Err(Error::UnknownAccount(String::from("99")))
+    );
+
+    let forecast = ledger
+        .get_forecast(&["01"], date(2019, 1, 31), date(2019, 1, 1), &options)
+        .unwrap();
+    assert_eq!(forecast.get_series("01"), vec![(date(2019, 1, 31), 330000)]);
+    assert!(forecast.get_series("05").is_empty());
+}
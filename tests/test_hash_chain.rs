@@ -0,0 +1,201 @@
+extern crate chrono;
+extern crate cratchit;
+extern crate json;
+
+mod common;
+
+use std::convert::TryFrom;
+
+use common::date;
+use cratchit::{
+    Account, AccountType, AccountsChart, BalanceOptions, Currency, Error, FiscalCalendar, Ledger,
+    Split, Transaction, TransactionStatus,
+};
+
+fn dues(id: &str, amount: i64) -> Transaction {
+    let mut transaction = Transaction::new(id, date(2018, 3, 1), "Membership dues");
+    transaction.add_split(Split::new("01", amount));
+    transaction.add_split(Split::new("04", -amount));
+
+    transaction
+}
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(Account::new(
+        "01",
+        "Checking",
+        "Checking",
+        AccountType::Asset,
+        Currency::USDollar,
+        false,
+    ));
+    chart.add_top_level_account(Account::new(
+        "04",
+        "Dues",
+        "Dues",
+        AccountType::Income,
+        Currency::USDollar,
+        false,
+    ));
+
+    let mut ledger = Ledger::new(chart);
+    for (id, amount) in &[("0001", 5000), ("0002", 7500), ("0003", 2500)] {
+        ledger.add_transaction(dues(id, *amount)).unwrap();
+    }
+
+    ledger
+}
+
+#[test]
+fn links_follow_the_journal() {
+    let ledger = get_ledger();
+    let chain = ledger.get_chain();
+
+    assert_eq!(chain.len(), 3);
+    assert_eq!(chain[0].get_previous_hash(), "0".repeat(64));
+    assert_eq!(chain[1].get_previous_hash(), chain[0].get_hash());
+    assert_eq!(chain[2].get_transaction_id(), "0003");
+    assert_eq!(chain[2].get_hash().len(), 64);
+    assert!(ledger.verify_journal().is_ok());
+}
+
+#[test]
+fn changes_through_the_ledger_keep_the_chain_intact() {
+    let mut ledger = get_ledger();
+    let original = ledger.get_chain();
+
+    ledger.update_transaction(dues("0002", 8000)).unwrap();
+    ledger
+        .set_transaction_status("0003", TransactionStatus::Cleared)
+        .unwrap();
+    ledger.void_transaction("0001", date(2018, 3, 5)).unwrap();
+    ledger.remove_transaction("0003").unwrap();
+
+    let chain = ledger.get_chain();
+    assert_eq!(chain.len(), 8);
+    assert_eq!(chain[..3], original[..]);
+    assert_eq!(chain[3].get_transaction_id(), "0002");
+    assert_eq!(chain[6].get_transaction_id(), "0001-void");
+    assert_eq!(chain[7].get_transaction_id(), "0003");
+    assert_eq!(chain[7].get_content_hash(), None);
+    assert!(ledger.verify_journal().is_ok());
+}
+
+#[test]
+fn undoing_appends_to_the_chain() {
+    let mut ledger = get_ledger();
+    ledger.update_transaction(dues("0002", 8000)).unwrap();
+    ledger.undo().unwrap();
+
+    let chain = ledger.get_chain();
+    assert_eq!(chain.len(), 5);
+    assert_eq!(chain[4].get_transaction_id(), "0002");
+    assert_eq!(chain[4].get_content_hash(), chain[1].get_content_hash());
+    assert!(ledger.verify_journal().is_ok());
+
+    let mut forged = ledger.to_json();
+    forged["chain"][3]["content_hash"] = forged["chain"][1]["content_hash"].clone();
+    assert_eq!(
+        Ledger::try_from(&forged).unwrap().verify_journal(),
+        Err(Error::BrokenChain(String::from("0002")))
+    );
+}
+
+#[test]
+fn saved_ledger_round_trips() {
+    let ledger = get_ledger();
+    let saved = json::parse(&ledger.to_json().pretty(2)).unwrap();
+    let loaded = Ledger::try_from(&saved).unwrap();
+
+    assert_eq!(loaded.get_chart().get_num_accounts(), 2);
+    assert_eq!(loaded.get_num_transactions(), 3);
+    assert_eq!(
+        loaded.get_transaction_by_id("0002"),
+        ledger.get_transaction_by_id("0002")
+    );
+    assert_eq!(loaded.get_chain(), ledger.get_chain());
+    assert_eq!(
+        loaded.get_balance("04", &BalanceOptions::new()).unwrap(),
+        15000
+    );
+    assert!(loaded.verify_journal().is_ok());
+}
+
+#[test]
+fn tampering_reports_first_broken_link() {
+    let ledger = get_ledger();
+
+    let mut altered = ledger.to_json();
+    altered["transactions"][1]["description"] = "Dues refund".into();
+    assert_eq!(
+        Ledger::try_from(&altered).unwrap().verify_journal(),
+        Err(Error::BrokenChain(String::from("0002")))
+    );
+
+    let mut removed = ledger.to_json();
+    removed["transactions"].array_remove(0);
+    assert_eq!(
+        Ledger::try_from(&removed).unwrap().verify_journal(),
+        Err(Error::BrokenChain(String::from("0001")))
+    );
+
+    let mut truncated = ledger.to_json();
+    truncated["transactions"].array_remove(2);
+    assert_eq!(
+        Ledger::try_from(&truncated).unwrap().verify_journal(),
+        Err(Error::BrokenChain(String::from("0003")))
+    );
+}
+
+#[test]
+fn new_fields_do_not_change_existing_hashes() {
+    let ledger = get_ledger();
+
+    let mut extended = ledger.to_json();
+    extended["transactions"][0]["notes"] = "Added by a later version".into();
+    assert!(Ledger::try_from(&extended)
+        .unwrap()
+        .verify_journal()
+        .is_ok());
+
+    let mut altered = ledger.to_json();
+    altered["transactions"][0]["splits"][0]["contact"] = "C001".into();
+    assert_eq!(
+        Ledger::try_from(&altered).unwrap().verify_journal(),
+        Err(Error::BrokenChain(String::from("0001")))
+    );
+}
+
+#[test]
+fn saved_ledger_keeps_periods_reconciliations_and_audit_trail() {
+    let mut ledger = get_ledger();
+    ledger
+        .add_fiscal_year(&FiscalCalendar::Monthly { start_month: 1 }, 2018)
+        .unwrap();
+    ledger
+        .close_period("FY2018-P03", "March books closed")
+        .unwrap();
+    let mut session = ledger
+        .start_reconciliation("01", date(2018, 3, 31), 15000)
+        .unwrap();
+    for id in &["0001", "0002", "0003"] {
+        session.mark_cleared(&ledger, id, 0).unwrap();
+    }
+    ledger.finalize_reconciliation(&session).unwrap();
+
+    let saved = json::parse(&ledger.to_json().pretty(2)).unwrap();
+    let mut loaded = Ledger::try_from(&saved).unwrap();
+
+    assert_eq!(loaded.get_periods(), ledger.get_periods());
+    assert_eq!(
+        loaded.add_transaction(dues("0004", 1000)),
+        Err(Error::ClosedPeriod(String::from("FY2018-P03")))
+    );
+    assert_eq!(
+        loaded.get_reconciliations("01"),
+        ledger.get_reconciliations("01")
+    );
+    assert_eq!(loaded.get_audit_log(), ledger.get_audit_log());
+    assert!(loaded.verify_journal().is_ok());
+}
@@ -0,0 +1,293 @@
+extern crate chrono;
+extern crate cratchit;
+extern crate json;
+
+mod common;
+
+use std::convert::TryFrom;
+
+use common::{account, date};
+use cratchit::{
+    AccountType, AccountsChart, BalanceOptions, Contact, ContactKind, EntrySource, Error, Invoice,
+    InvoiceLine, InvoiceStatus, Ledger, PaymentTerms, TaxCode,
+};
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(account("01", "Checking", AccountType::Asset));
+    chart.add_top_level_account(account("02", "Accounts Receivable", AccountType::Asset));
+    chart.add_top_level_account(account("03", "Sales Tax Payable", AccountType::Liability));
+    chart.add_top_level_account(account("04", "Ice Time", AccountType::Income));
+    chart.add_top_level_account(account("05", "Rink Rental", AccountType::Expense));
+    chart.add_top_level_account(account("06", "Camps", AccountType::Income));
+
+    let mut ledger = Ledger::new(chart);
+    let mut school = Contact::new("LNHS", "Lakeville North High School", ContactKind::Customer);
+    school.set_control_account("02");
+    school.set_payment_terms(PaymentTerms::net(30));
+    ledger.add_contact(school).unwrap();
+    ledger
+        .add_contact(Contact::new(
+            "LSHS",
+            "Lakeville South",
+            ContactKind::Customer,
+        ))
+        .unwrap();
+    ledger
+        .add_tax_code(TaxCode::new("MN", "Minnesota sales tax", 6.875, "03"))
+        .unwrap();
+
+    ledger
+}
+
+/// An invoice for 2.5 hours of ice time at 240.00 an hour, less 10% and taxed, and a 300.00
+/// camp registration.
+fn get_invoice(id: &str) -> Invoice {
+    let mut invoice = Invoice::new(id, "LNHS", date(2019, 1, 5));
+    invoice.add_line(
+        InvoiceLine::new("04", "Ice time (hours)", 2.5, 24000)
+            .discount(10.0)
+            .tax_code("MN"),
+    );
+    invoice.add_line(InvoiceLine::new("06", "Camp registration", 1.0, 30000));
+    invoice
+}
+
+fn issued_ledger() -> Ledger {
+    let mut ledger = get_ledger();
+    ledger.add_invoice(get_invoice("INV-1")).unwrap();
+    ledger.issue_invoice("INV-1").unwrap();
+    ledger
+}
+
+#[test]
+fn issuing_an_invoice_posts_receivable_income_and_tax() {
+    let mut ledger = get_ledger();
+    ledger.add_invoice(get_invoice("INV-1")).unwrap();
+
+    let draft = ledger.get_invoice("INV-1").unwrap();
+    assert_eq!(draft.get_status(date(2019, 1, 5)), InvoiceStatus::Draft);
+    assert_eq!(draft.get_subtotal(), 84000);
+    assert_eq!(draft.get_tax(), 3713);
+    assert_eq!(draft.get_total(), 87713);
+    assert_eq!(draft.get_outstanding(), 0);
+    assert_eq!(draft.get_due_date(), Some(date(2019, 2, 4)));
+    assert_eq!(ledger.get_num_transactions(), 0);
+
+    ledger.issue_invoice("INV-1").unwrap();
+    let options = BalanceOptions::new();
+    assert_eq!(ledger.get_balance("02", &options), Ok(87713));
+    assert_eq!(ledger.get_balance("04", &options), Ok(54000));
+    assert_eq!(ledger.get_balance("06", &options), Ok(30000));
+    assert_eq!(ledger.get_balance("03", &options), Ok(3713));
+    assert_eq!(
+        ledger.get_contact_balances("02", &options).unwrap()["LNHS"],
+        87713
+    );
+
+    let transaction = ledger.get_transaction_by_id("INV-1").unwrap();
+    assert_eq!(transaction.get_source(), EntrySource::Invoice);
+    assert_eq!(transaction.get_splits()[1].get_memo(), "Ice time (hours)");
+
+    let invoice = ledger.get_invoice("INV-1").unwrap();
+    assert_eq!(invoice.get_status(date(2019, 1, 6)), InvoiceStatus::Sent);
+    assert_eq!(invoice.get_outstanding(), 87713);
+    assert_eq!(
+        ledger.issue_invoice("INV-1"),
+        Err(Error::InvalidInvoiceState(String::from("INV-1")))
+    );
+}
+
+#[test]
+fn payments_are_tracked_against_the_outstanding_balance() {
+    let mut ledger = issued_ledger();
+    ledger
+        .record_invoice_payment("INV-1", "PMT-1", date(2019, 1, 20), 40000, "01")
+        .unwrap();
+
+    let invoice = ledger.get_invoice("INV-1").unwrap();
+    assert_eq!(invoice.get_paid(), 40000);
+    assert_eq!(invoice.get_outstanding(), 47713);
+    assert_eq!(
+        invoice.get_status(date(2019, 1, 20)),
+        InvoiceStatus::PartiallyPaid
+    );
+    assert_eq!(
+        invoice.get_status(date(2019, 2, 4)),
+        InvoiceStatus::PartiallyPaid
+    );
+    assert_eq!(invoice.get_status(date(2019, 2, 5)), InvoiceStatus::Overdue);
+
+    assert_eq!(
+        ledger.record_invoice_payment("INV-1", "PMT-2", date(2019, 2, 10), 50000, "01"),
+        Err(Error::Overpayment(String::from("INV-1")))
+    );
+    assert_eq!(
+        ledger.record_invoice_payment("INV-1", "PMT-2", date(2019, 2, 10), 0, "01"),
+        Err(Error::InvalidInvoiceState(String::from("INV-1")))
+    );
+    ledger
+        .record_invoice_payment("INV-1", "PMT-2", date(2019, 2, 10), 47713, "01")
+        .unwrap();
+
+    let invoice = ledger.get_invoice("INV-1").unwrap();
+    assert_eq!(invoice.get_status(date(2019, 3, 1)), InvoiceStatus::Paid);
+    assert_eq!(
+        invoice
+            .get_payments()
+            .iter()
+            .map(|payment| payment.get_transaction_id())
+            .collect::<Vec<String>>(),
+        vec!["PMT-1", "PMT-2"]
+    );
+
+    let options = BalanceOptions::new();
+    assert_eq!(ledger.get_balance("01", &options), Ok(87713));
+    assert_eq!(ledger.get_balance("02", &options), Ok(0));
+    assert_eq!(
+        ledger.get_contact_balances("02", &options).unwrap()["LNHS"],
+        0
+    );
+
+    ledger.undo().unwrap();
+    let invoice = ledger.get_invoice("INV-1").unwrap();
+    assert_eq!(invoice.get_outstanding(), 47713);
+}
+
+#[test]
+fn only_drafts_can_be_edited_and_paid_invoices_cannot_be_voided() {
+    let mut ledger = issued_ledger();
+    let mut edited = get_invoice("INV-1");
+    edited.add_line(InvoiceLine::new("04", "Extra ice", 1.0, 24000));
+    assert_eq!(
+        ledger.update_invoice(Invoice::clone(&edited)),
+        Err(Error::InvalidInvoiceState(String::from("INV-1")))
+    );
+
+    ledger.add_invoice(get_invoice("INV-2")).unwrap();
+    let mut edited = get_invoice("INV-2");
+    edited.set_due_date(date(2019, 1, 15));
+    edited.add_line(InvoiceLine::new("04", "Extra ice", 1.0, 24000));
+    ledger.update_invoice(edited).unwrap();
+    let invoice = ledger.get_invoice("INV-2").unwrap();
+    assert_eq!(invoice.get_subtotal(), 108000);
+    assert_eq!(invoice.get_due_date(), Some(date(2019, 1, 15)));
+
+    ledger.void_invoice("INV-2", date(2019, 1, 6)).unwrap();
+    assert_eq!(
+        ledger
+            .get_invoice("INV-2")
+            .unwrap()
+            .get_status(date(2019, 1, 6)),
+        InvoiceStatus::Void
+    );
+    assert_eq!(
+        ledger.issue_invoice("INV-2"),
+        Err(Error::InvalidInvoiceState(String::from("INV-2")))
+    );
+
+    ledger
+        .record_invoice_payment("INV-1", "PMT-1", date(2019, 1, 20), 10000, "01")
+        .unwrap();
+    assert_eq!(
+        ledger.void_invoice("INV-1", date(2019, 1, 31)),
+        Err(Error::InvalidInvoiceState(String::from("INV-1")))
+    );
+
+    ledger.add_invoice(get_invoice("INV-3")).unwrap();
+    ledger.issue_invoice("INV-3").unwrap();
+    ledger.void_invoice("INV-3", date(2019, 1, 31)).unwrap();
+    let invoice = ledger.get_invoice("INV-3").unwrap();
+    assert_eq!(invoice.get_status(date(2019, 3, 1)), InvoiceStatus::Void);
+    assert_eq!(invoice.get_outstanding(), 0);
+    assert_eq!(
+        ledger.get_balance("02", &BalanceOptions::new()),
+        Ok(87713 - 10000)
+    );
+}
+
+#[test]
+fn invoices_are_validated_and_saved() {
+    let mut ledger = get_ledger();
+    let mut invoice = Invoice::new("INV-1", "EHS", date(2019, 1, 5));
+    assert_eq!(
+        ledger.add_invoice(Invoice::clone(&invoice)),
+        Err(Error::UnknownContact(String::from("EHS")))
+    );
+
+    invoice = Invoice::new("INV-1", "LNHS", date(2019, 1, 5));
+    invoice.add_line(InvoiceLine::new("05", "Rink rental", 1.0, 10000));
+    assert_eq!(
+        ledger.add_invoice(Invoice::clone(&invoice)),
+        Err(Error::WrongAccountType(String::from("05")))
+    );
+
+    invoice = Invoice::new("INV-1", "LNHS", date(2019, 1, 5));
+    invoice.add_line(InvoiceLine::new("04", "Ice", 1.0, 10000).tax_code("WI"));
+    assert_eq!(
+        ledger.add_invoice(invoice),
+        Err(Error::UnknownTaxCode(String::from("WI")))
+    );
+
+    let mut invoice = Invoice::new("INV-1", "LSHS", date(2019, 1, 5));
+    invoice.add_line(InvoiceLine::new("04", "Ice", 1.0, 10000));
+    ledger.add_invoice(invoice).unwrap();
+    assert_eq!(
+        ledger.issue_invoice("INV-1"),
+        Err(Error::NoControlAccount(String::from("LSHS")))
+    );
+    assert_eq!(
+        ledger.add_invoice(get_invoice("INV-1")),
+        Err(Error::DuplicateInvoice(String::from("INV-1")))
+    );
+    assert_eq!(
+        ledger.add_tax_code(TaxCode::new("WI", "Wisconsin", 5.0, "04")),
+        Err(Error::WrongAccountType(String::from("04")))
+    );
+
+    ledger.add_invoice(get_invoice("INV-2")).unwrap();
+    ledger.issue_invoice("INV-2").unwrap();
+    ledger
+        .record_invoice_payment("INV-2", "PMT-1", date(2019, 1, 20), 40000, "01")
+        .unwrap();
+
+    let saved = json::parse(&ledger.to_json().dump()).unwrap();
+    assert_eq!(saved["tax_codes"][0]["code"], "MN");
+    assert_eq!(saved["invoices"][1]["payments"][0]["amount"], 40000);
+
+    let loaded = Ledger::try_from(&saved).unwrap();
+    assert_eq!(loaded.get_tax_codes(), ledger.get_tax_codes());
+    assert_eq!(loaded.get_invoices(), ledger.get_invoices());
+    assert_eq!(
+        loaded.get_invoice("INV-2").unwrap().get_outstanding(),
+        47713
+    );
+}
+
+#[test]
+fn voids_can_be_undone_and_terms_are_fixed_when_issued() {
+    let mut ledger = issued_ledger();
+    ledger.void_invoice("INV-1", date(2019, 1, 31)).unwrap();
+    ledger.undo().unwrap();
+
+    let invoice = ledger.get_invoice("INV-1").unwrap();
+    assert_eq!(invoice.get_status(date(2019, 1, 31)), InvoiceStatus::Sent);
+    assert_eq!(invoice.get_outstanding(), 87713);
+    assert_eq!(ledger.get_balance("02", &BalanceOptions::new()), Ok(87713));
+    ledger
+        .record_invoice_payment("INV-1", "PMT-1", date(2019, 2, 1), 87713, "01")
+        .unwrap();
+
+    ledger.add_invoice(get_invoice("INV-2")).unwrap();
+    let mut school = ledger.get_contact("LNHS").unwrap();
+    school.set_payment_terms(PaymentTerms::net(10));
+    ledger.update_contact(school).unwrap();
+    assert_eq!(
+        ledger.get_invoice("INV-1").unwrap().get_due_date(),
+        Some(date(2019, 2, 4))
+    );
+    assert_eq!(
+        ledger.get_invoice("INV-2").unwrap().get_due_date(),
+        Some(date(2019, 1, 15))
+    );
+}
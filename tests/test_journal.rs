@@ -0,0 +1,134 @@
+extern crate chrono;
+extern crate cratchit;
+
+mod common;
+
+use common::date;
+use cratchit::{
+    Account, AccountType, AccountsChart, BalanceOptions, Currency, DateBasis, Ledger,
+    RegisterOptions, Split, Transaction,
+};
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(Account::new(
+        "01",
+        "Checking",
+        "Checking",
+        AccountType::Asset,
+        Currency::USDollar,
+        false,
+    ));
+    chart.add_top_level_account(Account::new(
+        "02",
+        "Payroll Taxes Payable",
+        "Payroll Taxes Payable",
+        AccountType::Liability,
+        Currency::USDollar,
+        false,
+    ));
+    chart.add_top_level_account(Account::new(
+        "05",
+        "Expenses",
+        "Expenses",
+        AccountType::Expense,
+        Currency::USDollar,
+        true,
+    ));
+    chart.add_top_level_account(Account::new(
+        "05-01",
+        "Wages",
+        "Wages",
+        AccountType::Expense,
+        Currency::USDollar,
+        false,
+    ));
+
+    Ledger::new(chart)
+}
+
+fn get_payroll_entry() -> Transaction {
+    let mut entry = Transaction::new("JE-7", date(2019, 1, 2), "December payroll accrual");
+    entry.set_effective_date(date(2018, 12, 31));
+    entry.set_reference("CHK-1042");
+
+    let mut wages = Split::new("05-01", 100000);
+    wages.set_memo("Gross wages");
+    entry.add_split(wages);
+
+    let mut taxes = Split::new("02", -7650);
+    taxes.set_memo("FICA withheld");
+    entry.add_split(taxes);
+
+    let mut net_pay = Split::new("01", -92350);
+    net_pay.set_memo("Net pay");
+    entry.add_split(net_pay);
+
+    entry
+}
+
+#[test]
+fn journal_entry_with_many_lines() {
+    let entry = get_payroll_entry();
+
+    assert_eq!(entry.get_splits().len(), 3);
+    assert_eq!(entry.get_total_debits(), 100000);
+    assert_eq!(entry.get_total_credits(), 100000);
+    assert!(entry.is_balanced());
+    assert_eq!(entry.get_reference(), Some(String::from("CHK-1042")));
+    assert_eq!(entry.get_splits()[1].get_memo(), "FICA withheld");
+}
+
+#[test]
+fn effective_date_defaults_to_posting_date() {
+    let entry = Transaction::new("JE-8", date(2019, 1, 2), "No effective date");
+
+    assert_eq!(entry.get_effective_date(), date(2019, 1, 2));
+    assert_eq!(entry.get_date_for(DateBasis::Effective), date(2019, 1, 2));
+    assert_eq!(entry.get_reference(), None);
+}
+
+#[test]
+fn balances_by_posting_and_effective_date() {
+    let mut ledger = get_ledger();
+    ledger.add_transaction(get_payroll_entry()).unwrap();
+
+    let posted = BalanceOptions::new().as_of(date(2018, 12, 31));
+    let effective = BalanceOptions::new()
+        .as_of(date(2018, 12, 31))
+        .date_basis(DateBasis::Effective);
+
+    assert_eq!(ledger.get_balance("02", &posted).unwrap(), 0);
+    assert_eq!(ledger.get_balance("02", &effective).unwrap(), 7650);
+    assert_eq!(ledger.get_balance("01", &effective).unwrap(), -92350);
+
+    let balances = ledger.get_balances(&effective);
+    assert_eq!(balances["05-01"], 100000);
+}
+
+#[test]
+fn balance_over_a_period() {
+    let mut ledger = get_ledger();
+    ledger.add_transaction(get_payroll_entry()).unwrap();
+
+    let january = BalanceOptions::new()
+        .since(date(2019, 1, 1))
+        .as_of(date(2019, 1, 31));
+
+    assert_eq!(ledger.get_balance("05-01", &january).unwrap(), 100000);
+}
+
+#[test]
+fn register_shows_reference_and_memo() {
+    let mut ledger = get_ledger();
+    ledger.add_transaction(get_payroll_entry()).unwrap();
+
+    let options = RegisterOptions::new().date_basis(DateBasis::Effective);
+    let register = ledger.get_register("01", &options).unwrap();
+    let entry = &register.get_entries()[0];
+
+    assert_eq!(entry.get_date(), date(2018, 12, 31));
+    assert_eq!(entry.get_reference(), Some(String::from("CHK-1042")));
+    assert_eq!(entry.get_memo(), "Net pay");
+    assert_eq!(entry.get_counterparts(), vec!["05-01", "02"]);
+}
@@ -0,0 +1,271 @@
+extern crate chrono;
+extern crate cratchit;
+extern crate json;
+
+mod common;
+
+use std::convert::TryFrom;
+
+use chrono::NaiveDate;
+use common::date;
+use cratchit::{
+    Account, AccountType, AccountsChart, BalanceOptions, Commodity, CommodityKind, Currency, Error,
+    Ledger, LotSelection, Split, Transaction,
+};
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(Account::new(
+        "01",
+        "Cash",
+        "Cash",
+        AccountType::Asset,
+        Currency::USDollar,
+        false,
+    ));
+    let mut brokerage = Account::new(
+        "02",
+        "Brokerage (VTI)",
+        "Brokerage (VTI)",
+        AccountType::Asset,
+        Currency::USDollar,
+        false,
+    );
+    brokerage.set_commodity("VTI");
+    chart.add_top_level_account(brokerage);
+    chart.add_top_level_account(Account::new(
+        "04",
+        "Capital Gains",
+        "Capital Gains",
+        AccountType::Income,
+        Currency::USDollar,
+        false,
+    ));
+
+    let mut ledger = Ledger::new(chart);
+    ledger
+        .add_commodity(Commodity::new(
+            "VTI",
+            "Total Stock Market ETF",
+            CommodityKind::MutualFund,
+            1,
+        ))
+        .unwrap();
+    ledger.set_capital_gains_account("04").unwrap();
+
+    buy(&mut ledger, "BUY-1", date(2018, 1, 2), 10, 100000);
+    buy(&mut ledger, "BUY-2", date(2018, 2, 1), 10, 150000);
+    buy(&mut ledger, "BUY-3", date(2018, 3, 1), 10, 120000);
+
+    ledger
+}
+
+fn buy(ledger: &mut Ledger, id: &str, on: NaiveDate, units: i64, cost: i64) {
+    let mut transaction = Transaction::new(id, on, "Buy VTI");
+    transaction.add_split(Split::new_commodity("02", units, cost));
+    transaction.add_split(Split::new("01", -cost));
+    ledger.add_transaction(transaction).unwrap();
+}
+
+fn sell(units: i64, proceeds: i64, selection: LotSelection) -> Transaction {
+    sell_on(date(2018, 6, 1), units, proceeds, selection)
+}
+
+fn sell_on(on: NaiveDate, units: i64, proceeds: i64, selection: LotSelection) -> Transaction {
+    let mut transaction = Transaction::new("SELL-1", on, "Sell VTI");
+    let mut split = Split::new_commodity("02", -units, -proceeds);
+    split.set_lot_selection(selection);
+    transaction.add_split(split);
+    transaction.add_split(Split::new("01", proceeds));
+
+    transaction
+}
+
+#[test]
+fn lot_selection_determines_the_realized_gain() {
+    let options = BalanceOptions::new();
+    let cases = vec![
+        (LotSelection::Fifo, 50000, "BUY-2"),
+        (LotSelection::Lifo, 30000, "BUY-1"),
+        (LotSelection::HighestCost, 0, "BUY-1"),
+    ];
+
+    for (selection, gain, oldest_open) in cases {
+        let mut ledger = get_ledger();
+        ledger.add_transaction(sell(10, 150000, selection)).unwrap();
+
+        assert_eq!(ledger.get_balance("02", &options), Ok(20));
+        assert_eq!(ledger.get_balance("04", &options), Ok(gain));
+        assert_eq!(
+            ledger.get_carrying_value("02", &options),
+            Ok(370000 - 150000 + gain)
+        );
+
+        let lots = ledger.get_lots("02", &options).unwrap();
+        assert_eq!(lots.len(), 2);
+        assert_eq!(lots[0].get_id(), oldest_open);
+    }
+}
+
+#[test]
+fn specific_identification_sells_the_named_lots() {
+    let mut ledger = get_ledger();
+    let selection =
+        LotSelection::Specific(vec![(String::from("BUY-3"), 5), (String::from("BUY-1"), 2)]);
+    ledger.add_transaction(sell(8, 128000, selection)).unwrap();
+
+    // 5 units at 120.00, 2 at 100.00 and the last unit first-in, first-out at 100.00.
+    assert_eq!(
+        ledger.get_balance("04", &BalanceOptions::new()),
+        Ok(128000 - 60000 - 30000)
+    );
+
+    let lots = ledger.get_lots("02", &BalanceOptions::new()).unwrap();
+    let open: Vec<(String, i64, i64)> = lots
+        .iter()
+        .map(|lot| (lot.get_id(), lot.get_units(), lot.get_cost()))
+        .collect();
+    assert_eq!(
+        open,
+        vec![
+            (String::from("BUY-1"), 7, 70000),
+            (String::from("BUY-2"), 10, 150000),
+            (String::from("BUY-3"), 5, 60000),
+        ]
+    );
+
+    let mut ledger = get_ledger();
+    let unknown = LotSelection::Specific(vec![(String::from("BUY-9"), 1)]);
+    assert_eq!(
+        ledger.add_transaction(sell(1, 15000, unknown)),
+        Err(Error::UnknownLot(String::from("BUY-9")))
+    );
+}
+
+#[test]
+fn sales_cannot_exceed_the_units_held() {
+    let mut ledger = get_ledger();
+    assert_eq!(
+        ledger.add_transaction(sell(31, 400000, LotSelection::Fifo)),
+        Err(Error::InsufficientUnits(String::from("02")))
+    );
+
+    let early = sell_on(date(2017, 12, 31), 5, 60000, LotSelection::Fifo);
+    assert_eq!(
+        ledger.add_transaction(early),
+        Err(Error::InsufficientUnits(String::from("02")))
+    );
+
+    let mut chart = AccountsChart::new();
+    let mut bitcoin = Account::new(
+        "02",
+        "Wallet",
+        "Wallet",
+        AccountType::Asset,
+        Currency::USDollar,
+        false,
+    );
+    bitcoin.set_commodity("BTC");
+    chart.add_top_level_account(bitcoin);
+    chart.add_top_level_account(Account::new(
+        "01",
+        "Cash",
+        "Cash",
+        AccountType::Asset,
+        Currency::USDollar,
+        false,
+    ));
+    let mut ledger = Ledger::new(chart);
+    let mut purchase = Transaction::new("BUY-1", date(2018, 1, 2), "Buy BTC");
+    purchase.add_split(Split::new_commodity("02", 50000000, 700000));
+    purchase.add_split(Split::new("01", -700000));
+    assert_eq!(
+        ledger.add_transaction(purchase),
+        Err(Error::UnknownCommodity(String::from("BTC")))
+    );
+}
+
+#[test]
+fn commodities_and_lot_selections_are_saved_with_the_ledger() {
+    let mut ledger = get_ledger();
+    ledger
+        .add_transaction(sell(10, 150000, LotSelection::HighestCost))
+        .unwrap();
+
+    let saved = json::parse(&ledger.to_json().dump()).unwrap();
+    assert_eq!(saved["commodities"][0]["kind"], "MutualFund");
+
+    let loaded = Ledger::try_from(&saved).unwrap();
+    assert_eq!(loaded.get_commodities(), ledger.get_commodities());
+    assert_eq!(
+        loaded
+            .get_chart()
+            .get_account_by_id("02")
+            .unwrap()
+            .get_commodity(),
+        Some(String::from("VTI"))
+    );
+    assert_eq!(
+        loaded.get_transaction_by_id("SELL-1").unwrap().get_splits()[0].get_lot_selection(),
+        LotSelection::HighestCost
+    );
+    assert_eq!(
+        loaded.get_lots("02", &BalanceOptions::new()),
+        ledger.get_lots("02", &BalanceOptions::new())
+    );
+}
+
+#[test]
+fn voided_purchases_and_sales_leave_the_lots_unchanged() {
+    let options = BalanceOptions::new();
+    let mut ledger = get_ledger();
+
+    ledger.void_transaction("BUY-2", date(2018, 2, 5)).unwrap();
+    let lots = ledger.get_lots("02", &options).unwrap();
+    assert_eq!(lots.len(), 2);
+    assert_eq!(lots[0].get_id(), "BUY-1");
+    assert_eq!(lots[1].get_id(), "BUY-3");
+
+    ledger
+        .add_transaction(sell(10, 150000, LotSelection::Fifo))
+        .unwrap();
+    assert_eq!(ledger.get_balance("04", &options), Ok(50000));
+    ledger.void_transaction("SELL-1", date(2018, 6, 2)).unwrap();
+
+    assert_eq!(ledger.get_lots("02", &options).unwrap(), lots);
+    assert_eq!(ledger.get_balance("02", &options), Ok(20));
+    assert_eq!(ledger.get_balance("04", &options), Ok(0));
+    assert_eq!(ledger.get_carrying_value("02", &options), Ok(220000));
+}
+
+#[test]
+fn editing_an_account_changes_its_commodity() {
+    let mut ledger = get_ledger();
+    let mut account = Account::new(
+        "03",
+        "Brokerage (VXUS)",
+        "Brokerage (VXUS)",
+        AccountType::Asset,
+        Currency::USDollar,
+        false,
+    );
+    ledger
+        .add_top_level_account(Account::clone(&account))
+        .unwrap();
+
+    account.set_commodity("VXUS");
+    ledger.update_account(account).unwrap();
+    let commodity = |ledger: &Ledger| {
+        ledger
+            .get_chart()
+            .get_account_by_id("03")
+            .unwrap()
+            .get_commodity()
+    };
+    assert_eq!(commodity(&ledger), Some(String::from("VXUS")));
+
+    ledger.undo().unwrap();
+    assert_eq!(commodity(&ledger), None);
+    ledger.redo().unwrap();
+    assert_eq!(commodity(&ledger), Some(String::from("VXUS")));
+}
@@ -0,0 +1,146 @@
+extern crate chrono;
+extern crate cratchit;
+
+mod common;
+
+use std::collections::HashMap;
+
+use common::date;
+use cratchit::{
+    Account, AccountType, AccountsChart, BalanceOptions, Currency, EntrySource, Error,
+    FiscalCalendar, Ledger,
+};
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    let accounts = [
+        ("01", "Assets", AccountType::Asset, true),
+        ("01-01", "Checking", AccountType::Asset, false),
+        ("01-02", "Savings", AccountType::Asset, false),
+        ("02", "Credit Card", AccountType::Liability, false),
+        ("03", "Opening Balances", AccountType::Equity, false),
+        ("03-01", "Retained Earnings", AccountType::Equity, false),
+        ("04", "Dues", AccountType::Income, false),
+    ];
+    for &(id, name, account_type, placeholder) in &accounts {
+        chart.add_top_level_account(Account::new(
+            id,
+            name,
+            name,
+            account_type,
+            Currency::USDollar,
+            placeholder,
+        ));
+    }
+
+    Ledger::new(chart)
+}
+
+fn balances(entries: &[(&str, i64)]) -> HashMap<String, i64> {
+    entries
+        .iter()
+        .map(|&(id, balance)| (String::from(id), balance))
+        .collect()
+}
+
+#[test]
+fn opening_balances_offset_to_equity() {
+    let mut ledger = get_ledger();
+    let opening = balances(&[("01-01", 125000), ("01-02", 40000), ("02", 25000)]);
+    let id = ledger
+        .post_opening_balances(date(2018, 7, 1), &opening, Some("03"))
+        .unwrap();
+
+    let entry = ledger.get_transaction_by_id(&id).unwrap();
+    let splits = entry.get_splits();
+    assert_eq!(entry.get_source(), EntrySource::OpeningBalance);
+    assert!(entry.is_system_generated());
+    assert_eq!(splits.len(), 4);
+    assert_eq!(splits[3].get_account_id(), "03");
+    assert_eq!(splits[3].get_amount(), -140000);
+
+    let options = BalanceOptions::new();
+    assert_eq!(ledger.get_balance("01-01", &options).unwrap(), 125000);
+    assert_eq!(ledger.get_balance("02", &options).unwrap(), 25000);
+    assert_eq!(ledger.get_balance("03", &options).unwrap(), 140000);
+
+    assert_eq!(
+        ledger.post_opening_balances(date(2018, 7, 1), &opening, Some("03")),
+        Err(Error::DuplicateTransaction(id))
+    );
+}
+
+#[test]
+fn complete_trial_balance_needs_no_offset() {
+    let ledger = get_ledger();
+    let opening = balances(&[("01-01", 100000), ("02", 30000), ("03-01", 70000)]);
+    let entry = ledger
+        .preview_opening_balances(date(2018, 7, 1), &opening, None)
+        .unwrap();
+
+    assert!(entry.is_balanced());
+    assert_eq!(entry.get_splits().len(), 3);
+    assert!(entry
+        .get_splits()
+        .iter()
+        .all(|split| split.get_account_id() != "03"));
+    assert_eq!(ledger.get_num_transactions(), 0);
+}
+
+#[test]
+fn unbalanced_opening_balances_need_an_offset_account() {
+    let mut ledger = get_ledger();
+    let opening = balances(&[("01-01", 100000), ("02", 30000), ("03-01", 60000)]);
+
+    assert_eq!(
+        ledger.preview_opening_balances(date(2018, 7, 1), &opening, None),
+        Err(Error::Unbalanced(String::from("OPENING-2018-07-01")))
+    );
+    assert_eq!(
+        ledger.post_opening_balances(date(2018, 7, 1), &opening, None),
+        Err(Error::Unbalanced(String::from("OPENING-2018-07-01")))
+    );
+    assert_eq!(ledger.get_num_transactions(), 0);
+
+    ledger
+        .post_opening_balances(date(2018, 7, 1), &opening, Some("03"))
+        .unwrap();
+    assert_eq!(
+        ledger.get_balance("03", &BalanceOptions::new()).unwrap(),
+        10000
+    );
+}
+
+#[test]
+fn opening_balances_to_invalid_accounts() {
+    let mut ledger = get_ledger();
+    let day = date(2018, 7, 1);
+
+    assert_eq!(
+        ledger.post_opening_balances(day, &balances(&[("01", 1000)]), Some("03")),
+        Err(Error::PlaceholderAccount(String::from("01")))
+    );
+    assert_eq!(
+        ledger.post_opening_balances(day, &balances(&[("09", 1000)]), Some("03")),
+        Err(Error::UnknownAccount(String::from("09")))
+    );
+    assert_eq!(
+        ledger.post_opening_balances(day, &balances(&[("01-01", 1000)]), Some("04")),
+        Err(Error::WrongAccountType(String::from("04")))
+    );
+    assert_eq!(ledger.get_num_transactions(), 0);
+}
+
+#[test]
+fn opening_balances_in_closed_period() {
+    let mut ledger = get_ledger();
+    ledger
+        .add_fiscal_year(&FiscalCalendar::Monthly { start_month: 7 }, 2019)
+        .unwrap();
+    ledger.close_period("FY2019-P01", "Migrated").unwrap();
+
+    assert_eq!(
+        ledger.post_opening_balances(date(2018, 7, 1), &balances(&[("01-01", 1000)]), Some("03")),
+        Err(Error::ClosedPeriod(String::from("FY2019-P01")))
+    );
+}
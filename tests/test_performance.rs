@@ -0,0 +1,214 @@
+extern crate chrono;
+extern crate cratchit;
+
+mod common;
+
+use common::{account, date, post};
+use cratchit::{
+    Account, AccountType, AccountsChart, BalanceOptions, Commodity, CommodityKind, Currency, Error,
+    Ledger, PriceDatabase, Split,
+};
+
+/// A portfolio with 100,000.00 contributed and invested in 10 shares on 2018-01-01, and another
+/// 110,000.00 contributed in cash on 2018-07-01. Shares are worth 100.00, 110.00 and 121.00 on
+/// the first days of 2018-01, 2018-07 and 2019-01.
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(account("01", "Checking", AccountType::Asset));
+    let mut investments = Account::new(
+        "02",
+        "Investments",
+        "Investments",
+        AccountType::Asset,
+        Currency::USDollar,
+        true,
+    );
+    investments.add_sub_account(account("02-01", "Brokerage Cash", AccountType::Asset));
+    let mut shares = account("02-02", "Brokerage (ACME)", AccountType::Asset);
+    shares.set_commodity("ACME");
+    investments.add_sub_account(shares);
+    chart.add_top_level_account(investments);
+    chart.add_top_level_account(account("03", "Capital", AccountType::Equity));
+    chart.add_top_level_account(account("04", "Dividends", AccountType::Income));
+    chart.add_top_level_account(account("05", "Capital Gains", AccountType::Income));
+    chart.add_top_level_account(account("06", "Interest", AccountType::Income));
+
+    let mut ledger = Ledger::new(chart);
+    ledger
+        .add_commodity(Commodity::new(
+            "ACME",
+            "Acme Corp.",
+            CommodityKind::Stock,
+            1,
+        ))
+        .unwrap();
+    ledger.set_capital_gains_account("05").unwrap();
+    {
+        let prices = ledger.get_price_database_mut();
+        prices.add_price("ACME", "USD", date(2018, 1, 1), 10000.0);
+        prices.add_price("ACME", "USD", date(2018, 7, 1), 11000.0);
+        prices.add_price("ACME", "USD", date(2019, 1, 1), 12100.0);
+    }
+
+    post(
+        &mut ledger,
+        "DEP-1",
+        date(2018, 1, 1),
+        vec![Split::new("02-01", 100000), Split::new("01", -100000)],
+    )
+    .unwrap();
+    post(
+        &mut ledger,
+        "BUY-1",
+        date(2018, 1, 1),
+        vec![
+            Split::new_commodity("02-02", 10, 100000),
+            Split::new("02-01", -100000),
+        ],
+    )
+    .unwrap();
+    post(
+        &mut ledger,
+        "DEP-2",
+        date(2018, 7, 1),
+        vec![Split::new("02-01", 110000), Split::new("01", -110000)],
+    )
+    .unwrap();
+
+    ledger
+}
+
+#[test]
+fn returns_are_measured_around_contributions() {
+    let ledger = get_ledger();
+    let start = date(2018, 1, 1);
+    let end = date(2019, 1, 1);
+
+    assert_eq!(ledger.get_market_value("02", start), Ok(100000));
+    assert_eq!(ledger.get_market_value("02", date(2018, 7, 1)), Ok(220000));
+    assert_eq!(ledger.get_market_value("02", end), Ok(231000));
+
+    let time_weighted = ledger.get_time_weighted_return("02", start, end).unwrap();
+    assert!((time_weighted - 0.155).abs() < 1e-9);
+
+    let money_weighted = ledger.get_money_weighted_return("02", start, end).unwrap();
+    assert!((money_weighted - 0.136637).abs() < 1e-5);
+
+    let shares_only = ledger
+        .get_time_weighted_return("02-02", start, end)
+        .unwrap();
+    assert!((shares_only - 0.21).abs() < 1e-9);
+}
+
+#[test]
+fn unrealized_gains_are_reported_per_lot() {
+    let mut ledger = get_ledger();
+    post(
+        &mut ledger,
+        "BUY-2",
+        date(2018, 7, 1),
+        vec![
+            Split::new_commodity("02-02", 5, 55000),
+            Split::new("02-01", -55000),
+        ],
+    )
+    .unwrap();
+
+    let gains = ledger.get_unrealized_gains("02", date(2019, 1, 1)).unwrap();
+    let summary: Vec<(String, i64, i64)> = gains
+        .iter()
+        .map(|gain| {
+            (
+                gain.get_lot().get_id(),
+                gain.get_market_value(),
+                gain.get_gain(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (String::from("BUY-1"), 121000, 21000),
+            (String::from("BUY-2"), 60500, 5500),
+        ]
+    );
+
+    let earlier = ledger.get_unrealized_gains("02", date(2018, 3, 1)).unwrap();
+    assert_eq!(earlier.len(), 1);
+    assert_eq!(earlier[0].get_gain(), 0);
+}
+
+#[test]
+fn income_is_summarized_by_income_account() {
+    let mut ledger = get_ledger();
+    post(
+        &mut ledger,
+        "DIV-1",
+        date(2018, 3, 15),
+        vec![Split::new("02-01", 1500), Split::new("04", -1500)],
+    )
+    .unwrap();
+    post(
+        &mut ledger,
+        "INT-1",
+        date(2018, 6, 30),
+        vec![Split::new("02-01", 40), Split::new("06", -40)],
+    )
+    .unwrap();
+    post(
+        &mut ledger,
+        "DIV-2",
+        date(2018, 9, 15),
+        vec![Split::new("02-01", 1600), Split::new("04", -1600)],
+    )
+    .unwrap();
+    post(
+        &mut ledger,
+        "SELL-1",
+        date(2018, 10, 1),
+        vec![
+            Split::new_commodity("02-02", -2, -23000),
+            Split::new("02-01", 23000),
+        ],
+    )
+    .unwrap();
+
+    let all = ledger
+        .get_investment_income("02", &BalanceOptions::new())
+        .unwrap();
+    assert_eq!(all.len(), 2);
+    assert_eq!(all["04"], 3100);
+    assert_eq!(all["06"], 40);
+    assert_eq!(ledger.get_balance("05", &BalanceOptions::new()), Ok(3000));
+
+    let first_half = BalanceOptions::new().as_of(date(2018, 6, 30));
+    let income = ledger.get_investment_income("02", &first_half).unwrap();
+    assert_eq!(income["04"], 1500);
+}
+
+#[test]
+fn returns_require_an_investment_and_prices() {
+    let mut ledger = get_ledger();
+    assert_eq!(
+        ledger.get_time_weighted_return("01", date(2017, 1, 1), date(2017, 12, 31)),
+        Err(Error::UndefinedReturn(String::from("01")))
+    );
+    assert_eq!(
+        ledger.get_money_weighted_return("01", date(2017, 1, 1), date(2017, 12, 31)),
+        Err(Error::UndefinedReturn(String::from("01")))
+    );
+    assert_eq!(
+        ledger.get_market_value("99", date(2018, 1, 1)),
+        Err(Error::UnknownAccount(String::from("99")))
+    );
+
+    *ledger.get_price_database_mut() = PriceDatabase::new();
+    assert_eq!(
+        ledger.get_unrealized_gains("02", date(2018, 1, 1)),
+        Err(Error::UnknownRate(
+            String::from("ACME"),
+            String::from("USD"),
+            date(2018, 1, 1)
+        ))
+    );
+}
@@ -0,0 +1,102 @@
+extern crate chrono;
+extern crate cratchit;
+extern crate json;
+
+mod common;
+
+use std::convert::TryFrom;
+
+use common::date;
+use cratchit::{AccountsChart, Error, Ledger, Price, PriceDatabase, PriceFallback};
+
+fn get_prices() -> PriceDatabase {
+    let mut prices = PriceDatabase::new();
+    prices.add_price("EUR", "USD", date(2018, 3, 1), 1.25);
+    prices.add_price("EUR", "USD", date(2018, 3, 5), 1.2);
+    prices.add_price("USD", "CAD", date(2018, 3, 1), 1.25);
+
+    prices
+}
+
+#[test]
+fn rates_fall_back_to_earlier_quotes() {
+    let mut prices = get_prices();
+    assert_eq!(prices.get_rate("EUR", "USD", date(2018, 3, 4)), Ok(1.25));
+    assert_eq!(prices.get_rate("EUR", "USD", date(2018, 3, 31)), Ok(1.2));
+    assert_eq!(
+        prices.get_rate("EUR", "USD", date(2018, 2, 28)),
+        Err(Error::UnknownRate(
+            String::from("EUR"),
+            String::from("USD"),
+            date(2018, 2, 28)
+        ))
+    );
+
+    prices.set_fallback(PriceFallback::Exact);
+    assert!(prices.get_rate("EUR", "USD", date(2018, 3, 4)).is_err());
+    assert_eq!(prices.get_rate("EUR", "USD", date(2018, 3, 5)), Ok(1.2));
+
+    prices.set_fallback(PriceFallback::NearestEarlierWithin(3));
+    assert_eq!(prices.get_rate("EUR", "USD", date(2018, 3, 8)), Ok(1.2));
+    assert!(prices.get_rate("EUR", "USD", date(2018, 3, 9)).is_err());
+}
+
+#[test]
+fn inverse_and_triangulated_rates_are_derived() {
+    let prices = get_prices();
+    let on = date(2018, 3, 2);
+
+    assert_eq!(prices.get_rate("USD", "EUR", on), Ok(0.8));
+    assert_eq!(prices.get_rate("EUR", "CAD", on), Ok(1.5625));
+    assert!((prices.get_rate("CAD", "EUR", on).unwrap() - 0.64).abs() < 1e-12);
+    assert_eq!(prices.get_rate("GBP", "GBP", on), Ok(1.0));
+    assert!(prices.get_rate("GBP", "USD", on).is_err());
+}
+
+#[test]
+fn quotes_are_replaced_and_removed() {
+    let mut prices = get_prices();
+    prices.add_price("EUR", "USD", date(2018, 3, 1), 1.3);
+
+    let quotes = prices.get_prices_for("EUR", "USD");
+    assert_eq!(quotes.len(), 2);
+    assert_eq!(quotes[0].get_rate(), 1.3);
+    assert_eq!(prices.get_prices().len(), 3);
+
+    let removed = prices.remove_price("EUR", "USD", date(2018, 3, 5)).unwrap();
+    assert_eq!(removed.get_date(), date(2018, 3, 5));
+    assert!(prices
+        .remove_price("EUR", "USD", date(2018, 3, 5))
+        .is_none());
+    assert_eq!(prices.get_rate("EUR", "USD", date(2018, 3, 31)), Ok(1.3));
+}
+
+#[test]
+fn prices_are_saved_with_the_ledger() {
+    let mut ledger = Ledger::new(AccountsChart::new());
+    *ledger.get_price_database_mut() = get_prices();
+
+    let saved = json::parse(&ledger.to_json().dump()).unwrap();
+    assert_eq!(saved["prices"].len(), 3);
+    assert_eq!(saved["prices"][0]["commodity"], "EUR");
+
+    let loaded = Ledger::try_from(&saved).unwrap();
+    assert_eq!(loaded.get_price_database(), &get_prices());
+}
+
+#[test]
+fn malformed_quotes_are_skipped_when_loading() {
+    let mut saved = get_prices().to_json();
+    let mut undated = Price::new("GBP", "USD", date(2018, 3, 1), 1.4).to_json();
+    undated["date"] = "March 1".into();
+    let mut unrated = Price::new("JPY", "USD", date(2018, 3, 1), 0.01).to_json();
+    unrated.remove("rate");
+    saved.push(undated.clone()).unwrap();
+    saved.push(unrated).unwrap();
+
+    assert_eq!(PriceDatabase::from(&saved), get_prices());
+    assert!(matches!(
+        Price::try_from(&undated),
+        Err(Error::InvalidJson(_))
+    ));
+}
@@ -0,0 +1,239 @@
+extern crate chrono;
+extern crate cratchit;
+
+mod common;
+
+use common::date;
+use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Currency, Error, Ledger,
+               MatchTolerance, Split, StatementLine, StatusFilter, Transaction,
+               TransactionStatus};
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(Account::new(
+        "01",
+        "Checking",
+        "Checking",
+        AccountType::Asset,
+        Currency::USDollar,
+        false,
+    ));
+    chart.add_top_level_account(Account::new(
+        "04",
+        "Ice Time Income",
+        "Ice Time Income",
+        AccountType::Income,
+        Currency::USDollar,
+        false,
+    ));
+    chart.add_top_level_account(Account::new(
+        "05",
+        "Rink Rental",
+        "Rink Rental",
+        AccountType::Expense,
+        Currency::USDollar,
+        false,
+    ));
+
+    let mut ledger = Ledger::new(chart);
+    let entries = [
+        ("t1", 2, "Lakeville North deposit", "04", -50000),
+        ("t2", 5, "Ames Arena", "05", 20000),
+        ("t3", 9, "Hasse Arena", "05", 20000),
+        ("t4", 28, "Lakeville South deposit", "04", -10000),
+        ("t5", 3, "December rent", "05", 5000),
+    ];
+    for &(id, day, description, other, amount) in &entries {
+        let month = if id == "t5" { 12 } else { 11 };
+        let mut transaction = Transaction::new(id, date(2018, month, day), description);
+        transaction.add_split(Split::new("01", -amount));
+        transaction.add_split(Split::new(other, amount));
+        ledger.add_transaction(transaction).unwrap();
+    }
+
+    ledger
+}
+
+#[test]
+fn live_difference_while_marking() {
+    let ledger = get_ledger();
+    let mut session = ledger
+        .start_reconciliation("01", date(2018, 11, 30), 10000)
+        .unwrap();
+
+    assert_eq!(session.get_cleared_balance(&ledger), 0);
+    assert_eq!(session.get_difference(&ledger), 10000);
+
+    session.mark_cleared(&ledger, "t1", 0).unwrap();
+    session.mark_cleared(&ledger, "t2", 0).unwrap();
+    assert_eq!(session.get_difference(&ledger), -20000);
+
+    session.mark_cleared(&ledger, "t3", 0).unwrap();
+    assert_eq!(session.get_difference(&ledger), 0);
+
+    session.unmark_cleared("t3", 0);
+    assert!(!session.is_cleared("t3", 0));
+    assert_eq!(session.get_difference(&ledger), -20000);
+}
+
+#[test]
+fn auto_match_statement_lines() {
+    let ledger = get_ledger();
+    let mut session = ledger
+        .start_reconciliation("01", date(2018, 11, 30), 10000)
+        .unwrap();
+
+    let lines = vec![
+        StatementLine::new(date(2018, 11, 3), 50000, "DEPOSIT"),
+        StatementLine::new(date(2018, 11, 10), -20000, "HASSE ARENA"),
+        StatementLine::new(date(2018, 11, 7), -20000, "AMES ARENA"),
+        StatementLine::new(date(2018, 11, 15), -99900, "UNKNOWN"),
+    ];
+    let matches = session.auto_match(&ledger, &lines, MatchTolerance::new(3, 0));
+
+    assert_eq!(matches.len(), 3);
+    assert_eq!(matches[1].get_transaction_id(), "t3");
+    assert_eq!(matches[2].get_transaction_id(), "t2");
+    assert_eq!(session.get_difference(&ledger), 0);
+}
+
+#[test]
+fn auto_match_ignores_postings_after_statement_date() {
+    let ledger = get_ledger();
+    let mut session = ledger
+        .start_reconciliation("01", date(2018, 11, 30), 0)
+        .unwrap();
+
+    let lines = vec![StatementLine::new(date(2018, 11, 30), -5000, "December rent")];
+    let matches = session.auto_match(&ledger, &lines, MatchTolerance::new(10, 0));
+
+    assert!(matches.is_empty());
+}
+
+#[test]
+fn finalizing_reconciles_cleared_postings() {
+    let mut ledger = get_ledger();
+    ledger
+        .set_split_status("t1", 0, TransactionStatus::Cleared)
+        .unwrap();
+
+    let mut session = ledger
+        .start_reconciliation("01", date(2018, 11, 30), 30000)
+        .unwrap();
+    assert!(session.is_cleared("t1", 0));
+
+    session.mark_cleared(&ledger, "t2", 0).unwrap();
+    assert!(ledger.finalize_reconciliation(&session).is_ok());
+
+    let transaction = ledger.get_transaction_by_id("t2").unwrap();
+    assert_eq!(
+        transaction.get_splits()[0].get_status(),
+        TransactionStatus::Reconciled
+    );
+
+    let reconciled = BalanceOptions::new().status(StatusFilter::Reconciled);
+    assert_eq!(ledger.get_balance("01", &reconciled).unwrap(), 30000);
+
+    let history = ledger.get_reconciliations("01");
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].get_statement_date(), date(2018, 11, 30));
+    assert_eq!(history[0].get_ending_balance(), 30000);
+    assert_eq!(history[0].get_splits().len(), 2);
+
+    let next = ledger
+        .start_reconciliation("01", date(2018, 12, 31), 30000)
+        .unwrap();
+    assert_eq!(next.get_cleared_balance(&ledger), 30000);
+}
+
+#[test]
+fn finalizing_with_a_difference_is_rejected() {
+    let mut ledger = get_ledger();
+    let mut session = ledger
+        .start_reconciliation("01", date(2018, 11, 30), 10000)
+        .unwrap();
+    session.mark_cleared(&ledger, "t1", 0).unwrap();
+
+    let result = ledger.finalize_reconciliation(&session);
+    assert_eq!(result, Err(Error::ReconciliationDifference(-40000)));
+    assert!(ledger.get_reconciliations("01").is_empty());
+}
+
+#[test]
+fn only_postings_covered_by_the_statement_can_be_cleared() {
+    let mut ledger = get_ledger();
+    let mut session = ledger
+        .start_reconciliation("01", date(2018, 11, 30), 0)
+        .unwrap();
+
+    assert_eq!(
+        session.mark_cleared(&ledger, "t1", 1),
+        Err(Error::SplitOutsideReconciliation(String::from("t1"), 1))
+    );
+    assert_eq!(
+        session.mark_cleared(&ledger, "t5", 0),
+        Err(Error::SplitOutsideReconciliation(String::from("t5"), 0))
+    );
+    assert_eq!(
+        session.mark_cleared(&ledger, "t1", 2),
+        Err(Error::UnknownSplit(String::from("t1"), 2))
+    );
+    assert_eq!(
+        session.mark_cleared(&ledger, "t9", 0),
+        Err(Error::UnknownTransaction(String::from("t9")))
+    );
+    assert!(session.get_cleared().is_empty());
+
+    // A session marked before the posting was edited is checked again when finalized.
+    let mut session = ledger
+        .start_reconciliation("01", date(2018, 11, 30), 20000)
+        .unwrap();
+    session.mark_cleared(&ledger, "t4", 0).unwrap();
+    session.mark_cleared(&ledger, "t1", 0).unwrap();
+    session.mark_cleared(&ledger, "t2", 0).unwrap();
+    session.mark_cleared(&ledger, "t3", 0).unwrap();
+    let mut moved = Transaction::new("t4", date(2018, 12, 20), "Lakeville South deposit");
+    moved.add_split(Split::new("01", 10000));
+    moved.add_split(Split::new("04", -10000));
+    ledger.update_transaction(moved).unwrap();
+
+    assert_eq!(
+        ledger.finalize_reconciliation(&session),
+        Err(Error::SplitOutsideReconciliation(String::from("t4"), 0))
+    );
+    assert!(ledger.get_reconciliations("01").is_empty());
+}
+
+#[test]
+fn voided_postings_cannot_be_cleared() {
+    let mut ledger = get_ledger();
+    let mut session = ledger
+        .start_reconciliation("01", date(2018, 11, 30), 30000)
+        .unwrap();
+    session.mark_cleared(&ledger, "t1", 0).unwrap();
+    session.mark_cleared(&ledger, "t3", 0).unwrap();
+    ledger.void_transaction("t2", date(2018, 11, 20)).unwrap();
+
+    assert_eq!(
+        session.mark_cleared(&ledger, "t2", 0),
+        Err(Error::InvalidStatusTransition(
+            TransactionStatus::Voided,
+            TransactionStatus::Cleared
+        ))
+    );
+    ledger.finalize_reconciliation(&session).unwrap();
+
+    // A session marked before the posting was voided is checked again when finalized.
+    let mut session = ledger
+        .start_reconciliation("01", date(2018, 11, 30), 40000)
+        .unwrap();
+    session.mark_cleared(&ledger, "t4", 0).unwrap();
+    ledger.void_transaction("t4", date(2018, 11, 29)).unwrap();
+    assert_eq!(
+        ledger.finalize_reconciliation(&session),
+        Err(Error::InvalidStatusTransition(
+            TransactionStatus::Voided,
+            TransactionStatus::Cleared
+        ))
+    );
+}
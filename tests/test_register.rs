@@ -0,0 +1,176 @@
+extern crate chrono;
+extern crate cratchit;
+extern crate json;
+
+mod common;
+
+use chrono::NaiveDate;
+use common::date;
+use cratchit::{AccountsChart, Error, Ledger, RegisterOptions, Split, Transaction};
+
+fn get_chart_of_accounts() -> json::JsonValue {
+    let parsed = json::parse(
+        r#"
+
+        {
+          "accounts": [
+            {
+                "name": "Assets",
+                "description": "Assets",
+                "id": "01",
+                "type": 1,
+                "currency": "USD",
+                "placeholder": true,
+                "subaccounts": [
+                    {
+                      "name": "Checking",
+                      "description": "Checking Account",
+                      "id": "01-01",
+                      "type": 1,
+                      "currency": "USD",
+                      "placeholder": false,
+                      "subaccounts": []
+                    },
+                    {
+                      "name": "Savings",
+                      "description": "Savings Account",
+                      "id": "01-02",
+                      "type": 1,
+                      "currency": "USD",
+                      "placeholder": false,
+                      "subaccounts": []
+                    }
+                ]
+            },
+            {
+                "name": "Ice Time Income",
+                "description": "Income from ice time rentals",
+                "id": "04",
+                "type": 4,
+                "currency": "USD",
+                "placeholder": false,
+                "subaccounts": []
+            }
+          ]
+      }
+
+      "#,
+    );
+
+    parsed.unwrap()
+}
+
+fn transfer(id: &str, date: NaiveDate, debit: &str, credit: &str, amount: i64) -> Transaction {
+    let mut transaction = Transaction::new(id, date, id);
+    transaction.add_split(Split::new(debit, amount));
+    transaction.add_split(Split::new(credit, -amount));
+
+    transaction
+}
+
+fn get_ledger() -> Ledger {
+    let mut ledger = Ledger::new(AccountsChart::from(&get_chart_of_accounts()));
+    ledger
+        .add_transaction(transfer("t3", date(2018, 3, 1), "01-01", "04", 30000))
+        .unwrap();
+    ledger
+        .add_transaction(transfer("t1", date(2018, 1, 1), "01-01", "04", 10000))
+        .unwrap();
+    ledger
+        .add_transaction(transfer("t2", date(2018, 2, 1), "01-02", "01-01", 2500))
+        .unwrap();
+
+    ledger
+}
+
+#[test]
+fn register_is_in_date_order_with_running_balance() {
+    let ledger = get_ledger();
+    let register = ledger
+        .get_register("01-01", &RegisterOptions::new())
+        .unwrap();
+    let entries = register.get_entries();
+
+    assert_eq!(register.get_total_entries(), 3);
+    assert_eq!(entries[0].get_transaction_id(), "t1");
+    assert_eq!(entries[0].get_balance(), 10000);
+    assert_eq!(entries[1].get_transaction_id(), "t2");
+    assert_eq!(entries[1].get_amount(), -2500);
+    assert_eq!(entries[1].get_counterparts(), vec!["01-02"]);
+    assert_eq!(entries[1].get_balance(), 7500);
+    assert_eq!(entries[2].get_balance(), 37500);
+}
+
+#[test]
+fn register_for_credit_account_uses_normal_balance() {
+    let ledger = get_ledger();
+    let register = ledger.get_register("04", &RegisterOptions::new()).unwrap();
+    let entries = register.get_entries();
+
+    assert_eq!(entries[0].get_amount(), 10000);
+    assert_eq!(entries[1].get_balance(), 40000);
+}
+
+#[test]
+fn register_date_range_keeps_prior_balance() {
+    let ledger = get_ledger();
+    let options = RegisterOptions::new()
+        .starting(date(2018, 2, 1))
+        .ending(date(2018, 2, 28));
+    let register = ledger.get_register("01-01", &options).unwrap();
+
+    assert_eq!(register.get_total_entries(), 1);
+    assert_eq!(register.get_opening_balance(), 10000);
+    assert_eq!(register.get_entries()[0].get_balance(), 7500);
+}
+
+#[test]
+fn register_paging() {
+    let ledger = get_ledger();
+    let options = RegisterOptions::new().page(1, 1);
+    let register = ledger.get_register("01-01", &options).unwrap();
+    let entries = register.get_entries();
+
+    assert_eq!(register.get_total_entries(), 3);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].get_transaction_id(), "t2");
+    assert_eq!(register.get_opening_balance(), 10000);
+}
+
+#[test]
+fn register_for_placeholder_includes_sub_accounts() {
+    let ledger = get_ledger();
+    let direct = ledger.get_register("01", &RegisterOptions::new()).unwrap();
+    assert_eq!(direct.get_total_entries(), 0);
+
+    let options = RegisterOptions::new().include_sub_accounts(true);
+    let register = ledger.get_register("01", &options).unwrap();
+    let entries = register.get_entries();
+
+    assert_eq!(register.get_total_entries(), 4);
+    assert_eq!(entries[3].get_balance(), 40000);
+}
+
+#[test]
+fn register_for_unknown_account() {
+    let ledger = get_ledger();
+    let result = ledger.get_register("99", &RegisterOptions::new());
+
+    assert_eq!(result, Err(Error::UnknownAccount(String::from("99"))));
+}
+
+#[test]
+fn adding_invalid_transactions() {
+    let mut ledger = get_ledger();
+
+    let result = ledger.add_transaction(transfer("t4", date(2018, 4, 1), "01", "04", 100));
+    assert_eq!(result, Err(Error::PlaceholderAccount(String::from("01"))));
+
+    let result = ledger.add_transaction(transfer("t1", date(2018, 4, 1), "01-01", "04", 100));
+    assert_eq!(result, Err(Error::DuplicateTransaction(String::from("t1"))));
+
+    let mut unbalanced = Transaction::new("t5", date(2018, 4, 1), "Unbalanced");
+    unbalanced.add_split(Split::new("01-01", 100));
+    let result = ledger.add_transaction(unbalanced);
+    assert_eq!(result, Err(Error::Unbalanced(String::from("t5"))));
+}
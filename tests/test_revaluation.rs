@@ -0,0 +1,164 @@
+extern crate chrono;
+extern crate cratchit;
+
+mod common;
+
+use common::date;
+use cratchit::{
+    Account, AccountType, AccountsChart, BalanceOptions, Currency, EntrySource, Error, Ledger,
+    Split, Transaction,
+};
+
+/// Build a `Ledger` holding EUR 1,000.00 in a bank account and owing CAD 500.00 to a supplier.
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    for &(id, name, account_type, currency) in &[
+        ("01", "Checking (EUR)", AccountType::Asset, Currency::Euro),
+        ("01-USD", "Checking", AccountType::Asset, Currency::USDollar),
+        (
+            "02",
+            "Payable (CAD)",
+            AccountType::Liability,
+            Currency::CanadianDollar,
+        ),
+        ("03", "Capital", AccountType::Equity, Currency::USDollar),
+        (
+            "04",
+            "Exchange Gain",
+            AccountType::Income,
+            Currency::USDollar,
+        ),
+        (
+            "05",
+            "Unrealized FX",
+            AccountType::Expense,
+            Currency::USDollar,
+        ),
+        (
+            "05-01",
+            "Supplies",
+            AccountType::Expense,
+            Currency::USDollar,
+        ),
+    ] {
+        chart.add_top_level_account(Account::new(id, name, name, account_type, currency, false));
+    }
+
+    let mut ledger = Ledger::new(chart);
+    let mut deposit = Transaction::new("0001", date(2018, 1, 5), "Opening deposit");
+    deposit.add_split(Split::new_foreign("01", 100000, 1.2));
+    deposit.add_split(Split::new("03", -120000));
+    ledger.add_transaction(deposit).unwrap();
+
+    let mut bill = Transaction::new("0002", date(2018, 1, 10), "Supplies on credit");
+    bill.add_split(Split::new("05-01", 40000));
+    bill.add_split(Split::new_foreign("02", -50000, 0.8));
+    ledger.add_transaction(bill).unwrap();
+
+    let prices = ledger.get_price_database_mut();
+    prices.add_price("EUR", "USD", date(2018, 1, 31), 1.25);
+    prices.add_price("CAD", "USD", date(2018, 1, 31), 0.78);
+
+    ledger
+}
+
+fn carrying_value(ledger: &Ledger, id: &str) -> i64 {
+    ledger
+        .get_carrying_value(id, &BalanceOptions::new())
+        .unwrap()
+}
+
+#[test]
+fn foreign_balances_are_revalued() {
+    let mut ledger = get_ledger();
+    let preview = ledger
+        .preview_revaluation(date(2018, 1, 31), Currency::USDollar, "05")
+        .unwrap();
+    assert_eq!(preview.get_source(), EntrySource::Revaluation);
+    assert_eq!(preview.get_splits().len(), 3);
+    assert_eq!(ledger.get_num_transactions(), 2);
+
+    ledger
+        .post_revaluation(date(2018, 1, 31), Currency::USDollar, "05")
+        .unwrap();
+    assert_eq!(carrying_value(&ledger, "01"), 125000);
+    assert_eq!(carrying_value(&ledger, "02"), 39000);
+    assert_eq!(
+        ledger.get_balance("05", &BalanceOptions::new()).unwrap(),
+        -6000
+    );
+    assert_eq!(
+        ledger.get_balance("01", &BalanceOptions::new()).unwrap(),
+        100000
+    );
+}
+
+#[test]
+fn revaluation_requires_rates_and_a_gain_loss_account() {
+    let mut ledger = get_ledger();
+    assert_eq!(
+        ledger.preview_revaluation(date(2018, 1, 31), Currency::USDollar, "03"),
+        Err(Error::WrongAccountType(String::from("03")))
+    );
+    assert_eq!(
+        ledger.preview_revaluation(date(2018, 1, 31), Currency::USDollar, "09"),
+        Err(Error::UnknownAccount(String::from("09")))
+    );
+
+    ledger
+        .get_price_database_mut()
+        .remove_price("CAD", "USD", date(2018, 1, 31));
+    assert_eq!(
+        ledger.preview_revaluation(date(2018, 1, 31), Currency::USDollar, "05"),
+        Err(Error::UnknownRate(
+            String::from("CAD"),
+            String::from("USD"),
+            date(2018, 1, 31)
+        ))
+    );
+}
+
+#[test]
+fn later_revaluations_are_incremental() {
+    let mut ledger = get_ledger();
+    ledger
+        .post_revaluation(date(2018, 1, 31), Currency::USDollar, "05")
+        .unwrap();
+    assert_eq!(
+        ledger.post_revaluation(date(2018, 1, 31), Currency::USDollar, "05"),
+        Err(Error::DuplicateTransaction(String::from(
+            "REVAL-2018-01-31"
+        )))
+    );
+
+    ledger
+        .get_price_database_mut()
+        .add_price("EUR", "USD", date(2018, 2, 28), 1.22);
+    let february = ledger
+        .preview_revaluation(date(2018, 2, 28), Currency::USDollar, "05")
+        .unwrap();
+    let splits = february.get_splits();
+    assert_eq!(splits.len(), 2);
+    assert_eq!(splits[0].get_account_id(), "01");
+    assert_eq!(splits[0].get_value(), -3000);
+}
+
+#[test]
+fn realized_gains_are_measured_from_the_revalued_amount() {
+    let mut ledger = get_ledger();
+    ledger.set_exchange_gain_loss_account("04").unwrap();
+    ledger
+        .post_revaluation(date(2018, 1, 31), Currency::USDollar, "05")
+        .unwrap();
+
+    let mut transfer = Transaction::new("0003", date(2018, 2, 1), "Convert to dollars");
+    transfer.add_split(Split::new("01-USD", 126000));
+    transfer.add_split(Split::new_foreign("01", -100000, 1.26));
+    ledger.add_transaction(transfer).unwrap();
+
+    assert_eq!(
+        ledger.get_balance("04", &BalanceOptions::new()).unwrap(),
+        1000
+    );
+    assert_eq!(carrying_value(&ledger, "01"), 0);
+}
@@ -0,0 +1,205 @@
+extern crate chrono;
+extern crate cratchit;
+
+mod common;
+
+use std::convert::TryFrom;
+
+use chrono::Weekday;
+use common::date;
+use cratchit::{
+    Account, AccountType, AccountsChart, Currency, EntrySource, Error, Frequency, Ledger,
+    Recurrence, ScheduledTransaction, Split, Transaction,
+};
+
+fn rent(amount: i64) -> Transaction {
+    let mut transaction = Transaction::new("", date(2018, 1, 1), "Rink office rent");
+    transaction.add_split(Split::new("05", amount));
+    transaction.add_split(Split::new("01", -amount));
+
+    transaction
+}
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(Account::new(
+        "01",
+        "Checking",
+        "Checking",
+        AccountType::Asset,
+        Currency::USDollar,
+        false,
+    ));
+    chart.add_top_level_account(Account::new(
+        "05",
+        "Rent",
+        "Rent",
+        AccountType::Expense,
+        Currency::USDollar,
+        false,
+    ));
+
+    let mut ledger = Ledger::new(chart);
+    let recurrence = Recurrence::new(Frequency::MonthlyByDay(1), date(2018, 1, 1)).count(6);
+    ledger
+        .add_schedule(ScheduledTransaction::new("RENT", rent(90000), recurrence))
+        .unwrap();
+
+    ledger
+}
+
+#[test]
+fn recurrence_rules() {
+    let through = date(2018, 12, 31);
+
+    let biweekly = Recurrence::new(Frequency::Weekly, date(2018, 1, 5))
+        .every(2)
+        .until(date(2018, 2, 10));
+    assert_eq!(
+        biweekly.get_occurrences(through),
+        vec![date(2018, 1, 5), date(2018, 1, 19), date(2018, 2, 2)]
+    );
+
+    let thirty_first = Recurrence::new(Frequency::MonthlyByDay(31), date(2018, 1, 15)).count(3);
+    assert_eq!(
+        thirty_first.get_occurrences(through),
+        vec![date(2018, 1, 31), date(2018, 2, 28), date(2018, 3, 31)]
+    );
+
+    let last_friday = Recurrence::new(
+        Frequency::MonthlyByWeekday(5, Weekday::Fri),
+        date(2018, 3, 1),
+    )
+    .count(2);
+    assert_eq!(
+        last_friday.get_occurrences(through),
+        vec![date(2018, 3, 30), date(2018, 4, 27)]
+    );
+
+    let quarter_end = Recurrence::new(Frequency::EndOfMonth, date(2018, 1, 1)).every(3);
+    assert_eq!(
+        quarter_end.get_occurrences(date(2018, 9, 30)),
+        vec![date(2018, 1, 31), date(2018, 4, 30), date(2018, 7, 31)]
+    );
+
+    let leap_day = Recurrence::new(Frequency::Yearly, date(2016, 2, 29));
+    assert_eq!(
+        leap_day.get_occurrences(through),
+        vec![date(2016, 2, 29), date(2017, 2, 28), date(2018, 2, 28)]
+    );
+
+    let daily = Recurrence::new(Frequency::Daily, date(2018, 1, 30)).every(2);
+    assert_eq!(
+        daily.get_occurrences(date(2018, 2, 4)),
+        vec![date(2018, 1, 30), date(2018, 2, 1), date(2018, 2, 3)]
+    );
+    assert!(daily.occurs_on(date(2018, 2, 3)));
+    assert!(!daily.occurs_on(date(2018, 2, 4)));
+}
+
+#[test]
+fn materializing_tracks_created_occurrences() {
+    let mut ledger = get_ledger();
+
+    let posted = ledger.materialize_schedules(date(2018, 2, 15)).unwrap();
+    assert_eq!(posted, vec!["RENT-2018-01-01", "RENT-2018-02-01"]);
+    let posted = ledger.materialize_schedules(date(2018, 12, 31)).unwrap();
+    assert_eq!(posted.len(), 4);
+    assert_eq!(ledger.get_num_transactions(), 6);
+
+    let transaction = ledger.get_transaction_by_id("RENT-2018-03-01").unwrap();
+    assert_eq!(transaction.get_date(), date(2018, 3, 1));
+    assert_eq!(transaction.get_source(), EntrySource::Scheduled);
+    assert_eq!(transaction.get_description(), "Rink office rent");
+
+    let schedule = ledger.get_schedule_by_id("RENT").unwrap();
+    assert_eq!(schedule.get_created().len(), 6);
+    assert!(schedule.get_due_occurrences(date(2019, 12, 31)).is_empty());
+}
+
+#[test]
+fn skipping_and_editing_occurrences() {
+    let mut ledger = get_ledger();
+    ledger.skip_occurrence("RENT", date(2018, 2, 1)).unwrap();
+
+    let mut late = Transaction::new("", date(2018, 3, 4), "Rink office rent");
+    late.add_split(Split::new("05", 95000));
+    late.add_split(Split::new("01", -95000));
+    ledger
+        .edit_occurrence("RENT", date(2018, 3, 1), late)
+        .unwrap();
+
+    let posted = ledger.materialize_schedules(date(2018, 3, 31)).unwrap();
+    assert_eq!(posted, vec!["RENT-2018-01-01", "RENT-2018-03-01"]);
+
+    let edited = ledger.get_transaction_by_id("RENT-2018-03-01").unwrap();
+    assert_eq!(edited.get_date(), date(2018, 3, 4));
+    assert_eq!(edited.get_total_debits(), 95000);
+
+    assert_eq!(
+        ledger.skip_occurrence("RENT", date(2018, 1, 1)),
+        Err(Error::DuplicateTransaction(String::from("RENT-2018-01-01")))
+    );
+    assert_eq!(
+        ledger.skip_occurrence("RENT", date(2018, 4, 2)),
+        Err(Error::UnknownOccurrence(
+            String::from("RENT"),
+            date(2018, 4, 2)
+        ))
+    );
+    assert_eq!(
+        ledger.skip_occurrence("PAYROLL", date(2018, 4, 1)),
+        Err(Error::UnknownSchedule(String::from("PAYROLL")))
+    );
+}
+
+#[test]
+fn invalid_schedules_are_rejected() {
+    let mut ledger = get_ledger();
+    let recurrence = Recurrence::new(Frequency::Weekly, date(2018, 1, 1));
+
+    assert_eq!(
+        ledger.add_schedule(ScheduledTransaction::new(
+            "RENT",
+            rent(100),
+            Recurrence::clone(&recurrence)
+        )),
+        Err(Error::DuplicateSchedule(String::from("RENT")))
+    );
+
+    let mut unbalanced = rent(100);
+    unbalanced.add_split(Split::new("01", -1));
+    assert_eq!(
+        ledger.add_schedule(ScheduledTransaction::new("FEES", unbalanced, recurrence)),
+        Err(Error::Unbalanced(String::new()))
+    );
+
+    ledger.remove_schedule("RENT").unwrap();
+    assert!(ledger.get_schedules().is_empty());
+}
+
+#[test]
+fn saved_schedules_round_trip() {
+    let mut ledger = get_ledger();
+    let recurrence = Recurrence::new(
+        Frequency::MonthlyByWeekday(2, Weekday::Tue),
+        date(2018, 1, 1),
+    )
+    .every(3)
+    .until(date(2018, 12, 31));
+    ledger
+        .add_schedule(ScheduledTransaction::new("FEES", rent(2500), recurrence))
+        .unwrap();
+    ledger.skip_occurrence("RENT", date(2018, 2, 1)).unwrap();
+    ledger.materialize_schedules(date(2018, 3, 31)).unwrap();
+
+    let loaded = Ledger::try_from(&ledger.to_json()).unwrap();
+    assert_eq!(loaded.get_schedules(), ledger.get_schedules());
+    assert_eq!(
+        loaded
+            .get_schedule_by_id("RENT")
+            .unwrap()
+            .get_due_occurrences(date(2018, 4, 30)),
+        vec![date(2018, 4, 1)]
+    );
+}
@@ -0,0 +1,203 @@
+extern crate chrono;
+extern crate cratchit;
+
+mod common;
+
+use common::date;
+use cratchit::{Account, AccountType, AccountsChart, BalanceOptions, Currency, Error, Ledger,
+               Split, StatusFilter, Transaction, TransactionStatus};
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(Account::new(
+        "01",
+        "Checking",
+        "Checking",
+        AccountType::Asset,
+        Currency::USDollar,
+        false,
+    ));
+    chart.add_top_level_account(Account::new(
+        "05",
+        "Ice Time",
+        "Ice Time",
+        AccountType::Expense,
+        Currency::USDollar,
+        false,
+    ));
+
+    let mut ledger = Ledger::new(chart);
+    for &(id, day, amount) in &[("1041", 1, 10000), ("1042", 2, 20000), ("1043", 3, 40000)] {
+        let mut check = Transaction::new(id, date(2018, 11, day), "Ice time");
+        check.add_split(Split::new("05", amount));
+        check.add_split(Split::new("01", -amount));
+        ledger.add_transaction(check).unwrap();
+    }
+
+    ledger
+}
+
+#[test]
+fn status_transitions_are_enforced() {
+    let mut ledger = get_ledger();
+
+    let result = ledger.set_transaction_status("1041", TransactionStatus::Reconciled);
+    assert_eq!(
+        result,
+        Err(Error::InvalidStatusTransition(
+            TransactionStatus::Pending,
+            TransactionStatus::Reconciled
+        ))
+    );
+
+    ledger
+        .set_transaction_status("1041", TransactionStatus::Cleared)
+        .unwrap();
+    ledger
+        .set_transaction_status("1041", TransactionStatus::Reconciled)
+        .unwrap();
+
+    let transaction = ledger.get_transaction_by_id("1041").unwrap();
+    assert_eq!(transaction.get_status(), TransactionStatus::Reconciled);
+    assert_eq!(
+        transaction.get_splits()[1].get_status(),
+        TransactionStatus::Reconciled
+    );
+
+    let result = ledger.set_transaction_status("1041", TransactionStatus::Pending);
+    assert!(result.is_err());
+}
+
+#[test]
+fn statuses_cannot_be_set_to_voided_directly() {
+    let mut ledger = get_ledger();
+
+    let result = ledger.set_split_status("1041", 0, TransactionStatus::Voided);
+    assert_eq!(
+        result,
+        Err(Error::InvalidStatusTransition(
+            TransactionStatus::Pending,
+            TransactionStatus::Voided
+        ))
+    );
+
+    let result = ledger.set_split_status("1041", 5, TransactionStatus::Cleared);
+    assert_eq!(result, Err(Error::UnknownSplit(String::from("1041"), 5)));
+}
+
+#[test]
+fn voiding_creates_a_reversal() {
+    let mut ledger = get_ledger();
+    ledger
+        .set_transaction_status("1042", TransactionStatus::Cleared)
+        .unwrap();
+
+    let reversal_id = ledger.void_transaction("1042", date(2018, 11, 20)).unwrap();
+    assert_eq!(ledger.get_num_transactions(), 4);
+
+    let original = ledger.get_transaction_by_id("1042").unwrap();
+    let reversal = ledger.get_transaction_by_id(&reversal_id).unwrap();
+    assert_eq!(original.get_status(), TransactionStatus::Voided);
+    assert_eq!(reversal.get_status(), TransactionStatus::Voided);
+    assert_eq!(reversal.get_reverses(), Some(String::from("1042")));
+    assert_eq!(reversal.get_splits()[1].get_amount(), 20000);
+
+    assert_eq!(
+        ledger.get_balance("05", &BalanceOptions::new()).unwrap(),
+        50000
+    );
+    assert!(ledger.void_transaction("1042", date(2018, 11, 21)).is_err());
+}
+
+#[test]
+fn balances_restricted_by_status() {
+    let mut ledger = get_ledger();
+    ledger
+        .set_split_status("1041", 1, TransactionStatus::Cleared)
+        .unwrap();
+    ledger
+        .set_split_status("1042", 1, TransactionStatus::Cleared)
+        .unwrap();
+    ledger
+        .set_split_status("1042", 1, TransactionStatus::Reconciled)
+        .unwrap();
+    ledger.void_transaction("1043", date(2018, 11, 20)).unwrap();
+
+    let all = BalanceOptions::new();
+    let cleared = BalanceOptions::new().status(StatusFilter::Cleared);
+    let reconciled = BalanceOptions::new().status(StatusFilter::Reconciled);
+
+    assert_eq!(ledger.get_balance("01", &all).unwrap(), -30000);
+    assert_eq!(ledger.get_balance("01", &cleared).unwrap(), -30000);
+    assert_eq!(ledger.get_balance("01", &reconciled).unwrap(), -20000);
+    assert_eq!(ledger.get_balance("05", &cleared).unwrap(), 0);
+}
+
+#[test]
+fn voided_and_reconciled_transactions_cannot_be_edited() {
+    let mut ledger = get_ledger();
+    ledger.void_transaction("1041", date(2018, 11, 30)).unwrap();
+    ledger
+        .set_transaction_status("1042", TransactionStatus::Cleared)
+        .unwrap();
+    ledger
+        .set_split_status("1042", 1, TransactionStatus::Reconciled)
+        .unwrap();
+
+    for &(id, day, status) in &[
+        ("1041", 1, TransactionStatus::Voided),
+        ("1042", 2, TransactionStatus::Reconciled),
+    ] {
+        let mut edited = Transaction::new(id, date(2018, 11, day), "Ice time");
+        edited.add_split(Split::new("05", 5000));
+        edited.add_split(Split::new("01", -5000));
+        assert_eq!(
+            ledger.update_transaction(edited),
+            Err(Error::InvalidStatusTransition(
+                status,
+                TransactionStatus::Pending
+            ))
+        );
+    }
+
+    let voided = ledger.get_transaction_by_id("1041").unwrap();
+    assert_eq!(voided.get_status(), TransactionStatus::Voided);
+    assert_eq!(
+        ledger.get_balance("01", &BalanceOptions::new()),
+        Ok(-60000)
+    );
+
+    let mut edited = Transaction::new("1043", date(2018, 11, 3), "Ice time");
+    edited.add_split(Split::new("05", 5000));
+    edited.add_split(Split::new("01", -5000));
+    ledger.update_transaction(edited).unwrap();
+}
+
+#[test]
+fn voided_and_reconciled_transactions_cannot_be_removed() {
+    let mut ledger = get_ledger();
+    ledger.void_transaction("1041", date(2018, 11, 30)).unwrap();
+    ledger
+        .set_transaction_status("1042", TransactionStatus::Cleared)
+        .unwrap();
+    ledger
+        .set_split_status("1042", 1, TransactionStatus::Reconciled)
+        .unwrap();
+
+    for &(id, status) in &[
+        ("1041", TransactionStatus::Voided),
+        ("1041-void", TransactionStatus::Voided),
+        ("1042", TransactionStatus::Reconciled),
+    ] {
+        assert!(matches!(
+            ledger.remove_transaction(id),
+            Err(Error::InvalidStatusTransition(from, _)) if from == status
+        ));
+    }
+    assert_eq!(
+        ledger.get_balance("01", &BalanceOptions::new()),
+        Ok(-60000)
+    );
+
+    ledger.remove_transaction("1043").unwrap();
+}
@@ -0,0 +1,160 @@
+extern crate chrono;
+extern crate cratchit;
+
+mod common;
+
+use common::date;
+use cratchit::{
+    Account, AccountType, AccountsChart, BalanceOptions, Currency, Error, Ledger, RatePolicy,
+    Split, Transaction, Translation,
+};
+
+fn account(id: &str, account_type: AccountType, currency: Currency, placeholder: bool) -> Account {
+    Account::new(id, id, id, account_type, currency, placeholder)
+}
+
+/// Build a `Ledger` for a US parent with a Canadian branch. The branch is capitalized with
+/// CAD 1,000.00 on January 1st and earns CAD 300.00 of sales on January 3rd.
+fn get_ledger() -> Ledger {
+    let mut cash = account("01", AccountType::Asset, Currency::Unknown, true);
+    cash.add_sub_account(account(
+        "01-USD",
+        AccountType::Asset,
+        Currency::USDollar,
+        false,
+    ));
+    cash.add_sub_account(account(
+        "01-CAD",
+        AccountType::Asset,
+        Currency::CanadianDollar,
+        false,
+    ));
+
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(cash);
+    chart.add_top_level_account(account(
+        "03",
+        AccountType::Equity,
+        Currency::CanadianDollar,
+        false,
+    ));
+    chart.add_top_level_account(account(
+        "03-CTA",
+        AccountType::Equity,
+        Currency::USDollar,
+        false,
+    ));
+    chart.add_top_level_account(account(
+        "03-USD",
+        AccountType::Equity,
+        Currency::USDollar,
+        false,
+    ));
+    chart.add_top_level_account(account(
+        "04",
+        AccountType::Income,
+        Currency::CanadianDollar,
+        false,
+    ));
+
+    let mut ledger = Ledger::new(chart);
+    let mut capital = Transaction::new("0001", date(2018, 1, 1), "Branch capital");
+    capital.set_currency(Currency::CanadianDollar);
+    capital.add_split(Split::new("01-CAD", 100000));
+    capital.add_split(Split::new("03", -100000));
+    ledger.add_transaction(capital).unwrap();
+
+    let mut sales = Transaction::new("0002", date(2018, 1, 3), "Branch sales");
+    sales.set_currency(Currency::CanadianDollar);
+    sales.add_split(Split::new("01-CAD", 30000));
+    sales.add_split(Split::new("04", -30000));
+    ledger.add_transaction(sales).unwrap();
+
+    let mut parent = Transaction::new("0003", date(2018, 1, 1), "Parent capital");
+    parent.add_split(Split::new("01-USD", 50000));
+    parent.add_split(Split::new("03-USD", -50000));
+    ledger.add_transaction(parent).unwrap();
+
+    let prices = ledger.get_price_database_mut();
+    prices.add_price("CAD", "USD", date(2018, 1, 1), 0.8);
+    prices.add_price("CAD", "USD", date(2018, 1, 2), 0.7);
+    prices.add_price("CAD", "USD", date(2018, 1, 4), 0.75);
+
+    ledger
+}
+
+fn options(translation: Translation) -> BalanceOptions {
+    BalanceOptions::new()
+        .since(date(2018, 1, 1))
+        .as_of(date(2018, 1, 4))
+        .translate(translation)
+}
+
+#[test]
+fn rate_policies_choose_the_rate() {
+    let ledger = get_ledger();
+    let translation = Translation::new(Currency::USDollar);
+
+    // The daily rates are 0.8, 0.7, 0.7 and 0.75, averaging 0.7375.
+    let average = options(translation.clone());
+    assert_eq!(ledger.get_balance("04", &average).unwrap(), 22125);
+
+    let closing = options(
+        translation
+            .clone()
+            .income_statement_rate(RatePolicy::Closing),
+    );
+    assert_eq!(ledger.get_balance("04", &closing).unwrap(), 22500);
+
+    let historical = options(translation.income_statement_rate(RatePolicy::Historical));
+    assert_eq!(ledger.get_balance("04", &historical).unwrap(), 21000);
+    assert_eq!(ledger.get_balance("03", &historical).unwrap(), 80000);
+}
+
+#[test]
+fn placeholder_roll_ups_combine_currencies() {
+    let ledger = get_ledger();
+    let translated = options(Translation::new(Currency::USDollar)).include_sub_accounts(true);
+    assert_eq!(ledger.get_balance("01", &translated).unwrap(), 147500);
+    assert_eq!(ledger.get_balance("01-CAD", &translated).unwrap(), 97500);
+
+    let native = BalanceOptions::new();
+    assert_eq!(ledger.get_balance("01-CAD", &native).unwrap(), 130000);
+}
+
+#[test]
+fn translation_adjustment_balances_the_books() {
+    let ledger = get_ledger();
+    let translated = options(Translation::new(Currency::USDollar).adjustment_account("03-CTA"));
+
+    // Assets of 1475.00 less equity of 800.00 + 500.00 and income of 221.25.
+    assert_eq!(
+        ledger.get_translation_adjustment(&translated).unwrap(),
+        -4625
+    );
+    assert_eq!(ledger.get_balance("03-CTA", &translated).unwrap(), -4625);
+
+    let balances = ledger.get_balances(&translated);
+    let debits: i64 = ["01-USD", "01-CAD"].iter().map(|id| balances[*id]).sum();
+    let credits: i64 = ["03", "03-CTA", "03-USD", "04"]
+        .iter()
+        .map(|id| balances[*id])
+        .sum();
+    assert_eq!(debits, credits);
+}
+
+#[test]
+fn unknown_rates_are_reported() {
+    let ledger = get_ledger();
+    let translated = options(Translation::new(Currency::Euro));
+
+    assert_eq!(
+        ledger.get_balance("01-CAD", &translated),
+        Err(Error::UnknownRate(
+            String::from("CAD"),
+            String::from("EUR"),
+            date(2018, 1, 4)
+        ))
+    );
+    assert!(ledger.get_translation_adjustment(&translated).is_err());
+}
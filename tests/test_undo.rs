@@ -0,0 +1,221 @@
+extern crate chrono;
+extern crate cratchit;
+
+mod common;
+
+use common::{account, date};
+use cratchit::{
+    AccountType, AccountsChart, AuditAction, BalanceOptions, Error, FiscalCalendar, Frequency,
+    Ledger, Recurrence, ScheduledTransaction, Split, Transaction,
+};
+
+fn dues(id: &str, amount: i64) -> Transaction {
+    let mut transaction = Transaction::new(id, date(2018, 3, 1), "Membership dues");
+    transaction.add_split(Split::new("01", amount));
+    transaction.add_split(Split::new("04", -amount));
+
+    transaction
+}
+
+fn get_ledger() -> Ledger {
+    let mut chart = AccountsChart::new();
+    chart.add_top_level_account(account("01", "Checking", AccountType::Asset));
+    chart.add_top_level_account(account("04", "Dues", AccountType::Income));
+
+    Ledger::new(chart)
+}
+
+fn get_balance(ledger: &Ledger, id: &str) -> i64 {
+    ledger.get_balance(id, &BalanceOptions::new()).unwrap()
+}
+
+#[test]
+fn transaction_edits_can_be_undone_and_redone() {
+    let mut ledger = get_ledger();
+    assert!(!ledger.can_undo());
+    assert_eq!(ledger.undo(), Err(Error::NothingToUndo));
+
+    ledger.add_transaction(dues("0001", 5000)).unwrap();
+    ledger.update_transaction(dues("0001", 7500)).unwrap();
+    ledger.remove_transaction("0001").unwrap();
+
+    ledger.undo().unwrap();
+    assert_eq!(get_balance(&ledger, "04"), 7500);
+    ledger.undo().unwrap();
+    assert_eq!(get_balance(&ledger, "04"), 5000);
+    ledger.undo().unwrap();
+    assert_eq!(ledger.get_num_transactions(), 0);
+    assert!(!ledger.can_undo());
+
+    ledger.redo().unwrap();
+    ledger.redo().unwrap();
+    assert_eq!(get_balance(&ledger, "04"), 7500);
+    assert!(ledger.can_redo());
+    assert!(ledger.verify_journal().is_ok());
+
+    let history = ledger.get_transaction_history("0001");
+    assert_eq!(history.len(), 8);
+    assert_eq!(history[5].get_action(), AuditAction::RemoveTransaction);
+}
+
+#[test]
+fn account_edits_can_be_undone() {
+    let mut ledger = get_ledger();
+    ledger
+        .add_sub_account("01", account("01-01", "Savings", AccountType::Asset))
+        .unwrap();
+    ledger
+        .update_account(account("04", "Membership Dues", AccountType::Income))
+        .unwrap();
+
+    ledger.undo().unwrap();
+    assert_eq!(
+        ledger
+            .get_chart()
+            .get_account_by_id("04")
+            .unwrap()
+            .get_name(),
+        "Dues"
+    );
+    ledger.undo().unwrap();
+    assert!(ledger.get_chart().get_account_by_id("01-01").is_none());
+
+    let history = ledger.get_account_history("01-01");
+    assert_eq!(history[1].get_action(), AuditAction::RemoveAccount);
+    assert_eq!(history[1].get_parent_id(), Some(String::from("01")));
+
+    ledger.redo().unwrap();
+    assert_eq!(ledger.get_chart().get_num_accounts(), 3);
+}
+
+#[test]
+fn grouped_edits_are_undone_together() {
+    let mut ledger = get_ledger();
+    ledger.begin_group();
+    ledger.add_transaction(dues("0001", 5000)).unwrap();
+    ledger.begin_group();
+    ledger.add_transaction(dues("0002", 2500)).unwrap();
+    ledger.end_group();
+    ledger.void_transaction("0001", date(2018, 3, 2)).unwrap();
+    ledger.end_group();
+
+    ledger.undo().unwrap();
+    assert_eq!(ledger.get_num_transactions(), 0);
+    assert!(!ledger.can_undo());
+
+    ledger.redo().unwrap();
+    assert_eq!(ledger.get_num_transactions(), 3);
+    assert_eq!(get_balance(&ledger, "04"), 2500);
+
+    ledger.undo().unwrap();
+    ledger.add_transaction(dues("0003", 1000)).unwrap();
+    assert!(!ledger.can_redo());
+    assert_eq!(ledger.redo(), Err(Error::NothingToRedo));
+}
+
+#[test]
+fn edits_in_closed_periods_cannot_be_undone() {
+    let mut ledger = get_ledger();
+    ledger
+        .add_fiscal_year(&FiscalCalendar::Monthly { start_month: 1 }, 2018)
+        .unwrap();
+    ledger.add_transaction(dues("0001", 5000)).unwrap();
+    ledger
+        .close_period("FY2018-P03", "March books filed")
+        .unwrap();
+
+    assert_eq!(
+        ledger.undo(),
+        Err(Error::ClosedPeriod(String::from("FY2018-P03")))
+    );
+    assert_eq!(ledger.get_num_transactions(), 1);
+
+    ledger.reopen_period("FY2018-P03", "Missed refund").unwrap();
+    ledger.undo().unwrap();
+    assert_eq!(ledger.get_num_transactions(), 0);
+}
+
+#[test]
+fn undoing_schedules_and_reconciliations_restores_their_state() {
+    let mut ledger = get_ledger();
+    let template = dues("", 5000);
+    let recurrence = Recurrence::new(Frequency::MonthlyByDay(1), date(2018, 1, 1));
+    ledger
+        .add_schedule(ScheduledTransaction::new("DUES", template, recurrence))
+        .unwrap();
+
+    ledger.materialize_schedules(date(2018, 2, 28)).unwrap();
+    ledger.undo().unwrap();
+    assert!(ledger
+        .get_schedule_by_id("DUES")
+        .unwrap()
+        .get_created()
+        .is_empty());
+    assert_eq!(
+        ledger.materialize_schedules(date(2018, 2, 28)).unwrap(),
+        vec!["DUES-2018-01-01", "DUES-2018-02-01"]
+    );
+
+    let mut session = ledger
+        .start_reconciliation("01", date(2018, 2, 28), 10000)
+        .unwrap();
+    session.mark_cleared(&ledger, "DUES-2018-01-01", 0).unwrap();
+    session.mark_cleared(&ledger, "DUES-2018-02-01", 0).unwrap();
+    ledger.finalize_reconciliation(&session).unwrap();
+    ledger.undo().unwrap();
+    assert!(ledger.get_reconciliations("01").is_empty());
+    ledger.redo().unwrap();
+    assert_eq!(ledger.get_reconciliations("01").len(), 1);
+
+    ledger.undo().unwrap();
+    ledger.finalize_reconciliation(&session).unwrap();
+    ledger.undo().unwrap();
+    ledger.redo().unwrap();
+    assert_eq!(ledger.get_reconciliations("01").len(), 1);
+}
+
+#[test]
+fn json_api_edits_can_be_undone() {
+    let mut ledger = Ledger::from_json_string(&get_ledger().to_json_string()).unwrap();
+    let transaction = dues("0001", 5000).to_json().dump();
+    ledger.add_transaction_json(&transaction).unwrap();
+    ledger.void_transaction_json("0001", "2018-03-02").unwrap();
+    assert_eq!(get_balance(&ledger, "04"), 0);
+
+    ledger.undo().unwrap();
+    assert_eq!(get_balance(&ledger, "04"), 5000);
+
+    assert!(matches!(
+        ledger.add_transaction_json(r#"{ "id": "0002", "splits": [] }"#),
+        Err(Error::InvalidJson(_))
+    ));
+    assert!(matches!(
+        ledger.void_transaction_json("0001", "March 2"),
+        Err(Error::InvalidJson(_))
+    ));
+    assert!(matches!(
+        Ledger::from_json_string("{ accounts"),
+        Err(Error::InvalidJson(_))
+    ));
+
+    let mut saved = ledger.to_json();
+    saved["transactions"][0].remove("date");
+    assert_eq!(
+        Ledger::from_json_string(&saved.dump()).err(),
+        Some(Error::InvalidJson(String::from(
+            "a transaction has no 'date'"
+        )))
+    );
+    let mut saved = ledger.to_json();
+    saved["chain"][0].remove("hash");
+    assert!(matches!(
+        Ledger::from_json_string(&saved.dump()),
+        Err(Error::InvalidJson(_))
+    ));
+    let mut saved = ledger.to_json();
+    saved["accounts"][0].remove("name");
+    assert!(matches!(
+        Ledger::from_json_string(&saved.dump()),
+        Err(Error::InvalidJson(_))
+    ));
+}